@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+use crate::DexError;
+
+// 检查并去除8字节的Anchor账户discriminator，数据不足或不匹配时返回指定的错误
+pub fn strip_discriminator<'a>(
+    data: &'a [u8],
+    discriminator: &[u8; 8],
+    error: DexError,
+) -> Result<&'a [u8]> {
+    if data.len() < 8 || data[..8] != discriminator[..] {
+        return Err(error.into());
+    }
+    Ok(&data[8..])
+}
+
+// 编码指令数据: 指令前缀（Anchor程序为8字节discriminator，原生程序为指令标签）+ Borsh序列化参数
+pub fn encode_instruction_data<T: AnchorSerialize>(prefix: &[u8], args: &T) -> Vec<u8> {
+    let mut data = prefix.to_vec();
+    // 写入Vec<u8>不会失败
+    args.serialize(&mut data).unwrap();
+    data
+}
@@ -0,0 +1,139 @@
+use anchor_lang::prelude::*;
+use crate::math::{self, Price};
+use crate::{token_utils, DexError, DexType};
+
+// 读取余额: 代币账户读取代币余额，用户钱包等其他账户读取lamports（原生SOL）
+// 尚未创建的代币账户归系统程序所有且lamports为0，按余额为0处理
+pub fn read_balance(account_info: &AccountInfo) -> Result<u64> {
+    if token_utils::is_token_program(account_info.owner) {
+        Ok(token_utils::read_token_account(account_info)?.amount)
+    } else {
+        Ok(account_info.lamports())
+    }
+}
+
+// 交易对信息（用于计算成交价格和发出成交事件）
+#[derive(Clone, Copy, Debug)]
+pub struct FillPair {
+    pub dex: DexType,
+    pub token_in: Pubkey,
+    pub input_decimals: u8,
+    pub token_out: Pubkey,
+    pub output_decimals: u8,
+}
+
+// 交易的报价和滑点限制
+// 精确输入时max_amount_in为输入数量，精确输出时min_amount_out为期望的输出数量
+#[derive(Clone, Copy, Debug)]
+pub struct FillLimits {
+    pub expected_amount_in: u64,
+    pub expected_amount_out: u64,
+    pub max_amount_in: u64,
+    pub min_amount_out: u64,
+}
+
+// 实际成交数量（由交易前后用户账户的余额变化得到）
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fill {
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+// 交易前用户输入输出账户的余额快照
+// 必须在创建账户、包装SOL之后，CPI之前记录；在解包WSOL、关闭账户之前结算
+#[derive(Clone, Copy, Debug)]
+pub struct BalanceSnapshot {
+    input_account: Pubkey,
+    output_account: Pubkey,
+    input_balance: u64,
+    output_balance: u64,
+}
+
+impl BalanceSnapshot {
+    // 记录输入输出账户的当前余额
+    pub fn take(input_account: &AccountInfo, output_account: &AccountInfo) -> Result<Self> {
+        require_keys_neq!(input_account.key(), output_account.key(), DexError::InvalidArgument);
+        
+        Ok(BalanceSnapshot {
+            input_account: input_account.key(),
+            output_account: output_account.key(),
+            input_balance: read_balance(input_account)?,
+            output_balance: read_balance(output_account)?,
+        })
+    }
+    
+    // 交易后重新读取余额得到实际成交数量，按实际数量检查滑点并发出成交事件
+    pub fn settle(
+        &self,
+        input_account: &AccountInfo,
+        output_account: &AccountInfo,
+        user: Pubkey,
+        pair: &FillPair,
+        limits: &FillLimits,
+    ) -> Result<Fill> {
+        require_keys_eq!(input_account.key(), self.input_account, DexError::InvalidArgument);
+        require_keys_eq!(output_account.key(), self.output_account, DexError::InvalidArgument);
+        
+        let amount_in = self.input_balance
+            .checked_sub(read_balance(input_account)?)
+            .ok_or(DexError::UnexpectedBalanceChange)?;
+        let amount_out = read_balance(output_account)?
+            .checked_sub(self.output_balance)
+            .ok_or(DexError::UnexpectedBalanceChange)?;
+        require!(amount_in > 0, DexError::UnexpectedBalanceChange);
+        
+        // 检查滑点（以实际到账数量和实际花费为准）
+        require!(amount_out >= limits.min_amount_out, DexError::SlippageExceeded);
+        require!(amount_in <= limits.max_amount_in, DexError::SlippageExceeded);
+        
+        // 成交价格与报价价格对比，成交价格更优时滑点为0
+        let realized_price = Price::from_amounts(amount_in, pair.input_decimals, amount_out, pair.output_decimals)?;
+        let quoted_price = Price::from_amounts(
+            limits.expected_amount_in,
+            pair.input_decimals,
+            limits.expected_amount_out,
+            pair.output_decimals,
+        )?;
+        let slippage = math::price_impact(realized_price.value, quoted_price.value)?;
+        
+        msg!("实际成交: 输入 {}, 输出 {}, 成交价格: {}, 相对报价滑点: {}%",
+            amount_in,
+            amount_out,
+            realized_price.as_f64(),
+            slippage as f64 / 10_000.0
+        );
+        
+        emit!(SwapFilled {
+            user,
+            dex: pair.dex,
+            token_in: pair.token_in,
+            token_out: pair.token_out,
+            amount_in,
+            amount_out,
+            expected_amount_in: limits.expected_amount_in,
+            expected_amount_out: limits.expected_amount_out,
+            realized_price,
+            slippage,
+            slot: Clock::get()?.slot,
+        });
+        
+        Ok(Fill { amount_in, amount_out })
+    }
+}
+
+// 统一的成交事件（所有交易路径在CPI后按实际余额变化发出）
+#[event]
+pub struct SwapFilled {
+    pub user: Pubkey,
+    pub dex: DexType,
+    pub token_in: Pubkey,
+    pub token_out: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub expected_amount_in: u64,
+    pub expected_amount_out: u64,
+    pub realized_price: Price,
+    // 成交价格相对报价价格的滑点（以FEE_DENOMINATOR为分母的百万分比）
+    pub slippage: u64,
+    pub slot: u64,
+}
@@ -4,12 +4,21 @@ use solana_program::{system_instruction, pubkey::Pubkey};
 
 // 导入模块
 pub mod pumpfun;
+pub mod pumpswap;
 pub mod raydium;
+pub mod raydium_amm_v4;
+pub mod raydium_clmm;
+pub mod raydium_launchlab;
 pub mod router;
 pub mod mev_protection;
+pub mod token_utils;
+pub mod math;
+pub mod fill;
+pub mod codec;
 
 // 常量定义
 pub const MAX_PRICE_IMPACT: u64 = 50_000; // 5%的最大价格影响
+pub const MAX_TWAP_DEVIATION: u64 = 30_000; // 3%的交易前后现货价格相对TWAP最大偏离（Raydium CPMM）
 pub const FEE_DENOMINATOR: u64 = 1_000_000;
 pub const DEFAULT_FEE_RATE: u64 = 3_000; // 0.3%
 pub const PROTOCOL_VERSION: u8 = 1;
@@ -22,7 +31,7 @@ declare_id!("FZ6RHhMSv5xoE8GjK5KJi2i7Gue1DW3APGe4an4CJjte");
 #[program]
 pub mod solana_dex {
     use super::*;
-
+    
     pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
         let dex_account = &mut ctx.accounts.dex_account;
         dex_account.authority = ctx.accounts.authority.key();
@@ -35,28 +44,29 @@ pub mod solana_dex {
         msg!("DEX账户已初始化");
         Ok(())
     }
-
-    // 在Pump.fun上购买代币
-    pub fn buy_token_on_pump(
-        ctx: Context<pumpfun::TradeToken>,
+    
+    // 在Pump.fun上购买代币（精确SOL输入）
+    pub fn buy_token_on_pump<'info>(
+        ctx: Context<'_, '_, '_, 'info, pumpfun::TradeToken<'info>>,
         amount_in: u64,
         min_amount_out: u64,
+        allow_partial_fill: bool,
     ) -> Result<()> {
         // 检查重入锁
-        let dex_account = &mut ctx.accounts.dex_account;
-        require!(!dex_account.locked, DexError::ReentrancyDetected);
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
         
         // 设置锁定状态
-        dex_account.locked = true;
+        ctx.accounts.dex_account.locked = true;
         
         // 记录交易开始时间
         let start_time = Clock::get()?.unix_timestamp;
         
         // 执行交易
-        let result = pumpfun::buy_token(ctx, amount_in, min_amount_out);
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = pumpfun::buy_token_exact_sol_in(trade_ctx, amount_in, min_amount_out, allow_partial_fill).map(|_| ());
         
         // 解除锁定状态
-        dex_account.locked = false;
+        ctx.accounts.dex_account.locked = false;
         
         // 记录交易结束时间和性能指标
         let end_time = Clock::get()?.unix_timestamp;
@@ -65,28 +75,93 @@ pub mod solana_dex {
         
         result
     }
-
+    
+    // 在Pump.fun上购买指定数量的代币（精确代币输出）
+    pub fn buy_exact_token_on_pump<'info>(
+        ctx: Context<'_, '_, '_, 'info, pumpfun::TradeToken<'info>>,
+        amount_out: u64,
+        max_sol_cost: u64,
+        allow_partial_fill: bool,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = pumpfun::buy_token(trade_ctx, amount_out, max_sol_cost, allow_partial_fill).map(|_| ());
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 在Pump.fun上发行代币，并可选地进行创建者买入
+    pub fn launch_on_pump<'info>(
+        ctx: Context<'_, '_, '_, 'info, pumpfun::LaunchToken<'info>>,
+        name: String,
+        symbol: String,
+        uri: String,
+        initial_buy_sol: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行发行
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = pumpfun::launch_token(trade_ctx, name, symbol, uri, initial_buy_sol, min_amount_out);
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("代币发行执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
     // 在Pump.fun上卖出代币
-    pub fn sell_token_on_pump(
-        ctx: Context<pumpfun::TradeToken>,
+    pub fn sell_token_on_pump<'info>(
+        ctx: Context<'_, '_, '_, 'info, pumpfun::TradeToken<'info>>,
         amount_in: u64,
         min_amount_out: u64,
+        close_token_account: bool,
     ) -> Result<()> {
         // 检查重入锁
-        let dex_account = &mut ctx.accounts.dex_account;
-        require!(!dex_account.locked, DexError::ReentrancyDetected);
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
         
         // 设置锁定状态
-        dex_account.locked = true;
+        ctx.accounts.dex_account.locked = true;
         
         // 记录交易开始时间
         let start_time = Clock::get()?.unix_timestamp;
         
         // 执行交易
-        let result = pumpfun::sell_token(ctx, amount_in, min_amount_out);
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = pumpfun::sell_token(trade_ctx, amount_in, min_amount_out, close_token_account).map(|_| ());
         
         // 解除锁定状态
-        dex_account.locked = false;
+        ctx.accounts.dex_account.locked = false;
         
         // 记录交易结束时间和性能指标
         let end_time = Clock::get()?.unix_timestamp;
@@ -95,28 +170,29 @@ pub mod solana_dex {
         
         result
     }
-
+    
     // 在Raydium上购买代币
-    pub fn buy_token_on_raydium(
-        ctx: Context<raydium::TradeTokenRaydium>,
+    pub fn buy_token_on_raydium<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium::TradeTokenRaydium<'info>>,
         amount_in: u64,
         min_amount_out: u64,
+        max_twap_deviation: Option<u64>,
     ) -> Result<()> {
         // 检查重入锁
-        let dex_account = &mut ctx.accounts.dex_account;
-        require!(!dex_account.locked, DexError::ReentrancyDetected);
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
         
         // 设置锁定状态
-        dex_account.locked = true;
+        ctx.accounts.dex_account.locked = true;
         
         // 记录交易开始时间
         let start_time = Clock::get()?.unix_timestamp;
         
         // 执行交易
-        let result = raydium::buy_token(ctx, amount_in, min_amount_out);
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium::buy_token(trade_ctx, amount_in, min_amount_out, max_twap_deviation).map(|_| ());
         
         // 解除锁定状态
-        dex_account.locked = false;
+        ctx.accounts.dex_account.locked = false;
         
         // 记录交易结束时间和性能指标
         let end_time = Clock::get()?.unix_timestamp;
@@ -125,28 +201,30 @@ pub mod solana_dex {
         
         result
     }
-
+    
     // 在Raydium上卖出代币
-    pub fn sell_token_on_raydium(
-        ctx: Context<raydium::TradeTokenRaydium>,
+    pub fn sell_token_on_raydium<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium::TradeTokenRaydium<'info>>,
         amount_in: u64,
         min_amount_out: u64,
+        close_token_account: bool,
+        max_twap_deviation: Option<u64>,
     ) -> Result<()> {
         // 检查重入锁
-        let dex_account = &mut ctx.accounts.dex_account;
-        require!(!dex_account.locked, DexError::ReentrancyDetected);
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
         
         // 设置锁定状态
-        dex_account.locked = true;
+        ctx.accounts.dex_account.locked = true;
         
         // 记录交易开始时间
         let start_time = Clock::get()?.unix_timestamp;
         
         // 执行交易
-        let result = raydium::sell_token(ctx, amount_in, min_amount_out);
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium::sell_token(trade_ctx, amount_in, min_amount_out, close_token_account, max_twap_deviation).map(|_| ());
         
         // 解除锁定状态
-        dex_account.locked = false;
+        ctx.accounts.dex_account.locked = false;
         
         // 记录交易结束时间和性能指标
         let end_time = Clock::get()?.unix_timestamp;
@@ -155,7 +233,469 @@ pub mod solana_dex {
         
         result
     }
-
+    
+    // 在Raydium上购买精确数量的代币
+    pub fn buy_exact_out_on_raydium<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium::TradeTokenRaydium<'info>>,
+        amount_out: u64,
+        max_amount_in: u64,
+        max_twap_deviation: Option<u64>,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium::buy_exact_out(trade_ctx, amount_out, max_amount_in, max_twap_deviation).map(|_| ());
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 在Raydium上卖出代币以获得精确数量的SOL
+    pub fn sell_exact_out_on_raydium<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium::TradeTokenRaydium<'info>>,
+        amount_out: u64,
+        max_amount_in: u64,
+        close_token_account: bool,
+        max_twap_deviation: Option<u64>,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium::sell_exact_out(trade_ctx, amount_out, max_amount_in, close_token_account, max_twap_deviation).map(|_| ());
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 在Raydium CPMM池子中添加流动性（精确LP数量，两侧投入数量上限）
+    pub fn deposit_on_raydium<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium::LiquidityRaydium<'info>>,
+        lp_token_amount: u64,
+        maximum_token_0_amount: u64,
+        maximum_token_1_amount: u64,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium::deposit(trade_ctx, lp_token_amount, maximum_token_0_amount, maximum_token_1_amount);
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 从Raydium CPMM池子中移除流动性（精确LP数量，两侧取回数量下限）
+    pub fn withdraw_on_raydium<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium::LiquidityRaydium<'info>>,
+        lp_token_amount: u64,
+        minimum_token_0_amount: u64,
+        minimum_token_1_amount: u64,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium::withdraw(trade_ctx, lp_token_amount, minimum_token_0_amount, minimum_token_1_amount);
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 在Raydium CPMM上创建池子并注入初始流动性
+    // CPMM要求池子至少在下一秒才开放交易，初始买入无法在同一交易中完成，需在开放后另行调用buy_token_on_raydium
+    pub fn create_pool_on_raydium<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium::CreatePoolRaydium<'info>>,
+        init_amount_0: u64,
+        init_amount_1: u64,
+        open_time: u64,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium::create_pool(trade_ctx, init_amount_0, init_amount_1, open_time);
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 在PumpSwap上购买代币（精确SOL输入）
+    pub fn buy_token_on_pumpswap<'info>(
+        ctx: Context<'_, '_, '_, 'info, pumpswap::TradeTokenPumpSwap<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = pumpswap::buy_token(trade_ctx, amount_in, min_amount_out).map(|_| ());
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 在PumpSwap上卖出代币
+    pub fn sell_token_on_pumpswap<'info>(
+        ctx: Context<'_, '_, '_, 'info, pumpswap::TradeTokenPumpSwap<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        close_token_account: bool,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = pumpswap::sell_token(trade_ctx, amount_in, min_amount_out, close_token_account).map(|_| ());
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 在Raydium AMM v4上购买代币
+    pub fn buy_token_on_raydium_amm_v4<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium_amm_v4::TradeTokenRaydiumAmmV4<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium_amm_v4::buy_token(trade_ctx, amount_in, min_amount_out).map(|_| ());
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 在Raydium AMM v4上卖出代币
+    pub fn sell_token_on_raydium_amm_v4<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium_amm_v4::TradeTokenRaydiumAmmV4<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        close_token_account: bool,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium_amm_v4::sell_token(trade_ctx, amount_in, min_amount_out, close_token_account).map(|_| ());
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 在Raydium AMM v4上购买精确数量的代币
+    pub fn buy_exact_out_on_raydium_amm_v4<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium_amm_v4::TradeTokenRaydiumAmmV4<'info>>,
+        amount_out: u64,
+        max_amount_in: u64,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium_amm_v4::buy_exact_out(trade_ctx, amount_out, max_amount_in).map(|_| ());
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 在Raydium AMM v4上卖出代币以获得精确数量的SOL
+    pub fn sell_exact_out_on_raydium_amm_v4<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium_amm_v4::TradeTokenRaydiumAmmV4<'info>>,
+        amount_out: u64,
+        max_amount_in: u64,
+        close_token_account: bool,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium_amm_v4::sell_exact_out(trade_ctx, amount_out, max_amount_in, close_token_account).map(|_| ());
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 在Raydium CLMM上购买代币（TickArray通过剩余账户传入）
+    pub fn buy_token_on_raydium_clmm<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium_clmm::TradeTokenRaydiumClmm<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium_clmm::buy_token(trade_ctx, amount_in, min_amount_out).map(|_| ());
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 在Raydium CLMM上卖出代币（TickArray通过剩余账户传入）
+    pub fn sell_token_on_raydium_clmm<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium_clmm::TradeTokenRaydiumClmm<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        close_token_account: bool,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium_clmm::sell_token(trade_ctx, amount_in, min_amount_out, close_token_account).map(|_| ());
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 在Raydium LaunchLab上购买代币
+    pub fn buy_token_on_launchlab<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium_launchlab::TradeTokenLaunchLab<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium_launchlab::buy_token(trade_ctx, amount_in, min_amount_out).map(|_| ());
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
+    // 在Raydium LaunchLab上卖出代币
+    pub fn sell_token_on_launchlab<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium_launchlab::TradeTokenLaunchLab<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        close_token_account: bool,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = raydium_launchlab::sell_token(trade_ctx, amount_in, min_amount_out, close_token_account).map(|_| ());
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
     // 检查代币位置
     pub fn check_token_location(
         ctx: Context<router::CheckTokenLocationContext>,
@@ -163,39 +703,89 @@ pub mod solana_dex {
     ) -> Result<bool> {
         router::check_token_location(ctx, token_mint)
     }
-
-    // 获取最优价格路由
-    pub fn get_best_price(
-        ctx: Context<router::GetBestPrice>,
+    
+    // 查询Pump.fun报价（含手续费、储备变化和价格影响）
+    pub fn quote_on_pump(
+        ctx: Context<pumpfun::GetPriceContext>,
         amount_in: u64,
         is_buy: bool,
-    ) -> Result<bool> {
-        router::get_best_price(ctx, amount_in, is_buy)
+    ) -> Result<pumpfun::quote::PumpQuote> {
+        pumpfun::get_quote(ctx, amount_in, is_buy)
     }
-
-    // 智能路由：自动选择正确的DEX进行交易
-    pub fn smart_trade(
-        ctx: Context<router::SmartTradeContext>,
-        token_mint: Pubkey,
+    
+    // 查询PumpSwap报价（含手续费、储备变化和价格影响）
+    pub fn quote_on_pumpswap(
+        ctx: Context<pumpswap::GetPriceContext>,
+        amount_in: u64,
+        is_buy: bool,
+    ) -> Result<pumpswap::quote::PumpSwapQuote> {
+        pumpswap::get_quote(ctx, amount_in, is_buy)
+    }
+    
+    // 查询Raydium AMM v4报价（含手续费、储备变化和价格影响）
+    pub fn quote_on_raydium_amm_v4(
+        ctx: Context<raydium_amm_v4::GetPriceContext>,
         amount_in: u64,
-        min_amount_out: u64,
         is_buy: bool,
+    ) -> Result<raydium_amm_v4::quote::AmmV4Quote> {
+        raydium_amm_v4::get_quote(ctx, amount_in, is_buy)
+    }
+    
+    // 查询Raydium CLMM报价（沿tick计算输出、手续费和价格影响，TickArray通过剩余账户传入）
+    pub fn quote_on_raydium_clmm<'info>(
+        ctx: Context<'_, '_, '_, 'info, raydium_clmm::GetPriceContext<'info>>,
+        amount_in: u64,
+        is_buy: bool,
+    ) -> Result<raydium_clmm::quote::ClmmQuote> {
+        raydium_clmm::get_quote(ctx, amount_in, is_buy)
+    }
+    
+    // 查询Raydium LaunchLab报价（含手续费和价格影响）
+    pub fn quote_on_launchlab(
+        ctx: Context<raydium_launchlab::GetPriceContext>,
+        amount_in: u64,
+        is_buy: bool,
+    ) -> Result<raydium_launchlab::quote::LaunchLabQuote> {
+        raydium_launchlab::get_quote(ctx, amount_in, is_buy)
+    }
+    
+    // 查询Raydium LaunchLab绑定曲线的募集进度
+    pub fn get_launchlab_curve_progress(
+        ctx: Context<raydium_launchlab::GetPriceContext>,
+    ) -> Result<raydium_launchlab::quote::CurveProgress> {
+        raydium_launchlab::get_curve_progress(ctx)
+    }
+    
+    // 获取最优价格路由（返回预期输出最多的DEX）
+    pub fn get_best_price<'info>(
+        ctx: Context<'_, '_, '_, 'info, router::GetBestPrice<'info>>,
+        amount_in: u64,
+        is_buy: bool,
+    ) -> Result<DexType> {
+        router::get_best_price(ctx, amount_in, is_buy)
+    }
+    
+    // 智能路由：按各候选DEX的实际报价选择净输出最多的DEX进行交易，可选拆单
+    pub fn smart_trade<'info>(
+        ctx: Context<'_, '_, 'info, 'info, router::SmartTradeContext<'info>>,
+        instruction: TradeInstruction,
+        split: bool,
     ) -> Result<()> {
         // 检查重入锁
-        let dex_account = &mut ctx.accounts.dex_account;
-        require!(!dex_account.locked, DexError::ReentrancyDetected);
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
         
         // 设置锁定状态
-        dex_account.locked = true;
+        ctx.accounts.dex_account.locked = true;
         
         // 记录交易开始时间
         let start_time = Clock::get()?.unix_timestamp;
         
         // 执行交易
-        let result = router::smart_trade(ctx, token_mint, amount_in, min_amount_out, is_buy);
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = router::smart_trade(trade_ctx, instruction, split);
         
         // 解除锁定状态
-        dex_account.locked = false;
+        ctx.accounts.dex_account.locked = false;
         
         // 记录交易结束时间和性能指标
         let end_time = Clock::get()?.unix_timestamp;
@@ -206,25 +796,25 @@ pub mod solana_dex {
     }
     
     // 批量交易：一次执行多个交易指令
-    pub fn batch_trade(
-        ctx: Context<router::BatchTradeContext>,
+    pub fn batch_trade<'info>(
+        ctx: Context<'_, '_, 'info, 'info, router::BatchTradeContext<'info>>,
         instructions: Vec<TradeInstruction>,
     ) -> Result<()> {
         // 检查重入锁
-        let dex_account = &mut ctx.accounts.dex_account;
-        require!(!dex_account.locked, DexError::ReentrancyDetected);
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
         
         // 设置锁定状态
-        dex_account.locked = true;
+        ctx.accounts.dex_account.locked = true;
         
         // 记录交易开始时间
         let start_time = Clock::get()?.unix_timestamp;
         
         // 执行批量交易
-        let result = router::batch_trade(ctx, instructions);
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = router::batch_trade(trade_ctx, instructions);
         
         // 解除锁定状态
-        dex_account.locked = false;
+        ctx.accounts.dex_account.locked = false;
         
         // 记录交易结束时间和性能指标
         let end_time = Clock::get()?.unix_timestamp;
@@ -233,7 +823,38 @@ pub mod solana_dex {
         
         result
     }
-
+    
+    // 多跳路由：依次在多个DEX上交易，上一段的实际输出作为下一段的输入
+    pub fn route_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, router::RouteSwapContext<'info>>,
+        legs: Vec<RouteLeg>,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        // 检查重入锁
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
+        
+        // 设置锁定状态
+        ctx.accounts.dex_account.locked = true;
+        
+        // 记录交易开始时间
+        let start_time = Clock::get()?.unix_timestamp;
+        
+        // 执行多跳路由交易
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = router::route_swap(trade_ctx, legs, amount_in, min_amount_out);
+        
+        // 解除锁定状态
+        ctx.accounts.dex_account.locked = false;
+        
+        // 记录交易结束时间和性能指标
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("多跳路由交易执行时间: {}ms", execution_time);
+        
+        result
+    }
+    
     // MEV保护：创建交易承诺
     pub fn create_trade_commitment(
         ctx: Context<mev_protection::CreateCommitment>,
@@ -242,48 +863,35 @@ pub mod solana_dex {
     ) -> Result<()> {
         mev_protection::create_commitment(ctx, commitment_hash, min_slot_delay)
     }
-
+    
     // MEV保护：执行承诺交易
-    pub fn execute_committed_trade(
-        ctx: Context<mev_protection::ExecuteCommitment>,
-        token_mint: Pubkey,
-        amount_in: u64,
-        min_amount_out: u64,
-        is_buy: bool,
-        dex_type: DexType,
-        nonce: [u8; 32],
+    pub fn execute_committed_trade<'info>(
+        ctx: Context<'_, '_, 'info, 'info, mev_protection::ExecuteCommitment<'info>>,
+        params: mev_protection::CommitmentExecutionParams,
     ) -> Result<()> {
-        mev_protection::execute_commitment(
-            ctx,
-            token_mint,
-            amount_in,
-            min_amount_out,
-            is_buy,
-            dex_type,
-            nonce,
-        )
+        mev_protection::execute_commitment(ctx, params)
     }
-
+    
     // MEV保护：批量执行承诺交易
-    pub fn batch_execute_committed_trades(
-        ctx: Context<mev_protection::BatchExecuteCommitment>,
+    pub fn batch_execute_committed_trades<'info>(
+        ctx: Context<'_, '_, 'info, 'info, mev_protection::BatchExecuteCommitment<'info>>,
         params: Vec<mev_protection::CommitmentExecutionParams>,
     ) -> Result<()> {
         // 检查重入锁
-        let dex_account = &mut ctx.accounts.dex_account;
-        require!(!dex_account.locked, DexError::ReentrancyDetected);
+        require!(!ctx.accounts.dex_account.locked, DexError::ReentrancyDetected);
         
         // 设置锁定状态
-        dex_account.locked = true;
+        ctx.accounts.dex_account.locked = true;
         
         // 记录交易开始时间
         let start_time = Clock::get()?.unix_timestamp;
         
         // 执行批量承诺交易
-        let result = mev_protection::batch_execute_commitments(ctx, params);
+        let trade_ctx = Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts, ctx.bumps);
+        let result = mev_protection::batch_execute_commitments(trade_ctx, params);
         
         // 解除锁定状态
-        dex_account.locked = false;
+        ctx.accounts.dex_account.locked = false;
         
         // 记录交易结束时间和性能指标
         let end_time = Clock::get()?.unix_timestamp;
@@ -292,14 +900,14 @@ pub mod solana_dex {
         
         result
     }
-
+    
     // MEV保护：检查承诺是否过期
     pub fn check_commitment_expiry(
         ctx: Context<mev_protection::CheckExpiredCommitment>,
     ) -> Result<()> {
         mev_protection::check_expired_commitment(ctx)
     }
-
+    
     // MEV保护：查询承诺统计
     pub fn get_commitment_statistics(
         ctx: Context<mev_protection::GetCommitmentStats>,
@@ -344,15 +952,47 @@ pub struct TradeInstruction {
     pub amount_in: u64,
     pub min_amount_out: u64,
     pub is_buy: bool,
-    pub dex_type: DexType,  // 0 = Auto, 1 = Pump.fun, 2 = Raydium
+    pub dex_type: DexType,  // 0 = Auto, 1 = Pump.fun, 2 = Raydium, 3 = PumpSwap, 4 = Raydium AMM v4
+    // ExactOut时amount_in为最大输入数量，min_amount_out为需要获得的精确输出数量
+    pub swap_mode: SwapMode,
+    // 候选DEX及其剩余账户的结构，Auto时可包含多个DEX，指定DEX时只包含该DEX
+    pub venues: Vec<RouteVenue>,
+}
+
+// 多跳路由中的一段: 在指定DEX上进行SOL与代币之间的精确输入交易
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RouteLeg {
+    pub dex_type: DexType,  // 必须指定DEX，不支持Auto
+    pub token_mint: Pubkey,
+    pub is_buy: bool,  // true表示用SOL买入代币，false表示卖出代币换取SOL
+    pub tick_array_count: u8,  // Raydium CLMM段在交易账户之后附带的TickArray账户数量，其他DEX为0
+}
+
+// 智能路由和批量交易中的一个候选DEX
+// 剩余账户中依次为该DEX的报价账户（仅Auto时）和交易账户，Raydium CLMM之后紧跟tick_array_count个TickArray账户
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RouteVenue {
+    pub dex_type: DexType,  // 必须指定DEX，不支持Auto
+    pub tick_array_count: u8,  // Raydium CLMM在交易账户之后附带的TickArray账户数量，其他DEX为0
+}
+
+// 交易模式: 精确输入或精确输出
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub enum SwapMode {
+    ExactIn,
+    ExactOut,
 }
 
 // DEX类型枚举
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
 pub enum DexType {
     Auto,
     PumpFun,
     Raydium,
+    PumpSwap,
+    RaydiumAmmV4,
+    RaydiumClmm,
+    RaydiumLaunchLab,
 }
 
 // 错误码定义
@@ -420,4 +1060,97 @@ pub enum DexError {
     
     #[msg("批量承诺执行失败")]
     BatchCommitmentExecutionFailed,
+    
+    #[msg("滑点超出限制")]
+    SlippageExceeded,
+    
+    #[msg("绑定曲线剩余代币不足")]
+    CurveCapacityExceeded,
+    
+    #[msg("交易后余额变化异常")]
+    UnexpectedBalanceChange,
+    
+    #[msg("无效的Pump.fun账户")]
+    InvalidPumpAccount,
+    
+    #[msg("缺少Pump.fun账户")]
+    MissingPumpAccount,
+    
+    #[msg("无效的PumpSwap账户")]
+    InvalidPumpSwapAccount,
+    
+    #[msg("缺少PumpSwap账户")]
+    MissingPumpSwapAccount,
+    
+    #[msg("无效的代币账户")]
+    InvalidTokenAccount,
+    
+    #[msg("该DEX不支持此交易模式")]
+    UnsupportedSwapMode,
+    
+    #[msg("代币不在该Raydium池子中")]
+    TokenNotInRaydiumPool,
+    
+    #[msg("Raydium AMM配置与池子不匹配")]
+    InvalidRaydiumAmmConfig,
+    
+    #[msg("无效的Raydium池子权限账户")]
+    InvalidRaydiumAuthority,
+    
+    #[msg("Raydium观察状态账户与池子不匹配")]
+    InvalidRaydiumObservationState,
+    
+    #[msg("现货价格相对TWAP偏离过大")]
+    TwapDeviationExceeded,
+    
+    #[msg("Raydium代币Mint与交易方向不匹配")]
+    RaydiumMintMismatch,
+    
+    #[msg("Raydium金库账户与交易方向不匹配")]
+    RaydiumVaultMismatch,
+    
+    #[msg("Raydium LP Mint与池子不匹配")]
+    RaydiumLpMintMismatch,
+    
+    #[msg("Raydium池子两侧代币Mint顺序错误（须token_0_mint < token_1_mint）")]
+    InvalidRaydiumMintOrder,
+    
+    #[msg("Raydium费率配置不允许创建池子")]
+    RaydiumPoolCreationDisabled,
+    
+    #[msg("Raydium代币程序与池子不匹配")]
+    InvalidRaydiumTokenProgram,
+    
+    #[msg("无效的Raydium AMM v4账户")]
+    InvalidRaydiumAmmV4Account,
+    
+    #[msg("Raydium AMM v4池子当前不允许交易")]
+    RaydiumAmmV4SwapDisabled,
+    
+    #[msg("无效的Raydium CLMM账户")]
+    InvalidRaydiumClmmAccount,
+    
+    #[msg("Raydium CLMM池子当前不允许交易")]
+    RaydiumClmmSwapDisabled,
+    
+    #[msg("缺少交易所需的Raydium CLMM TickArray")]
+    MissingRaydiumClmmTickArray,
+    
+    #[msg("无效的Raydium LaunchLab账户")]
+    InvalidLaunchLabAccount,
+    
+    #[msg("LaunchLab绑定曲线已完成，代币已迁移或等待迁移")]
+    LaunchLabCurveCompleted,
+    
+    #[msg("不支持的LaunchLab绑定曲线类型")]
+    UnsupportedLaunchLabCurve,
+    
+    #[msg("Raydium观察历史不足，无法计算TWAP")]
+    TwapUnavailable,
+    
+    #[msg("报价池子与交易池子不一致")]
+    RoutePoolMismatch,
+    
+    #[msg("无效的多跳路由")]
+    InvalidRoute,
 }
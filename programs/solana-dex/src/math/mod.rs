@@ -0,0 +1,292 @@
+use anchor_lang::prelude::*;
+use crate::{DexError, FEE_DENOMINATOR};
+
+pub use self::uint_types::{U256, U512};
+
+// construct_uint!展开的代码使用标准库的Result，在单独的模块中定义，避免与Anchor预导入的Result冲突
+mod uint_types {
+    use uint::construct_uint;
+    
+    construct_uint! {
+        // 256位无符号整数，用于u128乘法的中间结果
+        pub struct U256(4);
+    }
+    
+    construct_uint! {
+        // 512位无符号整数，用于Q64.64价格与u128流动性相乘的中间结果
+        pub struct U512(8);
+    }
+}
+
+// SOL（WSOL）的小数位数
+pub const SOL_DECIMALS: u8 = 9;
+
+// 价格计算支持的最大小数位数（保证中间结果不超过U256）
+pub const MAX_DECIMALS: u8 = 30;
+
+// u128转换为u64，溢出时返回错误
+pub fn to_u64(value: u128) -> Result<u64> {
+    u64::try_from(value).map_err(|_| DexError::ArithmeticOverflow.into())
+}
+
+// U256转换为u128，溢出时返回错误
+fn to_u128(value: U256) -> Result<u128> {
+    require!(value <= U256::from(u128::MAX), DexError::ArithmeticOverflow);
+    Ok(value.as_u128())
+}
+
+// 向下取整的乘除: floor(a * b / denominator)，中间结果使用U256
+pub fn mul_div_floor(a: u128, b: u128, denominator: u128) -> Result<u128> {
+    require!(denominator > 0, DexError::ArithmeticOverflow);
+    to_u128(U256::from(a) * U256::from(b) / U256::from(denominator))
+}
+
+// 向上取整的乘除: ceil(a * b / denominator)，中间结果使用U256
+pub fn mul_div_ceil(a: u128, b: u128, denominator: u128) -> Result<u128> {
+    require!(denominator > 0, DexError::ArithmeticOverflow);
+    let denominator = U256::from(denominator);
+    let product = U256::from(a) * U256::from(b);
+    to_u128((product + denominator - U256::one()) / denominator)
+}
+
+// 向上取整的除法: ceil(numerator / denominator)
+pub fn ceil_div(numerator: u128, denominator: u128) -> Result<u128> {
+    mul_div_ceil(numerator, 1, denominator)
+}
+
+// U512转换为u128，溢出时返回错误
+pub fn u512_to_u128(value: U512) -> Result<u128> {
+    require!(value <= U512::from(u128::MAX), DexError::ArithmeticOverflow);
+    Ok(value.low_u128())
+}
+
+// 向上取整的除法（U512）: ceil(numerator / denominator)
+pub fn ceil_div_u512(numerator: U512, denominator: U512) -> Result<U512> {
+    require!(!denominator.is_zero(), DexError::ArithmeticOverflow);
+    let (quotient, remainder) = numerator.div_mod(denominator);
+    Ok(if remainder.is_zero() { quotient } else { quotient + U512::one() })
+}
+
+// 按费率计算手续费，向上取整: ceil(amount * fee_rate / fee_denominator)
+// 各DEX的手续费均向上取整，费率的分母各不相同（基点为10000，Raydium为1000000）
+pub fn fee_ceil(amount: u64, fee_rate: u64, fee_denominator: u64) -> Result<u64> {
+    to_u64(mul_div_ceil(amount as u128, fee_rate as u128, fee_denominator as u128)?)
+}
+
+// 价格影响 = 1 - smaller / larger，以FEE_DENOMINATOR为分母
+pub fn price_impact(smaller: u128, larger: u128) -> Result<u64> {
+    if larger == 0 || smaller >= larger {
+        return Ok(0);
+    }
+    
+    to_u64(mul_div_floor(larger - smaller, FEE_DENOMINATOR as u128, larger)?)
+}
+
+// 10的decimals次方
+fn pow10(decimals: u8) -> Result<U256> {
+    require!(decimals <= MAX_DECIMALS, DexError::InvalidArgument);
+    Ok(U256::exp10(decimals as usize))
+}
+
+// 定点价格: 每1个完整单位的输入代币可兑换的输出代币完整单位数量，以Price::SCALE放大
+// 同时记录两侧代币的小数位数，与最小单位数量互相换算时自动处理小数位差异
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Price {
+    pub value: u128,
+    pub input_decimals: u8,
+    pub output_decimals: u8,
+}
+
+impl Price {
+    // 价格精度（18位小数）
+    pub const SCALE: u128 = 1_000_000_000_000_000_000;
+    
+    // 由一次报价的输入输出数量（最小单位）计算价格
+    // value = amount_out * 10^input_decimals * SCALE / (amount_in * 10^output_decimals)
+    pub fn from_amounts(
+        amount_in: u64,
+        input_decimals: u8,
+        amount_out: u64,
+        output_decimals: u8,
+    ) -> Result<Self> {
+        require!(amount_in > 0, DexError::InvalidArgument);
+        
+        let numerator = U256::from(amount_out)
+            .checked_mul(pow10(input_decimals)?)
+            .and_then(|value| value.checked_mul(U256::from(Self::SCALE)))
+            .ok_or(DexError::ArithmeticOverflow)?;
+        let denominator = U256::from(amount_in)
+            .checked_mul(pow10(output_decimals)?)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        
+        Ok(Price {
+            value: to_u128(numerator / denominator)?,
+            input_decimals,
+            output_decimals,
+        })
+    }
+    
+    // 按该价格计算输入数量（最小单位）可兑换的输出数量（最小单位），向下取整
+    pub fn amount_out(&self, amount_in: u64) -> Result<u64> {
+        let output_scale = pow10(self.output_decimals)?;
+        let numerator = U256::from(amount_in)
+            .checked_mul(U256::from(self.value))
+            .and_then(|value| value.checked_mul(output_scale))
+            .ok_or(DexError::ArithmeticOverflow)?;
+        let denominator = U256::from(Self::SCALE)
+            .checked_mul(pow10(self.input_decimals)?)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        
+        to_u64(to_u128(numerator / denominator)?)
+    }
+    
+    // 按百万分比折减价格（用于预留滑点和费用），向下取整
+    pub fn discounted(&self, discount: u64) -> Result<Self> {
+        require!(discount <= FEE_DENOMINATOR, DexError::InvalidArgument);
+        
+        Ok(Price {
+            value: mul_div_floor(
+                self.value,
+                (FEE_DENOMINATOR - discount) as u128,
+                FEE_DENOMINATOR as u128,
+            )?,
+            ..*self
+        })
+    }
+    
+    // 以浮点数表示的价格（仅用于日志）
+    pub fn as_f64(&self) -> f64 {
+        self.value as f64 / Self::SCALE as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    
+    // 用U256计算精确的乘积与商，作为对照
+    fn exact_quotient(a: u128, b: u128, denominator: u128) -> (U256, U256) {
+        let product = U256::from(a) * U256::from(b);
+        let denominator = U256::from(denominator);
+        (product / denominator, product % denominator)
+    }
+    
+    #[test]
+    fn mul_div_handles_extreme_values() {
+        assert_eq!(mul_div_floor(u128::MAX, u128::MAX, u128::MAX).unwrap(), u128::MAX);
+        assert_eq!(mul_div_ceil(u128::MAX, u128::MAX, u128::MAX).unwrap(), u128::MAX);
+        assert_eq!(mul_div_floor(u128::MAX, 1, 2).unwrap(), u128::MAX / 2);
+        assert_eq!(mul_div_ceil(u128::MAX, 1, 2).unwrap(), u128::MAX / 2 + 1);
+        assert_eq!(mul_div_ceil(0, u128::MAX, 1).unwrap(), 0);
+        
+        // 结果超过u128时返回错误而不是截断
+        assert!(mul_div_floor(u128::MAX, 2, 1).is_err());
+        assert!(mul_div_ceil(u128::MAX, u128::MAX, u128::MAX - 1).is_err());
+        // 分母为0时返回错误
+        assert!(mul_div_floor(1, 1, 0).is_err());
+        assert!(mul_div_ceil(1, 1, 0).is_err());
+    }
+    
+    #[test]
+    fn price_round_trips_across_decimals() {
+        // 1 SOL = 150 USDC
+        let price = Price::from_amounts(1_000_000_000, SOL_DECIMALS, 150_000_000, 6).unwrap();
+        assert_eq!(price.value, 150 * Price::SCALE);
+        assert_eq!(price.amount_out(2_000_000_000).unwrap(), 300_000_000);
+        
+        // 超过MAX_DECIMALS的小数位数被拒绝
+        assert!(Price::from_amounts(1, MAX_DECIMALS + 1, 1, 0).is_err());
+        assert!(Price::from_amounts(0, 9, 1, 9).is_err());
+    }
+    
+    proptest! {
+        // 向下取整: floor * d <= a * b < (floor + 1) * d；结果不超过u128时不会返回错误
+        #[test]
+        fn mul_div_floor_is_exact(a in any::<u128>(), b in any::<u128>(), denominator in 1..=u128::MAX) {
+            let (quotient, _) = exact_quotient(a, b, denominator);
+            match mul_div_floor(a, b, denominator) {
+                Ok(value) => prop_assert_eq!(U256::from(value), quotient),
+                Err(_) => prop_assert!(quotient > U256::from(u128::MAX)),
+            }
+        }
+        
+        // 向上取整: 整除时等于向下取整，否则大1
+        #[test]
+        fn mul_div_ceil_is_exact(a in any::<u128>(), b in any::<u128>(), denominator in 1..=u128::MAX) {
+            let (quotient, remainder) = exact_quotient(a, b, denominator);
+            let expected = if remainder.is_zero() { quotient } else { quotient + U256::one() };
+            match mul_div_ceil(a, b, denominator) {
+                Ok(value) => prop_assert_eq!(U256::from(value), expected),
+                Err(_) => prop_assert!(expected > U256::from(u128::MAX)),
+            }
+        }
+        
+        // u64范围的输入（所有报价的使用场景）乘积不超过u128，不会溢出
+        #[test]
+        fn mul_div_never_overflows_for_u64_inputs(a in any::<u64>(), b in any::<u64>(), denominator in 1..=u64::MAX) {
+            prop_assert!(mul_div_floor(a as u128, b as u128, denominator as u128).is_ok());
+            prop_assert!(mul_div_ceil(a as u128, b as u128, denominator as u128).is_ok());
+        }
+        
+        // 对a单调不减，对分母单调不增
+        #[test]
+        fn mul_div_is_monotonic(
+            a in any::<u64>(),
+            delta in any::<u64>(),
+            b in any::<u64>(),
+            denominator in 1..=u64::MAX,
+            denominator_delta in any::<u64>(),
+        ) {
+            let (a, b, denominator) = (a as u128, b as u128, denominator as u128);
+            let larger_a = a + delta as u128;
+            let larger_denominator = denominator + denominator_delta as u128;
+            
+            prop_assert!(mul_div_floor(a, b, denominator).unwrap() <= mul_div_floor(larger_a, b, denominator).unwrap());
+            prop_assert!(mul_div_ceil(a, b, denominator).unwrap() <= mul_div_ceil(larger_a, b, denominator).unwrap());
+            prop_assert!(mul_div_floor(a, b, larger_denominator).unwrap() <= mul_div_floor(a, b, denominator).unwrap());
+            prop_assert!(mul_div_ceil(a, b, larger_denominator).unwrap() <= mul_div_ceil(a, b, denominator).unwrap());
+        }
+        
+        // 手续费向上取整，且不超过数量本身（费率不超过分母时）
+        #[test]
+        fn fee_ceil_rounds_up_and_is_bounded(amount in any::<u64>(), fee_rate in 0..=1_000_000u64) {
+            let fee = fee_ceil(amount, fee_rate, 1_000_000).unwrap();
+            prop_assert!(fee as u128 * 1_000_000 >= amount as u128 * fee_rate as u128);
+            prop_assert!(fee <= amount);
+        }
+        
+        // 价格影响在[0, FEE_DENOMINATOR]之内
+        #[test]
+        fn price_impact_is_bounded(smaller in any::<u128>(), larger in any::<u128>()) {
+            prop_assert!(price_impact(smaller, larger).unwrap() <= FEE_DENOMINATOR);
+        }
+        
+        // 任意u64数量和合法的小数位数都不会panic；输出越多价格越高，输入越多价格越低
+        #[test]
+        fn price_is_monotonic_and_never_panics(
+            amount_in in 1..=u64::MAX,
+            amount_out in any::<u64>(),
+            delta in any::<u64>(),
+            input_decimals in 0..=MAX_DECIMALS,
+            output_decimals in 0..=MAX_DECIMALS,
+        ) {
+            let price = Price::from_amounts(amount_in, input_decimals, amount_out, output_decimals);
+            let more_out = Price::from_amounts(amount_in, input_decimals, amount_out.saturating_add(delta), output_decimals);
+            let more_in = Price::from_amounts(amount_in.saturating_add(delta), input_decimals, amount_out, output_decimals);
+            
+            if let (Ok(price), Ok(more_out)) = (&price, &more_out) {
+                prop_assert!(price.value <= more_out.value);
+            }
+            if let (Ok(price), Ok(more_in)) = (&price, &more_in) {
+                prop_assert!(more_in.value <= price.value);
+            }
+            // 价格向下取整，按价格换算回的输出不超过原始输出
+            if let Ok(price) = price {
+                if let Ok(converted) = price.amount_out(amount_in) {
+                    prop_assert!(converted <= amount_out);
+                }
+            }
+        }
+    }
+}
@@ -4,8 +4,8 @@ use solana_program::{
     pubkey::Pubkey,
 };
 use crate::{
-    DexError, DexType, MIN_COMMITMENT_DELAY, MAX_COMMITMENT_DELAY, COMMITMENT_EXPIRY,
-    router, raydium, pumpfun
+    DexError, DexType, RouteVenue, SwapMode, TradeInstruction, MIN_COMMITMENT_DELAY, MAX_COMMITMENT_DELAY,
+    COMMITMENT_EXPIRY, router
 };
 
 // 交易承诺账户
@@ -77,9 +77,6 @@ pub struct ExecuteCommitment<'info> {
     )]
     pub commitment: Account<'info, TradeCommitment>,
     
-    // 智能交易所需的账户
-    pub smart_trade_ctx: router::SmartTradeContext<'info>,
-    
     // 系统程序
     pub system_program: Program<'info, System>,
 }
@@ -100,9 +97,6 @@ pub struct BatchExecuteCommitment<'info> {
     
     // 系统程序
     pub system_program: Program<'info, System>,
-    
-    // 智能交易所需的账户
-    pub smart_trade_ctx: router::SmartTradeContext<'info>,
 }
 
 // 批量执行承诺交易的参数
@@ -122,6 +116,8 @@ pub struct CommitmentExecutionParams {
     pub dex_type: DexType,
     // 随机数
     pub nonce: [u8; 32],
+    // 候选DEX及其剩余账户的结构，见TradeInstruction
+    pub venues: Vec<RouteVenue>,
 }
 
 // 检查承诺过期的上下文
@@ -242,15 +238,25 @@ pub fn create_commitment(
 }
 
 // 执行承诺交易
-pub fn execute_commitment(
-    ctx: Context<ExecuteCommitment>,
-    token_mint: Pubkey,
-    amount_in: u64,
-    min_amount_out: u64,
-    is_buy: bool,
-    dex_type: DexType,
-    nonce: [u8; 32],
+// 剩余账户: 交易所用DEX的账户，见router::execute_trade
+pub fn execute_commitment<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ExecuteCommitment<'info>>,
+    params: CommitmentExecutionParams,
 ) -> Result<()> {
+    let CommitmentExecutionParams {
+        commitment_address,
+        token_mint,
+        amount_in,
+        min_amount_out,
+        is_buy,
+        dex_type,
+        nonce,
+        venues,
+    } = params;
+    
+    // 承诺账户必须与参数中的承诺账户地址一致
+    require_keys_eq!(commitment_address, ctx.accounts.commitment.key(), DexError::InvalidArgument);
+    
     // 获取当前区块
     let clock = Clock::get()?;
     let current_slot = clock.slot;
@@ -299,51 +305,21 @@ pub fn execute_commitment(
         if is_buy { "买入" } else { "卖出" }
     );
     
-    // 根据DEX类型执行交易
-    match dex_type {
-        DexType::Auto => {
-            // 使用智能路由
-            router::smart_trade(
-                ctx.accounts.smart_trade_ctx.into(),
-                token_mint,
-                amount_in,
-                min_amount_out,
-                is_buy,
-            )?;
-        },
-        DexType::PumpFun => {
-            // 直接使用Pump.fun
-            if is_buy {
-                pumpfun::buy_token(
-                    ctx.accounts.smart_trade_ctx.pump_trade_ctx.into(),
-                    amount_in,
-                    min_amount_out,
-                )?;
-            } else {
-                pumpfun::sell_token(
-                    ctx.accounts.smart_trade_ctx.pump_trade_ctx.into(),
-                    amount_in,
-                    min_amount_out,
-                )?;
-            }
-        },
-        DexType::Raydium => {
-            // 直接使用Raydium
-            if is_buy {
-                raydium::buy_token(
-                    ctx.accounts.smart_trade_ctx.raydium_trade_ctx.into(),
-                    amount_in,
-                    min_amount_out,
-                )?;
-            } else {
-                raydium::sell_token(
-                    ctx.accounts.smart_trade_ctx.raydium_trade_ctx.into(),
-                    amount_in,
-                    min_amount_out,
-                )?;
-            }
-        },
-    }
+    // 执行交易
+    let instruction = TradeInstruction {
+        token_mint,
+        amount_in,
+        min_amount_out,
+        is_buy,
+        dex_type: dex_type.clone(),
+        swap_mode: SwapMode::ExactIn,
+        venues,
+    };
+    let mut remaining_accounts = ctx.remaining_accounts;
+    router::execute_trade(ctx.accounts.user.key(), &instruction, &mut remaining_accounts)?;
+    
+    // 剩余账户必须恰好为交易所用DEX的账户
+    require!(remaining_accounts.is_empty(), DexError::InvalidRoute);
     
     // 发出承诺执行事件
     emit!(CommitmentExecuted {
@@ -361,8 +337,9 @@ pub fn execute_commitment(
 }
 
 // 批量执行承诺交易
-pub fn batch_execute_commitments(
-    ctx: Context<BatchExecuteCommitment>,
+// 剩余账户: 依次为各承诺账户，随后依次为各承诺交易所用DEX的账户（见router::execute_trade）
+pub fn batch_execute_commitments<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BatchExecuteCommitment<'info>>,
     params: Vec<CommitmentExecutionParams>,
 ) -> Result<()> {
     // 验证参数数量
@@ -374,6 +351,10 @@ pub fn batch_execute_commitments(
     let current_slot = clock.slot;
     let current_timestamp = clock.unix_timestamp;
     
+    // 承诺账户之后的剩余账户为各承诺交易所用DEX的账户
+    let mut trade_accounts = ctx.remaining_accounts.get(params.len()..)
+        .ok_or(DexError::InvalidArgument)?;
+    
     // 记录批量执行开始
     msg!("开始批量执行 {} 个承诺交易", params.len());
     
@@ -431,51 +412,17 @@ pub fn batch_execute_commitments(
             if param.is_buy { "买入" } else { "卖出" }
         );
         
-        // 根据DEX类型执行交易
-        match param.dex_type {
-            DexType::Auto => {
-                // 使用智能路由
-                router::smart_trade(
-                    ctx.accounts.smart_trade_ctx.into(),
-                    param.token_mint,
-                    param.amount_in,
-                    param.min_amount_out,
-                    param.is_buy,
-                )?;
-            },
-            DexType::PumpFun => {
-                // 直接使用Pump.fun
-                if param.is_buy {
-                    pumpfun::buy_token(
-                        ctx.accounts.smart_trade_ctx.pump_trade_ctx.into(),
-                        param.amount_in,
-                        param.min_amount_out,
-                    )?;
-                } else {
-                    pumpfun::sell_token(
-                        ctx.accounts.smart_trade_ctx.pump_trade_ctx.into(),
-                        param.amount_in,
-                        param.min_amount_out,
-                    )?;
-                }
-            },
-            DexType::Raydium => {
-                // 直接使用Raydium
-                if param.is_buy {
-                    raydium::buy_token(
-                        ctx.accounts.smart_trade_ctx.raydium_trade_ctx.into(),
-                        param.amount_in,
-                        param.min_amount_out,
-                    )?;
-                } else {
-                    raydium::sell_token(
-                        ctx.accounts.smart_trade_ctx.raydium_trade_ctx.into(),
-                        param.amount_in,
-                        param.min_amount_out,
-                    )?;
-                }
-            },
-        }
+        // 执行交易
+        let instruction = TradeInstruction {
+            token_mint: param.token_mint,
+            amount_in: param.amount_in,
+            min_amount_out: param.min_amount_out,
+            is_buy: param.is_buy,
+            dex_type: param.dex_type.clone(),
+            swap_mode: SwapMode::ExactIn,
+            venues: param.venues.clone(),
+        };
+        router::execute_trade(ctx.accounts.user.key(), &instruction, &mut trade_accounts)?;
         
         // 发出承诺执行事件
         emit!(CommitmentExecuted {
@@ -490,6 +437,9 @@ pub fn batch_execute_commitments(
         });
     }
     
+    // 剩余账户必须恰好为各承诺交易所用DEX的账户
+    require!(trade_accounts.is_empty(), DexError::InvalidRoute);
+    
     // 记录批量执行完成
     msg!("批量执行 {} 个承诺交易完成", params.len());
     
@@ -515,6 +465,10 @@ fn calculate_commitment_hash(
         DexType::Auto => 0,
         DexType::PumpFun => 1,
         DexType::Raydium => 2,
+        DexType::PumpSwap => 3,
+        DexType::RaydiumAmmV4 => 4,
+        DexType::RaydiumClmm => 5,
+        DexType::RaydiumLaunchLab => 6,
     });
     data.extend_from_slice(&nonce);
     
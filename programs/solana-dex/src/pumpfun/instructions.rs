@@ -0,0 +1,589 @@
+use anchor_lang::prelude::*;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use std::str::FromStr;
+use crate::codec::encode_instruction_data;
+use crate::DexError;
+
+// Pump.fun指令discriminator（Anchor规则: sha256("global:<指令名>")的前8字节）
+pub const BUY_DISCRIMINATOR: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+pub const SELL_DISCRIMINATOR: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+pub const CREATE_DISCRIMINATOR: [u8; 8] = [24, 30, 200, 40, 5, 28, 7, 119];
+
+// Pump.fun事件权限账户（PDA: ["__event_authority"]）
+pub const PUMP_EVENT_AUTHORITY: &str = "Ce6TQqeHC9p8KetsN6JsjHK7UTZk7nasjjnr7XxXp9F1";
+// Metaplex代币元数据程序ID
+pub const MPL_TOKEN_METADATA_PROGRAM_ID: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+// 获取Pump.fun事件权限账户
+pub fn get_pump_event_authority() -> Pubkey {
+    Pubkey::from_str(PUMP_EVENT_AUTHORITY).unwrap()
+}
+
+// 获取Metaplex代币元数据程序ID
+pub fn get_mpl_token_metadata_program_id() -> Pubkey {
+    Pubkey::from_str(MPL_TOKEN_METADATA_PROGRAM_ID).unwrap()
+}
+
+// buy指令参数（精确代币输出）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct BuyArgs {
+    pub amount: u64,
+    pub max_sol_cost: u64,
+}
+
+// sell指令参数
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SellArgs {
+    pub amount: u64,
+    pub min_sol_output: u64,
+}
+
+// create指令参数
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct CreateArgs {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+// Pump.fun buy/sell指令的账户列表版本
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PumpAccountsVersion {
+    // 原始账户列表（buy使用rent，sell使用associated_token_program）
+    Legacy,
+    // 增加creator_vault
+    CreatorVault,
+    // buy额外增加全局/用户交易量累加器
+    VolumeAccumulator,
+    // 额外增加fee_config与fee_program
+    FeeConfig,
+}
+
+// 新版本账户列表中追加的账户
+#[derive(Clone, Default)]
+pub struct VersionedAccounts {
+    pub creator_vault: Option<Pubkey>,
+    pub global_volume_accumulator: Option<Pubkey>,
+    pub user_volume_accumulator: Option<Pubkey>,
+    pub fee_config: Option<Pubkey>,
+    pub fee_program: Option<Pubkey>,
+}
+
+impl VersionedAccounts {
+    // 根据提供的账户推断账户列表版本
+    pub fn version(&self) -> PumpAccountsVersion {
+        if self.creator_vault.is_none() {
+            PumpAccountsVersion::Legacy
+        } else if self.global_volume_accumulator.is_none() || self.user_volume_accumulator.is_none() {
+            PumpAccountsVersion::CreatorVault
+        } else if self.fee_config.is_none() || self.fee_program.is_none() {
+            PumpAccountsVersion::VolumeAccumulator
+        } else {
+            PumpAccountsVersion::FeeConfig
+        }
+    }
+}
+
+// 获取指定版本所需的可选账户
+fn required(account: Option<Pubkey>) -> Result<Pubkey> {
+    account.ok_or(error!(DexError::MissingPumpAccount))
+}
+
+// buy指令账户（顺序与Pump.fun IDL一致）
+pub struct BuyAccounts {
+    pub global: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub associated_bonding_curve: Pubkey,
+    pub associated_user: Pubkey,
+    pub user: Pubkey,
+    pub system_program: Pubkey,
+    pub token_program: Pubkey,
+    pub rent: Pubkey,
+    pub event_authority: Pubkey,
+    pub program: Pubkey,
+    pub versioned: VersionedAccounts,
+}
+
+impl BuyAccounts {
+    pub fn to_account_metas(&self, version: PumpAccountsVersion) -> Result<Vec<AccountMeta>> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.global, false),
+            AccountMeta::new(self.fee_recipient, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new(self.bonding_curve, false),
+            AccountMeta::new(self.associated_bonding_curve, false),
+            AccountMeta::new(self.associated_user, false),
+            AccountMeta::new(self.user, true),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        
+        // 新版本中rent的位置被creator_vault取代
+        if version == PumpAccountsVersion::Legacy {
+            metas.push(AccountMeta::new_readonly(self.rent, false));
+        } else {
+            metas.push(AccountMeta::new(required(self.versioned.creator_vault)?, false));
+        }
+        
+        metas.push(AccountMeta::new_readonly(self.event_authority, false));
+        metas.push(AccountMeta::new_readonly(self.program, false));
+        
+        if version >= PumpAccountsVersion::VolumeAccumulator {
+            metas.push(AccountMeta::new(required(self.versioned.global_volume_accumulator)?, false));
+            metas.push(AccountMeta::new(required(self.versioned.user_volume_accumulator)?, false));
+        }
+        
+        if version >= PumpAccountsVersion::FeeConfig {
+            metas.push(AccountMeta::new_readonly(required(self.versioned.fee_config)?, false));
+            metas.push(AccountMeta::new_readonly(required(self.versioned.fee_program)?, false));
+        }
+        
+        Ok(metas)
+    }
+}
+
+// sell指令账户（顺序与Pump.fun IDL一致）
+pub struct SellAccounts {
+    pub global: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub associated_bonding_curve: Pubkey,
+    pub associated_user: Pubkey,
+    pub user: Pubkey,
+    pub system_program: Pubkey,
+    pub associated_token_program: Pubkey,
+    pub token_program: Pubkey,
+    pub event_authority: Pubkey,
+    pub program: Pubkey,
+    pub versioned: VersionedAccounts,
+}
+
+impl SellAccounts {
+    pub fn to_account_metas(&self, version: PumpAccountsVersion) -> Result<Vec<AccountMeta>> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.global, false),
+            AccountMeta::new(self.fee_recipient, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new(self.bonding_curve, false),
+            AccountMeta::new(self.associated_bonding_curve, false),
+            AccountMeta::new(self.associated_user, false),
+            AccountMeta::new(self.user, true),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        
+        // 新版本中associated_token_program的位置被creator_vault取代
+        if version == PumpAccountsVersion::Legacy {
+            metas.push(AccountMeta::new_readonly(self.associated_token_program, false));
+        } else {
+            metas.push(AccountMeta::new(required(self.versioned.creator_vault)?, false));
+        }
+        
+        metas.push(AccountMeta::new_readonly(self.token_program, false));
+        metas.push(AccountMeta::new_readonly(self.event_authority, false));
+        metas.push(AccountMeta::new_readonly(self.program, false));
+        
+        // sell不使用交易量累加器
+        if version >= PumpAccountsVersion::FeeConfig {
+            metas.push(AccountMeta::new_readonly(required(self.versioned.fee_config)?, false));
+            metas.push(AccountMeta::new_readonly(required(self.versioned.fee_program)?, false));
+        }
+        
+        Ok(metas)
+    }
+}
+
+// create指令账户（顺序与Pump.fun IDL一致）
+pub struct CreateAccounts {
+    pub mint: Pubkey,
+    pub mint_authority: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub associated_bonding_curve: Pubkey,
+    pub global: Pubkey,
+    pub mpl_token_metadata: Pubkey,
+    pub metadata: Pubkey,
+    pub user: Pubkey,
+    pub system_program: Pubkey,
+    pub token_program: Pubkey,
+    pub associated_token_program: Pubkey,
+    pub rent: Pubkey,
+    pub event_authority: Pubkey,
+    pub program: Pubkey,
+}
+
+impl CreateAccounts {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.mint, true),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.bonding_curve, false),
+            AccountMeta::new(self.associated_bonding_curve, false),
+            AccountMeta::new_readonly(self.global, false),
+            AccountMeta::new_readonly(self.mpl_token_metadata, false),
+            AccountMeta::new(self.metadata, false),
+            AccountMeta::new(self.user, true),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.associated_token_program, false),
+            AccountMeta::new_readonly(self.rent, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new_readonly(self.program, false),
+        ]
+    }
+}
+
+// 编码buy指令数据
+pub fn encode_buy_data(args: &BuyArgs) -> Vec<u8> {
+    encode_instruction_data(&BUY_DISCRIMINATOR, args)
+}
+
+// 编码sell指令数据
+pub fn encode_sell_data(args: &SellArgs) -> Vec<u8> {
+    encode_instruction_data(&SELL_DISCRIMINATOR, args)
+}
+
+// 编码create指令数据
+pub fn encode_create_data(args: &CreateArgs) -> Vec<u8> {
+    encode_instruction_data(&CREATE_DISCRIMINATOR, args)
+}
+
+// 构建buy指令
+pub fn buy(
+    program_id: Pubkey,
+    accounts: &BuyAccounts,
+    args: &BuyArgs,
+    version: PumpAccountsVersion,
+) -> Result<Instruction> {
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(version)?,
+        data: encode_buy_data(args),
+    })
+}
+
+// 构建sell指令
+pub fn sell(
+    program_id: Pubkey,
+    accounts: &SellAccounts,
+    args: &SellArgs,
+    version: PumpAccountsVersion,
+) -> Result<Instruction> {
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(version)?,
+        data: encode_sell_data(args),
+    })
+}
+
+// 构建create指令
+pub fn create(program_id: Pubkey, accounts: &CreateAccounts, args: &CreateArgs) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data: encode_create_data(args),
+    }
+}
+
+// 计算绑定曲线PDA: ["bonding-curve", mint]
+pub fn get_bonding_curve_pda(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], program_id)
+}
+
+// 计算铸币权限PDA: ["mint-authority"]
+pub fn get_mint_authority_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"mint-authority"], program_id)
+}
+
+// 计算全局状态PDA: ["global"]
+pub fn get_global_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"global"], program_id)
+}
+
+// 计算代币元数据PDA: ["metadata", mpl_program, mint]
+pub fn get_metadata_pda(mint: &Pubkey) -> (Pubkey, u8) {
+    let mpl_program_id = get_mpl_token_metadata_program_id();
+    Pubkey::find_program_address(
+        &[b"metadata", mpl_program_id.as_ref(), mint.as_ref()],
+        &mpl_program_id,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // 指令数据的期望字节按Pump.fun IDL中的discriminator和Borsh布局（整数小端、字符串u32长度前缀）逐字节写出，
+    // 不经过被测的编码函数和常量；离线环境没有可用的主网交易数据，未与链上交易逐字节比对
+    
+    fn versioned_accounts() -> VersionedAccounts {
+        VersionedAccounts {
+            creator_vault: Some(Pubkey::new_unique()),
+            global_volume_accumulator: Some(Pubkey::new_unique()),
+            user_volume_accumulator: Some(Pubkey::new_unique()),
+            fee_config: Some(Pubkey::new_unique()),
+            fee_program: Some(Pubkey::new_unique()),
+        }
+    }
+    
+    fn buy_accounts(versioned: VersionedAccounts) -> BuyAccounts {
+        BuyAccounts {
+            global: Pubkey::new_unique(),
+            fee_recipient: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            bonding_curve: Pubkey::new_unique(),
+            associated_bonding_curve: Pubkey::new_unique(),
+            associated_user: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            system_program: Pubkey::new_unique(),
+            token_program: Pubkey::new_unique(),
+            rent: Pubkey::new_unique(),
+            event_authority: Pubkey::new_unique(),
+            program: Pubkey::new_unique(),
+            versioned,
+        }
+    }
+    
+    fn sell_accounts(versioned: VersionedAccounts) -> SellAccounts {
+        SellAccounts {
+            global: Pubkey::new_unique(),
+            fee_recipient: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            bonding_curve: Pubkey::new_unique(),
+            associated_bonding_curve: Pubkey::new_unique(),
+            associated_user: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            system_program: Pubkey::new_unique(),
+            associated_token_program: Pubkey::new_unique(),
+            token_program: Pubkey::new_unique(),
+            event_authority: Pubkey::new_unique(),
+            program: Pubkey::new_unique(),
+            versioned,
+        }
+    }
+    
+    #[test]
+    fn discriminators_match_idl() {
+        // Pump.fun IDL中instructions[].discriminator字段的值
+        assert_eq!(BUY_DISCRIMINATOR, [102, 6, 61, 18, 1, 218, 235, 234]);
+        assert_eq!(SELL_DISCRIMINATOR, [51, 230, 133, 164, 1, 127, 131, 173]);
+        assert_eq!(CREATE_DISCRIMINATOR, [24, 30, 200, 40, 5, 28, 7, 119]);
+    }
+    
+    #[test]
+    fn pump_program_addresses_match_mainnet() {
+        let program_id = crate::pumpfun::get_pump_program_id();
+        
+        let (event_authority, _) = Pubkey::find_program_address(&[b"__event_authority"], &program_id);
+        assert_eq!(event_authority, get_pump_event_authority());
+        
+        assert_eq!(
+            get_global_pda(&program_id).0,
+            Pubkey::from_str("4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf").unwrap()
+        );
+        assert_eq!(
+            get_mint_authority_pda(&program_id).0,
+            Pubkey::from_str("TSLvdd1pWpHVjahSpsvCXUbgwsL3JAcvokwaKt1eokM").unwrap()
+        );
+    }
+    
+    #[test]
+    fn buy_data_golden_bytes() {
+        // buy(amount = 1_000_000_000_000 = 0xE8_D4A5_1000, max_sol_cost = 101_000_000 = 0x0605_2340)
+        let data = encode_buy_data(&BuyArgs {
+            amount: 1_000_000_000_000,
+            max_sol_cost: 101_000_000,
+        });
+        assert_eq!(
+            data,
+            vec![
+                102, 6, 61, 18, 1, 218, 235, 234,
+                0, 16, 165, 212, 232, 0, 0, 0,
+                64, 35, 5, 6, 0, 0, 0, 0,
+            ]
+        );
+    }
+    
+    #[test]
+    fn sell_data_golden_bytes() {
+        // sell(amount = 500_000_000_000 = 0x74_6A52_8800, min_sol_output = 12_345_678 = 0xBC_614E)
+        let data = encode_sell_data(&SellArgs {
+            amount: 500_000_000_000,
+            min_sol_output: 12_345_678,
+        });
+        assert_eq!(
+            data,
+            vec![
+                51, 230, 133, 164, 1, 127, 131, 173,
+                0, 136, 82, 106, 116, 0, 0, 0,
+                78, 97, 188, 0, 0, 0, 0, 0,
+            ]
+        );
+    }
+    
+    #[test]
+    fn create_data_golden_bytes() {
+        // Borsh字符串: u32小端长度 + UTF-8字节
+        let data = encode_create_data(&CreateArgs {
+            name: "Pepe".to_string(),
+            symbol: "PEPE".to_string(),
+            uri: "ipfs://Qm".to_string(),
+        });
+        assert_eq!(
+            data,
+            vec![
+                24, 30, 200, 40, 5, 28, 7, 119,
+                4, 0, 0, 0, b'P', b'e', b'p', b'e',
+                4, 0, 0, 0, b'P', b'E', b'P', b'E',
+                9, 0, 0, 0, b'i', b'p', b'f', b's', b':', b'/', b'/', b'Q', b'm',
+            ]
+        );
+    }
+    
+    #[test]
+    fn buy_account_metas_follow_idl_order() {
+        let accounts = buy_accounts(versioned_accounts());
+        let versioned = &accounts.versioned;
+        let base = vec![
+            AccountMeta::new_readonly(accounts.global, false),
+            AccountMeta::new(accounts.fee_recipient, false),
+            AccountMeta::new_readonly(accounts.mint, false),
+            AccountMeta::new(accounts.bonding_curve, false),
+            AccountMeta::new(accounts.associated_bonding_curve, false),
+            AccountMeta::new(accounts.associated_user, false),
+            AccountMeta::new(accounts.user, true),
+            AccountMeta::new_readonly(accounts.system_program, false),
+            AccountMeta::new_readonly(accounts.token_program, false),
+        ];
+        let tail = vec![
+            AccountMeta::new_readonly(accounts.event_authority, false),
+            AccountMeta::new_readonly(accounts.program, false),
+        ];
+        
+        let mut legacy = base.clone();
+        legacy.push(AccountMeta::new_readonly(accounts.rent, false));
+        legacy.extend(tail.clone());
+        assert_eq!(accounts.to_account_metas(PumpAccountsVersion::Legacy).unwrap(), legacy);
+        
+        let mut creator_vault = base;
+        creator_vault.push(AccountMeta::new(versioned.creator_vault.unwrap(), false));
+        creator_vault.extend(tail);
+        assert_eq!(accounts.to_account_metas(PumpAccountsVersion::CreatorVault).unwrap(), creator_vault);
+        
+        let mut volume_accumulator = creator_vault;
+        volume_accumulator.push(AccountMeta::new(versioned.global_volume_accumulator.unwrap(), false));
+        volume_accumulator.push(AccountMeta::new(versioned.user_volume_accumulator.unwrap(), false));
+        assert_eq!(
+            accounts.to_account_metas(PumpAccountsVersion::VolumeAccumulator).unwrap(),
+            volume_accumulator
+        );
+        
+        let mut fee_config = volume_accumulator;
+        fee_config.push(AccountMeta::new_readonly(versioned.fee_config.unwrap(), false));
+        fee_config.push(AccountMeta::new_readonly(versioned.fee_program.unwrap(), false));
+        assert_eq!(accounts.to_account_metas(PumpAccountsVersion::FeeConfig).unwrap(), fee_config);
+        assert_eq!(fee_config.len(), 16);
+    }
+    
+    #[test]
+    fn sell_account_metas_follow_idl_order() {
+        let accounts = sell_accounts(versioned_accounts());
+        let versioned = &accounts.versioned;
+        let base = vec![
+            AccountMeta::new_readonly(accounts.global, false),
+            AccountMeta::new(accounts.fee_recipient, false),
+            AccountMeta::new_readonly(accounts.mint, false),
+            AccountMeta::new(accounts.bonding_curve, false),
+            AccountMeta::new(accounts.associated_bonding_curve, false),
+            AccountMeta::new(accounts.associated_user, false),
+            AccountMeta::new(accounts.user, true),
+            AccountMeta::new_readonly(accounts.system_program, false),
+        ];
+        let tail = vec![
+            AccountMeta::new_readonly(accounts.token_program, false),
+            AccountMeta::new_readonly(accounts.event_authority, false),
+            AccountMeta::new_readonly(accounts.program, false),
+        ];
+        
+        let mut legacy = base.clone();
+        legacy.push(AccountMeta::new_readonly(accounts.associated_token_program, false));
+        legacy.extend(tail.clone());
+        assert_eq!(accounts.to_account_metas(PumpAccountsVersion::Legacy).unwrap(), legacy);
+        
+        // sell不使用交易量累加器，VolumeAccumulator与CreatorVault的账户列表相同
+        let mut creator_vault = base;
+        creator_vault.push(AccountMeta::new(versioned.creator_vault.unwrap(), false));
+        creator_vault.extend(tail);
+        assert_eq!(accounts.to_account_metas(PumpAccountsVersion::CreatorVault).unwrap(), creator_vault);
+        assert_eq!(
+            accounts.to_account_metas(PumpAccountsVersion::VolumeAccumulator).unwrap(),
+            creator_vault
+        );
+        
+        let mut fee_config = creator_vault;
+        fee_config.push(AccountMeta::new_readonly(versioned.fee_config.unwrap(), false));
+        fee_config.push(AccountMeta::new_readonly(versioned.fee_program.unwrap(), false));
+        assert_eq!(accounts.to_account_metas(PumpAccountsVersion::FeeConfig).unwrap(), fee_config);
+        assert_eq!(fee_config.len(), 14);
+    }
+    
+    #[test]
+    fn create_account_metas_follow_idl_order() {
+        let keys: Vec<Pubkey> = (0..14).map(|_| Pubkey::new_unique()).collect();
+        let accounts = CreateAccounts {
+            mint: keys[0],
+            mint_authority: keys[1],
+            bonding_curve: keys[2],
+            associated_bonding_curve: keys[3],
+            global: keys[4],
+            mpl_token_metadata: keys[5],
+            metadata: keys[6],
+            user: keys[7],
+            system_program: keys[8],
+            token_program: keys[9],
+            associated_token_program: keys[10],
+            rent: keys[11],
+            event_authority: keys[12],
+            program: keys[13],
+        };
+        
+        let metas = accounts.to_account_metas();
+        assert_eq!(metas.iter().map(|meta| meta.pubkey).collect::<Vec<_>>(), keys);
+        
+        // 可写账户: mint, bonding_curve, associated_bonding_curve, metadata, user
+        let writable: Vec<usize> = (0..metas.len()).filter(|&i| metas[i].is_writable).collect();
+        assert_eq!(writable, vec![0, 2, 3, 6, 7]);
+        // 签名账户: mint, user
+        let signers: Vec<usize> = (0..metas.len()).filter(|&i| metas[i].is_signer).collect();
+        assert_eq!(signers, vec![0, 7]);
+    }
+    
+    #[test]
+    fn versioned_accounts_infer_version() {
+        let mut versioned = versioned_accounts();
+        assert_eq!(versioned.version(), PumpAccountsVersion::FeeConfig);
+        
+        versioned.fee_program = None;
+        assert_eq!(versioned.version(), PumpAccountsVersion::VolumeAccumulator);
+        
+        versioned.user_volume_accumulator = None;
+        assert_eq!(versioned.version(), PumpAccountsVersion::CreatorVault);
+        
+        versioned.creator_vault = None;
+        assert_eq!(versioned.version(), PumpAccountsVersion::Legacy);
+    }
+    
+    #[test]
+    fn missing_versioned_account_is_rejected() {
+        let mut versioned = versioned_accounts();
+        versioned.fee_config = None;
+        let accounts = buy_accounts(versioned);
+        
+        assert!(accounts.to_account_metas(PumpAccountsVersion::FeeConfig).is_err());
+        assert!(accounts.to_account_metas(PumpAccountsVersion::VolumeAccumulator).is_ok());
+    }
+}
@@ -1,18 +1,40 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, Token, TokenAccount};
+use anchor_spl::token_interface;
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
 use solana_program::{
     program::invoke,
     pubkey::Pubkey,
     system_instruction,
-    instruction::{Instruction, AccountMeta},
 };
 use std::str::FromStr;
+use crate::fill::{BalanceSnapshot, Fill, FillLimits, FillPair};
+use crate::math::{Price, SOL_DECIMALS};
+use crate::router::RouteQuoter;
+use crate::{token_utils, DexError, DexType};
+
+pub mod instructions;
+pub mod quote;
+pub mod state;
+
+pub use state::{BondingCurve, FeeConfig, Global};
 
 // 更新为正确的Pump.fun程序ID
 pub const PUMP_PROGRAM_ID: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
 // 更新为正确的Pump.fun费用账户
 pub const PUMP_FEE_ACCOUNT: &str = "3XMrhbv989VxAMi3DErLV9eJht1pHppW5LbKxe9fkEFR";
 
+// Pump.fun手续费基点分母
+pub const FEE_BASIS_POINTS_DENOMINATOR: u64 = 10_000;
+
+// Pump.fun发行代币的小数位数
+pub const PUMP_TOKEN_DECIMALS: u8 = 6;
+
+// 代币元数据长度限制（与Metaplex一致）
+pub const MAX_NAME_LENGTH: usize = 32;
+pub const MAX_SYMBOL_LENGTH: usize = 10;
+pub const MAX_URI_LENGTH: usize = 200;
+
 // 获取Pump.fun程序ID
 pub fn get_pump_program_id() -> Pubkey {
     Pubkey::from_str(PUMP_PROGRAM_ID).unwrap()
@@ -23,66 +45,553 @@ pub fn get_pump_fee_account() -> Pubkey {
     Pubkey::from_str(PUMP_FEE_ACCOUNT).unwrap()
 }
 
-// 在Pump.fun上购买代币
+// 在Pump.fun上购买代币（精确代币输出）
 pub fn buy_token(
     ctx: Context<TradeToken>,
     amount_out: u64,  // 期望获得的代币数量
     max_sol_cost: u64,  // 最大SOL花费（滑点控制）
-) -> Result<()> {
+    allow_partial_fill: bool,  // 绑定曲线剩余代币不足时是否允许部分成交
+) -> Result<Fill> {
     msg!("在Pump.fun上购买代币: 期望获得 {} 代币, 最大SOL花费: {}", amount_out, max_sol_cost);
     msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
     
-    // 构建Pump.fun的交易数据 - 根据真实交易格式
-    let mut data = vec![0u8; 16];
-    // 将amount_out编码到data中 (期望获得的代币数量)
-    data[0..8].copy_from_slice(&amount_out.to_le_bytes());
-    // 将max_sol_cost编码到data中 (最大SOL花费)
-    data[8..16].copy_from_slice(&max_sol_cost.to_le_bytes());
+    // 检查参数
+    require!(amount_out > 0, DexError::InvalidArgument);
+    require!(max_sol_cost > 0, DexError::InvalidArgument);
     
-    // 获取Pump.fun程序ID
-    let pump_program_id = get_pump_program_id();
+    let (global, bonding_curve) = ctx.accounts.load_pump_state()?;
     
-    // 构建Pump.fun的交易指令 - 根据真实交易格式
-    let swap_ix = Instruction {
-        program_id: pump_program_id,
-        accounts: vec![
-            // 根据真实交易格式添加所需账户
-            AccountMeta::new_readonly(ctx.accounts.global_state.key(), false), // Global state
-            AccountMeta::new(get_pump_fee_account(), false), // Fee recipient
-            AccountMeta::new_readonly(ctx.accounts.token_mint.key(), false), // Mint
-            AccountMeta::new(ctx.accounts.bonding_curve.key(), false), // Bonding curve
-            AccountMeta::new(ctx.accounts.bonding_curve_token_account.key(), false), // Associated bonding curve
-            AccountMeta::new(ctx.accounts.user_token_account.key(), false), // Associated user
-            AccountMeta::new(ctx.accounts.user.key(), true), // User (signer)
-            AccountMeta::new_readonly(ctx.accounts.system_program.key(), false), // System program
-            AccountMeta::new_readonly(ctx.accounts.token_program.key(), false), // Token program
-            AccountMeta::new_readonly(ctx.accounts.rent.key(), false), // Rent
-            AccountMeta::new_readonly(ctx.accounts.event_authority.key(), false), // Event authority
-            AccountMeta::new_readonly(pump_program_id, false), // Program
-        ],
-        data: data,
+    // 购买数量不能超过绑定曲线剩余的真实代币储备
+    let remaining_tokens = bonding_curve.real_token_reserves;
+    let (fill_amount, fill_max_sol_cost) = if amount_out > remaining_tokens {
+        require!(allow_partial_fill, DexError::CurveCapacityExceeded);
+        require!(remaining_tokens > 0, DexError::InsufficientLiquidity);
+        
+        msg!("绑定曲线剩余代币不足，部分成交: {} / {}", remaining_tokens, amount_out);
+        
+        // 按比例缩减最大花费，保持单价滑点限制不变
+        (remaining_tokens, scale_amount(max_sol_cost, remaining_tokens, amount_out)?)
+    } else {
+        (amount_out, max_sol_cost)
     };
     
-    // 执行Pump.fun的交易指令
+    let buy_quote = quote::quote_buy_exact_tokens_out(
+        &bonding_curve,
+        &ctx.accounts.pump_fees(&global, &bonding_curve)?,
+        fill_amount,
+    )?;
+    
+    msg!("预计花费: {}, 手续费: {}", buy_quote.amount_in, buy_quote.fee_amount);
+    
+    // 检查滑点
+    require!(buy_quote.amount_in <= fill_max_sol_cost, DexError::SlippageExceeded);
+    
+    // 用户代币账户不存在时在同一指令中创建
+    ctx.accounts.ensure_user_token_account()?;
+    
+    let snapshot = ctx.accounts.balance_snapshot(true)?;
+    
+    invoke_buy(ctx.accounts, &bonding_curve, fill_amount, fill_max_sol_cost)?;
+    
+    // 按实际余额变化检查成交数量
+    let fill = ctx.accounts.settle_fill(&snapshot, true, &FillLimits {
+        expected_amount_in: buy_quote.amount_in,
+        expected_amount_out: fill_amount,
+        max_amount_in: fill_max_sol_cost,
+        min_amount_out: fill_amount,
+    })?;
+    
+    // 部分成交或绑定曲线完成时发出事件
+    if fill_amount < amount_out || buy_quote.completes_curve {
+        emit!(PumpBuyFilled {
+            user: ctx.accounts.user.key(),
+            token_mint: ctx.accounts.token_mint.key(),
+            filled_amount_out: fill.amount_out,
+            partial_fill: fill_amount < amount_out,
+            sol_budget: max_sol_cost,
+            sol_spent: fill.amount_in,
+            sol_refunded: max_sol_cost.saturating_sub(fill.amount_in),
+            curve_completed: buy_quote.completes_curve,
+            slot: Clock::get()?.slot,
+        });
+    }
+    
+    msg!("交易完成，获得代币");
+    Ok(fill)
+}
+
+// 在Pump.fun上购买代币（精确SOL输入）
+pub fn buy_token_exact_sol_in(
+    ctx: Context<TradeToken>,
+    sol_amount_in: u64,  // 输入的SOL数量（包含手续费）
+    min_amount_out: u64,  // 最小获得的代币数量（滑点控制）
+    allow_partial_fill: bool,  // 绑定曲线剩余代币不足时是否允许部分成交
+) -> Result<Fill> {
+    msg!("在Pump.fun上购买代币: 输入 {} SOL, 最小获得代币数量: {}", sol_amount_in, min_amount_out);
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    // 检查参数
+    require!(sol_amount_in > 0, DexError::InvalidArgument);
+    require!(min_amount_out > 0, DexError::InvalidArgument);
+    
+    // 检查用户是否有足够的SOL
+    require!(ctx.accounts.user.lamports() >= sol_amount_in, DexError::InsufficientFunds);
+    
+    let (global, bonding_curve) = ctx.accounts.load_pump_state()?;
+    
+    // 根据绑定曲线虚拟储备和协议、创建者费率计算可获得的代币数量（不超过剩余真实代币储备）
+    let buy_quote = quote::quote_buy_exact_sol_in(
+        &bonding_curve,
+        &ctx.accounts.pump_fees(&global, &bonding_curve)?,
+        sol_amount_in,
+    )?;
+    let amount_out = buy_quote.net_amount_out;
+    
+    msg!("预计获得代币数量: {}, 预计花费: {}, 手续费: {}", 
+        amount_out, 
+        buy_quote.amount_in, 
+        buy_quote.fee_amount
+    );
+    
+    let required_amount_out = if buy_quote.capped_by_reserves {
+        require!(allow_partial_fill, DexError::CurveCapacityExceeded);
+        
+        msg!("绑定曲线剩余代币不足，部分成交，预计花费: {} / {}", buy_quote.amount_in, sol_amount_in);
+        
+        // 按预计花费比例缩减最小输出，保持单价滑点限制不变
+        scale_amount(min_amount_out, buy_quote.amount_in, sol_amount_in)?
+    } else {
+        min_amount_out
+    };
+    
+    // 最大花费始终为用户的SOL预算，部分成交时Pump.fun只扣除实际花费，
+    // 不以报价的花费作为上限，避免报价与链上取整的差异导致交易失败
+    let max_sol_cost = sol_amount_in;
+    
+    // 检查滑点
+    require!(amount_out >= required_amount_out, DexError::SlippageExceeded);
+    
+    // 用户代币账户不存在时在同一指令中创建
+    ctx.accounts.ensure_user_token_account()?;
+    
+    let snapshot = ctx.accounts.balance_snapshot(true)?;
+    
+    // Pump.fun的buy指令为精确输出，以输入SOL作为最大花费
+    invoke_buy(ctx.accounts, &bonding_curve, amount_out, max_sol_cost)?;
+    
+    // 按实际余额变化检查成交数量
+    let fill = ctx.accounts.settle_fill(&snapshot, true, &FillLimits {
+        expected_amount_in: buy_quote.amount_in,
+        expected_amount_out: amount_out,
+        max_amount_in: max_sol_cost,
+        min_amount_out: required_amount_out,
+    })?;
+    
+    // 部分成交或绑定曲线完成时发出事件，未使用的SOL预算保留在用户账户中
+    if buy_quote.capped_by_reserves || buy_quote.completes_curve {
+        emit!(PumpBuyFilled {
+            user: ctx.accounts.user.key(),
+            token_mint: ctx.accounts.token_mint.key(),
+            filled_amount_out: fill.amount_out,
+            partial_fill: buy_quote.capped_by_reserves,
+            sol_budget: sol_amount_in,
+            sol_spent: fill.amount_in,
+            sol_refunded: sol_amount_in.saturating_sub(fill.amount_in),
+            curve_completed: buy_quote.completes_curve,
+            slot: Clock::get()?.slot,
+        });
+    }
+    
+    msg!("交易完成，获得代币");
+    Ok(fill)
+}
+
+// 按比例缩放数量: amount * numerator / denominator
+fn scale_amount(amount: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    let scaled = (amount as u128)
+        .checked_mul(numerator as u128)
+        .ok_or(DexError::ArithmeticOverflow)?
+        .checked_div(denominator as u128)
+        .ok_or(DexError::ArithmeticOverflow)?;
+    u64::try_from(scaled).map_err(|_| DexError::ArithmeticOverflow.into())
+}
+
+// 调用Pump.fun的buy指令（精确代币输出，最大SOL花费）
+fn invoke_buy<'info>(
+    accounts: &TradeToken<'info>,
+    bonding_curve: &BondingCurve,
+    amount_out: u64,
+    max_sol_cost: u64,
+) -> Result<()> {
+    BuyCpiAccounts {
+        global: accounts.global_state.to_account_info(),
+        fee_recipient: accounts.fee_recipient.to_account_info(),
+        mint: accounts.token_mint.to_account_info(),
+        bonding_curve: accounts.bonding_curve.to_account_info(),
+        associated_bonding_curve: accounts.bonding_curve_token_account.to_account_info(),
+        associated_user: accounts.user_token_account.to_account_info(),
+        user: accounts.user.to_account_info(),
+        system_program: accounts.system_program.to_account_info(),
+        token_program: accounts.token_program.to_account_info(),
+        rent: accounts.rent.to_account_info(),
+        event_authority: accounts.event_authority.to_account_info(),
+        program: accounts.pump_program.to_account_info(),
+        versioned: accounts.versioned_accounts(bonding_curve.creator)?,
+    }
+    .invoke(amount_out, max_sol_cost)
+}
+
+// Pump.fun buy指令CPI所需的账户
+struct BuyCpiAccounts<'info> {
+    global: AccountInfo<'info>,
+    fee_recipient: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    bonding_curve: AccountInfo<'info>,
+    associated_bonding_curve: AccountInfo<'info>,
+    associated_user: AccountInfo<'info>,
+    user: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    rent: AccountInfo<'info>,
+    event_authority: AccountInfo<'info>,
+    program: AccountInfo<'info>,
+    versioned: VersionedCpiAccounts<'info>,
+}
+
+impl<'info> BuyCpiAccounts<'info> {
+    fn invoke(&self, amount_out: u64, max_sol_cost: u64) -> Result<()> {
+        // 构建Pump.fun的buy指令
+        let swap_ix = instructions::buy(
+            get_pump_program_id(),
+            &instructions::BuyAccounts {
+                global: self.global.key(),
+                fee_recipient: self.fee_recipient.key(),
+                mint: self.mint.key(),
+                bonding_curve: self.bonding_curve.key(),
+                associated_bonding_curve: self.associated_bonding_curve.key(),
+                associated_user: self.associated_user.key(),
+                user: self.user.key(),
+                system_program: self.system_program.key(),
+                token_program: self.token_program.key(),
+                rent: self.rent.key(),
+                event_authority: self.event_authority.key(),
+                program: self.program.key(),
+                versioned: self.versioned.keys(),
+            },
+            &instructions::BuyArgs {
+                amount: amount_out,
+                max_sol_cost,
+            },
+            self.versioned.version(),
+        )?;
+        
+        let mut account_infos = vec![
+            self.global.clone(),
+            self.fee_recipient.clone(),
+            self.mint.clone(),
+            self.bonding_curve.clone(),
+            self.associated_bonding_curve.clone(),
+            self.associated_user.clone(),
+            self.user.clone(),
+            self.system_program.clone(),
+            self.token_program.clone(),
+            self.rent.clone(),
+            self.event_authority.clone(),
+            self.program.clone(),
+        ];
+        self.versioned.append_to(&mut account_infos);
+        
+        // 执行Pump.fun的交易指令
+        invoke(&swap_ix, &account_infos)?;
+        
+        Ok(())
+    }
+}
+
+// 新版本Pump.fun账户列表中的可选账户
+struct VersionedCpiAccounts<'info> {
+    creator_vault: Option<AccountInfo<'info>>,
+    global_volume_accumulator: Option<AccountInfo<'info>>,
+    user_volume_accumulator: Option<AccountInfo<'info>>,
+    fee_config: Option<AccountInfo<'info>>,
+    fee_program: Option<AccountInfo<'info>>,
+}
+
+impl<'info> VersionedCpiAccounts<'info> {
+    // 校验可选账户的PDA地址，creator为绑定曲线中记录的创建者
+    fn new(
+        user: &Pubkey,
+        creator: Option<Pubkey>,
+        creator_vault: Option<AccountInfo<'info>>,
+        global_volume_accumulator: Option<AccountInfo<'info>>,
+        user_volume_accumulator: Option<AccountInfo<'info>>,
+        fee_config: Option<AccountInfo<'info>>,
+        fee_program: Option<AccountInfo<'info>>,
+    ) -> Result<Self> {
+        // 新布局的绑定曲线必须提供创建者金库
+        match (creator, &creator_vault) {
+            (Some(creator), Some(creator_vault)) => {
+                let (creator_vault_pda, _) = state::get_creator_vault_pda(&creator);
+                require_keys_eq!(creator_vault.key(), creator_vault_pda, DexError::InvalidPumpAccount);
+            }
+            (Some(_), None) => return err!(DexError::MissingPumpAccount),
+            (None, Some(_)) => return err!(DexError::InvalidPumpAccount),
+            (None, None) => {}
+        }
+        
+        if let Some(global_volume_accumulator) = &global_volume_accumulator {
+            let (pda, _) = state::get_global_volume_accumulator_pda();
+            require_keys_eq!(global_volume_accumulator.key(), pda, DexError::InvalidPumpAccount);
+        }
+        
+        if let Some(user_volume_accumulator) = &user_volume_accumulator {
+            let (pda, _) = state::get_user_volume_accumulator_pda(user);
+            require_keys_eq!(user_volume_accumulator.key(), pda, DexError::InvalidPumpAccount);
+        }
+        
+        if let Some(fee_config) = &fee_config {
+            let (pda, _) = state::get_fee_config_pda();
+            require_keys_eq!(fee_config.key(), pda, DexError::InvalidPumpAccount);
+        }
+        
+        if let Some(fee_program) = &fee_program {
+            require_keys_eq!(fee_program.key(), state::get_pump_fee_program_id(), DexError::InvalidPumpAccount);
+        }
+        
+        Ok(Self {
+            creator_vault,
+            global_volume_accumulator,
+            user_volume_accumulator,
+            fee_config,
+            fee_program,
+        })
+    }
+    
+    fn keys(&self) -> instructions::VersionedAccounts {
+        instructions::VersionedAccounts {
+            creator_vault: self.creator_vault.as_ref().map(|account| account.key()),
+            global_volume_accumulator: self.global_volume_accumulator.as_ref().map(|account| account.key()),
+            user_volume_accumulator: self.user_volume_accumulator.as_ref().map(|account| account.key()),
+            fee_config: self.fee_config.as_ref().map(|account| account.key()),
+            fee_program: self.fee_program.as_ref().map(|account| account.key()),
+        }
+    }
+    
+    fn version(&self) -> instructions::PumpAccountsVersion {
+        self.keys().version()
+    }
+    
+    // 将提供的可选账户追加到CPI账户列表
+    fn append_to(&self, account_infos: &mut Vec<AccountInfo<'info>>) {
+        account_infos.extend(
+            [
+                &self.creator_vault,
+                &self.global_volume_accumulator,
+                &self.user_volume_accumulator,
+                &self.fee_config,
+                &self.fee_program,
+            ]
+            .into_iter()
+            .flatten()
+            .cloned(),
+        );
+    }
+}
+
+// 在Pump.fun上发行代币，并可选地在同一指令中以精确SOL输入进行创建者买入
+pub fn launch_token(
+    ctx: Context<LaunchToken>,
+    name: String,
+    symbol: String,
+    uri: String,
+    initial_buy_sol: u64,  // 创建者买入的SOL数量（包含手续费），0表示不买入
+    min_amount_out: u64,  // 创建者买入的最小代币数量（滑点控制）
+) -> Result<()> {
+    msg!("在Pump.fun上发行代币: {} ({})", name, symbol);
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    // 检查元数据长度
+    require!(!name.is_empty() && name.len() <= MAX_NAME_LENGTH, DexError::InvalidArgument);
+    require!(!symbol.is_empty() && symbol.len() <= MAX_SYMBOL_LENGTH, DexError::InvalidArgument);
+    require!(!uri.is_empty() && uri.len() <= MAX_URI_LENGTH, DexError::InvalidArgument);
+    
+    let pump_program_id = get_pump_program_id();
+    let token_mint = ctx.accounts.token_mint.key();
+    
+    let global = Global::try_from_account_info(&ctx.accounts.global_state)?;
+    require!(
+        global.is_fee_recipient(&ctx.accounts.fee_recipient.key()),
+        DexError::InvalidPumpAccount
+    );
+    
+    // 检查Pump.fun派生账户
+    let (bonding_curve_pda, _) = instructions::get_bonding_curve_pda(&token_mint, &pump_program_id);
+    require_keys_eq!(ctx.accounts.bonding_curve.key(), bonding_curve_pda, DexError::InvalidArgument);
+    
+    let (mint_authority_pda, _) = instructions::get_mint_authority_pda(&pump_program_id);
+    require_keys_eq!(ctx.accounts.mint_authority.key(), mint_authority_pda, DexError::InvalidArgument);
+    
+    let (metadata_pda, _) = instructions::get_metadata_pda(&token_mint);
+    require_keys_eq!(ctx.accounts.metadata.key(), metadata_pda, DexError::InvalidArgument);
+    
+    let bonding_curve_token_account = get_associated_token_address(&bonding_curve_pda, &token_mint);
+    require_keys_eq!(
+        ctx.accounts.bonding_curve_token_account.key(),
+        bonding_curve_token_account,
+        DexError::InvalidArgument
+    );
+    
+    // 1. 调用Pump.fun的create指令
+    let create_ix = instructions::create(
+        pump_program_id,
+        &instructions::CreateAccounts {
+            mint: token_mint,
+            mint_authority: ctx.accounts.mint_authority.key(),
+            bonding_curve: ctx.accounts.bonding_curve.key(),
+            associated_bonding_curve: ctx.accounts.bonding_curve_token_account.key(),
+            global: ctx.accounts.global_state.key(),
+            mpl_token_metadata: ctx.accounts.mpl_token_metadata.key(),
+            metadata: ctx.accounts.metadata.key(),
+            user: ctx.accounts.user.key(),
+            system_program: ctx.accounts.system_program.key(),
+            token_program: ctx.accounts.token_program.key(),
+            associated_token_program: ctx.accounts.associated_token_program.key(),
+            rent: ctx.accounts.rent.key(),
+            event_authority: ctx.accounts.event_authority.key(),
+            program: ctx.accounts.pump_program.key(),
+        },
+        &instructions::CreateArgs {
+            name: name.clone(),
+            symbol: symbol.clone(),
+            uri: uri.clone(),
+        },
+    );
+    
     invoke(
-        &swap_ix,
+        &create_ix,
         &[
-            ctx.accounts.global_state.to_account_info(),
-            ctx.accounts.fee_recipient.to_account_info(),
             ctx.accounts.token_mint.to_account_info(),
+            ctx.accounts.mint_authority.to_account_info(),
             ctx.accounts.bonding_curve.to_account_info(),
             ctx.accounts.bonding_curve_token_account.to_account_info(),
-            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.global_state.to_account_info(),
+            ctx.accounts.mpl_token_metadata.to_account_info(),
+            ctx.accounts.metadata.to_account_info(),
             ctx.accounts.user.to_account_info(),
             ctx.accounts.system_program.to_account_info(),
             ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.associated_token_program.to_account_info(),
             ctx.accounts.rent.to_account_info(),
             ctx.accounts.event_authority.to_account_info(),
             ctx.accounts.pump_program.to_account_info(),
         ],
     )?;
     
-    msg!("交易完成，获得代币");
+    msg!("代币创建完成，绑定曲线: {}", bonding_curve_pda);
+    
+    // 2. 可选的创建者买入
+    let mut initial_buy_tokens = 0;
+    if initial_buy_sol > 0 {
+        require!(min_amount_out > 0, DexError::InvalidArgument);
+        
+        // 读取create指令初始化的绑定曲线（新布局中记录了创建者），其储备应等于Global中的初始储备
+        let bonding_curve = BondingCurve::try_from_account_info(&ctx.accounts.bonding_curve)?;
+        require!(
+            bonding_curve.real_token_reserves == global.initial_real_token_reserves,
+            DexError::InvalidPumpAccount
+        );
+        
+        let buy_quote = quote::quote_buy_exact_sol_in(
+            &bonding_curve,
+            &quote::PumpFees::new(&global, &bonding_curve, load_fee_config(&ctx.accounts.fee_config)?.as_ref())?,
+            initial_buy_sol,
+        )?;
+        
+        msg!("创建者买入: 预计获得代币数量: {}, 预计花费: {}", 
+            buy_quote.net_amount_out, 
+            buy_quote.amount_in
+        );
+        
+        // 检查滑点
+        require!(buy_quote.net_amount_out >= min_amount_out, DexError::SlippageExceeded);
+        
+        // 创建用户关联代币账户
+        token_utils::ensure_user_token_account(
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.token_mint.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+        )?;
+        
+        // 记录交易前余额
+        let user = ctx.accounts.user.to_account_info();
+        let user_token_account = ctx.accounts.user_token_account.to_account_info();
+        let snapshot = BalanceSnapshot::take(&user, &user_token_account)?;
+        
+        BuyCpiAccounts {
+            global: ctx.accounts.global_state.to_account_info(),
+            fee_recipient: ctx.accounts.fee_recipient.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            bonding_curve: ctx.accounts.bonding_curve.to_account_info(),
+            associated_bonding_curve: ctx.accounts.bonding_curve_token_account.to_account_info(),
+            associated_user: ctx.accounts.user_token_account.to_account_info(),
+            user: ctx.accounts.user.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+            event_authority: ctx.accounts.event_authority.to_account_info(),
+            program: ctx.accounts.pump_program.to_account_info(),
+            versioned: VersionedCpiAccounts::new(
+                &ctx.accounts.user.key(),
+                bonding_curve.creator,
+                ctx.accounts.creator_vault.as_ref().map(|account| account.to_account_info()),
+                ctx.accounts.global_volume_accumulator.as_ref().map(|account| account.to_account_info()),
+                ctx.accounts.user_volume_accumulator.as_ref().map(|account| account.to_account_info()),
+                ctx.accounts.fee_config.as_ref().map(|account| account.to_account_info()),
+                ctx.accounts.fee_program.as_ref().map(|account| account.to_account_info()),
+            )?,
+        }
+        .invoke(buy_quote.net_amount_out, initial_buy_sol)?;
+        
+        // 按实际余额变化检查成交数量
+        let fill = snapshot.settle(
+            &user,
+            &user_token_account,
+            ctx.accounts.user.key(),
+            &FillPair {
+                dex: DexType::PumpFun,
+                token_in: spl_token::native_mint::id(),
+                input_decimals: SOL_DECIMALS,
+                token_out: token_mint,
+                output_decimals: PUMP_TOKEN_DECIMALS,
+            },
+            &FillLimits {
+                expected_amount_in: buy_quote.amount_in,
+                expected_amount_out: buy_quote.net_amount_out,
+                max_amount_in: initial_buy_sol,
+                min_amount_out,
+            },
+        )?;
+        
+        initial_buy_tokens = fill.amount_out;
+        msg!("创建者买入完成，获得代币: {}", initial_buy_tokens);
+    }
+    
+    // 发出代币发行事件
+    emit!(TokenLaunched {
+        creator: ctx.accounts.user.key(),
+        token_mint,
+        bonding_curve: bonding_curve_pda,
+        name,
+        symbol,
+        uri,
+        initial_buy_sol,
+        initial_buy_tokens,
+        slot: Clock::get()?.slot,
+    });
+    
     Ok(())
 }
 
@@ -91,77 +600,112 @@ pub fn sell_token(
     ctx: Context<TradeToken>,
     amount_in: u64,  // 输入的代币数量
     min_sol_out: u64,  // 最小获得的SOL数量（滑点控制）
-) -> Result<()> {
+    close_token_account: bool,  // 全部卖出后是否关闭用户代币账户以回收租金
+) -> Result<Fill> {
     msg!("在Pump.fun上卖出代币: {} 代币, 最小获得SOL数量: {}", amount_in, min_sol_out);
     msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
     
-    // 1. 转移代币到Pump.fun的绑定曲线代币账户
-    let transfer_cpi_accounts = Transfer {
-        from: ctx.accounts.user_token_account.to_account_info(),
-        to: ctx.accounts.bonding_curve_token_account.to_account_info(),
-        authority: ctx.accounts.user.to_account_info(),
-    };
+    // 检查参数
+    require!(amount_in > 0, DexError::InvalidArgument);
+    require!(min_sol_out > 0, DexError::InvalidArgument);
     
-    let cpi_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        transfer_cpi_accounts,
+    // 检查用户代币账户
+    let user_token_account = token_utils::read_token_account(&ctx.accounts.user_token_account)?;
+    require!(
+        user_token_account.mint == ctx.accounts.token_mint.key(),
+        DexError::InvalidArgument
+    );
+    require!(
+        user_token_account.owner == ctx.accounts.user.key(),
+        DexError::InvalidArgument
     );
     
-    token::transfer(cpi_ctx, amount_in)?;
+    // 检查用户代币账户是否有足够的代币
+    require!(user_token_account.amount >= amount_in, DexError::InsufficientFunds);
     
-    // 2. 调用Pump.fun的交易指令
-    // 构建Pump.fun的交易数据 - 根据真实交易格式
-    let mut data = vec![0u8; 16];
-    // 将amount_in编码到data中 (卖出的代币数量)
-    data[0..8].copy_from_slice(&amount_in.to_le_bytes());
-    // 将min_sol_out编码到data中 (最小获得的SOL数量)
-    data[8..16].copy_from_slice(&min_sol_out.to_le_bytes());
+    let (global, bonding_curve) = ctx.accounts.load_pump_state()?;
     
-    // 获取Pump.fun程序ID
-    let pump_program_id = get_pump_program_id();
+    // 预先报价，提前拒绝必然失败的交易
+    let sell_quote = quote::quote_sell(
+        &bonding_curve,
+        &ctx.accounts.pump_fees(&global, &bonding_curve)?,
+        amount_in,
+    )?;
     
-    // 构建Pump.fun的交易指令 - 根据真实交易格式
-    let swap_ix = Instruction {
-        program_id: pump_program_id,
-        accounts: vec![
-            // 根据真实交易格式添加所需账户
-            AccountMeta::new_readonly(ctx.accounts.global_state.key(), false), // Global state
-            AccountMeta::new(get_pump_fee_account(), false), // Fee recipient
-            AccountMeta::new_readonly(ctx.accounts.token_mint.key(), false), // Mint
-            AccountMeta::new(ctx.accounts.bonding_curve.key(), false), // Bonding curve
-            AccountMeta::new(ctx.accounts.bonding_curve_token_account.key(), false), // Associated bonding curve
-            AccountMeta::new(ctx.accounts.user_token_account.key(), false), // Associated user
-            AccountMeta::new(ctx.accounts.user.key(), true), // User (signer)
-            AccountMeta::new_readonly(ctx.accounts.system_program.key(), false), // System program
-            AccountMeta::new_readonly(ctx.accounts.token_program.key(), false), // Token program
-            AccountMeta::new_readonly(ctx.accounts.rent.key(), false), // Rent
-            AccountMeta::new_readonly(ctx.accounts.event_authority.key(), false), // Event authority
-            AccountMeta::new_readonly(pump_program_id, false), // Program
-        ],
-        data: data,
-    };
+    msg!("预计获得SOL: {}, 手续费: {}", sell_quote.net_amount_out, sell_quote.fee_amount);
     
-    // 执行Pump.fun的交易指令
-    invoke(
-        &swap_ix,
-        &[
-            ctx.accounts.global_state.to_account_info(),
-            ctx.accounts.fee_recipient.to_account_info(),
-            ctx.accounts.token_mint.to_account_info(),
-            ctx.accounts.bonding_curve.to_account_info(),
-            ctx.accounts.bonding_curve_token_account.to_account_info(),
-            ctx.accounts.user_token_account.to_account_info(),
-            ctx.accounts.user.to_account_info(),
-            ctx.accounts.system_program.to_account_info(),
-            ctx.accounts.token_program.to_account_info(),
-            ctx.accounts.rent.to_account_info(),
-            ctx.accounts.event_authority.to_account_info(),
-            ctx.accounts.pump_program.to_account_info(),
-        ],
+    require!(sell_quote.net_amount_out >= min_sol_out, DexError::SlippageExceeded);
+    
+    let versioned = ctx.accounts.versioned_accounts(bonding_curve.creator)?;
+    
+    // 调用Pump.fun的sell指令，代币只通过Pump.fun转移
+    let swap_ix = instructions::sell(
+        get_pump_program_id(),
+        &instructions::SellAccounts {
+            global: ctx.accounts.global_state.key(),
+            fee_recipient: ctx.accounts.fee_recipient.key(),
+            mint: ctx.accounts.token_mint.key(),
+            bonding_curve: ctx.accounts.bonding_curve.key(),
+            associated_bonding_curve: ctx.accounts.bonding_curve_token_account.key(),
+            associated_user: ctx.accounts.user_token_account.key(),
+            user: ctx.accounts.user.key(),
+            system_program: ctx.accounts.system_program.key(),
+            associated_token_program: ctx.accounts.associated_token_program.key(),
+            token_program: ctx.accounts.token_program.key(),
+            event_authority: ctx.accounts.event_authority.key(),
+            program: ctx.accounts.pump_program.key(),
+            versioned: versioned.keys(),
+        },
+        &instructions::SellArgs {
+            amount: amount_in,
+            min_sol_output: min_sol_out,
+        },
+        versioned.version(),
     )?;
     
+    let mut account_infos = vec![
+        ctx.accounts.global_state.to_account_info(),
+        ctx.accounts.fee_recipient.to_account_info(),
+        ctx.accounts.token_mint.to_account_info(),
+        ctx.accounts.bonding_curve.to_account_info(),
+        ctx.accounts.bonding_curve_token_account.to_account_info(),
+        ctx.accounts.user_token_account.to_account_info(),
+        ctx.accounts.user.to_account_info(),
+        ctx.accounts.system_program.to_account_info(),
+        ctx.accounts.associated_token_program.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.event_authority.to_account_info(),
+        ctx.accounts.pump_program.to_account_info(),
+    ];
+    versioned.append_to(&mut account_infos);
+    
+    // 记录交易前余额
+    let snapshot = ctx.accounts.balance_snapshot(false)?;
+    
+    // 执行Pump.fun的交易指令
+    invoke(&swap_ix, &account_infos)?;
+    
+    // 按实际余额变化检查成交数量，代币必须按输入数量全部卖出
+    let fill = ctx.accounts.settle_fill(&snapshot, false, &FillLimits {
+        expected_amount_in: amount_in,
+        expected_amount_out: sell_quote.net_amount_out,
+        max_amount_in: amount_in,
+        min_amount_out: min_sol_out,
+    })?;
+    require!(fill.amount_in == amount_in, DexError::UnexpectedBalanceChange);
+    
+    // 全部卖出后可选地关闭代币账户
+    let token_balance_after = user_token_account.amount - fill.amount_in;
+    if close_token_account && token_balance_after == 0 {
+        token_utils::close_empty_token_account(
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+        )?;
+    }
+    
     msg!("交易完成，获得SOL");
-    Ok(())
+    Ok(fill)
 }
 
 // 检查代币是否在Pump.fun上
@@ -178,148 +722,197 @@ pub fn is_token_on_pump(
     
     msg!("计算的绑定曲线PDA地址: {}", bonding_curve_pda);
     
-    // 尝试获取绑定曲线账户数据
-    // 在实际实现中，我们需要检查账户是否存在
-    // 这里我们通过检查账户数据大小来判断
+    // 传入的绑定曲线账户必须是该代币的PDA，否则无法据此判断代币位置
     let bonding_curve_account_info = ctx.accounts.bonding_curve.to_account_info();
+    if bonding_curve_account_info.key() != bonding_curve_pda {
+        msg!("绑定曲线账户与PDA不匹配: {}", bonding_curve_account_info.key());
+        return Ok(false);
+    }
     
-    // 检查账户是否存在且数据大小正确
-    if bonding_curve_account_info.data_is_empty() {
+    // 账户不存在说明代币从未在Pump.fun上创建
+    if bonding_curve_account_info.data_is_empty() || bonding_curve_account_info.lamports() == 0 {
         msg!("绑定曲线账户不存在，代币不在Pump.fun上");
         return Ok(false);
     }
     
-    // 检查账户是否为BondingCurve类型
-    // 在实际实现中，我们需要检查账户的discriminator
-    // 这里我们假设账户已经被正确反序列化
+    // 手动检查账户所有者和discriminator，不符合时视为不在Pump.fun上
+    if *bonding_curve_account_info.owner != get_pump_program_id() {
+        msg!("绑定曲线账户所有者不是Pump.fun程序: {}", bonding_curve_account_info.owner);
+        return Ok(false);
+    }
+    
+    let bonding_curve = {
+        let data = bonding_curve_account_info.try_borrow_data()?;
+        match BondingCurve::try_deserialize_versioned(&data) {
+            Ok(bonding_curve) => bonding_curve,
+            Err(_) => {
+                msg!("绑定曲线账户数据无效，代币不在Pump.fun上");
+                return Ok(false);
+            }
+        }
+    };
     
     // 检查绑定曲线是否已完成
-    if ctx.accounts.bonding_curve.complete {
+    if bonding_curve.complete {
         msg!("绑定曲线已完成，代币不再在Pump.fun上交易");
         return Ok(false);
     }
     
     // 检查绑定曲线是否有足够的流动性
-    if ctx.accounts.bonding_curve.virtual_sol_reserves == 0 || ctx.accounts.bonding_curve.virtual_token_reserves == 0 {
+    if bonding_curve.virtual_sol_reserves == 0 || bonding_curve.virtual_token_reserves == 0 {
         msg!("绑定曲线没有足够的流动性，代币不可交易");
         return Ok(false);
     }
     
     msg!("代币在Pump.fun上可用，虚拟SOL储备: {}, 虚拟代币储备: {}", 
-        ctx.accounts.bonding_curve.virtual_sol_reserves,
-        ctx.accounts.bonding_curve.virtual_token_reserves
+        bonding_curve.virtual_sol_reserves,
+        bonding_curve.virtual_token_reserves
     );
     Ok(true)
 }
 
-// 获取Pump.fun上的价格
-pub fn get_price(
+// 获取Pump.fun上的报价
+pub fn get_quote(
     ctx: Context<GetPriceContext>,
     amount_in: u64,
-    is_buy: bool,  // true表示买入，false表示卖出
-) -> Result<u64> {
+    is_buy: bool,  // true表示买入（输入SOL），false表示卖出（输入代币）
+) -> Result<quote::PumpQuote> {
     // 检查输入金额是否大于0
-    if amount_in == 0 {
-        msg!("错误: 输入金额不能为0");
-        return Err(ProgramError::InvalidArgument.into());
-    }
+    require!(amount_in > 0, DexError::InvalidArgument);
     
     // 记录查询信息
-    msg!("查询Pump.fun上的价格: 输入金额 {}, 操作类型: {}", 
+    msg!("查询Pump.fun上的报价: 输入金额 {}, 操作类型: {}", 
         amount_in, 
         if is_buy { "买入" } else { "卖出" }
     );
     msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
     
-    // 从绑定曲线账户中获取价格计算所需的数据
-    // 在实际实现中，我们需要从绑定曲线账户中读取这些数据
-    // 这里我们假设已经从账户中读取了以下数据
-    let virtual_token_reserves = ctx.accounts.bonding_curve.virtual_token_reserves;
-    let virtual_sol_reserves = ctx.accounts.bonding_curve.virtual_sol_reserves;
+    let global = Global::try_from_account_info(&ctx.accounts.global_state)?;
+    let bonding_curve = &BondingCurve::try_from_account_info(&ctx.accounts.bonding_curve)?;
+    let fees = quote::PumpFees::new(&global, bonding_curve, load_fee_config(&ctx.accounts.fee_config)?.as_ref())?;
     
-    msg!("绑定曲线虚拟代币储备: {}", virtual_token_reserves);
-    msg!("绑定曲线虚拟SOL储备: {}", virtual_sol_reserves);
+    msg!("绑定曲线虚拟代币储备: {}", bonding_curve.virtual_token_reserves);
+    msg!("绑定曲线虚拟SOL储备: {}", bonding_curve.virtual_sol_reserves);
     
-    // 根据Pump.fun的恒定乘积公式计算价格
-    // 公式: k = virtual_token_reserves * virtual_sol_reserves
-    let k = virtual_token_reserves.checked_mul(virtual_sol_reserves).ok_or(ProgramError::ArithmeticOverflow)?;
+    let pump_quote = if is_buy {
+        quote::quote_buy_exact_sol_in(bonding_curve, &fees, amount_in)?
+    } else {
+        quote::quote_sell(bonding_curve, &fees, amount_in)?
+    };
+    
+    msg!("Pump.fun报价: 实际输入 {}, 毛输出 {}, 手续费 {}, 净输出 {}, 价格影响: {}%", 
+        pump_quote.amount_in,
+        pump_quote.gross_amount_out,
+        pump_quote.fee_amount,
+        pump_quote.net_amount_out,
+        pump_quote.price_impact as f64 / 10_000.0
+    );
     
+    Ok(pump_quote)
+}
+
+// 获取Pump.fun上的价格（每单位输入可获得的净输出，按两侧代币的小数位数换算）
+pub fn get_price(
+    ctx: Context<GetPriceContext>,
+    amount_in: u64,
+    is_buy: bool,  // true表示买入，false表示卖出
+) -> Result<Price> {
+    let token_decimals = ctx.accounts.token_mint.decimals;
+    let pump_quote = get_quote(ctx, amount_in, is_buy)?;
+    
+    // 计算价格（买入时输入为SOL，卖出时输入为代币）
     let price = if is_buy {
-        // 买入时，计算获得的代币数量
-        // 新的虚拟SOL储备 = virtual_sol_reserves + amount_in
-        let new_virtual_sol_reserves = virtual_sol_reserves.checked_add(amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
-        
-        // 新的虚拟代币储备 = k / new_virtual_sol_reserves
-        let new_virtual_token_reserves = k.checked_div(new_virtual_sol_reserves).ok_or(ProgramError::ArithmeticOverflow)?;
-        
-        // 获得的代币数量 = virtual_token_reserves - new_virtual_token_reserves
-        let tokens_out = virtual_token_reserves.checked_sub(new_virtual_token_reserves).ok_or(ProgramError::ArithmeticOverflow)?;
-        
-        // 计算每SOL可获得的代币数量
-        let tokens_per_sol = tokens_out.checked_div(amount_in).unwrap_or(0);
-        
-        msg!("Pump.fun买入: 每SOL可获得{}代币", tokens_per_sol);
-        tokens_per_sol
+        Price::from_amounts(amount_in, SOL_DECIMALS, pump_quote.net_amount_out, token_decimals)?
     } else {
-        // 卖出时，计算获得的SOL数量
-        // 新的虚拟代币储备 = virtual_token_reserves + amount_in
-        let new_virtual_token_reserves = virtual_token_reserves.checked_add(amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
-        
-        // 新的虚拟SOL储备 = k / new_virtual_token_reserves
-        let new_virtual_sol_reserves = k.checked_div(new_virtual_token_reserves).ok_or(ProgramError::ArithmeticOverflow)?;
-        
-        // 获得的SOL数量 = virtual_sol_reserves - new_virtual_sol_reserves
-        let sol_out = virtual_sol_reserves.checked_sub(new_virtual_sol_reserves).ok_or(ProgramError::ArithmeticOverflow)?;
-        
-        // 计算每代币可获得的SOL数量（以lamports为单位）
-        let lamports_per_token = sol_out.checked_mul(1_000_000_000).unwrap_or(0).checked_div(amount_in).unwrap_or(0);
-        
-        msg!("Pump.fun卖出: 每代币可获得{}lamports", lamports_per_token);
-        lamports_per_token
+        Price::from_amounts(amount_in, token_decimals, pump_quote.net_amount_out, SOL_DECIMALS)?
     };
     
-    msg!("Pump.fun上的最终价格: {}", price);
+    msg!("Pump.fun上的最终价格: {}", price.as_f64());
     Ok(price)
 }
 
+// 路由报价器: 读取一次绑定曲线，按输入数量计算净输出（买入为代币，卖出为SOL）
+pub fn route_quoter<'info>(accounts: &GetPriceContext<'info>, is_buy: bool) -> Result<RouteQuoter<'info>> {
+    let global = Global::try_from_account_info(&accounts.global_state)?;
+    let bonding_curve = BondingCurve::try_from_account_info(&accounts.bonding_curve)?;
+    let fees = quote::PumpFees::new(&global, &bonding_curve, load_fee_config(&accounts.fee_config)?.as_ref())?;
+    
+    Ok(Box::new(move |amount_in| {
+        let pump_quote = if is_buy {
+            quote::quote_buy_exact_sol_in(&bonding_curve, &fees, amount_in)?
+        } else {
+            quote::quote_sell(&bonding_curve, &fees, amount_in)?
+        };
+        Ok(pump_quote.net_amount_out)
+    }))
+}
+
+// 加载新版本账户列表中的Pump.fun手续费配置，未提供时返回None
+fn load_fee_config(fee_config: &Option<UncheckedAccount>) -> Result<Option<FeeConfig>> {
+    fee_config
+        .as_ref()
+        .map(|account| FeeConfig::try_from_account_info(account))
+        .transpose()
+}
+
+// 精确输出路由报价器: 读取一次绑定曲线，按需要获得的代币数量计算所需的SOL（含手续费），只支持买入
+pub fn route_exact_out_quoter<'info>(accounts: &GetPriceContext<'info>, is_buy: bool) -> Result<RouteQuoter<'info>> {
+    require!(is_buy, DexError::UnsupportedSwapMode);
+    
+    let global = Global::try_from_account_info(&accounts.global_state)?;
+    let bonding_curve = BondingCurve::try_from_account_info(&accounts.bonding_curve)?;
+    let fees = quote::PumpFees::new(&global, &bonding_curve, load_fee_config(&accounts.fee_config)?.as_ref())?;
+    
+    Ok(Box::new(move |amount_out| {
+        Ok(quote::quote_buy_exact_tokens_out(&bonding_curve, &fees, amount_out)?.amount_in)
+    }))
+}
+
 // Pump.fun交易所需的账户结构
 #[derive(Accounts)]
 pub struct TradeToken<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
     
+    // DEX账户
+    #[account(
+        mut,
+        seeds = [b"dex_account".as_ref()],
+        bump
+    )]
+    pub dex_account: Account<'info, crate::DexAccount>,
+    
     // 代币Mint地址
     pub token_mint: Account<'info, token::Mint>,
     
-    // Pump.fun全局状态账户
+    // Pump.fun全局状态账户（兼容新旧布局，在指令中手动反序列化）
     #[account(
         seeds = [b"global"],
         bump,
+        seeds::program = pump_program.key(),
     )]
-    pub global_state: Account<'info, Global>,
+    pub global_state: UncheckedAccount<'info>,
     
-    // Pump.fun费用接收账户
-    #[account(
-        mut,
-        constraint = fee_recipient.key() == global_state.fee_recipient
-    )]
+    // Pump.fun费用接收账户（在指令中检查是否为Global中的手续费接收账户）
+    #[account(mut)]
     pub fee_recipient: AccountInfo<'info>,
     
-    // Pump.fun绑定曲线账户
+    // Pump.fun绑定曲线账户（兼容新旧布局，在指令中手动反序列化）
     #[account(
         mut,
         seeds = [b"bonding-curve", token_mint.key().as_ref()],
         bump,
+        seeds::program = pump_program.key(),
     )]
-    pub bonding_curve: Account<'info, BondingCurve>,
+    pub bonding_curve: UncheckedAccount<'info>,
     
     // Pump.fun绑定曲线代币账户
     #[account(mut)]
     pub bonding_curve_token_account: Account<'info, TokenAccount>,
     
-    // 用户代币账户
+    // 用户代币账户（买入时不存在则以幂等方式创建关联代币账户）
     #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub user_token_account: UncheckedAccount<'info>,
     
     // 系统程序
     pub system_program: Program<'info, System>,
@@ -327,12 +920,199 @@ pub struct TradeToken<'info> {
     // 代币程序
     pub token_program: Program<'info, Token>,
     
+    // 关联代币程序
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    
     // 租金程序
     pub rent: AccountInfo<'info>,
     
     // 事件权限账户
     #[account(
-        constraint = event_authority.key() == Pubkey::from_str("Ce6TQqeHC9p8KetsN6JsjHK7UTZk7nasjjnr7XxXp9F1").unwrap()
+        constraint = event_authority.key() == instructions::get_pump_event_authority()
+    )]
+    pub event_authority: AccountInfo<'info>,
+    
+    // Pump.fun程序
+    #[account(
+        constraint = pump_program.key() == get_pump_program_id()
+    )]
+    pub pump_program: AccountInfo<'info>,    
+    // 创建者金库（新版本账户列表，绑定曲线记录了创建者时必须提供）
+    #[account(mut)]
+    pub creator_vault: Option<UncheckedAccount<'info>>,
+    
+    // 全局交易量累加器（新版本账户列表，仅buy使用）
+    #[account(mut)]
+    pub global_volume_accumulator: Option<UncheckedAccount<'info>>,
+    
+    // 用户交易量累加器（新版本账户列表，仅buy使用）
+    #[account(mut)]
+    pub user_volume_accumulator: Option<UncheckedAccount<'info>>,
+    
+    // Pump.fun手续费配置账户（新版本账户列表）
+    pub fee_config: Option<UncheckedAccount<'info>>,
+    
+    // Pump.fun手续费程序（新版本账户列表）
+    pub fee_program: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> TradeToken<'info> {
+    // 加载Pump.fun的Global与BondingCurve（兼容新旧布局），并检查手续费接收账户
+    pub fn load_pump_state(&self) -> Result<(Global, BondingCurve)> {
+        let global = Global::try_from_account_info(&self.global_state)?;
+        let bonding_curve = BondingCurve::try_from_account_info(&self.bonding_curve)?;
+        
+        require!(
+            global.is_fee_recipient(&self.fee_recipient.key()),
+            DexError::InvalidPumpAccount
+        );
+        
+        Ok((global, bonding_curve))
+    }
+    
+    // 绑定曲线的费率，新版本账户列表按fee_config中的市值分档费率
+    pub fn pump_fees(&self, global: &Global, bonding_curve: &BondingCurve) -> Result<quote::PumpFees> {
+        quote::PumpFees::new(global, bonding_curve, load_fee_config(&self.fee_config)?.as_ref())
+    }
+    
+    // 确保用户代币账户存在（不存在时以幂等方式创建关联代币账户）
+    fn ensure_user_token_account(&self) -> Result<token_interface::TokenAccount> {
+        token_utils::ensure_user_token_account(
+            &self.user.to_account_info(),
+            &self.user_token_account.to_account_info(),
+            &self.token_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.associated_token_program.to_account_info(),
+        )
+    }
+    
+    // 记录交易前用户SOL和代币账户的余额
+    fn balance_snapshot(&self, is_buy: bool) -> Result<BalanceSnapshot> {
+        let (input_account, output_account) = self.fill_accounts(is_buy);
+        BalanceSnapshot::take(&input_account, &output_account)
+    }
+    
+    // 按交易后的余额变化结算实际成交数量并发出成交事件
+    // 买入时SOL直接从用户钱包扣除，花费中包含Pump.fun在CPI中收取的全部费用
+    fn settle_fill(&self, snapshot: &BalanceSnapshot, is_buy: bool, limits: &FillLimits) -> Result<Fill> {
+        let (input_account, output_account) = self.fill_accounts(is_buy);
+        let (token_in, input_decimals, token_out, output_decimals) = if is_buy {
+            (spl_token::native_mint::id(), SOL_DECIMALS, self.token_mint.key(), self.token_mint.decimals)
+        } else {
+            (self.token_mint.key(), self.token_mint.decimals, spl_token::native_mint::id(), SOL_DECIMALS)
+        };
+        
+        snapshot.settle(
+            &input_account,
+            &output_account,
+            self.user.key(),
+            &FillPair {
+                dex: DexType::PumpFun,
+                token_in,
+                input_decimals,
+                token_out,
+                output_decimals,
+            },
+            limits,
+        )
+    }
+    
+    // 交易的(输入账户, 输出账户)，SOL一侧为用户钱包
+    fn fill_accounts(&self, is_buy: bool) -> (AccountInfo<'info>, AccountInfo<'info>) {
+        let user = self.user.to_account_info();
+        let user_token_account = self.user_token_account.to_account_info();
+        if is_buy {
+            (user, user_token_account)
+        } else {
+            (user_token_account, user)
+        }
+    }
+    
+    // 收集并校验新版本账户列表中的可选账户
+    fn versioned_accounts(&self, creator: Option<Pubkey>) -> Result<VersionedCpiAccounts<'info>> {
+        VersionedCpiAccounts::new(
+            &self.user.key(),
+            creator,
+            self.creator_vault.as_ref().map(|account| account.to_account_info()),
+            self.global_volume_accumulator.as_ref().map(|account| account.to_account_info()),
+            self.user_volume_accumulator.as_ref().map(|account| account.to_account_info()),
+            self.fee_config.as_ref().map(|account| account.to_account_info()),
+            self.fee_program.as_ref().map(|account| account.to_account_info()),
+        )
+    }
+}
+
+// Pump.fun发行代币所需的账户结构（字段命名与TradeToken保持一致）
+#[derive(Accounts)]
+pub struct LaunchToken<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // DEX账户
+    #[account(
+        mut,
+        seeds = [b"dex_account".as_ref()],
+        bump
+    )]
+    pub dex_account: Account<'info, crate::DexAccount>,
+    
+    // 新代币Mint（新生成的密钥对，需要签名）
+    #[account(mut)]
+    pub token_mint: Signer<'info>,
+    
+    // Pump.fun铸币权限账户
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    // Pump.fun全局状态账户（兼容新旧布局，在指令中手动反序列化）
+    #[account(
+        seeds = [b"global"],
+        bump,
+        seeds::program = pump_program.key(),
+    )]
+    pub global_state: UncheckedAccount<'info>,
+    
+    // Pump.fun费用接收账户（在指令中检查是否为Global中的手续费接收账户）
+    #[account(mut)]
+    pub fee_recipient: AccountInfo<'info>,
+    
+    // Pump.fun绑定曲线账户（由create指令初始化）
+    #[account(mut)]
+    pub bonding_curve: UncheckedAccount<'info>,
+    
+    // Pump.fun绑定曲线代币账户（由create指令初始化）
+    #[account(mut)]
+    pub bonding_curve_token_account: UncheckedAccount<'info>,
+    
+    // 代币元数据账户（由create指令初始化）
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    
+    // Metaplex代币元数据程序
+    #[account(
+        constraint = mpl_token_metadata.key() == instructions::get_mpl_token_metadata_program_id()
+    )]
+    pub mpl_token_metadata: AccountInfo<'info>,
+    
+    // 用户代币账户（创建者买入时创建）
+    #[account(mut)]
+    pub user_token_account: UncheckedAccount<'info>,
+    
+    // 系统程序
+    pub system_program: Program<'info, System>,
+    
+    // 代币程序
+    pub token_program: Program<'info, Token>,
+    
+    // 关联代币程序
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    
+    // 租金程序
+    pub rent: Sysvar<'info, Rent>,
+    
+    // 事件权限账户
+    #[account(
+        constraint = event_authority.key() == instructions::get_pump_event_authority()
     )]
     pub event_authority: AccountInfo<'info>,
     
@@ -340,7 +1120,24 @@ pub struct TradeToken<'info> {
     #[account(
         constraint = pump_program.key() == get_pump_program_id()
     )]
-    pub pump_program: AccountInfo<'info>,
+    pub pump_program: AccountInfo<'info>,    
+    // 创建者金库（新版本账户列表，绑定曲线记录了创建者时必须提供）
+    #[account(mut)]
+    pub creator_vault: Option<UncheckedAccount<'info>>,
+    
+    // 全局交易量累加器（新版本账户列表，仅buy使用）
+    #[account(mut)]
+    pub global_volume_accumulator: Option<UncheckedAccount<'info>>,
+    
+    // 用户交易量累加器（新版本账户列表，仅buy使用）
+    #[account(mut)]
+    pub user_volume_accumulator: Option<UncheckedAccount<'info>>,
+    
+    // Pump.fun手续费配置账户（新版本账户列表）
+    pub fee_config: Option<UncheckedAccount<'info>>,
+    
+    // Pump.fun手续费程序（新版本账户列表）
+    pub fee_program: Option<UncheckedAccount<'info>>,
 }
 
 // 检查代币位置所需的账户结构
@@ -350,11 +1147,11 @@ pub struct CheckTokenLocation<'info> {
     pub user: Signer<'info>,
     
     // Pump.fun全局状态账户
-    pub global_state: Account<'info, Global>,
+    pub global_state: UncheckedAccount<'info>,
     
-    // Pump.fun绑定曲线账户
+    // Pump.fun绑定曲线账户（可能不存在，在指令中检查PDA、所有者和discriminator）
     // 注意：这里我们不使用seeds约束，因为我们需要动态检查不同的代币
-    pub bonding_curve: Account<'info, BondingCurve>,
+    pub bonding_curve: UncheckedAccount<'info>,
     
     // 系统程序
     pub system_program: Program<'info, System>,
@@ -369,15 +1166,21 @@ pub struct GetPriceContext<'info> {
     // 代币Mint地址
     pub token_mint: Account<'info, token::Mint>,
     
-    // Pump.fun全局状态账户
-    pub global_state: Account<'info, Global>,
+    // Pump.fun全局状态账户（兼容新旧布局，在指令中手动反序列化）
+    #[account(
+        seeds = [b"global"],
+        bump,
+        seeds::program = get_pump_program_id(),
+    )]
+    pub global_state: UncheckedAccount<'info>,
     
-    // Pump.fun绑定曲线账户
+    // Pump.fun绑定曲线账户（兼容新旧布局，在指令中手动反序列化）
     #[account(
         seeds = [b"bonding-curve", token_mint.key().as_ref()],
         bump,
+        seeds::program = get_pump_program_id(),
     )]
-    pub bonding_curve: Account<'info, BondingCurve>,
+    pub bonding_curve: UncheckedAccount<'info>,
     
     // Pump.fun绑定曲线代币账户
     pub bonding_curve_token_account: Account<'info, TokenAccount>,
@@ -387,28 +1190,35 @@ pub struct GetPriceContext<'info> {
     
     // 代币程序
     pub token_program: Program<'info, Token>,
+    
+    // Pump.fun手续费配置账户（提供时按市值分档费率报价，与交易使用的账户列表版本一致）
+    pub fee_config: Option<UncheckedAccount<'info>>,
 }
 
-// 定义Pump.fun的Global账户结构
-#[account]
-pub struct Global {
-    pub initialized: bool,
-    pub authority: Pubkey,
-    pub fee_recipient: Pubkey,
-    pub initial_virtual_token_reserves: u64,
-    pub initial_virtual_sol_reserves: u64,
-    pub initial_real_token_reserves: u64,
-    pub token_total_supply: u64,
-    pub fee_basis_points: u64,
+// Pump.fun部分成交/绑定曲线完成事件
+#[event]
+pub struct PumpBuyFilled {
+    pub user: Pubkey,
+    pub token_mint: Pubkey,
+    pub filled_amount_out: u64,
+    pub partial_fill: bool,
+    pub sol_budget: u64,
+    pub sol_spent: u64,
+    pub sol_refunded: u64,
+    pub curve_completed: bool,
+    pub slot: u64,
 }
 
-// 定义Pump.fun的BondingCurve账户结构
-#[account]
-pub struct BondingCurve {
-    pub virtual_token_reserves: u64,
-    pub virtual_sol_reserves: u64,
-    pub real_token_reserves: u64,
-    pub real_sol_reserves: u64,
-    pub token_total_supply: u64,
-    pub complete: bool,
-} 
\ No newline at end of file
+// 代币发行事件
+#[event]
+pub struct TokenLaunched {
+    pub creator: Pubkey,
+    pub token_mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub initial_buy_sol: u64,
+    pub initial_buy_tokens: u64,
+    pub slot: u64,
+}
@@ -0,0 +1,485 @@
+use anchor_lang::prelude::*;
+use crate::math::{self, to_u64};
+use crate::DexError;
+use super::{BondingCurve, FeeConfig, Global, FEE_BASIS_POINTS_DENOMINATOR};
+
+// Pump.fun手续费率（基点），协议手续费与创建者手续费均以SOL计算
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct PumpFees {
+    pub protocol_fee_basis_points: u64,
+    // 绑定曲线未记录creator（V1布局）时为0
+    pub creator_fee_basis_points: u64,
+}
+
+impl PumpFees {
+    // 确定绑定曲线的费率，只有记录了creator的绑定曲线收取创建者手续费
+    // 新版本账户列表提供了fee_config时按绑定曲线当前市值所在的分档费率，否则使用Global中的费率
+    pub fn new(global: &Global, bonding_curve: &BondingCurve, fee_config: Option<&FeeConfig>) -> Result<Self> {
+        let (protocol_fee_basis_points, creator_fee_basis_points) = match fee_config {
+            Some(fee_config) => {
+                let fees = fee_config.fees_for_market_cap(bonding_curve.market_cap()?);
+                (fees.protocol_fee_bps, fees.creator_fee_bps)
+            },
+            None => (global.fee_basis_points, global.creator_fee_basis_points),
+        };
+        
+        let has_creator = matches!(bonding_curve.creator, Some(creator) if creator != Pubkey::default());
+        Ok(PumpFees {
+            protocol_fee_basis_points,
+            creator_fee_basis_points: if has_creator { creator_fee_basis_points } else { 0 },
+        })
+    }
+    
+    fn total_basis_points(&self) -> u64 {
+        self.protocol_fee_basis_points.saturating_add(self.creator_fee_basis_points)
+    }
+    
+    // 分别计算协议手续费和创建者手续费（与Pump.fun一致向上取整），返回总手续费
+    fn calculate(&self, amount: u64) -> Result<u64> {
+        let protocol_fee = math::fee_ceil(amount, self.protocol_fee_basis_points, FEE_BASIS_POINTS_DENOMINATOR)?;
+        let creator_fee = math::fee_ceil(amount, self.creator_fee_basis_points, FEE_BASIS_POINTS_DENOMINATOR)?;
+        
+        protocol_fee
+            .checked_add(creator_fee)
+            .ok_or(DexError::ArithmeticOverflow.into())
+    }
+}
+
+// Pump.fun报价结果
+// 买入时手续费从输入的SOL中扣除，gross_amount_out与net_amount_out相同；
+// 卖出时手续费从输出的SOL中扣除
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct PumpQuote {
+    // 用户实际支付的数量（买入为含手续费的SOL，卖出为代币）
+    pub amount_in: u64,
+    // 扣除手续费前的输出数量
+    pub gross_amount_out: u64,
+    // 手续费总额（协议手续费与创建者手续费，lamports）
+    pub fee_amount: u64,
+    // 用户实际获得的数量
+    pub net_amount_out: u64,
+    // 交易后的绑定曲线储备
+    pub new_virtual_token_reserves: u64,
+    pub new_virtual_sol_reserves: u64,
+    pub new_real_token_reserves: u64,
+    pub new_real_sol_reserves: u64,
+    // 价格影响（以FEE_DENOMINATOR为分母的百万分比，不含手续费）
+    pub price_impact: u64,
+    // 买入数量是否因剩余真实代币储备不足而被截断
+    pub capped_by_reserves: bool,
+    // 交易后绑定曲线是否完成（剩余真实代币储备为0）
+    pub completes_curve: bool,
+}
+
+// 报价: 购买指定数量的代币（精确代币输出）
+pub fn quote_buy_exact_tokens_out(
+    bonding_curve: &BondingCurve,
+    fees: &PumpFees,
+    amount_out: u64,
+) -> Result<PumpQuote> {
+    require!(amount_out > 0, DexError::InvalidArgument);
+    require!(!bonding_curve.complete, DexError::InsufficientLiquidity);
+    require!(
+        amount_out <= bonding_curve.real_token_reserves,
+        DexError::InsufficientLiquidity
+    );
+    
+    let sol_cost = calculate_buy_sol_cost_before_fee(bonding_curve, amount_out)?;
+    let fee_amount = fees.calculate(sol_cost)?;
+    let amount_in = sol_cost.checked_add(fee_amount).ok_or(DexError::ArithmeticOverflow)?;
+    
+    build_buy_quote(bonding_curve, amount_in, sol_cost, fee_amount, amount_out)
+}
+
+// 报价: 使用指定数量的SOL（含手续费）购买代币（精确SOL输入）
+pub fn quote_buy_exact_sol_in(
+    bonding_curve: &BondingCurve,
+    fees: &PumpFees,
+    sol_amount_in: u64,
+) -> Result<PumpQuote> {
+    require!(sol_amount_in > 0, DexError::InvalidArgument);
+    require!(!bonding_curve.complete, DexError::InsufficientLiquidity);
+    
+    let virtual_token_reserves = bonding_curve.virtual_token_reserves as u128;
+    let virtual_sol_reserves = bonding_curve.virtual_sol_reserves as u128;
+    
+    require!(
+        virtual_token_reserves > 0 && virtual_sol_reserves > 0,
+        DexError::InsufficientLiquidity
+    );
+    
+    // 扣除手续费后的净SOL输入: sol_amount_in * 10000 / (10000 + 总费率)
+    let mut net_sol_in = (sol_amount_in as u128)
+        .checked_mul(FEE_BASIS_POINTS_DENOMINATOR as u128)
+        .ok_or(DexError::ArithmeticOverflow)?
+        .checked_div((FEE_BASIS_POINTS_DENOMINATOR as u128) + (fees.total_basis_points() as u128))
+        .ok_or(DexError::ArithmeticOverflow)?;
+    
+    // Pump.fun的成本计算向上取整，取整误差最多几个lamports，逐步收缩净输入直到总花费不超过预算
+    for _ in 0..4 {
+        // 代币数量 = net_sol_in * virtual_token_reserves / (virtual_sol_reserves + net_sol_in)
+        let amount_out = net_sol_in
+            .checked_mul(virtual_token_reserves)
+            .ok_or(DexError::ArithmeticOverflow)?
+            .checked_div(virtual_sol_reserves + net_sol_in)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        
+        // 不能超过绑定曲线剩余的真实代币储备
+        let capped_by_reserves = amount_out > bonding_curve.real_token_reserves as u128;
+        let amount_out = std::cmp::min(amount_out, bonding_curve.real_token_reserves as u128) as u64;
+        require!(amount_out > 0, DexError::InsufficientLiquidity);
+        
+        let sol_cost = calculate_buy_sol_cost_before_fee(bonding_curve, amount_out)?;
+        let fee_amount = fees.calculate(sol_cost)?;
+        let total_cost = sol_cost.checked_add(fee_amount).ok_or(DexError::ArithmeticOverflow)?;
+        
+        if total_cost <= sol_amount_in {
+            // 报价中的amount_in为实际花费，剩余的SOL不会被Pump.fun扣除
+            let mut buy_quote = build_buy_quote(bonding_curve, total_cost, sol_cost, fee_amount, amount_out)?;
+            buy_quote.capped_by_reserves = capped_by_reserves;
+            return Ok(buy_quote);
+        }
+        
+        net_sol_in = net_sol_in.saturating_sub((total_cost - sol_amount_in) as u128);
+    }
+    
+    Err(DexError::PriceQueryFailed.into())
+}
+
+// 报价: 卖出指定数量的代币
+pub fn quote_sell(
+    bonding_curve: &BondingCurve,
+    fees: &PumpFees,
+    amount_in: u64,
+) -> Result<PumpQuote> {
+    require!(amount_in > 0, DexError::InvalidArgument);
+    require!(!bonding_curve.complete, DexError::InsufficientLiquidity);
+    
+    let virtual_token_reserves = bonding_curve.virtual_token_reserves as u128;
+    let virtual_sol_reserves = bonding_curve.virtual_sol_reserves as u128;
+    
+    require!(
+        virtual_token_reserves > 0 && virtual_sol_reserves > 0,
+        DexError::InsufficientLiquidity
+    );
+    
+    // 获得的SOL = amount_in * virtual_sol_reserves / (virtual_token_reserves + amount_in)
+    let gross_amount_out = to_u64(
+        (amount_in as u128)
+            .checked_mul(virtual_sol_reserves)
+            .ok_or(DexError::ArithmeticOverflow)?
+            .checked_div(virtual_token_reserves + amount_in as u128)
+            .ok_or(DexError::ArithmeticOverflow)?,
+    )?;
+    
+    require!(
+        gross_amount_out <= bonding_curve.real_sol_reserves,
+        DexError::InsufficientLiquidity
+    );
+    
+    let fee_amount = fees.calculate(gross_amount_out)?;
+    let net_amount_out = gross_amount_out.saturating_sub(fee_amount);
+    
+    // 价格影响 = 1 - 成交价格 / 现货价格 = 1 - gross_amount_out * vtok / (amount_in * vsol)
+    let price_impact = math::price_impact(
+        gross_amount_out as u128 * virtual_token_reserves,
+        amount_in as u128 * virtual_sol_reserves,
+    )?;
+    
+    Ok(PumpQuote {
+        amount_in,
+        gross_amount_out,
+        fee_amount,
+        net_amount_out,
+        new_virtual_token_reserves: bonding_curve.virtual_token_reserves
+            .checked_add(amount_in)
+            .ok_or(DexError::ArithmeticOverflow)?,
+        new_virtual_sol_reserves: bonding_curve.virtual_sol_reserves
+            .checked_sub(gross_amount_out)
+            .ok_or(DexError::ArithmeticOverflow)?,
+        new_real_token_reserves: bonding_curve.real_token_reserves
+            .checked_add(amount_in)
+            .ok_or(DexError::ArithmeticOverflow)?,
+        new_real_sol_reserves: bonding_curve.real_sol_reserves
+            .checked_sub(gross_amount_out)
+            .ok_or(DexError::ArithmeticOverflow)?,
+        price_impact,
+        capped_by_reserves: false,
+        completes_curve: false,
+    })
+}
+
+// 购买指定数量代币所需的SOL（不含手续费）: amount_out * vsol / (vtok - amount_out) + 1
+fn calculate_buy_sol_cost_before_fee(
+    bonding_curve: &BondingCurve,
+    amount_out: u64,
+) -> Result<u64> {
+    let virtual_token_reserves = bonding_curve.virtual_token_reserves as u128;
+    let virtual_sol_reserves = bonding_curve.virtual_sol_reserves as u128;
+    let amount_out = amount_out as u128;
+    
+    require!(amount_out < virtual_token_reserves, DexError::InsufficientLiquidity);
+    
+    let sol_cost = amount_out
+        .checked_mul(virtual_sol_reserves)
+        .ok_or(DexError::ArithmeticOverflow)?
+        .checked_div(virtual_token_reserves - amount_out)
+        .ok_or(DexError::ArithmeticOverflow)?
+        .checked_add(1)
+        .ok_or(DexError::ArithmeticOverflow)?;
+    
+    to_u64(sol_cost)
+}
+
+// 构建买入报价，更新储备并计算价格影响
+fn build_buy_quote(
+    bonding_curve: &BondingCurve,
+    amount_in: u64,
+    sol_cost: u64,
+    fee_amount: u64,
+    amount_out: u64,
+) -> Result<PumpQuote> {
+    // 价格影响 = 1 - 现货价格 / 成交价格 = 1 - amount_out * vsol / (sol_cost * vtok)
+    let numerator = (sol_cost as u128)
+        .checked_mul(bonding_curve.virtual_token_reserves as u128)
+        .ok_or(DexError::ArithmeticOverflow)?;
+    let denominator = (amount_out as u128)
+        .checked_mul(bonding_curve.virtual_sol_reserves as u128)
+        .ok_or(DexError::ArithmeticOverflow)?;
+    let price_impact = math::price_impact(denominator, numerator)?;
+    
+    let new_real_token_reserves = bonding_curve.real_token_reserves
+        .checked_sub(amount_out)
+        .ok_or(DexError::ArithmeticOverflow)?;
+    
+    Ok(PumpQuote {
+        amount_in,
+        gross_amount_out: amount_out,
+        fee_amount,
+        net_amount_out: amount_out,
+        new_virtual_token_reserves: bonding_curve.virtual_token_reserves
+            .checked_sub(amount_out)
+            .ok_or(DexError::ArithmeticOverflow)?,
+        new_virtual_sol_reserves: bonding_curve.virtual_sol_reserves
+            .checked_add(sol_cost)
+            .ok_or(DexError::ArithmeticOverflow)?,
+        new_real_token_reserves,
+        new_real_sol_reserves: bonding_curve.real_sol_reserves
+            .checked_add(sol_cost)
+            .ok_or(DexError::ArithmeticOverflow)?,
+        price_impact,
+        capped_by_reserves: false,
+        completes_curve: new_real_token_reserves == 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pumpfun::state::{FeeTier, Fees};
+    
+    // 期望值按Pump.fun程序绑定曲线的整数运算独立计算:
+    // 买入成本floor(amount * vsol / (vtok - amount)) + 1，卖出所得floor(amount * vsol / (vtok + amount))，
+    // 协议手续费和创建者手续费分别按基点向上取整
+    
+    // 新创建的绑定曲线（Global中的初始储备）
+    fn fresh_curve(creator: Option<Pubkey>) -> BondingCurve {
+        BondingCurve {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+            creator,
+        }
+    }
+    
+    // 已买入约16 SOL的绑定曲线
+    fn mid_curve(creator: Option<Pubkey>) -> BondingCurve {
+        BondingCurve {
+            virtual_token_reserves: 700_000_000_000_000,
+            virtual_sol_reserves: 45_985_714_285,
+            real_token_reserves: 420_100_000_000_000,
+            real_sol_reserves: 15_985_714_285,
+            ..fresh_curve(creator)
+        }
+    }
+    
+    // 0.95%协议手续费、0.05%创建者手续费
+    fn global() -> Global {
+        Global {
+            fee_basis_points: 95,
+            creator_fee_basis_points: 5,
+            ..Default::default()
+        }
+    }
+    
+    const CREATOR_FEES: PumpFees = PumpFees { protocol_fee_basis_points: 95, creator_fee_basis_points: 5 };
+    const NO_CREATOR_FEES: PumpFees = PumpFees { protocol_fee_basis_points: 95, creator_fee_basis_points: 0 };
+    
+    // 按市值分档: 低于50 SOL为0.95%/0.30%，50 SOL起为0.93%/0.02%，100 SOL起为0.90%/0.01%
+    fn fee_config() -> FeeConfig {
+        let fees = |protocol_fee_bps, creator_fee_bps| Fees { lp_fee_bps: 0, protocol_fee_bps, creator_fee_bps };
+        FeeConfig {
+            flat_fees: fees(100, 0),
+            fee_tiers: vec![
+                FeeTier { market_cap_lamports_threshold: 30_000_000_000, fees: fees(95, 30) },
+                FeeTier { market_cap_lamports_threshold: 50_000_000_000, fees: fees(93, 2) },
+                FeeTier { market_cap_lamports_threshold: 100_000_000_000, fees: fees(90, 1) },
+            ],
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn buy_exact_tokens_out_matches_reference() {
+        let curve = fresh_curve(Some(Pubkey::new_unique()));
+        let quote = quote_buy_exact_tokens_out(&curve, &CREATOR_FEES, 1_000_000_000_000).unwrap();
+        assert_eq!(
+            quote,
+            PumpQuote {
+                amount_in: 28_264_927,
+                gross_amount_out: 1_000_000_000_000,
+                fee_amount: 279_852,
+                net_amount_out: 1_000_000_000_000,
+                new_virtual_token_reserves: 1_072_000_000_000_000,
+                new_virtual_sol_reserves: 30_027_985_075,
+                new_real_token_reserves: 792_100_000_000_000,
+                new_real_sol_reserves: 27_985_075,
+                price_impact: 931,
+                capped_by_reserves: false,
+                completes_curve: false,
+            }
+        );
+        
+        // 不收取创建者手续费时只有协议手续费，成本相同
+        let quote = quote_buy_exact_tokens_out(&curve, &NO_CREATOR_FEES, 1_000_000_000_000).unwrap();
+        assert_eq!((quote.amount_in, quote.fee_amount), (28_250_934, 265_859));
+        assert_eq!(quote.new_virtual_sol_reserves, 30_027_985_075);
+    }
+    
+    #[test]
+    fn buy_exact_tokens_out_adds_one_and_rounds_fees_up() {
+        // 35000 * vsol / (vtok - 35000) < 1，加1后成本为1；两项手续费均不足1，各自向上取整为1
+        let quote = quote_buy_exact_tokens_out(&fresh_curve(Some(Pubkey::new_unique())), &CREATOR_FEES, 35_000).unwrap();
+        assert_eq!(quote.new_virtual_sol_reserves - 30_000_000_000, 1);
+        assert_eq!((quote.amount_in, quote.fee_amount), (3, 2));
+        
+        // 没有创建者手续费时只有协议手续费向上取整
+        let quote = quote_buy_exact_tokens_out(&fresh_curve(None), &NO_CREATOR_FEES, 35_000).unwrap();
+        assert_eq!((quote.amount_in, quote.fee_amount), (2, 1));
+    }
+    
+    #[test]
+    fn buy_exact_sol_in_matches_reference() {
+        let quote = quote_buy_exact_sol_in(&fresh_curve(Some(Pubkey::new_unique())), &CREATOR_FEES, 1_000_000_000).unwrap();
+        assert_eq!(
+            quote,
+            PumpQuote {
+                amount_in: 1_000_000_000,
+                gross_amount_out: 34_281_150_129_545,
+                fee_amount: 9_900_991,
+                net_amount_out: 34_281_150_129_545,
+                new_virtual_token_reserves: 1_038_718_849_870_455,
+                new_virtual_sol_reserves: 30_990_099_009,
+                new_real_token_reserves: 758_818_849_870_455,
+                new_real_sol_reserves: 990_099_009,
+                price_impact: 31_948,
+                capped_by_reserves: false,
+                completes_curve: false,
+            }
+        );
+    }
+    
+    #[test]
+    fn buy_exact_sol_in_shrinks_net_input_within_budget() {
+        // 按费率反推的净输入990099053对应的总花费为1000000045，超出预算1，收缩一次后在预算内
+        let quote = quote_buy_exact_sol_in(&fresh_curve(Some(Pubkey::new_unique())), &CREATOR_FEES, 1_000_000_044).unwrap();
+        assert_eq!(quote.amount_in, 1_000_000_043);
+        assert_eq!(quote.net_amount_out, 34_281_151_570_809);
+        assert_eq!(quote.fee_amount, 9_900_991);
+        assert_eq!(quote.new_virtual_sol_reserves, 30_990_099_052);
+    }
+    
+    #[test]
+    fn buy_exact_sol_in_caps_at_real_token_reserves() {
+        let curve = BondingCurve {
+            real_token_reserves: 1_000_000_000_000,
+            ..fresh_curve(Some(Pubkey::new_unique()))
+        };
+        let quote = quote_buy_exact_sol_in(&curve, &CREATOR_FEES, 1_000_000_000).unwrap();
+        
+        // 截断后的花费与精确买入剩余全部代币相同
+        let mut expected = quote_buy_exact_tokens_out(&curve, &CREATOR_FEES, 1_000_000_000_000).unwrap();
+        expected.capped_by_reserves = true;
+        assert_eq!(quote, expected);
+        assert_eq!(quote.amount_in, 28_264_927);
+        assert!(quote.completes_curve);
+    }
+    
+    #[test]
+    fn sell_matches_reference() {
+        let quote = quote_sell(&mid_curve(Some(Pubkey::new_unique())), &CREATOR_FEES, 10_000_000_000_000).unwrap();
+        assert_eq!(
+            quote,
+            PumpQuote {
+                amount_in: 10_000_000_000_000,
+                gross_amount_out: 647_686_116,
+                fee_amount: 6_476_863,
+                net_amount_out: 641_209_253,
+                new_virtual_token_reserves: 710_000_000_000_000,
+                new_virtual_sol_reserves: 45_338_028_169,
+                new_real_token_reserves: 430_100_000_000_000,
+                new_real_sol_reserves: 15_338_028_169,
+                price_impact: 14_084,
+                capped_by_reserves: false,
+                completes_curve: false,
+            }
+        );
+        
+        // 不收取创建者手续费时只有协议手续费ceil(647686116 * 95 / 10000)
+        let quote = quote_sell(&mid_curve(None), &NO_CREATOR_FEES, 10_000_000_000_000).unwrap();
+        assert_eq!((quote.fee_amount, quote.net_amount_out), (6_153_019, 641_533_097));
+    }
+    
+    #[test]
+    fn fees_use_global_without_fee_config() {
+        assert_eq!(PumpFees::new(&global(), &fresh_curve(Some(Pubkey::new_unique())), None).unwrap(), CREATOR_FEES);
+        // V1布局的绑定曲线没有creator，不收取创建者手续费
+        assert_eq!(PumpFees::new(&global(), &fresh_curve(None), None).unwrap(), NO_CREATOR_FEES);
+    }
+    
+    #[test]
+    fn fees_follow_fee_config_market_cap_tier() {
+        let fee_config = fee_config();
+        let creator = Some(Pubkey::new_unique());
+        
+        // 新绑定曲线的市值为27958993476 lamports，低于第一档门槛，使用第一档
+        assert_eq!(fresh_curve(creator).market_cap().unwrap(), 27_958_993_476);
+        assert_eq!(
+            PumpFees::new(&global(), &fresh_curve(creator), Some(&fee_config)).unwrap(),
+            PumpFees { protocol_fee_basis_points: 95, creator_fee_basis_points: 30 }
+        );
+        
+        // 市值65693877550 lamports，使用50 SOL一档
+        assert_eq!(mid_curve(creator).market_cap().unwrap(), 65_693_877_550);
+        let fees = PumpFees::new(&global(), &mid_curve(creator), Some(&fee_config)).unwrap();
+        assert_eq!(fees, PumpFees { protocol_fee_basis_points: 93, creator_fee_basis_points: 2 });
+        let quote = quote_buy_exact_tokens_out(&mid_curve(creator), &fees, 1_000_000_000_000).unwrap();
+        assert_eq!((quote.amount_in, quote.fee_amount), (66_412_847, 624_986));
+        
+        // 没有creator时不收取分档中的创建者手续费
+        assert_eq!(
+            PumpFees::new(&global(), &mid_curve(None), Some(&fee_config)).unwrap(),
+            PumpFees { protocol_fee_basis_points: 93, creator_fee_basis_points: 0 }
+        );
+        
+        // 未配置分档时使用统一费率
+        let flat = FeeConfig { fee_tiers: vec![], ..fee_config };
+        assert_eq!(
+            PumpFees::new(&global(), &mid_curve(creator), Some(&flat)).unwrap(),
+            PumpFees { protocol_fee_basis_points: 100, creator_fee_basis_points: 0 }
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use anchor_lang::prelude::*;
+use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
+use crate::codec::strip_discriminator;
+use crate::DexError;
+use super::get_pump_program_id;
+
+// Pump.fun账户discriminator（Anchor规则: sha256("account:<账户名>")的前8字节）
+pub const GLOBAL_DISCRIMINATOR: [u8; 8] = [167, 232, 232, 177, 200, 108, 114, 127];
+pub const BONDING_CURVE_DISCRIMINATOR: [u8; 8] = [23, 183, 248, 55, 96, 216, 172, 96];
+pub const FEE_CONFIG_DISCRIMINATOR: [u8; 8] = [143, 52, 146, 187, 219, 123, 76, 155];
+
+// Pump.fun手续费程序ID（fee_config账户所属程序）
+pub const PUMP_FEE_PROGRAM_ID: &str = "pfeeUxB6jkeY1Hxd7CsFCAjcbHA9rWtchMGdZ6VojVZ";
+
+// Global中额外手续费接收账户的数量
+pub const FEE_RECIPIENTS_LEN: usize = 7;
+
+// 获取Pump.fun手续费程序ID
+pub fn get_pump_fee_program_id() -> Pubkey {
+    Pubkey::from_str(PUMP_FEE_PROGRAM_ID).unwrap()
+}
+
+// Pump.fun账户布局版本
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PumpLayoutVersion {
+    // 原始布局
+    #[default]
+    V1,
+    // 增加创建者相关字段（Global的创建者费率/手续费接收账户，BondingCurve的creator）
+    V2,
+}
+
+// Pump.fun的Global账户（兼容新旧布局）
+#[derive(Clone, Debug, Default)]
+pub struct Global {
+    pub initialized: bool,
+    pub authority: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub initial_virtual_token_reserves: u64,
+    pub initial_virtual_sol_reserves: u64,
+    pub initial_real_token_reserves: u64,
+    pub token_total_supply: u64,
+    pub fee_basis_points: u64,
+    // 以下字段仅在V2布局中存在，旧布局下为默认值
+    pub withdraw_authority: Pubkey,
+    pub enable_migrate: bool,
+    pub pool_migration_fee: u64,
+    pub creator_fee_basis_points: u64,
+    pub fee_recipients: [Pubkey; FEE_RECIPIENTS_LEN],
+    pub version: PumpLayoutVersion,
+}
+
+// Global的V1字段
+#[derive(AnchorDeserialize)]
+struct GlobalV1 {
+    initialized: bool,
+    authority: Pubkey,
+    fee_recipient: Pubkey,
+    initial_virtual_token_reserves: u64,
+    initial_virtual_sol_reserves: u64,
+    initial_real_token_reserves: u64,
+    token_total_supply: u64,
+    fee_basis_points: u64,
+}
+
+// Global在V2中追加的字段
+#[derive(AnchorDeserialize)]
+struct GlobalV2Extension {
+    withdraw_authority: Pubkey,
+    enable_migrate: bool,
+    pool_migration_fee: u64,
+    creator_fee_basis_points: u64,
+    fee_recipients: [Pubkey; FEE_RECIPIENTS_LEN],
+}
+
+impl Global {
+    // 从账户数据反序列化，检查discriminator并兼容新旧布局
+    pub fn try_deserialize_versioned(data: &[u8]) -> Result<Self> {
+        let mut data = strip_discriminator(data, &GLOBAL_DISCRIMINATOR, DexError::InvalidPumpAccount)?;
+        
+        let v1 = GlobalV1::deserialize(&mut data)
+            .map_err(|_| DexError::InvalidPumpAccount)?;
+        
+        let mut global = Global {
+            initialized: v1.initialized,
+            authority: v1.authority,
+            fee_recipient: v1.fee_recipient,
+            initial_virtual_token_reserves: v1.initial_virtual_token_reserves,
+            initial_virtual_sol_reserves: v1.initial_virtual_sol_reserves,
+            initial_real_token_reserves: v1.initial_real_token_reserves,
+            token_total_supply: v1.token_total_supply,
+            fee_basis_points: v1.fee_basis_points,
+            ..Default::default()
+        };
+        
+        // 剩余数据足够时读取V2字段，未知的尾部字段直接忽略
+        if let Ok(v2) = GlobalV2Extension::deserialize(&mut data) {
+            global.withdraw_authority = v2.withdraw_authority;
+            global.enable_migrate = v2.enable_migrate;
+            global.pool_migration_fee = v2.pool_migration_fee;
+            global.creator_fee_basis_points = v2.creator_fee_basis_points;
+            global.fee_recipients = v2.fee_recipients;
+            global.version = PumpLayoutVersion::V2;
+        }
+        
+        Ok(global)
+    }
+    
+    // 从账户加载，检查所有者为Pump.fun程序
+    pub fn try_from_account_info(account_info: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(*account_info.owner, get_pump_program_id(), DexError::InvalidPumpAccount);
+        let data = account_info.try_borrow_data()?;
+        Self::try_deserialize_versioned(&data)
+    }
+    
+    // 检查是否为有效的手续费接收账户
+    pub fn is_fee_recipient(&self, key: &Pubkey) -> bool {
+        self.fee_recipient == *key
+            || self.fee_recipients.iter().any(|recipient| *recipient != Pubkey::default() && recipient == key)
+    }
+}
+
+// Pump.fun的BondingCurve账户（兼容新旧布局）
+#[derive(Clone, Debug, Default)]
+pub struct BondingCurve {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+    // 仅在V2布局中存在，旧布局下为None
+    pub creator: Option<Pubkey>,
+}
+
+// BondingCurve的V1字段
+#[derive(AnchorDeserialize)]
+struct BondingCurveV1 {
+    virtual_token_reserves: u64,
+    virtual_sol_reserves: u64,
+    real_token_reserves: u64,
+    real_sol_reserves: u64,
+    token_total_supply: u64,
+    complete: bool,
+}
+
+impl BondingCurve {
+    // 从账户数据反序列化，检查discriminator并兼容新旧布局
+    pub fn try_deserialize_versioned(data: &[u8]) -> Result<Self> {
+        let mut data = strip_discriminator(data, &BONDING_CURVE_DISCRIMINATOR, DexError::InvalidPumpAccount)?;
+        
+        let v1 = BondingCurveV1::deserialize(&mut data)
+            .map_err(|_| DexError::InvalidPumpAccount)?;
+        
+        // 剩余数据足够时读取V2的creator字段
+        let creator = Pubkey::deserialize(&mut data).ok();
+        
+        Ok(BondingCurve {
+            virtual_token_reserves: v1.virtual_token_reserves,
+            virtual_sol_reserves: v1.virtual_sol_reserves,
+            real_token_reserves: v1.real_token_reserves,
+            real_sol_reserves: v1.real_sol_reserves,
+            token_total_supply: v1.token_total_supply,
+            complete: v1.complete,
+            creator,
+        })
+    }
+    
+    // 从账户加载，检查所有者为Pump.fun程序
+    pub fn try_from_account_info(account_info: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(*account_info.owner, get_pump_program_id(), DexError::InvalidPumpAccount);
+        let data = account_info.try_borrow_data()?;
+        Self::try_deserialize_versioned(&data)
+    }
+    
+    // 按虚拟储备计算的市值（lamports）: virtual_sol_reserves * token_total_supply / virtual_token_reserves
+    pub fn market_cap(&self) -> Result<u128> {
+        require!(self.virtual_token_reserves > 0, DexError::InsufficientLiquidity);
+        
+        Ok((self.virtual_sol_reserves as u128)
+            .checked_mul(self.token_total_supply as u128)
+            .ok_or(DexError::ArithmeticOverflow)?
+            / self.virtual_token_reserves as u128)
+    }
+    
+    // 账户布局版本
+    pub fn version(&self) -> PumpLayoutVersion {
+        if self.creator.is_some() {
+            PumpLayoutVersion::V2
+        } else {
+            PumpLayoutVersion::V1
+        }
+    }
+}
+
+// Pump.fun手续费程序中的一组费率（基点）
+#[derive(AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Fees {
+    // 只用于PumpSwap池子，绑定曲线不收取
+    pub lp_fee_bps: u64,
+    pub protocol_fee_bps: u64,
+    pub creator_fee_bps: u64,
+}
+
+// 按市值分档的费率，市值达到门槛（lamports）后适用
+#[derive(AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct FeeTier {
+    pub market_cap_lamports_threshold: u128,
+    pub fees: Fees,
+}
+
+// Pump.fun手续费程序的FeeConfig账户，新版本账户列表中绑定曲线按其中的市值分档费率收费
+#[derive(AnchorDeserialize, Clone, Debug, Default)]
+pub struct FeeConfig {
+    pub bump: u8,
+    pub admin: Pubkey,
+    pub flat_fees: Fees,
+    pub fee_tiers: Vec<FeeTier>,
+}
+
+impl FeeConfig {
+    // 从账户数据反序列化，检查discriminator，未知的尾部字段直接忽略
+    pub fn try_deserialize_checked(data: &[u8]) -> Result<Self> {
+        let mut data = strip_discriminator(data, &FEE_CONFIG_DISCRIMINATOR, DexError::InvalidPumpAccount)?;
+        FeeConfig::deserialize(&mut data).map_err(|_| DexError::InvalidPumpAccount.into())
+    }
+    
+    // 从账户加载，检查账户为Pump.fun的手续费配置PDA且所有者为手续费程序
+    pub fn try_from_account_info(account_info: &AccountInfo) -> Result<Self> {
+        let (fee_config, _) = get_fee_config_pda();
+        require_keys_eq!(account_info.key(), fee_config, DexError::InvalidPumpAccount);
+        require_keys_eq!(*account_info.owner, get_pump_fee_program_id(), DexError::InvalidPumpAccount);
+        let data = account_info.try_borrow_data()?;
+        Self::try_deserialize_checked(&data)
+    }
+    
+    // 按市值选择费率: 低于第一档门槛时使用第一档，否则使用门槛不超过市值的最高一档，未配置分档时使用统一费率
+    pub fn fees_for_market_cap(&self, market_cap: u128) -> Fees {
+        let first_tier = match self.fee_tiers.first() {
+            Some(tier) => tier,
+            None => return self.flat_fees,
+        };
+        
+        self.fee_tiers
+            .iter()
+            .rev()
+            .find(|tier| market_cap >= tier.market_cap_lamports_threshold)
+            .unwrap_or(first_tier)
+            .fees
+    }
+}
+
+// 计算创建者金库PDA: ["creator-vault", creator]
+pub fn get_creator_vault_pda(creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"creator-vault", creator.as_ref()], &get_pump_program_id())
+}
+
+// 计算全局交易量累加器PDA: ["global_volume_accumulator"]
+pub fn get_global_volume_accumulator_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"global_volume_accumulator"], &get_pump_program_id())
+}
+
+// 计算用户交易量累加器PDA: ["user_volume_accumulator", user]
+pub fn get_user_volume_accumulator_pda(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"user_volume_accumulator", user.as_ref()], &get_pump_program_id())
+}
+
+// 计算手续费配置PDA: ["fee_config", pump_program]（属于Pump.fun手续费程序）
+pub fn get_fee_config_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"fee_config", get_pump_program_id().as_ref()],
+        &get_pump_fee_program_id(),
+    )
+}
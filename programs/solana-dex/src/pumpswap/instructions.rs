@@ -0,0 +1,168 @@
+use anchor_lang::prelude::*;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use std::str::FromStr;
+use crate::codec::encode_instruction_data;
+use crate::DexError;
+use crate::pumpfun::instructions::PumpAccountsVersion;
+
+// PumpSwap指令discriminator（Anchor规则: sha256("global:<指令名>")的前8字节）
+pub const BUY_DISCRIMINATOR: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+pub const SELL_DISCRIMINATOR: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+
+// PumpSwap事件权限账户（PDA: ["__event_authority"]）
+pub const PUMPSWAP_EVENT_AUTHORITY: &str = "GS4CU59F31iL7aR2Q8zVS8DRrcRnXX1yjQ66TqNVQnaR";
+
+// 获取PumpSwap事件权限账户
+pub fn get_pumpswap_event_authority() -> Pubkey {
+    Pubkey::from_str(PUMPSWAP_EVENT_AUTHORITY).unwrap()
+}
+
+// buy指令参数（精确基础代币输出）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct BuyArgs {
+    pub base_amount_out: u64,
+    pub max_quote_amount_in: u64,
+}
+
+// sell指令参数（精确基础代币输入）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SellArgs {
+    pub base_amount_in: u64,
+    pub min_quote_amount_out: u64,
+}
+
+// 新版本账户列表中追加的账户（版本划分与Pump.fun一致）
+#[derive(Clone, Default)]
+pub struct VersionedAccounts {
+    pub coin_creator_vault_ata: Option<Pubkey>,
+    pub coin_creator_vault_authority: Option<Pubkey>,
+    pub global_volume_accumulator: Option<Pubkey>,
+    pub user_volume_accumulator: Option<Pubkey>,
+    pub fee_config: Option<Pubkey>,
+    pub fee_program: Option<Pubkey>,
+}
+
+impl VersionedAccounts {
+    // 根据提供的账户推断账户列表版本
+    pub fn version(&self) -> PumpAccountsVersion {
+        if self.coin_creator_vault_ata.is_none() || self.coin_creator_vault_authority.is_none() {
+            PumpAccountsVersion::Legacy
+        } else if self.global_volume_accumulator.is_none() || self.user_volume_accumulator.is_none() {
+            PumpAccountsVersion::CreatorVault
+        } else if self.fee_config.is_none() || self.fee_program.is_none() {
+            PumpAccountsVersion::VolumeAccumulator
+        } else {
+            PumpAccountsVersion::FeeConfig
+        }
+    }
+}
+
+// 获取指定版本所需的可选账户
+fn required(account: Option<Pubkey>) -> Result<Pubkey> {
+    account.ok_or(error!(DexError::MissingPumpSwapAccount))
+}
+
+// buy/sell指令共用的账户（顺序与PumpSwap IDL一致）
+pub struct SwapAccounts {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub global_config: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub user_base_token_account: Pubkey,
+    pub user_quote_token_account: Pubkey,
+    pub pool_base_token_account: Pubkey,
+    pub pool_quote_token_account: Pubkey,
+    pub protocol_fee_recipient: Pubkey,
+    pub protocol_fee_recipient_token_account: Pubkey,
+    pub base_token_program: Pubkey,
+    pub quote_token_program: Pubkey,
+    pub system_program: Pubkey,
+    pub associated_token_program: Pubkey,
+    pub event_authority: Pubkey,
+    pub program: Pubkey,
+    pub versioned: VersionedAccounts,
+}
+
+impl SwapAccounts {
+    // is_buy为true时追加交易量累加器（sell不使用）
+    pub fn to_account_metas(&self, version: PumpAccountsVersion, is_buy: bool) -> Result<Vec<AccountMeta>> {
+        let mut metas = vec![
+            AccountMeta::new(self.pool, false),
+            AccountMeta::new(self.user, true),
+            AccountMeta::new_readonly(self.global_config, false),
+            AccountMeta::new_readonly(self.base_mint, false),
+            AccountMeta::new_readonly(self.quote_mint, false),
+            AccountMeta::new(self.user_base_token_account, false),
+            AccountMeta::new(self.user_quote_token_account, false),
+            AccountMeta::new(self.pool_base_token_account, false),
+            AccountMeta::new(self.pool_quote_token_account, false),
+            AccountMeta::new_readonly(self.protocol_fee_recipient, false),
+            AccountMeta::new(self.protocol_fee_recipient_token_account, false),
+            AccountMeta::new_readonly(self.base_token_program, false),
+            AccountMeta::new_readonly(self.quote_token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new_readonly(self.associated_token_program, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new_readonly(self.program, false),
+        ];
+        
+        if version >= PumpAccountsVersion::CreatorVault {
+            metas.push(AccountMeta::new(required(self.versioned.coin_creator_vault_ata)?, false));
+            metas.push(AccountMeta::new_readonly(required(self.versioned.coin_creator_vault_authority)?, false));
+        }
+        
+        if is_buy && version >= PumpAccountsVersion::VolumeAccumulator {
+            metas.push(AccountMeta::new(required(self.versioned.global_volume_accumulator)?, false));
+            metas.push(AccountMeta::new(required(self.versioned.user_volume_accumulator)?, false));
+        }
+        
+        if version >= PumpAccountsVersion::FeeConfig {
+            metas.push(AccountMeta::new_readonly(required(self.versioned.fee_config)?, false));
+            metas.push(AccountMeta::new_readonly(required(self.versioned.fee_program)?, false));
+        }
+        
+        Ok(metas)
+    }
+}
+
+// 编码buy指令数据
+pub fn encode_buy_data(args: &BuyArgs) -> Vec<u8> {
+    encode_instruction_data(&BUY_DISCRIMINATOR, args)
+}
+
+// 编码sell指令数据
+pub fn encode_sell_data(args: &SellArgs) -> Vec<u8> {
+    encode_instruction_data(&SELL_DISCRIMINATOR, args)
+}
+
+// 构建buy指令
+pub fn buy(
+    program_id: Pubkey,
+    accounts: &SwapAccounts,
+    args: &BuyArgs,
+    version: PumpAccountsVersion,
+) -> Result<Instruction> {
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(version, true)?,
+        data: encode_buy_data(args),
+    })
+}
+
+// 构建sell指令
+pub fn sell(
+    program_id: Pubkey,
+    accounts: &SwapAccounts,
+    args: &SellArgs,
+    version: PumpAccountsVersion,
+) -> Result<Instruction> {
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(version, false)?,
+        data: encode_sell_data(args),
+    })
+}
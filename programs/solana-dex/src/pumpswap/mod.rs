@@ -0,0 +1,791 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+use anchor_spl::token_interface;
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
+use solana_program::{
+    instruction::Instruction,
+    program::invoke,
+    pubkey::Pubkey,
+};
+use std::str::FromStr;
+use crate::pumpfun::instructions::PumpAccountsVersion;
+use crate::fill::{read_balance, BalanceSnapshot, Fill, FillLimits, FillPair};
+use crate::math::{Price, SOL_DECIMALS};
+use crate::router::RouteQuoter;
+use crate::{token_utils, DexError, DexType, MAX_PRICE_IMPACT};
+
+pub mod instructions;
+pub mod quote;
+pub mod state;
+
+pub use state::{GlobalConfig, Pool};
+
+// PumpSwap（Pump.fun AMM）程序ID
+pub const PUMPSWAP_PROGRAM_ID: &str = "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA";
+
+// 获取PumpSwap程序ID
+pub fn get_pumpswap_program_id() -> Pubkey {
+    Pubkey::from_str(PUMPSWAP_PROGRAM_ID).unwrap()
+}
+
+// 在PumpSwap上购买代币（精确SOL输入）
+pub fn buy_token(
+    ctx: Context<TradeTokenPumpSwap>,
+    amount_in: u64,  // 输入的SOL数量（包含手续费）
+    min_amount_out: u64,  // 最小获得的代币数量（滑点控制）
+) -> Result<Fill> {
+    msg!("在PumpSwap上购买代币: {} SOL, 最小获得代币数量: {}", amount_in, min_amount_out);
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    // 检查参数
+    require!(amount_in > 0, DexError::InvalidArgument);
+    require!(min_amount_out > 0, DexError::InvalidArgument);
+    
+    // 记录WSOL账户交易前的余额，交易后只解包超出交易前余额的部分，不动用账户中原有的WSOL
+    let wsol_balance_before = token_utils::balance_before_trade(&ctx.accounts.user_quote_token_account)?;
+    
+    // 用户代币账户和WSOL账户不存在时在同一指令中创建
+    ctx.accounts.ensure_user_base_token_account()?;
+    ctx.accounts.ensure_user_quote_token_account()?;
+    
+    // 检查用户是否有足够的SOL
+    require!(ctx.accounts.user.lamports() >= amount_in, DexError::InsufficientFunds);
+    
+    let (global_config, pool) = ctx.accounts.load_pool_state()?;
+    require!(
+        global_config.disable_flags & GlobalConfig::DISABLE_BUY == 0,
+        DexError::TransactionRejected
+    );
+    
+    let buy_quote = quote::quote_buy_exact_quote_in(
+        ctx.accounts.pool_base_token_account.amount,
+        ctx.accounts.pool_quote_token_account.amount,
+        &pool_fees(&global_config, &pool),
+        amount_in,
+    )?;
+    
+    msg!("预计获得代币数量: {}, 预计花费: {}, 手续费: {}, 价格影响: {}%",
+        buy_quote.net_amount_out,
+        buy_quote.amount_in,
+        buy_quote.fee_amount,
+        buy_quote.price_impact as f64 / 10_000.0
+    );
+    
+    // 检查价格影响和滑点
+    require!(buy_quote.price_impact <= MAX_PRICE_IMPACT, DexError::ExcessivePriceImpact);
+    require!(buy_quote.net_amount_out >= min_amount_out, DexError::SlippageExceeded);
+    
+    // PumpSwap以WSOL作为报价代币，从用户的SOL中包装全部输入，未用完的部分交易后解包退还
+    token_utils::wrap_sol(
+        &ctx.accounts.user.to_account_info(),
+        &ctx.accounts.user_quote_token_account.to_account_info(),
+        amount_in,
+        &ctx.accounts.system_program.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+    )?;
+    
+    // PumpSwap的buy指令为精确代币输出，以输入SOL作为最大花费
+    let versioned = ctx.accounts.versioned_accounts(&pool)?;
+    let swap_ix = instructions::buy(
+        get_pumpswap_program_id(),
+        &ctx.accounts.swap_accounts(versioned.keys()),
+        &instructions::BuyArgs {
+            base_amount_out: buy_quote.net_amount_out,
+            max_quote_amount_in: amount_in,
+        },
+        versioned.version(),
+    )?;
+    let snapshot = ctx.accounts.balance_snapshot(true)?;
+    ctx.accounts.invoke_swap(&swap_ix, &versioned)?;
+    
+    // 按实际余额变化检查成交数量
+    let fill = ctx.accounts.settle_fill(&snapshot, true, &FillLimits {
+        expected_amount_in: buy_quote.amount_in,
+        expected_amount_out: buy_quote.net_amount_out,
+        max_amount_in: amount_in,
+        min_amount_out,
+    })?;
+    
+    // 交易完成后将WSOL解包为SOL
+    ctx.accounts.unwrap_quote_token_account(wsol_balance_before)?;
+    
+    msg!("交易完成，获得代币");
+    Ok(fill)
+}
+
+// 在PumpSwap上卖出代币
+pub fn sell_token(
+    ctx: Context<TradeTokenPumpSwap>,
+    amount_in: u64,  // 输入的代币数量
+    min_amount_out: u64,  // 最小获得的SOL数量（滑点控制）
+    close_token_account: bool,  // 全部卖出后是否关闭用户代币账户以回收租金
+) -> Result<Fill> {
+    msg!("在PumpSwap上卖出代币: {} 代币, 最小获得SOL数量: {}", amount_in, min_amount_out);
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    // 检查参数
+    require!(amount_in > 0, DexError::InvalidArgument);
+    require!(min_amount_out > 0, DexError::InvalidArgument);
+    
+    // 检查用户代币账户是否有足够的代币
+    let user_base_token_account = token_utils::read_token_account(&ctx.accounts.user_base_token_account)?;
+    require_keys_eq!(user_base_token_account.mint, ctx.accounts.token_mint.key(), DexError::InvalidTokenAccount);
+    require_keys_eq!(user_base_token_account.owner, ctx.accounts.user.key(), DexError::InvalidTokenAccount);
+    require!(
+        user_base_token_account.amount >= amount_in,
+        DexError::InsufficientFunds
+    );
+    
+    // 记录WSOL账户交易前的余额，交易后只解包卖出所得的WSOL
+    let wsol_balance_before = token_utils::balance_before_trade(&ctx.accounts.user_quote_token_account)?;
+    
+    // 用户WSOL账户不存在时在同一指令中创建
+    ctx.accounts.ensure_user_quote_token_account()?;
+    
+    let (global_config, pool) = ctx.accounts.load_pool_state()?;
+    require!(
+        global_config.disable_flags & GlobalConfig::DISABLE_SELL == 0,
+        DexError::TransactionRejected
+    );
+    
+    let sell_quote = quote::quote_sell(
+        ctx.accounts.pool_base_token_account.amount,
+        ctx.accounts.pool_quote_token_account.amount,
+        &pool_fees(&global_config, &pool),
+        amount_in,
+    )?;
+    
+    msg!("预计获得SOL: {}, 手续费: {}, 价格影响: {}%",
+        sell_quote.net_amount_out,
+        sell_quote.fee_amount,
+        sell_quote.price_impact as f64 / 10_000.0
+    );
+    
+    // 检查价格影响和滑点
+    require!(sell_quote.price_impact <= MAX_PRICE_IMPACT, DexError::ExcessivePriceImpact);
+    require!(sell_quote.net_amount_out >= min_amount_out, DexError::SlippageExceeded);
+    
+    // 卖出所得为WSOL，转入用户的WSOL账户
+    let versioned = ctx.accounts.versioned_accounts(&pool)?;
+    let swap_ix = instructions::sell(
+        get_pumpswap_program_id(),
+        &ctx.accounts.swap_accounts(versioned.keys()),
+        &instructions::SellArgs {
+            base_amount_in: amount_in,
+            min_quote_amount_out: min_amount_out,
+        },
+        versioned.version(),
+    )?;
+    let snapshot = ctx.accounts.balance_snapshot(false)?;
+    ctx.accounts.invoke_swap(&swap_ix, &versioned)?;
+    
+    // 按实际余额变化检查成交数量
+    let fill = ctx.accounts.settle_fill(&snapshot, false, &FillLimits {
+        expected_amount_in: amount_in,
+        expected_amount_out: sell_quote.net_amount_out,
+        max_amount_in: amount_in,
+        min_amount_out,
+    })?;
+    
+    // 交易完成后将WSOL解包为SOL
+    ctx.accounts.unwrap_quote_token_account(wsol_balance_before)?;
+    
+    // 全部卖出后可选地关闭用户代币账户
+    if close_token_account {
+        let token_balance_after = token_utils::read_token_account(&ctx.accounts.user_base_token_account)?.amount;
+        if token_balance_after == 0 {
+            token_utils::close_empty_token_account(
+                &ctx.accounts.user.to_account_info(),
+                &ctx.accounts.user_base_token_account.to_account_info(),
+                &ctx.accounts.token_program.to_account_info(),
+            )?;
+        }
+    }
+    
+    msg!("交易完成，获得SOL");
+    Ok(fill)
+}
+
+// 检查代币是否在PumpSwap上（以WSOL为报价代币的池子）
+pub fn is_token_on_pumpswap(
+    ctx: Context<CheckTokenLocation>,
+    token_mint: Pubkey,
+) -> Result<bool> {
+    // 记录查询信息
+    msg!("检查代币是否在PumpSwap上: {}", token_mint);
+    
+    let pool_account_info = ctx.accounts.pool.to_account_info();
+    
+    // 池子账户不存在或不属于PumpSwap时，代币不在PumpSwap上
+    if pool_account_info.data_is_empty() || *pool_account_info.owner != get_pumpswap_program_id() {
+        msg!("PumpSwap池子账户不存在，代币不在PumpSwap上");
+        return Ok(false);
+    }
+    
+    let pool = match Pool::try_deserialize_versioned(&pool_account_info.try_borrow_data()?) {
+        Ok(pool) => pool,
+        Err(_) => {
+            msg!("账户不是PumpSwap池子，代币不在PumpSwap上");
+            return Ok(false);
+        }
+    };
+    
+    // 检查池子是否为该代币与WSOL的交易对
+    if pool.base_mint != token_mint || pool.quote_mint != spl_token::native_mint::id() {
+        msg!("PumpSwap池子交易对不匹配: {} / {}", pool.base_mint, pool.quote_mint);
+        return Ok(false);
+    }
+    
+    // 检查池子金库并读取储备
+    if ctx.accounts.pool_base_token_account.key() != pool.pool_base_token_account
+        || ctx.accounts.pool_quote_token_account.key() != pool.pool_quote_token_account
+    {
+        msg!("PumpSwap池子金库账户不匹配");
+        return Ok(false);
+    }
+    
+    let base_reserve = read_balance(&ctx.accounts.pool_base_token_account)?;
+    let quote_reserve = read_balance(&ctx.accounts.pool_quote_token_account)?;
+    
+    // 检查池子是否有足够的流动性
+    if base_reserve == 0 || quote_reserve == 0 {
+        msg!("PumpSwap池子流动性不足，代币不可交易");
+        return Ok(false);
+    }
+    
+    msg!("代币在PumpSwap上可用，代币储备: {}, SOL储备: {}", base_reserve, quote_reserve);
+    Ok(true)
+}
+
+// 获取PumpSwap上的报价
+pub fn get_quote(
+    ctx: Context<GetPriceContext>,
+    amount_in: u64,
+    is_buy: bool,  // true表示买入（输入SOL），false表示卖出（输入代币）
+) -> Result<quote::PumpSwapQuote> {
+    // 检查输入金额是否大于0
+    require!(amount_in > 0, DexError::InvalidArgument);
+    
+    // 记录查询信息
+    msg!("查询PumpSwap上的报价: 输入金额 {}, 操作类型: {}",
+        amount_in,
+        if is_buy { "买入" } else { "卖出" }
+    );
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    let (global_config, pool) = load_pool(
+        &ctx.accounts.pool,
+        &ctx.accounts.global_config,
+        &ctx.accounts.token_mint.key(),
+        &ctx.accounts.pool_base_token_account.key(),
+        &ctx.accounts.pool_quote_token_account.key(),
+    )?;
+    
+    let base_reserve = ctx.accounts.pool_base_token_account.amount;
+    let quote_reserve = ctx.accounts.pool_quote_token_account.amount;
+    let fees = pool_fees(&global_config, &pool);
+    
+    msg!("池子代币储备: {}", base_reserve);
+    msg!("池子SOL储备: {}", quote_reserve);
+    
+    let pumpswap_quote = if is_buy {
+        quote::quote_buy_exact_quote_in(base_reserve, quote_reserve, &fees, amount_in)?
+    } else {
+        quote::quote_sell(base_reserve, quote_reserve, &fees, amount_in)?
+    };
+    
+    msg!("PumpSwap报价: 实际输入 {}, 毛输出 {}, 手续费 {}, 净输出 {}, 价格影响: {}%",
+        pumpswap_quote.amount_in,
+        pumpswap_quote.gross_amount_out,
+        pumpswap_quote.fee_amount,
+        pumpswap_quote.net_amount_out,
+        pumpswap_quote.price_impact as f64 / 10_000.0
+    );
+    
+    Ok(pumpswap_quote)
+}
+
+// 获取PumpSwap上的价格（每单位输入可获得的净输出，按两侧代币的小数位数换算）
+pub fn get_price(
+    ctx: Context<GetPriceContext>,
+    amount_in: u64,
+    is_buy: bool,  // true表示买入，false表示卖出
+) -> Result<Price> {
+    let token_decimals = ctx.accounts.token_mint.decimals;
+    let pumpswap_quote = get_quote(ctx, amount_in, is_buy)?;
+    
+    // 计算价格（买入时输入为SOL，卖出时输入为代币）
+    let price = if is_buy {
+        Price::from_amounts(amount_in, SOL_DECIMALS, pumpswap_quote.net_amount_out, token_decimals)?
+    } else {
+        Price::from_amounts(amount_in, token_decimals, pumpswap_quote.net_amount_out, SOL_DECIMALS)?
+    };
+    
+    msg!("PumpSwap上的最终价格: {}", price.as_f64());
+    Ok(price)
+}
+
+// 路由报价器: 读取一次池子储备，按输入数量计算净输出（买入为代币，卖出为SOL）
+pub fn route_quoter<'info>(accounts: &GetPriceContext<'info>, is_buy: bool) -> Result<RouteQuoter<'info>> {
+    let (global_config, pool) = load_pool(
+        &accounts.pool,
+        &accounts.global_config,
+        &accounts.token_mint.key(),
+        &accounts.pool_base_token_account.key(),
+        &accounts.pool_quote_token_account.key(),
+    )?;
+    
+    let base_reserve = accounts.pool_base_token_account.amount;
+    let quote_reserve = accounts.pool_quote_token_account.amount;
+    let fees = pool_fees(&global_config, &pool);
+    
+    Ok(Box::new(move |amount_in| {
+        let pumpswap_quote = if is_buy {
+            quote::quote_buy_exact_quote_in(base_reserve, quote_reserve, &fees, amount_in)?
+        } else {
+            quote::quote_sell(base_reserve, quote_reserve, &fees, amount_in)?
+        };
+        Ok(pumpswap_quote.net_amount_out)
+    }))
+}
+
+// 加载并校验PumpSwap的GlobalConfig与Pool（兼容新旧布局）
+fn load_pool(
+    pool_account: &AccountInfo,
+    global_config_account: &AccountInfo,
+    token_mint: &Pubkey,
+    pool_base_token_account: &Pubkey,
+    pool_quote_token_account: &Pubkey,
+) -> Result<(GlobalConfig, Pool)> {
+    let global_config = GlobalConfig::try_from_account_info(global_config_account)?;
+    let pool = Pool::try_from_account_info(pool_account)?;
+    
+    // 只支持以WSOL为报价代币的池子
+    require_keys_eq!(pool.base_mint, *token_mint, DexError::InvalidPumpSwapAccount);
+    require_keys_eq!(pool.quote_mint, spl_token::native_mint::id(), DexError::InvalidPumpSwapAccount);
+    require_keys_eq!(pool.pool_base_token_account, *pool_base_token_account, DexError::InvalidPumpSwapAccount);
+    require_keys_eq!(pool.pool_quote_token_account, *pool_quote_token_account, DexError::InvalidPumpSwapAccount);
+    
+    Ok((global_config, pool))
+}
+
+// 池子适用的手续费率，未设置coin_creator的池子不收取创建者手续费
+fn pool_fees(global_config: &GlobalConfig, pool: &Pool) -> quote::PumpSwapFees {
+    quote::PumpSwapFees {
+        lp_fee_basis_points: global_config.lp_fee_basis_points,
+        protocol_fee_basis_points: global_config.protocol_fee_basis_points,
+        coin_creator_fee_basis_points: if pool.coin_creator.is_some() {
+            global_config.coin_creator_fee_basis_points
+        } else {
+            0
+        },
+    }
+}
+
+// 新版本PumpSwap账户列表中的可选账户
+struct VersionedCpiAccounts<'info> {
+    coin_creator_vault_ata: Option<AccountInfo<'info>>,
+    coin_creator_vault_authority: Option<AccountInfo<'info>>,
+    global_volume_accumulator: Option<AccountInfo<'info>>,
+    user_volume_accumulator: Option<AccountInfo<'info>>,
+    fee_config: Option<AccountInfo<'info>>,
+    fee_program: Option<AccountInfo<'info>>,
+}
+
+impl<'info> VersionedCpiAccounts<'info> {
+    fn keys(&self) -> instructions::VersionedAccounts {
+        instructions::VersionedAccounts {
+            coin_creator_vault_ata: self.coin_creator_vault_ata.as_ref().map(|account| account.key()),
+            coin_creator_vault_authority: self.coin_creator_vault_authority.as_ref().map(|account| account.key()),
+            global_volume_accumulator: self.global_volume_accumulator.as_ref().map(|account| account.key()),
+            user_volume_accumulator: self.user_volume_accumulator.as_ref().map(|account| account.key()),
+            fee_config: self.fee_config.as_ref().map(|account| account.key()),
+            fee_program: self.fee_program.as_ref().map(|account| account.key()),
+        }
+    }
+    
+    fn version(&self) -> PumpAccountsVersion {
+        self.keys().version()
+    }
+    
+    // 将提供的可选账户追加到CPI账户列表
+    fn append_to(&self, account_infos: &mut Vec<AccountInfo<'info>>) {
+        account_infos.extend(
+            [
+                &self.coin_creator_vault_ata,
+                &self.coin_creator_vault_authority,
+                &self.global_volume_accumulator,
+                &self.user_volume_accumulator,
+                &self.fee_config,
+                &self.fee_program,
+            ]
+            .into_iter()
+            .flatten()
+            .cloned(),
+        );
+    }
+}
+
+// PumpSwap交易所需的账户结构
+#[derive(Accounts)]
+pub struct TradeTokenPumpSwap<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // DEX账户
+    #[account(
+        mut,
+        seeds = [b"dex_account".as_ref()],
+        bump
+    )]
+    pub dex_account: Account<'info, crate::DexAccount>,
+    
+    // 代币Mint地址（池子的基础代币）
+    pub token_mint: Account<'info, token::Mint>,
+    
+    // WSOL Mint（池子的报价代币）
+    #[account(
+        constraint = quote_mint.key() == spl_token::native_mint::id()
+    )]
+    pub quote_mint: Account<'info, token::Mint>,
+    
+    // PumpSwap池子账户（兼容新旧布局，在指令中手动反序列化）
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    
+    // PumpSwap全局配置账户（兼容新旧布局，在指令中手动反序列化）
+    #[account(
+        seeds = [b"global_config"],
+        bump,
+        seeds::program = pumpswap_program.key(),
+    )]
+    pub global_config: UncheckedAccount<'info>,
+    
+    // 池子代币金库
+    #[account(mut)]
+    pub pool_base_token_account: Account<'info, TokenAccount>,
+    
+    // 池子WSOL金库
+    #[account(mut)]
+    pub pool_quote_token_account: Account<'info, TokenAccount>,
+    
+    // 用户代币账户（不存在时以幂等方式创建关联代币账户，在指令中检查mint和所有者）
+    #[account(mut)]
+    pub user_base_token_account: UncheckedAccount<'info>,
+    
+    // 用户WSOL账户（不存在时以幂等方式创建关联代币账户，在指令中检查mint和所有者）
+    #[account(mut)]
+    pub user_quote_token_account: UncheckedAccount<'info>,
+    
+    // 协议手续费接收账户（在指令中检查是否为GlobalConfig中的接收账户）
+    pub protocol_fee_recipient: AccountInfo<'info>,
+    
+    // 协议手续费接收账户的WSOL关联代币账户
+    #[account(mut)]
+    pub protocol_fee_recipient_token_account: UncheckedAccount<'info>,
+    
+    // 代币程序
+    pub token_program: Program<'info, Token>,
+    
+    // 系统程序
+    pub system_program: Program<'info, System>,
+    
+    // 关联代币程序
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    
+    // 事件权限账户
+    #[account(
+        constraint = event_authority.key() == instructions::get_pumpswap_event_authority()
+    )]
+    pub event_authority: AccountInfo<'info>,
+    
+    // PumpSwap程序
+    #[account(
+        constraint = pumpswap_program.key() == get_pumpswap_program_id()
+    )]
+    pub pumpswap_program: AccountInfo<'info>,
+    
+    // 创建者金库的WSOL关联代币账户（新版本账户列表，池子设置了coin_creator时必须提供）
+    #[account(mut)]
+    pub coin_creator_vault_ata: Option<UncheckedAccount<'info>>,
+    
+    // 创建者金库权限账户（新版本账户列表）
+    pub coin_creator_vault_authority: Option<UncheckedAccount<'info>>,
+    
+    // 全局交易量累加器（新版本账户列表，仅buy使用）
+    #[account(mut)]
+    pub global_volume_accumulator: Option<UncheckedAccount<'info>>,
+    
+    // 用户交易量累加器（新版本账户列表，仅buy使用）
+    #[account(mut)]
+    pub user_volume_accumulator: Option<UncheckedAccount<'info>>,
+    
+    // 手续费配置账户（新版本账户列表）
+    pub fee_config: Option<UncheckedAccount<'info>>,
+    
+    // Pump.fun手续费程序（新版本账户列表）
+    pub fee_program: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> TradeTokenPumpSwap<'info> {
+    // 确保用户代币账户存在
+    fn ensure_user_base_token_account(&self) -> Result<token_interface::TokenAccount> {
+        token_utils::ensure_user_token_account(
+            &self.user.to_account_info(),
+            &self.user_base_token_account.to_account_info(),
+            &self.token_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.associated_token_program.to_account_info(),
+        )
+    }
+    
+    // 确保用户WSOL账户存在
+    fn ensure_user_quote_token_account(&self) -> Result<token_interface::TokenAccount> {
+        token_utils::ensure_user_token_account(
+            &self.user.to_account_info(),
+            &self.user_quote_token_account.to_account_info(),
+            &self.quote_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.associated_token_program.to_account_info(),
+        )
+    }
+    
+    // 将WSOL账户中超出交易前余额的部分解包为SOL
+    fn unwrap_quote_token_account(&self, balance_before: Option<u64>) -> Result<()> {
+        token_utils::unwrap_sol(
+            &self.user.to_account_info(),
+            &self.user_quote_token_account.to_account_info(),
+            &self.quote_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.associated_token_program.to_account_info(),
+            balance_before,
+        )
+    }
+    
+    // 记录交易前用户代币账户和WSOL账户的余额
+    fn balance_snapshot(&self, is_buy: bool) -> Result<BalanceSnapshot> {
+        let (input_account, output_account) = self.fill_accounts(is_buy);
+        BalanceSnapshot::take(&input_account, &output_account)
+    }
+    
+    // 按交易后的余额变化结算实际成交数量并发出成交事件
+    fn settle_fill(&self, snapshot: &BalanceSnapshot, is_buy: bool, limits: &FillLimits) -> Result<Fill> {
+        let (input_account, output_account) = self.fill_accounts(is_buy);
+        let (input_mint, output_mint) = if is_buy {
+            (&self.quote_mint, &self.token_mint)
+        } else {
+            (&self.token_mint, &self.quote_mint)
+        };
+        
+        snapshot.settle(
+            &input_account,
+            &output_account,
+            self.user.key(),
+            &FillPair {
+                dex: DexType::PumpSwap,
+                token_in: input_mint.key(),
+                input_decimals: input_mint.decimals,
+                token_out: output_mint.key(),
+                output_decimals: output_mint.decimals,
+            },
+            limits,
+        )
+    }
+    
+    // 交易的(输入账户, 输出账户)
+    fn fill_accounts(&self, is_buy: bool) -> (AccountInfo<'info>, AccountInfo<'info>) {
+        let base_account = self.user_base_token_account.to_account_info();
+        let quote_account = self.user_quote_token_account.to_account_info();
+        if is_buy {
+            (quote_account, base_account)
+        } else {
+            (base_account, quote_account)
+        }
+    }
+    
+    // 加载PumpSwap的GlobalConfig与Pool，并检查协议手续费接收账户
+    pub fn load_pool_state(&self) -> Result<(GlobalConfig, Pool)> {
+        let (global_config, pool) = load_pool(
+            &self.pool,
+            &self.global_config,
+            &self.token_mint.key(),
+            &self.pool_base_token_account.key(),
+            &self.pool_quote_token_account.key(),
+        )?;
+        
+        require!(
+            global_config.is_protocol_fee_recipient(&self.protocol_fee_recipient.key()),
+            DexError::InvalidPumpSwapAccount
+        );
+        require_keys_eq!(
+            self.protocol_fee_recipient_token_account.key(),
+            get_associated_token_address(&self.protocol_fee_recipient.key(), &pool.quote_mint),
+            DexError::InvalidPumpSwapAccount
+        );
+        
+        Ok((global_config, pool))
+    }
+    
+    // 收集并校验新版本账户列表中的可选账户
+    fn versioned_accounts(&self, pool: &Pool) -> Result<VersionedCpiAccounts<'info>> {
+        // 未设置coin_creator的池子使用默认公钥派生创建者金库
+        let coin_creator = pool.coin_creator.unwrap_or_default();
+        
+        match (&self.coin_creator_vault_authority, &self.coin_creator_vault_ata) {
+            (Some(vault_authority), Some(vault_ata)) => {
+                let (vault_authority_pda, _) = state::get_coin_creator_vault_authority_pda(&coin_creator);
+                require_keys_eq!(vault_authority.key(), vault_authority_pda, DexError::InvalidPumpSwapAccount);
+                require_keys_eq!(
+                    vault_ata.key(),
+                    get_associated_token_address(&vault_authority_pda, &pool.quote_mint),
+                    DexError::InvalidPumpSwapAccount
+                );
+            }
+            (None, None) => {
+                require!(pool.coin_creator.is_none(), DexError::MissingPumpSwapAccount);
+            }
+            _ => return err!(DexError::MissingPumpSwapAccount),
+        }
+        
+        if let Some(global_volume_accumulator) = &self.global_volume_accumulator {
+            let (pda, _) = state::get_global_volume_accumulator_pda();
+            require_keys_eq!(global_volume_accumulator.key(), pda, DexError::InvalidPumpSwapAccount);
+        }
+        
+        if let Some(user_volume_accumulator) = &self.user_volume_accumulator {
+            let (pda, _) = state::get_user_volume_accumulator_pda(&self.user.key());
+            require_keys_eq!(user_volume_accumulator.key(), pda, DexError::InvalidPumpSwapAccount);
+        }
+        
+        if let Some(fee_config) = &self.fee_config {
+            let (pda, _) = state::get_fee_config_pda();
+            require_keys_eq!(fee_config.key(), pda, DexError::InvalidPumpSwapAccount);
+        }
+        
+        if let Some(fee_program) = &self.fee_program {
+            require_keys_eq!(
+                fee_program.key(),
+                crate::pumpfun::state::get_pump_fee_program_id(),
+                DexError::InvalidPumpSwapAccount
+            );
+        }
+        
+        Ok(VersionedCpiAccounts {
+            coin_creator_vault_ata: self.coin_creator_vault_ata.as_ref().map(|account| account.to_account_info()),
+            coin_creator_vault_authority: self.coin_creator_vault_authority.as_ref().map(|account| account.to_account_info()),
+            global_volume_accumulator: self.global_volume_accumulator.as_ref().map(|account| account.to_account_info()),
+            user_volume_accumulator: self.user_volume_accumulator.as_ref().map(|account| account.to_account_info()),
+            fee_config: self.fee_config.as_ref().map(|account| account.to_account_info()),
+            fee_program: self.fee_program.as_ref().map(|account| account.to_account_info()),
+        })
+    }
+    
+    // buy/sell指令账户，基础代币与报价代币使用同一个代币程序
+    fn swap_accounts(&self, versioned: instructions::VersionedAccounts) -> instructions::SwapAccounts {
+        instructions::SwapAccounts {
+            pool: self.pool.key(),
+            user: self.user.key(),
+            global_config: self.global_config.key(),
+            base_mint: self.token_mint.key(),
+            quote_mint: self.quote_mint.key(),
+            user_base_token_account: self.user_base_token_account.key(),
+            user_quote_token_account: self.user_quote_token_account.key(),
+            pool_base_token_account: self.pool_base_token_account.key(),
+            pool_quote_token_account: self.pool_quote_token_account.key(),
+            protocol_fee_recipient: self.protocol_fee_recipient.key(),
+            protocol_fee_recipient_token_account: self.protocol_fee_recipient_token_account.key(),
+            base_token_program: self.token_program.key(),
+            quote_token_program: self.token_program.key(),
+            system_program: self.system_program.key(),
+            associated_token_program: self.associated_token_program.key(),
+            event_authority: self.event_authority.key(),
+            program: self.pumpswap_program.key(),
+            versioned,
+        }
+    }
+    
+    // 执行PumpSwap的交易指令
+    fn invoke_swap(
+        &self,
+        swap_ix: &Instruction,
+        versioned: &VersionedCpiAccounts<'info>,
+    ) -> Result<()> {
+        let mut account_infos = vec![
+            self.pool.to_account_info(),
+            self.user.to_account_info(),
+            self.global_config.to_account_info(),
+            self.token_mint.to_account_info(),
+            self.quote_mint.to_account_info(),
+            self.user_base_token_account.to_account_info(),
+            self.user_quote_token_account.to_account_info(),
+            self.pool_base_token_account.to_account_info(),
+            self.pool_quote_token_account.to_account_info(),
+            self.protocol_fee_recipient.to_account_info(),
+            self.protocol_fee_recipient_token_account.to_account_info(),
+            self.token_program.to_account_info(),
+            self.system_program.to_account_info(),
+            self.associated_token_program.to_account_info(),
+            self.event_authority.to_account_info(),
+            self.pumpswap_program.to_account_info(),
+        ];
+        versioned.append_to(&mut account_infos);
+        
+        invoke(swap_ix, &account_infos)?;
+        Ok(())
+    }
+}
+
+// 检查代币位置所需的账户结构
+#[derive(Accounts)]
+pub struct CheckTokenLocation<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // PumpSwap池子账户（可能不存在，在指令中手动检查）
+    pub pool: UncheckedAccount<'info>,
+    
+    // 池子代币金库
+    pub pool_base_token_account: UncheckedAccount<'info>,
+    
+    // 池子WSOL金库
+    pub pool_quote_token_account: UncheckedAccount<'info>,
+    
+    // 系统程序
+    pub system_program: Program<'info, System>,
+}
+
+// 获取价格所需的账户结构
+#[derive(Accounts)]
+pub struct GetPriceContext<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // 代币Mint地址
+    pub token_mint: Account<'info, token::Mint>,
+    
+    // PumpSwap池子账户（兼容新旧布局，在指令中手动反序列化）
+    pub pool: UncheckedAccount<'info>,
+    
+    // PumpSwap全局配置账户（兼容新旧布局，在指令中手动反序列化）
+    #[account(
+        seeds = [b"global_config"],
+        bump,
+        seeds::program = get_pumpswap_program_id(),
+    )]
+    pub global_config: UncheckedAccount<'info>,
+    
+    // 池子代币金库
+    pub pool_base_token_account: Account<'info, TokenAccount>,
+    
+    // 池子WSOL金库
+    pub pool_quote_token_account: Account<'info, TokenAccount>,
+    
+    // 系统程序
+    pub system_program: Program<'info, System>,
+    
+    // 代币程序
+    pub token_program: Program<'info, Token>,
+}
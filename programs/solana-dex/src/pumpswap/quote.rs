@@ -0,0 +1,289 @@
+use anchor_lang::prelude::*;
+use crate::math::{self, to_u64};
+use crate::DexError;
+use crate::pumpfun::FEE_BASIS_POINTS_DENOMINATOR;
+
+// PumpSwap池子手续费率（基点），三项手续费均以报价代币（SOL）计算
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct PumpSwapFees {
+    pub lp_fee_basis_points: u64,
+    pub protocol_fee_basis_points: u64,
+    // 池子未设置coin_creator时为0
+    pub coin_creator_fee_basis_points: u64,
+}
+
+impl PumpSwapFees {
+    fn total_basis_points(&self) -> u64 {
+        self.lp_fee_basis_points
+            .saturating_add(self.protocol_fee_basis_points)
+            .saturating_add(self.coin_creator_fee_basis_points)
+    }
+    
+    // 分别计算三项手续费（与PumpSwap一致向上取整），返回总手续费
+    fn calculate(&self, amount: u64) -> Result<u64> {
+        let lp_fee = math::fee_ceil(amount, self.lp_fee_basis_points, FEE_BASIS_POINTS_DENOMINATOR)?;
+        let protocol_fee = math::fee_ceil(amount, self.protocol_fee_basis_points, FEE_BASIS_POINTS_DENOMINATOR)?;
+        let coin_creator_fee = math::fee_ceil(amount, self.coin_creator_fee_basis_points, FEE_BASIS_POINTS_DENOMINATOR)?;
+        
+        lp_fee
+            .checked_add(protocol_fee)
+            .and_then(|fee| fee.checked_add(coin_creator_fee))
+            .ok_or(DexError::ArithmeticOverflow.into())
+    }
+}
+
+// PumpSwap报价结果
+// 买入时手续费加在输入的SOL上，卖出时手续费从输出的SOL中扣除
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct PumpSwapQuote {
+    // 用户实际支付的数量（买入为含手续费的SOL，卖出为代币）
+    pub amount_in: u64,
+    // 扣除手续费前的输出数量
+    pub gross_amount_out: u64,
+    // 手续费总额（lamports）
+    pub fee_amount: u64,
+    // 用户实际获得的数量
+    pub net_amount_out: u64,
+    // 交易后的池子储备
+    pub new_base_reserve: u64,
+    pub new_quote_reserve: u64,
+    // 价格影响（以FEE_DENOMINATOR为分母的百万分比，不含手续费）
+    pub price_impact: u64,
+}
+
+// 报价: 购买指定数量的基础代币（精确代币输出）
+pub fn quote_buy_exact_base_out(
+    base_reserve: u64,
+    quote_reserve: u64,
+    fees: &PumpSwapFees,
+    base_amount_out: u64,
+) -> Result<PumpSwapQuote> {
+    require!(base_amount_out > 0, DexError::InvalidArgument);
+    require!(base_reserve > 0 && quote_reserve > 0, DexError::InsufficientLiquidity);
+    require!(base_amount_out < base_reserve, DexError::InsufficientLiquidity);
+    
+    // 不含手续费的SOL花费 = ceil(quote_reserve * base_amount_out / (base_reserve - base_amount_out))
+    let denominator = (base_reserve - base_amount_out) as u128;
+    let quote_cost = to_u64(math::mul_div_ceil(
+        quote_reserve as u128,
+        base_amount_out as u128,
+        denominator,
+    )?)?;
+    
+    let fee_amount = fees.calculate(quote_cost)?;
+    let amount_in = quote_cost.checked_add(fee_amount).ok_or(DexError::ArithmeticOverflow)?;
+    
+    build_buy_quote(base_reserve, quote_reserve, amount_in, quote_cost, fee_amount, base_amount_out)
+}
+
+// 报价: 使用指定数量的SOL（含手续费）购买基础代币（精确SOL输入）
+pub fn quote_buy_exact_quote_in(
+    base_reserve: u64,
+    quote_reserve: u64,
+    fees: &PumpSwapFees,
+    quote_amount_in: u64,
+) -> Result<PumpSwapQuote> {
+    require!(quote_amount_in > 0, DexError::InvalidArgument);
+    require!(base_reserve > 0 && quote_reserve > 0, DexError::InsufficientLiquidity);
+    
+    // 扣除手续费后的净SOL输入: quote_amount_in * 10000 / (10000 + 总费率)
+    let mut net_quote_in = (quote_amount_in as u128)
+        .checked_mul(FEE_BASIS_POINTS_DENOMINATOR as u128)
+        .ok_or(DexError::ArithmeticOverflow)?
+        .checked_div((FEE_BASIS_POINTS_DENOMINATOR as u128) + (fees.total_basis_points() as u128))
+        .ok_or(DexError::ArithmeticOverflow)?;
+    
+    // 三项手续费分别向上取整，取整误差最多几个lamports，逐步收缩净输入直到总花费不超过预算
+    for _ in 0..4 {
+        // 代币数量 = net_quote_in * base_reserve / (quote_reserve + net_quote_in)
+        let base_amount_out = to_u64(
+            net_quote_in
+                .checked_mul(base_reserve as u128)
+                .ok_or(DexError::ArithmeticOverflow)?
+                .checked_div(quote_reserve as u128 + net_quote_in)
+                .ok_or(DexError::ArithmeticOverflow)?,
+        )?;
+        require!(base_amount_out > 0, DexError::InsufficientLiquidity);
+        
+        let buy_quote = quote_buy_exact_base_out(base_reserve, quote_reserve, fees, base_amount_out)?;
+        if buy_quote.amount_in <= quote_amount_in {
+            // 报价中的amount_in为实际花费，剩余的SOL不会被PumpSwap扣除
+            return Ok(buy_quote);
+        }
+        
+        net_quote_in = net_quote_in.saturating_sub((buy_quote.amount_in - quote_amount_in) as u128);
+    }
+    
+    Err(DexError::PriceQueryFailed.into())
+}
+
+// 报价: 卖出指定数量的基础代币
+pub fn quote_sell(
+    base_reserve: u64,
+    quote_reserve: u64,
+    fees: &PumpSwapFees,
+    base_amount_in: u64,
+) -> Result<PumpSwapQuote> {
+    require!(base_amount_in > 0, DexError::InvalidArgument);
+    require!(base_reserve > 0 && quote_reserve > 0, DexError::InsufficientLiquidity);
+    
+    // 获得的SOL = base_amount_in * quote_reserve / (base_reserve + base_amount_in)
+    let gross_amount_out = to_u64(
+        (base_amount_in as u128)
+            .checked_mul(quote_reserve as u128)
+            .ok_or(DexError::ArithmeticOverflow)?
+            .checked_div(base_reserve as u128 + base_amount_in as u128)
+            .ok_or(DexError::ArithmeticOverflow)?,
+    )?;
+    
+    let fee_amount = fees.calculate(gross_amount_out)?;
+    let net_amount_out = gross_amount_out
+        .checked_sub(fee_amount)
+        .ok_or(DexError::InsufficientLiquidity)?;
+    require!(net_amount_out > 0, DexError::InsufficientLiquidity);
+    
+    // 价格影响 = 1 - 成交价格 / 现货价格 = 1 - gross_amount_out * base_reserve / (base_amount_in * quote_reserve)
+    let price_impact = math::price_impact(
+        gross_amount_out as u128 * base_reserve as u128,
+        base_amount_in as u128 * quote_reserve as u128,
+    )?;
+    
+    Ok(PumpSwapQuote {
+        amount_in: base_amount_in,
+        gross_amount_out,
+        fee_amount,
+        net_amount_out,
+        new_base_reserve: base_reserve
+            .checked_add(base_amount_in)
+            .ok_or(DexError::ArithmeticOverflow)?,
+        new_quote_reserve: quote_reserve
+            .checked_sub(gross_amount_out)
+            .ok_or(DexError::ArithmeticOverflow)?,
+        price_impact,
+    })
+}
+
+// 构建买入报价，更新储备并计算价格影响
+fn build_buy_quote(
+    base_reserve: u64,
+    quote_reserve: u64,
+    amount_in: u64,
+    quote_cost: u64,
+    fee_amount: u64,
+    base_amount_out: u64,
+) -> Result<PumpSwapQuote> {
+    // 价格影响 = 1 - 现货价格 / 成交价格 = 1 - base_amount_out * quote_reserve / (quote_cost * base_reserve)
+    let price_impact = math::price_impact(
+        base_amount_out as u128 * quote_reserve as u128,
+        quote_cost as u128 * base_reserve as u128,
+    )?;
+    
+    Ok(PumpSwapQuote {
+        amount_in,
+        gross_amount_out: base_amount_out,
+        fee_amount,
+        net_amount_out: base_amount_out,
+        new_base_reserve: base_reserve
+            .checked_sub(base_amount_out)
+            .ok_or(DexError::ArithmeticOverflow)?,
+        new_quote_reserve: quote_reserve
+            .checked_add(quote_cost)
+            .ok_or(DexError::ArithmeticOverflow)?,
+        price_impact,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // 期望值按PumpSwap程序的整数运算独立计算:
+    // 买入成本ceil(quote_reserve * base_out / (base_reserve - base_out))，卖出所得floor(base_in * quote_reserve / (base_reserve + base_in))，
+    // LP、协议、创建者三项手续费分别按基点向上取整后相加
+    
+    // 200M代币 / 85 SOL的池子
+    const BASE_RESERVE: u64 = 200_000_000_000_000;
+    const QUOTE_RESERVE: u64 = 85_000_000_000;
+    
+    // 0.20% LP、0.05%协议、0.05%创建者手续费
+    const CREATOR_FEES: PumpSwapFees = PumpSwapFees {
+        lp_fee_basis_points: 20,
+        protocol_fee_basis_points: 5,
+        coin_creator_fee_basis_points: 5,
+    };
+    // 池子未设置coin_creator
+    const NO_CREATOR_FEES: PumpSwapFees = PumpSwapFees {
+        lp_fee_basis_points: 20,
+        protocol_fee_basis_points: 5,
+        coin_creator_fee_basis_points: 0,
+    };
+    
+    #[test]
+    fn buy_exact_base_out_matches_reference() {
+        let quote = quote_buy_exact_base_out(BASE_RESERVE, QUOTE_RESERVE, &CREATOR_FEES, 1_000_000_000_000).unwrap();
+        assert_eq!(
+            quote,
+            PumpSwapQuote {
+                amount_in: 428_417_087,
+                gross_amount_out: 1_000_000_000_000,
+                fee_amount: 1_281_408,
+                net_amount_out: 1_000_000_000_000,
+                new_base_reserve: 199_000_000_000_000,
+                new_quote_reserve: 85_427_135_679,
+                price_impact: 5_000,
+            }
+        );
+        
+        let quote = quote_buy_exact_base_out(BASE_RESERVE, QUOTE_RESERVE, &NO_CREATOR_FEES, 1_000_000_000_000).unwrap();
+        assert_eq!((quote.amount_in, quote.fee_amount), (428_203_519, 1_067_840));
+    }
+    
+    #[test]
+    fn fee_components_round_up_separately() {
+        // 1个代币的成本向上取整为1 lamport，三项手续费各自向上取整为1，合计按0.30%取整只有1
+        let quote = quote_buy_exact_base_out(BASE_RESERVE, QUOTE_RESERVE, &CREATOR_FEES, 1).unwrap();
+        assert_eq!(quote.new_quote_reserve - QUOTE_RESERVE, 1);
+        assert_eq!((quote.amount_in, quote.fee_amount), (4, 3));
+        
+        let quote = quote_buy_exact_base_out(BASE_RESERVE, QUOTE_RESERVE, &NO_CREATOR_FEES, 1).unwrap();
+        assert_eq!((quote.amount_in, quote.fee_amount), (3, 2));
+    }
+    
+    #[test]
+    fn buy_exact_quote_in_shrinks_net_input_within_budget() {
+        // 按总费率反推的净输入997008973对应的总花费为1000000001，超出预算1，收缩一次后恰好用完预算
+        let quote = quote_buy_exact_quote_in(BASE_RESERVE, QUOTE_RESERVE, &CREATOR_FEES, 1_000_000_000).unwrap();
+        assert_eq!(
+            quote,
+            PumpSwapQuote {
+                amount_in: 1_000_000_000,
+                gross_amount_out: 2_318_706_159_477,
+                fee_amount: 2_991_028,
+                net_amount_out: 2_318_706_159_477,
+                new_base_reserve: 197_681_293_840_523,
+                new_quote_reserve: 85_997_008_972,
+                price_impact: 11_593,
+            }
+        );
+    }
+    
+    #[test]
+    fn sell_matches_reference() {
+        let quote = quote_sell(BASE_RESERVE, QUOTE_RESERVE, &CREATOR_FEES, 10_000_000_000_000).unwrap();
+        assert_eq!(
+            quote,
+            PumpSwapQuote {
+                amount_in: 10_000_000_000_000,
+                gross_amount_out: 4_047_619_047,
+                fee_amount: 12_142_859,
+                net_amount_out: 4_035_476_188,
+                new_base_reserve: 210_000_000_000_000,
+                new_quote_reserve: 80_952_380_953,
+                price_impact: 47_619,
+            }
+        );
+        
+        let quote = quote_sell(BASE_RESERVE, QUOTE_RESERVE, &NO_CREATOR_FEES, 10_000_000_000_000).unwrap();
+        assert_eq!((quote.fee_amount, quote.net_amount_out), (10_119_049, 4_037_499_998));
+    }
+}
@@ -0,0 +1,193 @@
+use anchor_lang::prelude::*;
+use solana_program::pubkey::Pubkey;
+use crate::codec::strip_discriminator;
+use crate::DexError;
+use super::get_pumpswap_program_id;
+
+// PumpSwap账户discriminator（Anchor规则: sha256("account:<账户名>")的前8字节）
+pub const GLOBAL_CONFIG_DISCRIMINATOR: [u8; 8] = [149, 8, 156, 202, 160, 252, 176, 217];
+pub const POOL_DISCRIMINATOR: [u8; 8] = [241, 154, 109, 4, 17, 177, 109, 188];
+
+// GlobalConfig中协议手续费接收账户的数量
+pub const PROTOCOL_FEE_RECIPIENTS_LEN: usize = 8;
+
+// PumpSwap的GlobalConfig账户（兼容新旧布局）
+#[derive(Clone, Debug, Default)]
+pub struct GlobalConfig {
+    pub admin: Pubkey,
+    pub lp_fee_basis_points: u64,
+    pub protocol_fee_basis_points: u64,
+    pub disable_flags: u8,
+    pub protocol_fee_recipients: [Pubkey; PROTOCOL_FEE_RECIPIENTS_LEN],
+    // 以下字段仅在新布局中存在，旧布局下为默认值
+    pub coin_creator_fee_basis_points: u64,
+}
+
+// GlobalConfig的原始字段
+#[derive(AnchorDeserialize)]
+struct GlobalConfigV1 {
+    admin: Pubkey,
+    lp_fee_basis_points: u64,
+    protocol_fee_basis_points: u64,
+    disable_flags: u8,
+    protocol_fee_recipients: [Pubkey; PROTOCOL_FEE_RECIPIENTS_LEN],
+}
+
+impl GlobalConfig {
+    // disable_flags中禁用buy/sell的标志位
+    pub const DISABLE_BUY: u8 = 1 << 3;
+    pub const DISABLE_SELL: u8 = 1 << 4;
+    
+    // 从账户数据反序列化，检查discriminator并兼容新旧布局
+    pub fn try_deserialize_versioned(data: &[u8]) -> Result<Self> {
+        let mut data = strip_discriminator(data, &GLOBAL_CONFIG_DISCRIMINATOR, DexError::InvalidPumpSwapAccount)?;
+        
+        let v1 = GlobalConfigV1::deserialize(&mut data)
+            .map_err(|_| DexError::InvalidPumpSwapAccount)?;
+        
+        // 剩余数据足够时读取创建者费率，未知的尾部字段直接忽略
+        let coin_creator_fee_basis_points = u64::deserialize(&mut data).unwrap_or(0);
+        
+        Ok(GlobalConfig {
+            admin: v1.admin,
+            lp_fee_basis_points: v1.lp_fee_basis_points,
+            protocol_fee_basis_points: v1.protocol_fee_basis_points,
+            disable_flags: v1.disable_flags,
+            protocol_fee_recipients: v1.protocol_fee_recipients,
+            coin_creator_fee_basis_points,
+        })
+    }
+    
+    // 从账户加载，检查所有者为PumpSwap程序
+    pub fn try_from_account_info(account_info: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(*account_info.owner, get_pumpswap_program_id(), DexError::InvalidPumpSwapAccount);
+        let data = account_info.try_borrow_data()?;
+        Self::try_deserialize_versioned(&data)
+    }
+    
+    // 检查是否为有效的协议手续费接收账户
+    pub fn is_protocol_fee_recipient(&self, key: &Pubkey) -> bool {
+        *key != Pubkey::default() && self.protocol_fee_recipients.iter().any(|recipient| recipient == key)
+    }
+}
+
+// PumpSwap的Pool账户（兼容新旧布局）
+#[derive(Clone, Debug, Default)]
+pub struct Pool {
+    pub pool_bump: u8,
+    pub index: u16,
+    pub creator: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub pool_base_token_account: Pubkey,
+    pub pool_quote_token_account: Pubkey,
+    pub lp_supply: u64,
+    // 仅在新布局中存在，旧布局或未设置时为None
+    pub coin_creator: Option<Pubkey>,
+}
+
+// Pool的原始字段
+#[derive(AnchorDeserialize)]
+struct PoolV1 {
+    pool_bump: u8,
+    index: u16,
+    creator: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    lp_mint: Pubkey,
+    pool_base_token_account: Pubkey,
+    pool_quote_token_account: Pubkey,
+    lp_supply: u64,
+}
+
+impl Pool {
+    // 从账户数据反序列化，检查discriminator并兼容新旧布局
+    pub fn try_deserialize_versioned(data: &[u8]) -> Result<Self> {
+        let mut data = strip_discriminator(data, &POOL_DISCRIMINATOR, DexError::InvalidPumpSwapAccount)?;
+        
+        let v1 = PoolV1::deserialize(&mut data)
+            .map_err(|_| DexError::InvalidPumpSwapAccount)?;
+        
+        // 新布局中追加了coin_creator，全零表示未设置
+        let coin_creator = Pubkey::deserialize(&mut data)
+            .ok()
+            .filter(|coin_creator| *coin_creator != Pubkey::default());
+        
+        Ok(Pool {
+            pool_bump: v1.pool_bump,
+            index: v1.index,
+            creator: v1.creator,
+            base_mint: v1.base_mint,
+            quote_mint: v1.quote_mint,
+            lp_mint: v1.lp_mint,
+            pool_base_token_account: v1.pool_base_token_account,
+            pool_quote_token_account: v1.pool_quote_token_account,
+            lp_supply: v1.lp_supply,
+            coin_creator,
+        })
+    }
+    
+    // 从账户加载，检查所有者为PumpSwap程序
+    pub fn try_from_account_info(account_info: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(*account_info.owner, get_pumpswap_program_id(), DexError::InvalidPumpSwapAccount);
+        let data = account_info.try_borrow_data()?;
+        Self::try_deserialize_versioned(&data)
+    }
+}
+
+// 计算全局配置PDA: ["global_config"]
+pub fn get_global_config_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"global_config"], &get_pumpswap_program_id())
+}
+
+// 计算池子PDA: ["pool", index, creator, base_mint, quote_mint]
+pub fn get_pool_pda(index: u16, creator: &Pubkey, base_mint: &Pubkey, quote_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"pool",
+            &index.to_le_bytes(),
+            creator.as_ref(),
+            base_mint.as_ref(),
+            quote_mint.as_ref(),
+        ],
+        &get_pumpswap_program_id(),
+    )
+}
+
+// 计算Pump.fun迁移池的创建者PDA: ["pool-authority", mint]（属于Pump.fun程序）
+pub fn get_pump_pool_authority_pda(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"pool-authority", mint.as_ref()],
+        &crate::pumpfun::get_pump_program_id(),
+    )
+}
+
+// 计算Pump.fun毕业代币的标准池子PDA（index为0，创建者为迁移权限PDA）
+pub fn get_canonical_pool_pda(base_mint: &Pubkey, quote_mint: &Pubkey) -> (Pubkey, u8) {
+    let (pool_authority, _) = get_pump_pool_authority_pda(base_mint);
+    get_pool_pda(0, &pool_authority, base_mint, quote_mint)
+}
+
+// 计算创建者金库权限PDA: ["creator_vault", coin_creator]
+pub fn get_coin_creator_vault_authority_pda(coin_creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"creator_vault", coin_creator.as_ref()], &get_pumpswap_program_id())
+}
+
+// 计算全局交易量累加器PDA: ["global_volume_accumulator"]
+pub fn get_global_volume_accumulator_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"global_volume_accumulator"], &get_pumpswap_program_id())
+}
+
+// 计算用户交易量累加器PDA: ["user_volume_accumulator", user]
+pub fn get_user_volume_accumulator_pda(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"user_volume_accumulator", user.as_ref()], &get_pumpswap_program_id())
+}
+
+// 计算手续费配置PDA: ["fee_config", pumpswap_program]（属于Pump.fun手续费程序）
+pub fn get_fee_config_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"fee_config", get_pumpswap_program_id().as_ref()],
+        &crate::pumpfun::state::get_pump_fee_program_id(),
+    )
+}
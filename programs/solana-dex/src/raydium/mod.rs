@@ -1,5 +1,8 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
 use solana_program::{
     program::invoke,
     pubkey::Pubkey,
@@ -12,22 +15,71 @@ use raydium_cpmm_cpi::{
     program::RaydiumCpmm,
     states::{AmmConfig, ObservationState, PoolState},
 };
-use crate::{MAX_PRICE_IMPACT, FEE_DENOMINATOR, DexError};
+use crate::fill::{read_balance, BalanceSnapshot, Fill, FillLimits, FillPair};
+use crate::math::Price;
+use crate::router::RouteQuoter;
+use crate::{token_utils, MAX_PRICE_IMPACT, MAX_TWAP_DEVIATION, FEE_DENOMINATOR, DexError, DexType};
+
+pub mod quote;
+pub mod twap;
 
 // Raydium CPMM程序ID
 pub const RAYDIUM_CPMM_PROGRAM_ID: &str = "CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C";
 
+// Raydium CPMM池子权限PDA种子
+pub const RAYDIUM_CPMM_AUTH_SEED: &[u8] = b"vault_and_lp_mint_auth_seed";
+
+// Raydium CPMM池子相关PDA种子
+pub const RAYDIUM_CPMM_POOL_SEED: &[u8] = b"pool";
+pub const RAYDIUM_CPMM_POOL_LP_MINT_SEED: &[u8] = b"pool_lp_mint";
+pub const RAYDIUM_CPMM_POOL_VAULT_SEED: &[u8] = b"pool_vault";
+pub const RAYDIUM_CPMM_OBSERVATION_SEED: &[u8] = b"observation";
+
 // 获取Raydium CPMM程序ID
 pub fn get_raydium_cpmm_program_id() -> Pubkey {
     Pubkey::from_str(RAYDIUM_CPMM_PROGRAM_ID).unwrap()
 }
 
+// 计算池子权限PDA: [RAYDIUM_CPMM_AUTH_SEED]
+pub fn get_authority_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RAYDIUM_CPMM_AUTH_SEED], program_id)
+}
+
+// 计算池子PDA: ["pool", amm_config, token_0_mint, token_1_mint]，要求token_0_mint < token_1_mint
+pub fn get_pool_pda(
+    amm_config: &Pubkey,
+    token_0_mint: &Pubkey,
+    token_1_mint: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[RAYDIUM_CPMM_POOL_SEED, amm_config.as_ref(), token_0_mint.as_ref(), token_1_mint.as_ref()],
+        program_id,
+    )
+}
+
+// 计算LP Mint PDA: ["pool_lp_mint", pool]
+pub fn get_lp_mint_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RAYDIUM_CPMM_POOL_LP_MINT_SEED, pool.as_ref()], program_id)
+}
+
+// 计算池子金库PDA: ["pool_vault", pool, mint]
+pub fn get_pool_vault_pda(pool: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RAYDIUM_CPMM_POOL_VAULT_SEED, pool.as_ref(), mint.as_ref()], program_id)
+}
+
+// 计算观察状态PDA: ["observation", pool]
+pub fn get_observation_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RAYDIUM_CPMM_OBSERVATION_SEED, pool.as_ref()], program_id)
+}
+
 // 在Raydium上购买代币
 pub fn buy_token(
     ctx: Context<TradeTokenRaydium>,
     amount_in: u64,  // 输入的SOL数量
     min_amount_out: u64,  // 最小获得的代币数量（滑点控制）
-) -> Result<()> {
+    max_twap_deviation: Option<u64>,  // 交易前后现货价格相对TWAP的最大偏离，为空时使用默认值，观察历史不足时只有FEE_DENOMINATOR允许交易
+) -> Result<Fill> {
     msg!("在Raydium上购买代币: {} SOL, 最小获得代币数量: {}", amount_in, min_amount_out);
     msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
     
@@ -37,30 +89,34 @@ pub fn buy_token(
     // 检查最小输出金额是否大于0
     require!(min_amount_out > 0, DexError::InvalidArgument);
     
-    // 检查用户是否有足够的SOL
-    let user_lamports = ctx.accounts.user.lamports();
-    require!(user_lamports >= amount_in, DexError::InsufficientFunds);
+    // 校验池子相关账户并按买卖方向确认输入输出两侧
+    ctx.accounts.validate_pool_accounts(true)?;
     
-    // 检查目标代币账户是否属于正确的代币类型
+    // 检查现货价格与TWAP的偏离，防止在流动性较差的池子中被操纵价格
+    let twap_limit = ctx.accounts.check_twap_deviation(max_twap_deviation)?;
+    
+    // 记录两侧代币账户交易前的余额，交易后只解包WSOL账户中超出交易前余额的部分
+    let token_balances_before = ctx.accounts.token_balances_before()?;
+    
+    // 输入为SOL时在同一指令中创建WSOL账户并包装不足部分的SOL
+    ctx.accounts.prepare_user_source_token_account(amount_in)?;
+    
+    // 目标代币账户不存在时在同一指令中创建，并检查是否属于正确的代币类型
+    let user_destination_token_account = ctx.accounts.ensure_user_destination_token_account()?;
     require!(
-        ctx.accounts.user_destination_token_account.mint == ctx.accounts.token_mint.key(),
+        user_destination_token_account.mint == ctx.accounts.token_mint.key(),
         DexError::InvalidArgument
     );
     
-    // 加载池状态（只加载一次）
-    let pool_state = ctx.accounts.pool_state.load()?;
-    
-    // 计算价格影响
-    let (price_impact, expected_amount_out) = calculate_price_impact(
-        &pool_state,
-        amount_in,
-        true,
-        ctx.accounts.token_mint.key()
-    )?;
+    // 计算价格影响和扣除转账手续费后的预期到账数量
+    let (price_impact, expected_amount_out) = ctx.accounts.quote_with_transfer_fees(amount_in)?;
     
     // 检查价格影响是否过大
     require!(price_impact <= MAX_PRICE_IMPACT, DexError::ExcessivePriceImpact);
     
+    // 检查滑点（min_amount_out为实际到账数量）
+    require!(expected_amount_out >= min_amount_out, DexError::SlippageExceeded);
+    
     // 记录预期输出金额和价格影响
     msg!("预期输出金额: {}, 价格影响: {}%", 
         expected_amount_out, 
@@ -71,53 +127,35 @@ pub fn buy_token(
     msg!("使用Raydium CPI进行交易");
     
     // 构建CPI账户
-    let cpi_accounts = cpi::accounts::Swap {
-        payer: ctx.accounts.user.to_account_info(),
-        authority: ctx.accounts.amm_authority.to_account_info(),
-        amm_config: ctx.accounts.amm_config.to_account_info(),
-        pool_state: ctx.accounts.pool_state.to_account_info(),
-        input_token_account: ctx.accounts.user_source_token_account.to_account_info(),
-        output_token_account: ctx.accounts.user_destination_token_account.to_account_info(),
-        input_vault: ctx.accounts.input_vault.to_account_info(),
-        output_vault: ctx.accounts.output_vault.to_account_info(),
-        input_token_program: ctx.accounts.token_program.to_account_info(),
-        output_token_program: ctx.accounts.token_program.to_account_info(),
-        input_token_mint: ctx.accounts.input_token_mint.to_account_info(),
-        output_token_mint: ctx.accounts.output_token_mint.to_account_info(),
-        observation_state: ctx.accounts.observation_state.to_account_info(),
-    };
+    let cpi_accounts = ctx.accounts.swap_cpi_accounts();
     
     // 创建CPI上下文
     let cpi_program = ctx.accounts.raydium_program.to_account_info();
     let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
     
-    // 记录交易开始时间
-    let start_time = Clock::get()?.unix_timestamp;
+    // 记录交易前余额
+    let snapshot = ctx.accounts.balance_snapshot()?;
     
     // 执行交易
     msg!("执行Raydium swap_base_input交易");
     cpi::swap_base_input(cpi_context, amount_in, min_amount_out)?;
     
-    // 记录交易结束时间
-    let end_time = Clock::get()?.unix_timestamp;
-    let execution_time = end_time - start_time;
-    
-    // 发出交易完成事件
-    emit!(SwapExecuted {
-        user: ctx.accounts.user.key(),
-        dex: "Raydium".to_string(),
-        token_in: ctx.accounts.input_token_mint.key(),
-        token_out: ctx.accounts.output_token_mint.key(),
-        amount_in,
+    // 按实际余额变化检查成交数量（解包WSOL之前）
+    let fill = ctx.accounts.settle_fill(&snapshot, &FillLimits {
+        expected_amount_in: amount_in,
+        expected_amount_out,
+        max_amount_in: amount_in,
         min_amount_out,
-        actual_amount_out: expected_amount_out, // 实际值需要从交易后的账户余额中获取
-        price_impact,
-        execution_time,
-        slot: Clock::get()?.slot,
-    });
+    })?;
+    
+    // 成交后的价格同样不能偏离TWAP过多，限制单笔交易把价格推离TWAP的幅度
+    ctx.accounts.check_post_trade_twap_deviation(twap_limit)?;
+    
+    // 交易完成后将WSOL解包为SOL
+    ctx.accounts.unwrap_native_accounts(token_balances_before)?;
     
     msg!("交易完成，获得代币");
-    Ok(())
+    Ok(fill)
 }
 
 // 在Raydium上卖出代币
@@ -125,7 +163,9 @@ pub fn sell_token(
     ctx: Context<TradeTokenRaydium>,
     amount_in: u64,  // 输入的代币数量
     min_amount_out: u64,  // 最小获得的SOL数量（滑点控制）
-) -> Result<()> {
+    close_token_account: bool,  // 全部卖出后是否关闭用户代币账户以回收租金
+    max_twap_deviation: Option<u64>,  // 交易前后现货价格相对TWAP的最大偏离，为空时使用默认值，观察历史不足时只有FEE_DENOMINATOR允许交易
+) -> Result<Fill> {
     msg!("在Raydium上卖出代币: {} 代币, 最小获得SOL数量: {}", amount_in, min_amount_out);
     msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
     
@@ -135,32 +175,40 @@ pub fn sell_token(
     // 检查最小输出金额是否大于0
     require!(min_amount_out > 0, DexError::InvalidArgument);
     
-    // 检查用户代币账户是否有足够的代币
-    require!(
-        ctx.accounts.user_source_token_account.amount >= amount_in,
-        DexError::InsufficientFunds
-    );
+    // 校验池子相关账户并按买卖方向确认输入输出两侧
+    ctx.accounts.validate_pool_accounts(false)?;
+    
+    // 检查现货价格与TWAP的偏离，防止在流动性较差的池子中被操纵价格
+    let twap_limit = ctx.accounts.check_twap_deviation(max_twap_deviation)?;
+    
+    // 记录两侧代币账户交易前的余额，交易后只解包WSOL账户中超出交易前余额的部分
+    let token_balances_before = ctx.accounts.token_balances_before()?;
     
     // 检查源代币账户是否属于正确的代币类型
+    let user_source_token_account = ctx.accounts.prepare_user_source_token_account(amount_in)?;
     require!(
-        ctx.accounts.user_source_token_account.mint == ctx.accounts.token_mint.key(),
+        user_source_token_account.mint == ctx.accounts.token_mint.key(),
         DexError::InvalidArgument
     );
     
-    // 加载池状态（只加载一次）
-    let pool_state = ctx.accounts.pool_state.load()?;
+    // 检查用户代币账户是否有足够的代币
+    require!(
+        user_source_token_account.amount >= amount_in,
+        DexError::InsufficientFunds
+    );
     
-    // 计算价格影响
-    let (price_impact, expected_amount_out) = calculate_price_impact(
-        &pool_state,
-        amount_in,
-        false,
-        ctx.accounts.token_mint.key()
-    )?;
+    // 目标代币账户不存在时在同一指令中创建
+    ctx.accounts.ensure_user_destination_token_account()?;
+    
+    // 计算价格影响和扣除转账手续费后的预期到账数量
+    let (price_impact, expected_amount_out) = ctx.accounts.quote_with_transfer_fees(amount_in)?;
     
     // 检查价格影响是否过大
     require!(price_impact <= MAX_PRICE_IMPACT, DexError::ExcessivePriceImpact);
     
+    // 检查滑点（min_amount_out为实际到账数量）
+    require!(expected_amount_out >= min_amount_out, DexError::SlippageExceeded);
+    
     // 记录预期输出金额和价格影响
     msg!("预期输出金额: {}, 价格影响: {}%", 
         expected_amount_out, 
@@ -171,149 +219,484 @@ pub fn sell_token(
     msg!("使用Raydium CPI进行交易");
     
     // 构建CPI账户
-    let cpi_accounts = cpi::accounts::Swap {
-        payer: ctx.accounts.user.to_account_info(),
-        authority: ctx.accounts.amm_authority.to_account_info(),
-        amm_config: ctx.accounts.amm_config.to_account_info(),
-        pool_state: ctx.accounts.pool_state.to_account_info(),
-        input_token_account: ctx.accounts.user_source_token_account.to_account_info(),
-        output_token_account: ctx.accounts.user_destination_token_account.to_account_info(),
-        input_vault: ctx.accounts.input_vault.to_account_info(),
-        output_vault: ctx.accounts.output_vault.to_account_info(),
-        input_token_program: ctx.accounts.token_program.to_account_info(),
-        output_token_program: ctx.accounts.token_program.to_account_info(),
-        input_token_mint: ctx.accounts.input_token_mint.to_account_info(),
-        output_token_mint: ctx.accounts.output_token_mint.to_account_info(),
-        observation_state: ctx.accounts.observation_state.to_account_info(),
-    };
+    let cpi_accounts = ctx.accounts.swap_cpi_accounts();
     
     // 创建CPI上下文
     let cpi_program = ctx.accounts.raydium_program.to_account_info();
     let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
     
-    // 记录交易开始时间
-    let start_time = Clock::get()?.unix_timestamp;
+    // 记录交易前余额
+    let snapshot = ctx.accounts.balance_snapshot()?;
     
     // 执行交易
     msg!("执行Raydium swap_base_input交易");
     cpi::swap_base_input(cpi_context, amount_in, min_amount_out)?;
     
-    // 记录交易结束时间
-    let end_time = Clock::get()?.unix_timestamp;
-    let execution_time = end_time - start_time;
-    
-    // 发出交易完成事件
-    emit!(SwapExecuted {
-        user: ctx.accounts.user.key(),
-        dex: "Raydium".to_string(),
-        token_in: ctx.accounts.input_token_mint.key(),
-        token_out: ctx.accounts.output_token_mint.key(),
-        amount_in,
+    // 按实际余额变化检查成交数量（解包WSOL之前）
+    let fill = ctx.accounts.settle_fill(&snapshot, &FillLimits {
+        expected_amount_in: amount_in,
+        expected_amount_out,
+        max_amount_in: amount_in,
         min_amount_out,
-        actual_amount_out: expected_amount_out, // 实际值需要从交易后的账户余额中获取
-        price_impact,
-        execution_time,
+    })?;
+    
+    // 成交后的价格同样不能偏离TWAP过多，限制单笔交易把价格推离TWAP的幅度
+    ctx.accounts.check_post_trade_twap_deviation(twap_limit)?;
+    
+    // 交易完成后将WSOL解包为SOL
+    ctx.accounts.unwrap_native_accounts(token_balances_before)?;
+    
+    // 全部卖出后可选地关闭源代币账户
+    if close_token_account {
+        ctx.accounts.close_empty_user_source_token_account()?;
+    }
+    
+    msg!("交易完成，获得SOL");
+    Ok(fill)
+}
+
+// 在Raydium上购买精确数量的代币（swap_base_output）
+pub fn buy_exact_out(
+    ctx: Context<TradeTokenRaydium>,
+    amount_out: u64,  // 需要获得的代币数量（实际到账数量）
+    max_amount_in: u64,  // 最大SOL花费（滑点控制）
+    max_twap_deviation: Option<u64>,  // 交易前后现货价格相对TWAP的最大偏离，为空时使用默认值，观察历史不足时只有FEE_DENOMINATOR允许交易
+) -> Result<Fill> {
+    msg!("在Raydium上购买代币: 期望获得 {} 代币, 最大SOL花费: {}", amount_out, max_amount_in);
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    // 检查参数
+    require!(amount_out > 0, DexError::InvalidArgument);
+    require!(max_amount_in > 0, DexError::InvalidArgument);
+    
+    // 校验池子相关账户并按买卖方向确认输入输出两侧
+    ctx.accounts.validate_pool_accounts(true)?;
+    
+    // 检查现货价格与TWAP的偏离，防止在流动性较差的池子中被操纵价格
+    let twap_limit = ctx.accounts.check_twap_deviation(max_twap_deviation)?;
+    
+    // 记录两侧代币账户交易前的余额，交易后只解包WSOL账户中超出交易前余额的部分
+    let token_balances_before = ctx.accounts.token_balances_before()?;
+    
+    // 输入为SOL时在同一指令中创建WSOL账户并按最大花费包装SOL，未用完的部分交易后解包退还
+    ctx.accounts.prepare_user_source_token_account(max_amount_in)?;
+    
+    // 目标代币账户不存在时在同一指令中创建，并检查是否属于正确的代币类型
+    let user_destination_token_account = ctx.accounts.ensure_user_destination_token_account()?;
+    require!(
+        user_destination_token_account.mint == ctx.accounts.token_mint.key(),
+        DexError::InvalidArgument
+    );
+    
+    // 记录交易前余额
+    let snapshot = ctx.accounts.balance_snapshot()?;
+    
+    let expected_amount_in = ctx.accounts.swap_exact_out(amount_out, max_amount_in)?;
+    
+    // 按实际余额变化检查成交数量（解包WSOL之前）
+    let fill = ctx.accounts.settle_fill(&snapshot, &FillLimits {
+        expected_amount_in,
+        expected_amount_out: amount_out,
+        max_amount_in,
+        min_amount_out: amount_out,
+    })?;
+    
+    // 成交后的价格同样不能偏离TWAP过多，限制单笔交易把价格推离TWAP的幅度
+    ctx.accounts.check_post_trade_twap_deviation(twap_limit)?;
+    
+    // 交易完成后将WSOL解包为SOL
+    ctx.accounts.unwrap_native_accounts(token_balances_before)?;
+    
+    msg!("交易完成，获得代币");
+    Ok(fill)
+}
+
+// 在Raydium上卖出代币以获得精确数量的SOL（swap_base_output）
+pub fn sell_exact_out(
+    ctx: Context<TradeTokenRaydium>,
+    amount_out: u64,  // 需要获得的SOL数量（实际到账数量）
+    max_amount_in: u64,  // 最多卖出的代币数量（滑点控制）
+    close_token_account: bool,  // 全部卖出后是否关闭用户代币账户以回收租金
+    max_twap_deviation: Option<u64>,  // 交易前后现货价格相对TWAP的最大偏离，为空时使用默认值，观察历史不足时只有FEE_DENOMINATOR允许交易
+) -> Result<Fill> {
+    msg!("在Raydium上卖出代币: 期望获得 {} SOL, 最多卖出代币数量: {}", amount_out, max_amount_in);
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    // 检查参数
+    require!(amount_out > 0, DexError::InvalidArgument);
+    require!(max_amount_in > 0, DexError::InvalidArgument);
+    
+    // 校验池子相关账户并按买卖方向确认输入输出两侧
+    ctx.accounts.validate_pool_accounts(false)?;
+    
+    // 检查现货价格与TWAP的偏离，防止在流动性较差的池子中被操纵价格
+    let twap_limit = ctx.accounts.check_twap_deviation(max_twap_deviation)?;
+    
+    // 记录两侧代币账户交易前的余额，交易后只解包WSOL账户中超出交易前余额的部分
+    let token_balances_before = ctx.accounts.token_balances_before()?;
+    
+    // 检查源代币账户是否属于正确的代币类型
+    let user_source_token_account = ctx.accounts.prepare_user_source_token_account(max_amount_in)?;
+    require!(
+        user_source_token_account.mint == ctx.accounts.token_mint.key(),
+        DexError::InvalidArgument
+    );
+    
+    // 目标代币账户不存在时在同一指令中创建
+    ctx.accounts.ensure_user_destination_token_account()?;
+    
+    // 记录交易前余额
+    let snapshot = ctx.accounts.balance_snapshot()?;
+    
+    let expected_amount_in = ctx.accounts.swap_exact_out(amount_out, max_amount_in)?;
+    
+    // 按实际余额变化检查成交数量（解包WSOL之前）
+    let fill = ctx.accounts.settle_fill(&snapshot, &FillLimits {
+        expected_amount_in,
+        expected_amount_out: amount_out,
+        max_amount_in,
+        min_amount_out: amount_out,
+    })?;
+    
+    // 成交后的价格同样不能偏离TWAP过多，限制单笔交易把价格推离TWAP的幅度
+    ctx.accounts.check_post_trade_twap_deviation(twap_limit)?;
+    
+    // 交易完成后将WSOL解包为SOL
+    ctx.accounts.unwrap_native_accounts(token_balances_before)?;
+    
+    // 全部卖出后可选地关闭源代币账户
+    if close_token_account {
+        ctx.accounts.close_empty_user_source_token_account()?;
+    }
+    
+    msg!("交易完成，获得SOL");
+    Ok(fill)
+}
+
+// 向Raydium CPMM池子添加流动性（按LP数量存入两侧代币）
+pub fn deposit(
+    ctx: Context<LiquidityRaydium>,
+    lp_token_amount: u64,  // 获得的LP数量
+    maximum_token_0_amount: u64,  // 最多投入的token_0数量（滑点控制）
+    maximum_token_1_amount: u64,  // 最多投入的token_1数量（滑点控制）
+) -> Result<()> {
+    msg!("向Raydium池子添加流动性: LP数量 {}, 最多投入token_0: {}, token_1: {}",
+        lp_token_amount,
+        maximum_token_0_amount,
+        maximum_token_1_amount
+    );
+    msg!("池子地址: {}", ctx.accounts.pool_state.key());
+    
+    // 检查参数
+    require!(lp_token_amount > 0, DexError::InvalidArgument);
+    require!(maximum_token_0_amount > 0 && maximum_token_1_amount > 0, DexError::InvalidArgument);
+    
+    // 校验池子相关账户
+    ctx.accounts.validate_pool_accounts()?;
+    
+    // 按当前储备计算需要投入的两侧代币，加上Token-2022转账手续费后为用户实际转出的数量
+    let (reserve_0, reserve_1, lp_supply) = ctx.accounts.pool_reserves()?;
+    let (token_0_amount, token_1_amount) =
+        quote::lp_to_token_amounts(lp_token_amount, lp_supply, reserve_0, reserve_1, true)?;
+    let token_0_amount = token_utils::amount_before_transfer_fee(&ctx.accounts.vault_0_mint.to_account_info(), token_0_amount)?;
+    let token_1_amount = token_utils::amount_before_transfer_fee(&ctx.accounts.vault_1_mint.to_account_info(), token_1_amount)?;
+    
+    msg!("预计投入token_0: {}, token_1: {}", token_0_amount, token_1_amount);
+    
+    // 检查滑点
+    require!(
+        token_0_amount <= maximum_token_0_amount && token_1_amount <= maximum_token_1_amount,
+        DexError::SlippageExceeded
+    );
+    
+    // 准备用户两侧代币账户，SOL一侧按最大投入数量包装，未用完的部分之后解包退还
+    // 交易后只解包WSOL账户中超出交易前余额的部分
+    let accounts = &ctx.accounts;
+    let token_balances_before = accounts.token_balances_before()?;
+    accounts.prepare_user_token_account(&accounts.user_token_0_account, &accounts.vault_0_mint, maximum_token_0_amount, token_0_amount)?;
+    accounts.prepare_user_token_account(&accounts.user_token_1_account, &accounts.vault_1_mint, maximum_token_1_amount, token_1_amount)?;
+    
+    // LP代币账户不存在时在同一指令中创建
+    accounts.ensure_user_token_account(&accounts.user_lp_token_account, &accounts.lp_mint)?;
+    
+    // 记录交易前余额
+    let balances_before = accounts.user_balances()?;
+    
+    // 执行添加流动性
+    let cpi_context = CpiContext::new(accounts.raydium_program.to_account_info(), accounts.deposit_cpi_accounts());
+    cpi::deposit(cpi_context, lp_token_amount, maximum_token_0_amount, maximum_token_1_amount)?;
+    
+    // 按实际余额变化检查投入数量和获得的LP数量（解包WSOL之前）
+    let balances_after = accounts.user_balances()?;
+    let token_0_spent = balance_difference(balances_before.0, balances_after.0)?;
+    let token_1_spent = balance_difference(balances_before.1, balances_after.1)?;
+    let lp_received = balance_difference(balances_after.2, balances_before.2)?;
+    require!(lp_received == lp_token_amount, DexError::UnexpectedBalanceChange);
+    require!(
+        token_0_spent <= maximum_token_0_amount && token_1_spent <= maximum_token_1_amount,
+        DexError::SlippageExceeded
+    );
+    
+    msg!("实际投入token_0: {}, token_1: {}, 获得LP: {}", token_0_spent, token_1_spent, lp_received);
+    
+    // 交易完成后将WSOL解包为SOL
+    accounts.unwrap_native_accounts(token_balances_before)?;
+    
+    // 记录添加流动性后的池子储备（入场储备）
+    let (reserve_0, reserve_1, lp_supply) = ctx.accounts.reload_pool_reserves()?;
+    let accounts = &ctx.accounts;
+    emit!(LiquidityDeposited {
+        user: accounts.user.key(),
+        pool_state: accounts.pool_state.key(),
+        lp_mint: accounts.lp_mint.key(),
+        token_0_mint: accounts.vault_0_mint.key(),
+        token_1_mint: accounts.vault_1_mint.key(),
+        lp_amount: lp_received,
+        token_0_amount: token_0_spent,
+        token_1_amount: token_1_spent,
+        reserve_0,
+        reserve_1,
+        lp_supply,
+        entry_price: accounts.pool_price(reserve_0, reserve_1)?,
         slot: Clock::get()?.slot,
     });
     
-    msg!("交易完成，获得SOL");
+    msg!("添加流动性完成");
     Ok(())
 }
 
-// 计算价格影响
-fn calculate_price_impact(
-    pool_state: &PoolState,
-    amount_in: u64,
-    is_buy: bool,
-    token_mint: Pubkey,
-) -> Result<(u64, u64)> {
-    // 获取池子中的代币储备
-    let token_0_vault_amount = pool_state.token_0_vault_amount;
-    let token_1_vault_amount = pool_state.token_1_vault_amount;
+// 从Raydium CPMM池子移除流动性（销毁LP取回两侧代币）
+pub fn withdraw(
+    ctx: Context<LiquidityRaydium>,
+    lp_token_amount: u64,  // 销毁的LP数量
+    minimum_token_0_amount: u64,  // 最少取回的token_0数量（实际到账数量，滑点控制）
+    minimum_token_1_amount: u64,  // 最少取回的token_1数量（实际到账数量，滑点控制）
+) -> Result<()> {
+    msg!("从Raydium池子移除流动性: LP数量 {}, 最少取回token_0: {}, token_1: {}",
+        lp_token_amount,
+        minimum_token_0_amount,
+        minimum_token_1_amount
+    );
+    msg!("池子地址: {}", ctx.accounts.pool_state.key());
+    
+    // 检查参数
+    require!(lp_token_amount > 0, DexError::InvalidArgument);
+    
+    // 校验池子相关账户
+    ctx.accounts.validate_pool_accounts()?;
+    
+    // 检查用户LP代币账户是否有足够的LP
+    let accounts = &ctx.accounts;
+    let user_lp_token_account = token_utils::read_token_account(&accounts.user_lp_token_account)?;
+    require_keys_eq!(user_lp_token_account.mint, accounts.lp_mint.key(), DexError::InvalidTokenAccount);
+    require_keys_eq!(user_lp_token_account.owner, accounts.user.key(), DexError::InvalidTokenAccount);
+    require!(user_lp_token_account.amount >= lp_token_amount, DexError::InsufficientFunds);
     
-    // 如果储备为0，返回错误
+    // 按当前储备计算可取回的两侧代币，扣除Token-2022转账手续费后为用户实际到账数量
+    let (reserve_0, reserve_1, lp_supply) = accounts.pool_reserves()?;
+    let (token_0_amount, token_1_amount) =
+        quote::lp_to_token_amounts(lp_token_amount, lp_supply, reserve_0, reserve_1, false)?;
+    let token_0_amount = token_utils::amount_after_transfer_fee(&accounts.vault_0_mint.to_account_info(), token_0_amount)?;
+    let token_1_amount = token_utils::amount_after_transfer_fee(&accounts.vault_1_mint.to_account_info(), token_1_amount)?;
+    
+    msg!("预计取回token_0: {}, token_1: {}", token_0_amount, token_1_amount);
+    
+    // 检查滑点
     require!(
-        token_0_vault_amount > 0 && token_1_vault_amount > 0,
-        DexError::InsufficientLiquidity
+        token_0_amount >= minimum_token_0_amount && token_1_amount >= minimum_token_1_amount,
+        DexError::SlippageExceeded
     );
     
-    // 确定输入和输出代币
-    let (input_amount, output_amount) = if token_mint == pool_state.token_0_mint {
-        // 如果查询的是代币0
-        if is_buy {
-            // 买入代币0，使用代币1作为输入
-            (token_1_vault_amount, token_0_vault_amount)
-        } else {
-            // 卖出代币0，使用代币0作为输入
-            (token_0_vault_amount, token_1_vault_amount)
-        }
-    } else {
-        // 如果查询的是代币1
-        if is_buy {
-            // 买入代币1，使用代币0作为输入
-            (token_0_vault_amount, token_1_vault_amount)
-        } else {
-            // 卖出代币1，使用代币1作为输入
-            (token_1_vault_amount, token_0_vault_amount)
-        }
-    };
+    // 两侧代币账户不存在时在同一指令中创建，交易后只解包WSOL账户中超出交易前余额的部分
+    let token_balances_before = accounts.token_balances_before()?;
+    accounts.ensure_user_token_account(&accounts.user_token_0_account, &accounts.vault_0_mint)?;
+    accounts.ensure_user_token_account(&accounts.user_token_1_account, &accounts.vault_1_mint)?;
     
-    // 使用恒定乘积公式计算价格
-    // 公式: k = input_amount * output_amount
-    let k = input_amount
-        .checked_mul(output_amount)
-        .ok_or(ProgramError::ArithmeticOverflow)?;
-    
-    // 计算交易后的新储备
-    let new_input_amount = input_amount
-        .checked_add(amount_in)
-        .ok_or(ProgramError::ArithmeticOverflow)?;
-    
-    let new_output_amount = k
-        .checked_div(new_input_amount)
-        .ok_or(ProgramError::ArithmeticOverflow)?;
-    
-    // 计算输出金额
-    let amount_out = output_amount
-        .checked_sub(new_output_amount)
-        .ok_or(ProgramError::ArithmeticOverflow)?;
-    
-    // 考虑交易费用
-    let fee_rate = pool_state.fee_rate;
-    let fee_amount = amount_out
-        .checked_mul(fee_rate as u64)
-        .unwrap_or(0)
-        .checked_div(FEE_DENOMINATOR)
-        .unwrap_or(0);
-    
-    let amount_out_after_fee = amount_out.checked_sub(fee_amount).unwrap_or(amount_out);
-    
-    // 计算价格影响（以万分比表示）
-    let ideal_price = output_amount
-        .checked_mul(10_000)
-        .unwrap_or(0)
-        .checked_div(input_amount)
-        .unwrap_or(0);
-    
-    let actual_price = amount_out_after_fee
-        .checked_mul(10_000)
-        .unwrap_or(0)
-        .checked_div(amount_in)
-        .unwrap_or(0);
-    
-    let price_impact = if ideal_price > actual_price {
-        ideal_price
-            .checked_sub(actual_price)
-            .unwrap_or(0)
-            .checked_mul(10_000)
-            .unwrap_or(0)
-            .checked_div(ideal_price)
-            .unwrap_or(0)
-    } else {
-        0
+    // 记录交易前余额
+    let balances_before = accounts.user_balances()?;
+    
+    // 执行移除流动性
+    let cpi_context = CpiContext::new(accounts.raydium_program.to_account_info(), accounts.withdraw_cpi_accounts());
+    cpi::withdraw(cpi_context, lp_token_amount, minimum_token_0_amount, minimum_token_1_amount)?;
+    
+    // 按实际余额变化检查取回数量和销毁的LP数量（解包WSOL之前）
+    let balances_after = accounts.user_balances()?;
+    let token_0_received = balance_difference(balances_after.0, balances_before.0)?;
+    let token_1_received = balance_difference(balances_after.1, balances_before.1)?;
+    let lp_burned = balance_difference(balances_before.2, balances_after.2)?;
+    require!(lp_burned == lp_token_amount, DexError::UnexpectedBalanceChange);
+    require!(
+        token_0_received >= minimum_token_0_amount && token_1_received >= minimum_token_1_amount,
+        DexError::SlippageExceeded
+    );
+    
+    msg!("实际取回token_0: {}, token_1: {}, 销毁LP: {}", token_0_received, token_1_received, lp_burned);
+    
+    // 交易完成后将WSOL解包为SOL
+    accounts.unwrap_native_accounts(token_balances_before)?;
+    
+    // 记录移除流动性后的池子储备（出场储备）
+    let (reserve_0, reserve_1, lp_supply) = ctx.accounts.reload_pool_reserves()?;
+    let accounts = &ctx.accounts;
+    emit!(LiquidityWithdrawn {
+        user: accounts.user.key(),
+        pool_state: accounts.pool_state.key(),
+        lp_mint: accounts.lp_mint.key(),
+        token_0_mint: accounts.vault_0_mint.key(),
+        token_1_mint: accounts.vault_1_mint.key(),
+        lp_amount: lp_burned,
+        token_0_amount: token_0_received,
+        token_1_amount: token_1_received,
+        reserve_0,
+        reserve_1,
+        lp_supply,
+        exit_price: accounts.pool_price(reserve_0, reserve_1)?,
+        slot: Clock::get()?.slot,
+    });
+    
+    msg!("移除流动性完成");
+    Ok(())
+}
+
+// 在Raydium CPMM上创建池子并注入初始流动性
+// 池子、金库、LP Mint和观察状态均为CPMM的PDA，由账户约束按种子校验
+// 不支持在建池的同一交易中初始买入: CPMM的initialize会将不晚于当前时间的open_time调整为当前时间+1秒，
+// 而swap要求当前时间不早于open_time，因此同一交易（同一区块时间）内的任何买入都会被CPMM拒绝。
+// 需要初始买入时，应在池子开放后另行调用buy_token_on_raydium
+pub fn create_pool(
+    ctx: Context<CreatePoolRaydium>,
+    init_amount_0: u64,  // 注入的token_0数量
+    init_amount_1: u64,  // 注入的token_1数量
+    open_time: u64,  // 开放交易的时间戳，0表示尽快开放
+) -> Result<()> {
+    msg!("在Raydium上创建池子: token_0 {}, token_1 {}",
+        ctx.accounts.token_0_mint.key(),
+        ctx.accounts.token_1_mint.key()
+    );
+    msg!("池子地址: {}, 费率配置: {}", ctx.accounts.pool_state.key(), ctx.accounts.amm_config.key());
+    
+    // 检查参数
+    require!(init_amount_0 > 0 && init_amount_1 > 0, DexError::InvalidArgument);
+    require!(!ctx.accounts.amm_config.disable_create_pool, DexError::RaydiumPoolCreationDisabled);
+    
+    // 创建者LP代币账户由CPMM以关联代币账户的形式创建
+    let accounts = &ctx.accounts;
+    require_keys_eq!(
+        accounts.user_lp_token_account.key(),
+        get_associated_token_address(&accounts.user.key(), &accounts.lp_mint.key()),
+        DexError::InvalidTokenAccount
+    );
+    
+    // 准备用户两侧代币账户，SOL一侧按注入数量包装，交易后只解包WSOL账户中超出交易前余额的部分
+    let token_balances_before = accounts.token_balances_before()?;
+    accounts.prepare_user_token_account(&accounts.user_token_0_account, &accounts.token_0_mint, &accounts.token_0_program, init_amount_0)?;
+    accounts.prepare_user_token_account(&accounts.user_token_1_account, &accounts.token_1_mint, &accounts.token_1_program, init_amount_1)?;
+    
+    // 记录交易前余额
+    let token_0_balance_before = read_balance(&accounts.user_token_0_account)?;
+    let token_1_balance_before = read_balance(&accounts.user_token_1_account)?;
+    
+    // 执行创建池子
+    let cpi_context = CpiContext::new(accounts.raydium_program.to_account_info(), accounts.initialize_cpi_accounts());
+    cpi::initialize(cpi_context, init_amount_0, init_amount_1, open_time)?;
+    
+    // 按实际余额变化记录注入数量和获得的LP数量（解包WSOL之前）
+    let token_0_amount = balance_difference(token_0_balance_before, read_balance(&accounts.user_token_0_account)?)?;
+    let token_1_amount = balance_difference(token_1_balance_before, read_balance(&accounts.user_token_1_account)?)?;
+    let lp_amount = read_balance(&accounts.user_lp_token_account)?;
+    
+    msg!("实际注入token_0: {}, token_1: {}, 获得LP: {}", token_0_amount, token_1_amount, lp_amount);
+    
+    // 交易完成后将WSOL解包为SOL
+    accounts.unwrap_native_accounts(token_balances_before)?;
+    
+    // 记录创建者LP仓位的入场储备: 新池子尚无协议和基金手续费，储备即为金库余额
+    let reserve_0 = token_utils::read_token_account(&accounts.token_0_vault)?.amount;
+    let reserve_1 = token_utils::read_token_account(&accounts.token_1_vault)?.amount;
+    let lp_supply = {
+        let data = accounts.lp_mint.try_borrow_data()?;
+        Mint::try_deserialize(&mut &data[..])?.supply
     };
+    let initial_price = Price::from_amounts(
+        reserve_0,
+        accounts.token_0_mint.decimals,
+        reserve_1,
+        accounts.token_1_mint.decimals,
+    )?;
+    
+    // 与CPMM一致: 开放交易时间不早于当前时间+1秒
+    let clock = Clock::get()?;
+    let open_time = open_time.max(clock.unix_timestamp as u64 + 1);
+    let slot = clock.slot;
+    
+    msg!("池子创建完成，开放交易时间: {}, 初始价格: {}", open_time, initial_price.as_f64());
+    
+    emit!(PoolCreated {
+        user: accounts.user.key(),
+        pool_state: accounts.pool_state.key(),
+        amm_config: accounts.amm_config.key(),
+        token_0_mint: accounts.token_0_mint.key(),
+        token_1_mint: accounts.token_1_mint.key(),
+        lp_mint: accounts.lp_mint.key(),
+        open_time,
+        slot,
+    });
     
-    Ok((price_impact, amount_out_after_fee))
+    emit!(LiquidityDeposited {
+        user: accounts.user.key(),
+        pool_state: accounts.pool_state.key(),
+        lp_mint: accounts.lp_mint.key(),
+        token_0_mint: accounts.token_0_mint.key(),
+        token_1_mint: accounts.token_1_mint.key(),
+        lp_amount,
+        token_0_amount,
+        token_1_amount,
+        reserve_0,
+        reserve_1,
+        lp_supply,
+        entry_price: initial_price,
+        slot,
+    });
+    
+    Ok(())
+}
+
+// 余额变化量: minuend - subtrahend，余额向相反方向变化时返回错误
+fn balance_difference(minuend: u64, subtrahend: u64) -> Result<u64> {
+    minuend.checked_sub(subtrahend).ok_or_else(|| error!(DexError::UnexpectedBalanceChange))
+}
+
+// 读取池子两侧的实际储备(reserve_0, reserve_1)，两个金库账户可以任意顺序传入
+fn load_pool_reserves(
+    pool_state: &PoolState,
+    vault_a: &InterfaceAccount<TokenAccount>,
+    vault_b: &InterfaceAccount<TokenAccount>,
+) -> Result<(u64, u64)> {
+    let (token_0_vault_amount, token_1_vault_amount) =
+        if vault_a.key() == pool_state.token_0_vault && vault_b.key() == pool_state.token_1_vault {
+            (vault_a.amount, vault_b.amount)
+        } else if vault_a.key() == pool_state.token_1_vault && vault_b.key() == pool_state.token_0_vault {
+            (vault_b.amount, vault_a.amount)
+        } else {
+            return err!(DexError::RaydiumVaultMismatch);
+        };
+    
+    quote::pool_reserves(pool_state, token_0_vault_amount, token_1_vault_amount)
+}
+
+// 池子权限PDA: [RAYDIUM_CPMM_AUTH_SEED, auth_bump]
+fn amm_authority_address(pool_state: &PoolState, program_id: &Pubkey) -> Result<Pubkey> {
+    Pubkey::create_program_address(&[RAYDIUM_CPMM_AUTH_SEED, &[pool_state.auth_bump]], program_id)
+        .map_err(|_| error!(DexError::InvalidRaydiumAuthority))
+}
+
+// 按交易方向排列储备，返回(输入储备, 输出储备)
+fn directional_reserves(reserves: (u64, u64), input_is_token_0: bool) -> (u64, u64) {
+    if input_is_token_0 {
+        reserves
+    } else {
+        (reserves.1, reserves.0)
+    }
 }
 
 // Raydium交易所需的账户结构
@@ -330,8 +713,8 @@ pub struct TradeTokenRaydium<'info> {
     )]
     pub dex_account: Account<'info, crate::DexAccount>,
     
-    // 代币Mint地址
-    pub token_mint: Account<'info, token::Mint>,
+    // 代币Mint地址（支持SPL Token和Token-2022）
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
     
     // Raydium程序
     pub raydium_program: Program<'info, RaydiumCpmm>,
@@ -344,26 +727,808 @@ pub struct TradeTokenRaydium<'info> {
     
     // 代币账户
     #[account(mut)]
-    pub input_vault: Box<Account<'info, TokenAccount>>,
+    pub input_vault: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(mut)]
-    pub output_vault: Box<Account<'info, TokenAccount>>,
+    pub output_vault: Box<InterfaceAccount<'info, TokenAccount>>,
     
     // 用户账户
+    // 输入为SOL时源代币账户为用户的WSOL账户，不存在时以幂等方式创建关联代币账户
     #[account(mut)]
-    pub user_source_token_account: Account<'info, TokenAccount>,
+    pub user_source_token_account: UncheckedAccount<'info>,
+    // 目标代币账户不存在时以幂等方式创建关联代币账户
     #[account(mut)]
-    pub user_destination_token_account: Account<'info, TokenAccount>,
+    pub user_destination_token_account: UncheckedAccount<'info>,
     
-    // 代币Mint
-    pub input_token_mint: Box<Account<'info, token::Mint>>,
-    pub output_token_mint: Box<Account<'info, token::Mint>>,
+    // 代币Mint，输入和输出两侧可以分别属于SPL Token或Token-2022
+    #[account(mint::token_program = input_token_program)]
+    pub input_token_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program = output_token_program)]
+    pub output_token_mint: Box<InterfaceAccount<'info, Mint>>,
     
     // 观察状态
     #[account(mut)]
     pub observation_state: AccountLoader<'info, ObservationState>,
     
+    // 输入和输出代币各自的代币程序
+    pub input_token_program: Interface<'info, TokenInterface>,
+    pub output_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> TradeTokenRaydium<'info> {
+    // 校验AMM配置、权限、观察状态账户属于该池子，
+    // 并根据pool_state中的token_0/token_1和买卖方向校验输入输出两侧的Mint、金库和代币程序
+    fn validate_pool_accounts(&self, is_buy: bool) -> Result<()> {
+        let pool_state = self.pool_state.load()?;
+        
+        require_keys_eq!(self.amm_config.key(), pool_state.amm_config, DexError::InvalidRaydiumAmmConfig);
+        require_keys_eq!(
+            self.observation_state.key(),
+            pool_state.observation_key,
+            DexError::InvalidRaydiumObservationState
+        );
+        
+        require_keys_eq!(
+            self.amm_authority.key(),
+            amm_authority_address(&pool_state, &self.raydium_program.key())?,
+            DexError::InvalidRaydiumAuthority
+        );
+        
+        // 买入时代币一侧为输出，卖出时代币一侧为输入
+        let token_mint = self.token_mint.key();
+        let token_is_token_0 = if token_mint == pool_state.token_0_mint {
+            true
+        } else if token_mint == pool_state.token_1_mint {
+            false
+        } else {
+            return err!(DexError::TokenNotInRaydiumPool);
+        };
+        let input_is_token_0 = token_is_token_0 != is_buy;
+        
+        // (mint, 金库, 代币程序)
+        let token_0_side = (pool_state.token_0_mint, pool_state.token_0_vault, pool_state.token_0_program);
+        let token_1_side = (pool_state.token_1_mint, pool_state.token_1_vault, pool_state.token_1_program);
+        let (input_side, output_side) = if input_is_token_0 {
+            (token_0_side, token_1_side)
+        } else {
+            (token_1_side, token_0_side)
+        };
+        
+        require_keys_eq!(self.input_token_mint.key(), input_side.0, DexError::RaydiumMintMismatch);
+        require_keys_eq!(self.output_token_mint.key(), output_side.0, DexError::RaydiumMintMismatch);
+        require_keys_eq!(self.input_vault.key(), input_side.1, DexError::RaydiumVaultMismatch);
+        require_keys_eq!(self.output_vault.key(), output_side.1, DexError::RaydiumVaultMismatch);
+        require_keys_eq!(self.input_token_program.key(), input_side.2, DexError::InvalidRaydiumTokenProgram);
+        require_keys_eq!(self.output_token_program.key(), output_side.2, DexError::InvalidRaydiumTokenProgram);
+        
+        Ok(())
+    }
+    
+    // 检查池子现货价格与观察状态计算的TWAP的偏离，返回交易后检查使用的TWAP上限
+    // max_deviation为空时使用默认的MAX_TWAP_DEVIATION，观察历史不足时拒绝交易
+    fn check_twap_deviation(&self, max_deviation: Option<u64>) -> Result<Option<twap::TwapLimit>> {
+        let max_deviation = max_deviation.unwrap_or(MAX_TWAP_DEVIATION);
+        require!(max_deviation <= FEE_DENOMINATOR, DexError::InvalidArgument);
+        
+        // 只有调用方显式接受任意偏离（FEE_DENOMINATOR，即100%）时才在观察历史不足时跳过检查
+        let twap = match twap::token_0_twap(&*self.observation_state.load()?, twap::TWAP_WINDOW_SECONDS) {
+            Some(twap) => twap,
+            None if max_deviation == FEE_DENOMINATOR => {
+                msg!("Raydium观察历史不足，调用方已接受任意偏离，跳过TWAP检查");
+                return Ok(None);
+            },
+            None => return Err(DexError::TwapUnavailable.into()),
+        };
+        
+        let twap_limit = twap::TwapLimit { twap, max_deviation };
+        self.check_spot_price_against_twap(&twap_limit, "现货价格")?;
+        Ok(Some(twap_limit))
+    }
+    
+    // 交易后重新读取金库余额，检查成交后的现货价格与TWAP的偏离，未做交易前检查时跳过
+    fn check_post_trade_twap_deviation(&mut self, twap_limit: Option<twap::TwapLimit>) -> Result<()> {
+        if let Some(twap_limit) = twap_limit {
+            self.input_vault.reload()?;
+            self.output_vault.reload()?;
+            self.check_spot_price_against_twap(&twap_limit, "交易后价格")?;
+        }
+        Ok(())
+    }
+    
+    // 按当前金库余额计算现货价格并检查相对TWAP的偏离
+    fn check_spot_price_against_twap(&self, twap_limit: &twap::TwapLimit, label: &str) -> Result<()> {
+        let pool_state = self.pool_state.load()?;
+        let (reserve_0, reserve_1) = load_pool_reserves(&pool_state, &self.input_vault, &self.output_vault)?;
+        let spot_price_x32 = twap::spot_token_0_price_x32(reserve_0, reserve_1)?;
+        let deviation = twap::price_deviation(spot_price_x32, twap_limit.twap.token_0_price_x32)?;
+        
+        msg!("{}相对TWAP偏离: {}%, 时间窗口: {}秒",
+            label,
+            deviation as f64 / 10_000.0,
+            twap_limit.twap.window
+        );
+        
+        require!(deviation <= twap_limit.max_deviation, DexError::TwapDeviationExceeded);
+        Ok(())
+    }
+    
+    // 准备源代币账户并返回其状态
+    // 输入为SOL时确保WSOL账户存在，并从用户的SOL中包装amount_in，不动用账户中原有的WSOL
+    fn prepare_user_source_token_account(&self, amount_in: u64) -> Result<TokenAccount> {
+        let user_source_token_account = self.user_source_token_account.to_account_info();
+        
+        if !self.is_native_mint(&self.input_token_mint) {
+            require_keys_eq!(
+                *user_source_token_account.owner,
+                self.input_token_program.key(),
+                DexError::InvalidTokenAccount
+            );
+            let token_account = token_utils::read_token_account(&user_source_token_account)?;
+            require_keys_eq!(token_account.mint, self.input_token_mint.key(), DexError::InvalidTokenAccount);
+            require_keys_eq!(token_account.owner, self.user.key(), DexError::InvalidTokenAccount);
+            return Ok(token_account);
+        }
+        
+        token_utils::ensure_user_token_account(
+            &self.user.to_account_info(),
+            &user_source_token_account,
+            &self.input_token_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.input_token_program.to_account_info(),
+            &self.associated_token_program.to_account_info(),
+        )?;
+        
+        require!(self.user.lamports() >= amount_in, DexError::InsufficientFunds);
+        token_utils::wrap_sol(
+            &self.user.to_account_info(),
+            &user_source_token_account,
+            amount_in,
+            &self.system_program.to_account_info(),
+            &self.input_token_program.to_account_info(),
+        )?;
+        
+        token_utils::read_token_account(&user_source_token_account)
+    }
+    
+    // 记录交易前用户源和目标代币账户的余额
+    fn balance_snapshot(&self) -> Result<BalanceSnapshot> {
+        BalanceSnapshot::take(&self.user_source_token_account, &self.user_destination_token_account)
+    }
+    
+    // 按交易后的余额变化结算实际成交数量并发出成交事件
+    fn settle_fill(&self, snapshot: &BalanceSnapshot, limits: &FillLimits) -> Result<Fill> {
+        snapshot.settle(
+            &self.user_source_token_account,
+            &self.user_destination_token_account,
+            self.user.key(),
+            &FillPair {
+                dex: DexType::Raydium,
+                token_in: self.input_token_mint.key(),
+                input_decimals: self.input_token_mint.decimals,
+                token_out: self.output_token_mint.key(),
+                output_decimals: self.output_token_mint.decimals,
+            },
+            limits,
+        )
+    }
+    
+    // 源和目标代币账户交易前的余额(source, destination)，账户尚未创建时为None
+    fn token_balances_before(&self) -> Result<(Option<u64>, Option<u64>)> {
+        Ok((
+            token_utils::balance_before_trade(&self.user_source_token_account)?,
+            token_utils::balance_before_trade(&self.user_destination_token_account)?,
+        ))
+    }
+    
+    // 交易完成后解包WSOL账户中超出交易前余额的部分: 买入时退还未用完的WSOL，卖出时将所得WSOL转为SOL
+    fn unwrap_native_accounts(
+        &self,
+        (source_balance_before, destination_balance_before): (Option<u64>, Option<u64>),
+    ) -> Result<()> {
+        if self.is_native_mint(&self.input_token_mint) {
+            token_utils::unwrap_sol(
+                &self.user.to_account_info(),
+                &self.user_source_token_account.to_account_info(),
+                &self.input_token_mint.to_account_info(),
+                &self.system_program.to_account_info(),
+                &self.input_token_program.to_account_info(),
+                &self.associated_token_program.to_account_info(),
+                source_balance_before,
+            )?;
+        }
+        
+        if self.is_native_mint(&self.output_token_mint) {
+            token_utils::unwrap_sol(
+                &self.user.to_account_info(),
+                &self.user_destination_token_account.to_account_info(),
+                &self.output_token_mint.to_account_info(),
+                &self.system_program.to_account_info(),
+                &self.output_token_program.to_account_info(),
+                &self.associated_token_program.to_account_info(),
+                destination_balance_before,
+            )?;
+        }
+        
+        Ok(())
+    }
+    
+    // 源代币账户余额为0时关闭并回收租金
+    fn close_empty_user_source_token_account(&self) -> Result<()> {
+        token_utils::close_empty_token_account(
+            &self.user.to_account_info(),
+            &self.user_source_token_account.to_account_info(),
+            &self.input_token_program.to_account_info(),
+        )?;
+        Ok(())
+    }
+    
+    fn is_native_mint(&self, mint: &InterfaceAccount<'info, Mint>) -> bool {
+        mint.key() == spl_token::native_mint::id()
+    }
+    
+    // 确保目标代币账户存在（不存在时以幂等方式创建关联代币账户）
+    fn ensure_user_destination_token_account(&self) -> Result<TokenAccount> {
+        token_utils::ensure_user_token_account(
+            &self.user.to_account_info(),
+            &self.user_destination_token_account.to_account_info(),
+            &self.output_token_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.output_token_program.to_account_info(),
+            &self.associated_token_program.to_account_info(),
+        )
+    }
+    
+    // 计算价格影响和用户实际到账的数量
+    // 与Raydium一致: 输入代币转入金库时先扣除转账手续费，输出代币转给用户时再扣除一次
+    fn quote_with_transfer_fees(&self, amount_in: u64) -> Result<(u64, u64)> {
+        let actual_amount_in = token_utils::amount_after_transfer_fee(
+            &self.input_token_mint.to_account_info(),
+            amount_in,
+        )?;
+        require!(actual_amount_in > 0, DexError::InvalidArgument);
+        
+        let (input_reserve, output_reserve) = self.swap_reserves()?;
+        let swap_quote = quote::swap_base_input(
+            input_reserve,
+            output_reserve,
+            &quote::CpmmFees::from_amm_config(&self.amm_config),
+            actual_amount_in,
+        )?;
+        
+        let amount_received = token_utils::amount_after_transfer_fee(
+            &self.output_token_mint.to_account_info(),
+            swap_quote.amount_out,
+        )?;
+        
+        Ok((swap_quote.price_impact, amount_received))
+    }
+    
+    // 精确输出报价，返回(价格影响, 用户需要转出的输入数量)
+    // 与Raydium一致: 先按输出代币的转账手续费反推金库需转出的数量，再按输入代币的转账手续费反推用户需转出的数量
+    fn quote_exact_out_with_transfer_fees(&self, amount_out: u64) -> Result<(u64, u64)> {
+        let actual_amount_out = token_utils::amount_before_transfer_fee(
+            &self.output_token_mint.to_account_info(),
+            amount_out,
+        )?;
+        
+        let (input_reserve, output_reserve) = self.swap_reserves()?;
+        let swap_quote = quote::swap_base_output(
+            input_reserve,
+            output_reserve,
+            &quote::CpmmFees::from_amm_config(&self.amm_config),
+            actual_amount_out,
+        )?;
+        
+        let amount_in = token_utils::amount_before_transfer_fee(
+            &self.input_token_mint.to_account_info(),
+            swap_quote.amount_in,
+        )?;
+        
+        Ok((swap_quote.price_impact, amount_in))
+    }
+    
+    // 按交易方向读取池子实际储备，返回(输入储备, 输出储备)
+    fn swap_reserves(&self) -> Result<(u64, u64)> {
+        let pool_state = self.pool_state.load()?;
+        let input_mint = self.input_token_mint.key();
+        
+        Ok(directional_reserves(
+            load_pool_reserves(&pool_state, &self.input_vault, &self.output_vault)?,
+            input_mint == pool_state.token_0_mint,
+        ))
+    }
+    
+    // 执行swap_base_output: 报价检查通过后调用Raydium CPI，返回预计输入数量
+    fn swap_exact_out(&self, amount_out: u64, max_amount_in: u64) -> Result<u64> {
+        let (price_impact, expected_amount_in) = self.quote_exact_out_with_transfer_fees(amount_out)?;
+        
+        msg!("预计输入数量: {}, 价格影响: {}%", 
+            expected_amount_in, 
+            price_impact as f64 / 10_000.0
+        );
+        
+        // 检查价格影响和滑点（max_amount_in为用户实际转出的数量）
+        require!(price_impact <= MAX_PRICE_IMPACT, DexError::ExcessivePriceImpact);
+        require!(expected_amount_in <= max_amount_in, DexError::SlippageExceeded);
+        
+        // 检查用户源代币账户是否有足够的余额
+        require!(
+            token_utils::read_token_account(&self.user_source_token_account)?.amount >= expected_amount_in,
+            DexError::InsufficientFunds
+        );
+        
+        let cpi_context = CpiContext::new(self.raydium_program.to_account_info(), self.swap_cpi_accounts());
+        
+        msg!("执行Raydium swap_base_output交易");
+        cpi::swap_base_output(cpi_context, max_amount_in, amount_out)?;
+        
+        Ok(expected_amount_in)
+    }
+    
+    // 构建Raydium swap CPI账户（swap_base_input与swap_base_output共用）
+    fn swap_cpi_accounts(&self) -> cpi::accounts::Swap<'info> {
+        cpi::accounts::Swap {
+            payer: self.user.to_account_info(),
+            authority: self.amm_authority.to_account_info(),
+            amm_config: self.amm_config.to_account_info(),
+            pool_state: self.pool_state.to_account_info(),
+            input_token_account: self.user_source_token_account.to_account_info(),
+            output_token_account: self.user_destination_token_account.to_account_info(),
+            input_vault: self.input_vault.to_account_info(),
+            output_vault: self.output_vault.to_account_info(),
+            input_token_program: self.input_token_program.to_account_info(),
+            output_token_program: self.output_token_program.to_account_info(),
+            input_token_mint: self.input_token_mint.to_account_info(),
+            output_token_mint: self.output_token_mint.to_account_info(),
+            observation_state: self.observation_state.to_account_info(),
+        }
+    }
+}
+
+// Raydium添加/移除流动性所需的账户结构
+#[derive(Accounts)]
+pub struct LiquidityRaydium<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // DEX账户
+    #[account(
+        mut,
+        seeds = [b"dex_account".as_ref()],
+        bump
+    )]
+    pub dex_account: Account<'info, crate::DexAccount>,
+    
+    // Raydium程序
+    pub raydium_program: Program<'info, RaydiumCpmm>,
+    
+    // Raydium AMM账户
+    pub amm_authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+    
+    // 池子两侧金库
+    #[account(mut)]
+    pub token_0_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_1_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    // 池子两侧代币Mint（可以分别属于SPL Token或Token-2022）和LP Mint
+    pub vault_0_mint: Box<InterfaceAccount<'info, Mint>>,
+    pub vault_1_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    // 用户账户（不存在时以幂等方式创建关联代币账户）
+    // SOL一侧为用户的WSOL账户，交易完成后解包为SOL
+    #[account(mut)]
+    pub user_token_0_account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user_token_1_account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user_lp_token_account: UncheckedAccount<'info>,
+    
+    // 代币程序（LP代币属于SPL Token）
     pub token_program: Program<'info, Token>,
+    pub token_program_2022: Program<'info, Token2022>,
+    
+    // SPL Memo程序（移除流动性时需要）
+    #[account(
+        constraint = memo_program.key() == token_utils::get_memo_program_id()
+    )]
+    pub memo_program: UncheckedAccount<'info>,
+    
     pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> LiquidityRaydium<'info> {
+    // 校验池子权限、金库、两侧Mint和LP Mint属于该池子
+    fn validate_pool_accounts(&self) -> Result<()> {
+        let pool_state = self.pool_state.load()?;
+        
+        require_keys_eq!(
+            self.amm_authority.key(),
+            amm_authority_address(&pool_state, &self.raydium_program.key())?,
+            DexError::InvalidRaydiumAuthority
+        );
+        
+        require_keys_eq!(self.token_0_vault.key(), pool_state.token_0_vault, DexError::RaydiumVaultMismatch);
+        require_keys_eq!(self.token_1_vault.key(), pool_state.token_1_vault, DexError::RaydiumVaultMismatch);
+        require_keys_eq!(self.vault_0_mint.key(), pool_state.token_0_mint, DexError::RaydiumMintMismatch);
+        require_keys_eq!(self.vault_1_mint.key(), pool_state.token_1_mint, DexError::RaydiumMintMismatch);
+        require_keys_eq!(self.lp_mint.key(), pool_state.lp_mint, DexError::RaydiumLpMintMismatch);
+        
+        Ok(())
+    }
+    
+    // 读取池子实际储备和LP总供应量，返回(reserve_0, reserve_1, lp_supply)
+    fn pool_reserves(&self) -> Result<(u64, u64, u64)> {
+        let pool_state = self.pool_state.load()?;
+        let (reserve_0, reserve_1) = quote::pool_reserves(
+            &pool_state,
+            self.token_0_vault.amount,
+            self.token_1_vault.amount,
+        )?;
+        
+        Ok((reserve_0, reserve_1, pool_state.lp_supply))
+    }
+    
+    // CPI后重新加载金库账户并读取池子储备
+    fn reload_pool_reserves(&mut self) -> Result<(u64, u64, u64)> {
+        self.token_0_vault.reload()?;
+        self.token_1_vault.reload()?;
+        self.pool_reserves()
+    }
+    
+    // 池子价格: 每单位token_0可兑换的token_1数量
+    fn pool_price(&self, reserve_0: u64, reserve_1: u64) -> Result<Price> {
+        Price::from_amounts(reserve_0, self.vault_0_mint.decimals, reserve_1, self.vault_1_mint.decimals)
+    }
+    
+    // Mint所属的代币程序（SPL Token或Token-2022）
+    fn token_program_for(&self, mint: &InterfaceAccount<'info, Mint>) -> AccountInfo<'info> {
+        if *mint.to_account_info().owner == self.token_program_2022.key() {
+            self.token_program_2022.to_account_info()
+        } else {
+            self.token_program.to_account_info()
+        }
+    }
+    
+    // 确保用户代币账户存在（不存在时以幂等方式创建关联代币账户）
+    fn ensure_user_token_account(
+        &self,
+        user_token_account: &UncheckedAccount<'info>,
+        mint: &InterfaceAccount<'info, Mint>,
+    ) -> Result<TokenAccount> {
+        token_utils::ensure_user_token_account(
+            &self.user.to_account_info(),
+            &user_token_account.to_account_info(),
+            &mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.token_program_for(mint),
+            &self.associated_token_program.to_account_info(),
+        )
+    }
+    
+    // 准备用户代币账户并检查余额是否足够required_amount
+    // SOL一侧从用户的SOL中包装wrap_amount，不动用账户中原有的WSOL
+    fn prepare_user_token_account(
+        &self,
+        user_token_account: &UncheckedAccount<'info>,
+        mint: &InterfaceAccount<'info, Mint>,
+        wrap_amount: u64,
+        required_amount: u64,
+    ) -> Result<()> {
+        let mut balance = self.ensure_user_token_account(user_token_account, mint)?.amount;
+        
+        if self.is_native_mint(mint) {
+            require!(self.user.lamports() >= wrap_amount, DexError::InsufficientFunds);
+            token_utils::wrap_sol(
+                &self.user.to_account_info(),
+                &user_token_account.to_account_info(),
+                wrap_amount,
+                &self.system_program.to_account_info(),
+                &self.token_program_for(mint),
+            )?;
+            balance = wrap_amount;
+        }
+        
+        require!(balance >= required_amount, DexError::InsufficientFunds);
+        Ok(())
+    }
+    
+    // 用户两侧代币账户和LP代币账户的余额(token_0, token_1, lp)
+    fn user_balances(&self) -> Result<(u64, u64, u64)> {
+        Ok((
+            read_balance(&self.user_token_0_account)?,
+            read_balance(&self.user_token_1_account)?,
+            read_balance(&self.user_lp_token_account)?,
+        ))
+    }
+    
+    // 用户两侧代币账户交易前的余额(token_0, token_1)，账户尚未创建时为None
+    fn token_balances_before(&self) -> Result<(Option<u64>, Option<u64>)> {
+        Ok((
+            token_utils::balance_before_trade(&self.user_token_0_account)?,
+            token_utils::balance_before_trade(&self.user_token_1_account)?,
+        ))
+    }
+    
+    // 交易完成后解包SOL一侧WSOL账户中超出交易前余额的部分
+    fn unwrap_native_accounts(
+        &self,
+        (token_0_balance_before, token_1_balance_before): (Option<u64>, Option<u64>),
+    ) -> Result<()> {
+        for (user_token_account, mint, balance_before) in [
+            (&self.user_token_0_account, &self.vault_0_mint, token_0_balance_before),
+            (&self.user_token_1_account, &self.vault_1_mint, token_1_balance_before),
+        ] {
+            if self.is_native_mint(mint) {
+                token_utils::unwrap_sol(
+                    &self.user.to_account_info(),
+                    &user_token_account.to_account_info(),
+                    &mint.to_account_info(),
+                    &self.system_program.to_account_info(),
+                    &self.token_program_for(mint),
+                    &self.associated_token_program.to_account_info(),
+                    balance_before,
+                )?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    fn is_native_mint(&self, mint: &InterfaceAccount<'info, Mint>) -> bool {
+        mint.key() == spl_token::native_mint::id()
+    }
+    
+    // deposit指令CPI账户
+    fn deposit_cpi_accounts(&self) -> cpi::accounts::Deposit<'info> {
+        cpi::accounts::Deposit {
+            owner: self.user.to_account_info(),
+            authority: self.amm_authority.to_account_info(),
+            pool_state: self.pool_state.to_account_info(),
+            owner_lp_token: self.user_lp_token_account.to_account_info(),
+            token_0_account: self.user_token_0_account.to_account_info(),
+            token_1_account: self.user_token_1_account.to_account_info(),
+            token_0_vault: self.token_0_vault.to_account_info(),
+            token_1_vault: self.token_1_vault.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            token_program_2022: self.token_program_2022.to_account_info(),
+            vault_0_mint: self.vault_0_mint.to_account_info(),
+            vault_1_mint: self.vault_1_mint.to_account_info(),
+            lp_mint: self.lp_mint.to_account_info(),
+        }
+    }
+    
+    // withdraw指令CPI账户
+    fn withdraw_cpi_accounts(&self) -> cpi::accounts::Withdraw<'info> {
+        cpi::accounts::Withdraw {
+            owner: self.user.to_account_info(),
+            authority: self.amm_authority.to_account_info(),
+            pool_state: self.pool_state.to_account_info(),
+            owner_lp_token: self.user_lp_token_account.to_account_info(),
+            token_0_account: self.user_token_0_account.to_account_info(),
+            token_1_account: self.user_token_1_account.to_account_info(),
+            token_0_vault: self.token_0_vault.to_account_info(),
+            token_1_vault: self.token_1_vault.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            token_program_2022: self.token_program_2022.to_account_info(),
+            vault_0_mint: self.vault_0_mint.to_account_info(),
+            vault_1_mint: self.vault_1_mint.to_account_info(),
+            lp_mint: self.lp_mint.to_account_info(),
+            memo_program: self.memo_program.to_account_info(),
+        }
+    }
+}
+
+// Raydium创建池子所需的账户结构
+#[derive(Accounts)]
+pub struct CreatePoolRaydium<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // DEX账户
+    #[account(
+        mut,
+        seeds = [b"dex_account".as_ref()],
+        bump
+    )]
+    pub dex_account: Account<'info, crate::DexAccount>,
+    
+    // Raydium程序
+    pub raydium_program: Program<'info, RaydiumCpmm>,
+    
+    // 费率配置（决定池子的手续费档位）
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+    
+    // 池子权限账户
+    #[account(
+        seeds = [RAYDIUM_CPMM_AUTH_SEED],
+        bump,
+        seeds::program = raydium_program.key(),
+    )]
+    pub amm_authority: UncheckedAccount<'info>,
+    
+    // 池子账户（由CPMM创建）
+    #[account(
+        mut,
+        seeds = [
+            RAYDIUM_CPMM_POOL_SEED,
+            amm_config.key().as_ref(),
+            token_0_mint.key().as_ref(),
+            token_1_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = raydium_program.key(),
+    )]
+    pub pool_state: UncheckedAccount<'info>,
+    
+    // 池子两侧代币Mint，CPMM要求token_0_mint < token_1_mint
+    #[account(
+        mint::token_program = token_0_program,
+        constraint = token_0_mint.key() < token_1_mint.key() @ DexError::InvalidRaydiumMintOrder
+    )]
+    pub token_0_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program = token_1_program)]
+    pub token_1_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    // LP Mint（由CPMM创建）
+    #[account(
+        mut,
+        seeds = [RAYDIUM_CPMM_POOL_LP_MINT_SEED, pool_state.key().as_ref()],
+        bump,
+        seeds::program = raydium_program.key(),
+    )]
+    pub lp_mint: UncheckedAccount<'info>,
+    
+    // 池子两侧金库（由CPMM创建）
+    #[account(
+        mut,
+        seeds = [RAYDIUM_CPMM_POOL_VAULT_SEED, pool_state.key().as_ref(), token_0_mint.key().as_ref()],
+        bump,
+        seeds::program = raydium_program.key(),
+    )]
+    pub token_0_vault: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [RAYDIUM_CPMM_POOL_VAULT_SEED, pool_state.key().as_ref(), token_1_mint.key().as_ref()],
+        bump,
+        seeds::program = raydium_program.key(),
+    )]
+    pub token_1_vault: UncheckedAccount<'info>,
+    
+    // 观察状态（由CPMM创建）
+    #[account(
+        mut,
+        seeds = [RAYDIUM_CPMM_OBSERVATION_SEED, pool_state.key().as_ref()],
+        bump,
+        seeds::program = raydium_program.key(),
+    )]
+    pub observation_state: UncheckedAccount<'info>,
+    
+    // 用户账户（不存在时以幂等方式创建关联代币账户）
+    // SOL一侧为用户的WSOL账户，交易完成后解包为SOL
+    #[account(mut)]
+    pub user_token_0_account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user_token_1_account: UncheckedAccount<'info>,
+    // 用户LP代币关联账户（由CPMM创建）
+    #[account(mut)]
+    pub user_lp_token_account: UncheckedAccount<'info>,
+    
+    // 创建池子手续费接收账户（由CPMM校验）
+    #[account(mut)]
+    pub create_pool_fee: UncheckedAccount<'info>,
+    
+    // 代币程序（LP代币属于SPL Token，两侧代币可以分别属于SPL Token或Token-2022）
+    pub token_program: Program<'info, Token>,
+    pub token_0_program: Interface<'info, TokenInterface>,
+    pub token_1_program: Interface<'info, TokenInterface>,
+    
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    
+    // 租金程序
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> CreatePoolRaydium<'info> {
+    // 准备用户代币账户并检查余额是否足够amount，SOL一侧从用户的SOL中包装amount，不动用账户中原有的WSOL
+    fn prepare_user_token_account(
+        &self,
+        user_token_account: &UncheckedAccount<'info>,
+        mint: &InterfaceAccount<'info, Mint>,
+        token_program: &Interface<'info, TokenInterface>,
+        amount: u64,
+    ) -> Result<()> {
+        let mut balance = token_utils::ensure_user_token_account(
+            &self.user.to_account_info(),
+            &user_token_account.to_account_info(),
+            &mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &token_program.to_account_info(),
+            &self.associated_token_program.to_account_info(),
+        )?
+        .amount;
+        
+        if mint.key() == spl_token::native_mint::id() {
+            require!(self.user.lamports() >= amount, DexError::InsufficientFunds);
+            token_utils::wrap_sol(
+                &self.user.to_account_info(),
+                &user_token_account.to_account_info(),
+                amount,
+                &self.system_program.to_account_info(),
+                &token_program.to_account_info(),
+            )?;
+            balance = amount;
+        }
+        
+        require!(balance >= amount, DexError::InsufficientFunds);
+        Ok(())
+    }
+    
+    // 用户两侧代币账户交易前的余额(token_0, token_1)，账户尚未创建时为None
+    fn token_balances_before(&self) -> Result<(Option<u64>, Option<u64>)> {
+        Ok((
+            token_utils::balance_before_trade(&self.user_token_0_account)?,
+            token_utils::balance_before_trade(&self.user_token_1_account)?,
+        ))
+    }
+    
+    // 交易完成后解包SOL一侧WSOL账户中超出交易前余额的部分
+    fn unwrap_native_accounts(
+        &self,
+        (token_0_balance_before, token_1_balance_before): (Option<u64>, Option<u64>),
+    ) -> Result<()> {
+        for (user_token_account, mint, token_program, balance_before) in [
+            (&self.user_token_0_account, &self.token_0_mint, &self.token_0_program, token_0_balance_before),
+            (&self.user_token_1_account, &self.token_1_mint, &self.token_1_program, token_1_balance_before),
+        ] {
+            if mint.key() == spl_token::native_mint::id() {
+                token_utils::unwrap_sol(
+                    &self.user.to_account_info(),
+                    &user_token_account.to_account_info(),
+                    &mint.to_account_info(),
+                    &self.system_program.to_account_info(),
+                    &token_program.to_account_info(),
+                    &self.associated_token_program.to_account_info(),
+                    balance_before,
+                )?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    // initialize指令CPI账户
+    fn initialize_cpi_accounts(&self) -> cpi::accounts::Initialize<'info> {
+        cpi::accounts::Initialize {
+            creator: self.user.to_account_info(),
+            amm_config: self.amm_config.to_account_info(),
+            authority: self.amm_authority.to_account_info(),
+            pool_state: self.pool_state.to_account_info(),
+            token_0_mint: self.token_0_mint.to_account_info(),
+            token_1_mint: self.token_1_mint.to_account_info(),
+            lp_mint: self.lp_mint.to_account_info(),
+            creator_token_0: self.user_token_0_account.to_account_info(),
+            creator_token_1: self.user_token_1_account.to_account_info(),
+            creator_lp_token: self.user_lp_token_account.to_account_info(),
+            token_0_vault: self.token_0_vault.to_account_info(),
+            token_1_vault: self.token_1_vault.to_account_info(),
+            create_pool_fee: self.create_pool_fee.to_account_info(),
+            observation_state: self.observation_state.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            token_0_program: self.token_0_program.to_account_info(),
+            token_1_program: self.token_1_program.to_account_info(),
+            associated_token_program: self.associated_token_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            rent: self.rent.to_account_info(),
+        }
+    }
 }
 
 // 检查代币位置所需的账户结构
@@ -408,8 +1573,8 @@ pub struct GetPriceContext<'info> {
     )]
     pub dex_account: Account<'info, crate::DexAccount>,
     
-    // 代币Mint地址
-    pub token_mint: Account<'info, token::Mint>,
+    // 代币Mint地址（支持SPL Token和Token-2022）
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
     
     // Raydium程序
     pub raydium_program: Program<'info, RaydiumCpmm>,
@@ -422,9 +1587,9 @@ pub struct GetPriceContext<'info> {
     
     // 代币账户
     #[account(mut)]
-    pub input_vault: Box<Account<'info, TokenAccount>>,
+    pub input_vault: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(mut)]
-    pub output_vault: Box<Account<'info, TokenAccount>>,
+    pub output_vault: Box<InterfaceAccount<'info, TokenAccount>>,
     
     // 观察状态
     #[account(mut)]
@@ -433,8 +1598,8 @@ pub struct GetPriceContext<'info> {
     // 系统程序
     pub system_program: Program<'info, System>,
     
-    // Token程序
-    pub token_program: Program<'info, Token>,
+    // Token程序（代币所属的SPL Token或Token-2022程序）
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 // 检查代币是否在Raydium上
@@ -460,15 +1625,13 @@ pub fn is_token_on_raydium(
     if token_0_mint == token_mint || token_1_mint == token_mint {
         msg!("代币在Raydium池子中找到");
         
-        // 检查池子流动性
-        let token_0_vault_amount = pool_state.token_0_vault_amount;
-        let token_1_vault_amount = pool_state.token_1_vault_amount;
-        
-        msg!("池子代币0数量: {}", token_0_vault_amount);
-        msg!("池子代币1数量: {}", token_1_vault_amount);
+        // 检查池子流动性（储备在金库账户中，这里以LP供应量判断池子是否有流动性）
+        // PoolState为紧凑布局，先复制字段再格式化，避免引用未对齐的字段
+        let lp_supply = pool_state.lp_supply;
+        msg!("池子LP供应量: {}", lp_supply);
         
         // 检查流动性是否足够
-        if token_0_vault_amount > 0 && token_1_vault_amount > 0 {
+        if lp_supply > 0 {
             msg!("池子流动性充足，代币在Raydium上可用");
             return Ok(true);
         } else {
@@ -486,7 +1649,7 @@ pub fn get_price(
     ctx: Context<GetPriceContext>,
     amount_in: u64,
     is_buy: bool,  // true表示买入，false表示卖出
-) -> Result<u64> {
+) -> Result<Price> {
     // 检查输入金额是否大于0
     require!(amount_in > 0, DexError::InvalidArgument);
     
@@ -500,44 +1663,183 @@ pub fn get_price(
     // 从池状态中读取代币信息（只加载一次）
     let pool_state = ctx.accounts.pool_state.load()?;
     
+    // 报价代币（SOL）一侧没有转账手续费，卖出时代币转入金库先扣除转账手续费
+    let token_mint_info = ctx.accounts.token_mint.to_account_info();
+    let actual_amount_in = if is_buy {
+        amount_in
+    } else {
+        token_utils::amount_after_transfer_fee(&token_mint_info, amount_in)?
+    };
+    
+    // 确定交易方向: 买入时输入为池子中的另一种代币，卖出时输入为查询的代币
+    let token_mint = ctx.accounts.token_mint.key();
+    require!(
+        token_mint == pool_state.token_0_mint || token_mint == pool_state.token_1_mint,
+        DexError::TokenNotInRaydiumPool
+    );
+    let token_is_token_0 = token_mint == pool_state.token_0_mint;
+    
     // 计算价格影响和预期输出金额
-    let (price_impact, amount_out) = calculate_price_impact(
-        &pool_state,
-        amount_in,
-        is_buy,
-        ctx.accounts.token_mint.key()
+    let (input_reserve, output_reserve) = directional_reserves(
+        load_pool_reserves(&pool_state, &ctx.accounts.input_vault, &ctx.accounts.output_vault)?,
+        token_is_token_0 != is_buy,
+    );
+    let swap_quote = quote::swap_base_input(
+        input_reserve,
+        output_reserve,
+        &quote::CpmmFees::from_amm_config(&ctx.accounts.amm_config),
+        actual_amount_in,
     )?;
+    let price_impact = swap_quote.price_impact;
+    let amount_out = swap_quote.amount_out;
     
-    // 计算价格比率
-    let price_ratio = if amount_in > 0 {
+    // 买入时代币转给用户再扣除转账手续费
+    let amount_out = if is_buy {
+        token_utils::amount_after_transfer_fee(&token_mint_info, amount_out)?
+    } else {
         amount_out
-            .checked_mul(1_000_000_000)
-            .unwrap_or(0)
-            .checked_div(amount_in)
-            .unwrap_or(0)
+    };
+    
+    // 计算价格（小数位数取自池子记录的两侧Mint）
+    let (token_decimals, other_decimals) = if token_is_token_0 {
+        (pool_state.mint_0_decimals, pool_state.mint_1_decimals)
+    } else {
+        (pool_state.mint_1_decimals, pool_state.mint_0_decimals)
+    };
+    let price = if is_buy {
+        Price::from_amounts(amount_in, other_decimals, amount_out, token_decimals)?
     } else {
-        0
+        Price::from_amounts(amount_in, token_decimals, amount_out, other_decimals)?
     };
     
     msg!("Raydium上的最终价格: {}, 价格影响: {}%", 
-        price_ratio, 
+        price.as_f64(), 
         price_impact as f64 / 10_000.0
     );
     
-    Ok(price_ratio)
+    Ok(price)
+}
+
+// 路由报价器: 读取一次池子储备，按输入数量计算用户实际到账的输出（含Token-2022转账手续费）
+pub fn route_quoter<'info>(accounts: &GetPriceContext<'info>, is_buy: bool) -> Result<RouteQuoter<'info>> {
+    let pool_state = accounts.pool_state.load()?;
+    
+    let token_mint = accounts.token_mint.key();
+    require!(
+        token_mint == pool_state.token_0_mint || token_mint == pool_state.token_1_mint,
+        DexError::TokenNotInRaydiumPool
+    );
+    let token_is_token_0 = token_mint == pool_state.token_0_mint;
+    
+    let (input_reserve, output_reserve) = directional_reserves(
+        load_pool_reserves(&pool_state, &accounts.input_vault, &accounts.output_vault)?,
+        token_is_token_0 != is_buy,
+    );
+    let fees = quote::CpmmFees::from_amm_config(&accounts.amm_config);
+    let token_mint_info = accounts.token_mint.to_account_info();
+    
+    Ok(Box::new(move |amount_in| {
+        // 卖出时代币转入金库先扣除转账手续费，买入时代币转给用户再扣除转账手续费
+        let actual_amount_in = if is_buy {
+            amount_in
+        } else {
+            token_utils::amount_after_transfer_fee(&token_mint_info, amount_in)?
+        };
+        let amount_out = quote::swap_base_input(input_reserve, output_reserve, &fees, actual_amount_in)?.amount_out;
+        
+        if is_buy {
+            token_utils::amount_after_transfer_fee(&token_mint_info, amount_out)
+        } else {
+            Ok(amount_out)
+        }
+    }))
+}
+
+// 精确输出路由报价器: 读取一次池子储备，按用户需要实际到账的输出计算用户实际转出的输入（含Token-2022转账手续费）
+pub fn route_exact_out_quoter<'info>(accounts: &GetPriceContext<'info>, is_buy: bool) -> Result<RouteQuoter<'info>> {
+    let pool_state = accounts.pool_state.load()?;
+    
+    let token_mint = accounts.token_mint.key();
+    require!(
+        token_mint == pool_state.token_0_mint || token_mint == pool_state.token_1_mint,
+        DexError::TokenNotInRaydiumPool
+    );
+    let token_is_token_0 = token_mint == pool_state.token_0_mint;
+    
+    let (input_reserve, output_reserve) = directional_reserves(
+        load_pool_reserves(&pool_state, &accounts.input_vault, &accounts.output_vault)?,
+        token_is_token_0 != is_buy,
+    );
+    let fees = quote::CpmmFees::from_amm_config(&accounts.amm_config);
+    let token_mint_info = accounts.token_mint.to_account_info();
+    
+    Ok(Box::new(move |amount_out| {
+        // 买入时金库转出的代币需覆盖转账手续费，卖出时用户转出的代币需覆盖转账手续费
+        let actual_amount_out = if is_buy {
+            token_utils::amount_before_transfer_fee(&token_mint_info, amount_out)?
+        } else {
+            amount_out
+        };
+        let amount_in = quote::swap_base_output(input_reserve, output_reserve, &fees, actual_amount_out)?.amount_in;
+        
+        if is_buy {
+            Ok(amount_in)
+        } else {
+            token_utils::amount_before_transfer_fee(&token_mint_info, amount_in)
+        }
+    }))
+}
+
+// Raydium创建池子事件（创建者的初始流动性另以LiquidityDeposited记录）
+#[event]
+pub struct PoolCreated {
+    pub user: Pubkey,
+    pub pool_state: Pubkey,
+    pub amm_config: Pubkey,
+    pub token_0_mint: Pubkey,
+    pub token_1_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    // 实际的开放交易时间
+    pub open_time: u64,
+    pub slot: u64,
 }
 
-// 交易执行事件
+// Raydium添加流动性事件（记录入场时的池子储备和LP总供应量，用于之后计算无常损失）
 #[event]
-pub struct SwapExecuted {
+pub struct LiquidityDeposited {
     pub user: Pubkey,
-    pub dex: String,
-    pub token_in: Pubkey,
-    pub token_out: Pubkey,
-    pub amount_in: u64,
-    pub min_amount_out: u64,
-    pub actual_amount_out: u64,
-    pub price_impact: u64,
-    pub execution_time: i64,
+    pub pool_state: Pubkey,
+    pub lp_mint: Pubkey,
+    pub token_0_mint: Pubkey,
+    pub token_1_mint: Pubkey,
+    pub lp_amount: u64,
+    pub token_0_amount: u64,
+    pub token_1_amount: u64,
+    // 添加流动性后的池子储备和LP总供应量
+    pub reserve_0: u64,
+    pub reserve_1: u64,
+    pub lp_supply: u64,
+    // 入场价格: 每单位token_0可兑换的token_1数量
+    pub entry_price: Price,
     pub slot: u64,
-} 
\ No newline at end of file
+}
+
+// Raydium移除流动性事件（记录出场时的池子储备和LP总供应量）
+#[event]
+pub struct LiquidityWithdrawn {
+    pub user: Pubkey,
+    pub pool_state: Pubkey,
+    pub lp_mint: Pubkey,
+    pub token_0_mint: Pubkey,
+    pub token_1_mint: Pubkey,
+    pub lp_amount: u64,
+    pub token_0_amount: u64,
+    pub token_1_amount: u64,
+    // 移除流动性后的池子储备和LP总供应量
+    pub reserve_0: u64,
+    pub reserve_1: u64,
+    pub lp_supply: u64,
+    // 出场价格: 每单位token_0可兑换的token_1数量
+    pub exit_price: Price,
+    pub slot: u64,
+}
@@ -0,0 +1,337 @@
+use anchor_lang::prelude::*;
+use raydium_cpmm_cpi::states::{AmmConfig, PoolState};
+use crate::math::{self, to_u64};
+use crate::DexError;
+
+// Raydium CPMM费率分母（与链上FEE_RATE_DENOMINATOR_VALUE一致）
+pub const CPMM_FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+// CPMM池子费率，来自AmmConfig
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct CpmmFees {
+    pub trade_fee_rate: u64,
+    pub protocol_fee_rate: u64,
+    pub fund_fee_rate: u64,
+}
+
+impl CpmmFees {
+    pub fn from_amm_config(amm_config: &AmmConfig) -> Self {
+        CpmmFees {
+            trade_fee_rate: amm_config.trade_fee_rate,
+            protocol_fee_rate: amm_config.protocol_fee_rate,
+            fund_fee_rate: amm_config.fund_fee_rate,
+        }
+    }
+    
+    // 交易手续费，以输入代币计算并向上取整
+    fn trading_fee(&self, amount: u64) -> Result<u64> {
+        math::fee_ceil(amount, self.trade_fee_rate, CPMM_FEE_RATE_DENOMINATOR)
+    }
+    
+    // 协议手续费和基金手续费从交易手续费中按比例划分，向下取整
+    fn split_fee(trade_fee: u64, rate: u64) -> Result<u64> {
+        to_u64(math::mul_div_floor(
+            trade_fee as u128,
+            rate as u128,
+            CPMM_FEE_RATE_DENOMINATOR as u128,
+        )?)
+    }
+    
+    // 已知扣除手续费后的数量，反推扣费前的数量（向上取整）
+    fn pre_fee_amount(&self, post_fee_amount: u64) -> Result<u64> {
+        if self.trade_fee_rate == 0 {
+            return Ok(post_fee_amount);
+        }
+        
+        let denominator = CPMM_FEE_RATE_DENOMINATOR
+            .checked_sub(self.trade_fee_rate)
+            .filter(|denominator| *denominator > 0)
+            .ok_or(DexError::InvalidArgument)?;
+        
+        to_u64(math::mul_div_ceil(
+            post_fee_amount as u128,
+            CPMM_FEE_RATE_DENOMINATOR as u128,
+            denominator as u128,
+        )?)
+    }
+}
+
+// CPMM报价结果（不含Token-2022转账手续费）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct CpmmQuote {
+    // 转入金库的输入代币数量（含交易手续费）
+    pub amount_in: u64,
+    // 从金库转出的输出代币数量
+    pub amount_out: u64,
+    // 交易手续费及其中的协议手续费和基金手续费
+    pub trade_fee: u64,
+    pub protocol_fee: u64,
+    pub fund_fee: u64,
+    // 交易后的池子储备（不含未领取的协议和基金手续费）
+    pub new_input_reserve: u64,
+    pub new_output_reserve: u64,
+    // 价格影响（以FEE_DENOMINATOR为分母的百万分比，不含手续费）
+    pub price_impact: u64,
+}
+
+// 池子实际储备 = 金库余额 - 未领取的协议手续费和基金手续费
+pub fn pool_reserves(
+    pool_state: &PoolState,
+    token_0_vault_amount: u64,
+    token_1_vault_amount: u64,
+) -> Result<(u64, u64)> {
+    let reserve_0 = token_0_vault_amount
+        .checked_sub(pool_state.protocol_fees_token_0)
+        .and_then(|amount| amount.checked_sub(pool_state.fund_fees_token_0))
+        .ok_or(DexError::InsufficientLiquidity)?;
+    let reserve_1 = token_1_vault_amount
+        .checked_sub(pool_state.protocol_fees_token_1)
+        .and_then(|amount| amount.checked_sub(pool_state.fund_fees_token_1))
+        .ok_or(DexError::InsufficientLiquidity)?;
+    
+    Ok((reserve_0, reserve_1))
+}
+
+// 报价: swap_base_input（精确输入）
+pub fn swap_base_input(
+    input_reserve: u64,
+    output_reserve: u64,
+    fees: &CpmmFees,
+    amount_in: u64,
+) -> Result<CpmmQuote> {
+    require!(amount_in > 0, DexError::InvalidArgument);
+    require!(input_reserve > 0 && output_reserve > 0, DexError::InsufficientLiquidity);
+    
+    let trade_fee = fees.trading_fee(amount_in)?;
+    let amount_in_less_fees = amount_in
+        .checked_sub(trade_fee)
+        .ok_or(DexError::ArithmeticOverflow)?;
+    
+    // 输出数量 = floor(amount_in_less_fees * output_reserve / (input_reserve + amount_in_less_fees))
+    let amount_out = to_u64(math::mul_div_floor(
+        amount_in_less_fees as u128,
+        output_reserve as u128,
+        input_reserve as u128 + amount_in_less_fees as u128,
+    )?)?;
+    require!(amount_out > 0, DexError::InsufficientLiquidity);
+    
+    build_quote(input_reserve, output_reserve, fees, amount_in, amount_in_less_fees, amount_out, trade_fee)
+}
+
+// 报价: swap_base_output（精确输出）
+pub fn swap_base_output(
+    input_reserve: u64,
+    output_reserve: u64,
+    fees: &CpmmFees,
+    amount_out: u64,
+) -> Result<CpmmQuote> {
+    require!(amount_out > 0, DexError::InvalidArgument);
+    require!(input_reserve > 0 && output_reserve > 0, DexError::InsufficientLiquidity);
+    require!(amount_out < output_reserve, DexError::InsufficientLiquidity);
+    
+    // 不含手续费的输入 = ceil(amount_out * input_reserve / (output_reserve - amount_out))
+    let amount_in_less_fees = to_u64(math::mul_div_ceil(
+        amount_out as u128,
+        input_reserve as u128,
+        (output_reserve - amount_out) as u128,
+    )?)?;
+    
+    // 与链上一致: 先反推含手续费的输入，再按该输入重新计算交易手续费
+    let amount_in = fees.pre_fee_amount(amount_in_less_fees)?;
+    let trade_fee = fees.trading_fee(amount_in)?;
+    
+    build_quote(input_reserve, output_reserve, fees, amount_in, amount_in_less_fees, amount_out, trade_fee)
+}
+
+// 构建报价，拆分手续费、更新储备并计算价格影响
+fn build_quote(
+    input_reserve: u64,
+    output_reserve: u64,
+    fees: &CpmmFees,
+    amount_in: u64,
+    amount_in_less_fees: u64,
+    amount_out: u64,
+    trade_fee: u64,
+) -> Result<CpmmQuote> {
+    let protocol_fee = CpmmFees::split_fee(trade_fee, fees.protocol_fee_rate)?;
+    let fund_fee = CpmmFees::split_fee(trade_fee, fees.fund_fee_rate)?;
+    
+    // 交易手续费中的LP部分留在池子储备中，协议和基金部分单独记账
+    let new_input_reserve = input_reserve
+        .checked_add(amount_in)
+        .and_then(|reserve| reserve.checked_sub(protocol_fee))
+        .and_then(|reserve| reserve.checked_sub(fund_fee))
+        .ok_or(DexError::ArithmeticOverflow)?;
+    let new_output_reserve = output_reserve
+        .checked_sub(amount_out)
+        .ok_or(DexError::InsufficientLiquidity)?;
+    
+    // 价格影响 = 1 - 成交价格 / 现货价格 = 1 - amount_out * input_reserve / (amount_in_less_fees * output_reserve)
+    let price_impact = math::price_impact(
+        amount_out as u128 * input_reserve as u128,
+        amount_in_less_fees as u128 * output_reserve as u128,
+    )?;
+    
+    Ok(CpmmQuote {
+        amount_in,
+        amount_out,
+        trade_fee,
+        protocol_fee,
+        fund_fee,
+        new_input_reserve,
+        new_output_reserve,
+        price_impact,
+    })
+}
+
+// LP数量对应的两侧代币数量(token_0, token_1)，不含Token-2022转账手续费
+// 与链上一致: 添加流动性时向上取整，移除流动性时向下取整
+pub fn lp_to_token_amounts(
+    lp_token_amount: u64,
+    lp_supply: u64,
+    reserve_0: u64,
+    reserve_1: u64,
+    round_up: bool,
+) -> Result<(u64, u64)> {
+    require!(lp_token_amount > 0, DexError::InvalidArgument);
+    require!(lp_supply > 0 && reserve_0 > 0 && reserve_1 > 0, DexError::InsufficientLiquidity);
+    
+    let share = |reserve: u64| -> Result<u64> {
+        let amount = if round_up {
+            math::mul_div_ceil(lp_token_amount as u128, reserve as u128, lp_supply as u128)?
+        } else {
+            math::mul_div_floor(lp_token_amount as u128, reserve as u128, lp_supply as u128)?
+        };
+        to_u64(amount)
+    };
+    
+    let token_0_amount = share(reserve_0)?;
+    let token_1_amount = share(reserve_1)?;
+    require!(token_0_amount > 0 && token_1_amount > 0, DexError::InvalidArgument);
+    
+    Ok((token_0_amount, token_1_amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // 期望值按Raydium CPMM程序curve模块的整数运算独立计算:
+    // 交易手续费ceil(amount * trade_fee_rate / 1e6)，协议和基金手续费向下取整，
+    // 精确输入的输出向下取整，精确输出的输入向上取整后按pre_fee_amount向上反推
+    
+    // AmmConfig索引0: 0.25%交易手续费，其中12%为协议手续费、4%为基金手续费
+    const CONFIG_0_FEES: CpmmFees = CpmmFees {
+        trade_fee_rate: 2_500,
+        protocol_fee_rate: 120_000,
+        fund_fee_rate: 40_000,
+    };
+    
+    // 约85432 SOL / 12345678 USDC（6位小数）规模的池子
+    const SOL_RESERVE: u64 = 85_432_109_876_543;
+    const USDC_RESERVE: u64 = 12_345_678_901_234;
+    
+    #[test]
+    fn swap_base_input_matches_reference() {
+        let quote = swap_base_input(SOL_RESERVE, USDC_RESERVE, &CONFIG_0_FEES, 1_000_000_000).unwrap();
+        assert_eq!(
+            quote,
+            CpmmQuote {
+                amount_in: 1_000_000_000,
+                amount_out: 144_145_695,
+                trade_fee: 2_500_000,
+                protocol_fee: 300_000,
+                fund_fee: 100_000,
+                new_input_reserve: 85_433_109_476_543,
+                new_output_reserve: 12_345_534_755_539,
+                price_impact: 11,
+            }
+        );
+    }
+    
+    #[test]
+    fn swap_base_input_rounds_trade_fee_up() {
+        // 401 * 0.25% = 1.0025，向上取整为2
+        let quote = swap_base_input(1_000_000, 1_000_000, &CONFIG_0_FEES, 401).unwrap();
+        assert_eq!(quote.trade_fee, 2);
+        // 手续费正好为整数时不额外加1
+        let quote = swap_base_input(1_000_000, 1_000_000, &CONFIG_0_FEES, 400).unwrap();
+        assert_eq!(quote.trade_fee, 1);
+        // 两者扣费后的输入相同（399），输出相同
+        assert_eq!(quote.amount_out, 398);
+        // 协议和基金手续费从交易手续费中向下取整
+        assert_eq!((quote.protocol_fee, quote.fund_fee), (0, 0));
+    }
+    
+    #[test]
+    fn swap_base_input_floors_output_and_rejects_zero() {
+        // 扣费后输入2，输出floor(2 * 250000000 / 5000000002) = 0，与链上一致拒绝
+        assert!(swap_base_input(5_000_000_000, 250_000_000, &CONFIG_0_FEES, 3).is_err());
+        assert!(swap_base_input(SOL_RESERVE, USDC_RESERVE, &CONFIG_0_FEES, 0).is_err());
+    }
+    
+    #[test]
+    fn swap_base_output_matches_reference() {
+        let quote = swap_base_output(SOL_RESERVE, USDC_RESERVE, &CONFIG_0_FEES, 144_000_000).unwrap();
+        assert_eq!(
+            quote,
+            CpmmQuote {
+                amount_in: 998_989_236,
+                amount_out: 144_000_000,
+                trade_fee: 2_497_474,
+                protocol_fee: 299_696,
+                fund_fee: 99_898,
+                new_input_reserve: 85_433_108_466_185,
+                new_output_reserve: 12_345_534_901_234,
+                price_impact: 11,
+            }
+        );
+    }
+    
+    #[test]
+    fn swap_base_output_rounds_pre_fee_amount_up() {
+        // 不含手续费的输入ceil(1000000 / 999999) = 2，含手续费ceil(2 * 1e6 / 997500) = 3
+        let quote = swap_base_output(1_000_000, 1_000_000, &CONFIG_0_FEES, 1).unwrap();
+        assert_eq!(quote.amount_in, 3);
+        assert_eq!(quote.trade_fee, 1);
+        
+        // 几乎取空输出储备时中间结果超过u64，仍按U256精确计算
+        let quote = swap_base_output(1_000_000_000, 999_000, &CONFIG_0_FEES, 998_999).unwrap();
+        assert_eq!(quote.amount_in, 1_001_502_756_892_231);
+        assert_eq!(quote.trade_fee, 2_503_756_892_231);
+        assert_eq!(quote.new_output_reserve, 1);
+        
+        // 输出不能达到或超过输出储备
+        assert!(swap_base_output(1_000_000, 1_000_000, &CONFIG_0_FEES, 1_000_000).is_err());
+    }
+    
+    #[test]
+    fn zero_fee_rate_passes_amounts_through() {
+        let fees = CpmmFees::default();
+        
+        let quote = swap_base_input(1_000_000, 2_000_000, &fees, 1_000).unwrap();
+        assert_eq!((quote.amount_out, quote.trade_fee), (1_998, 0));
+        
+        // ceil(1000 * 1000000 / 1999000) = 501
+        let quote = swap_base_output(1_000_000, 2_000_000, &fees, 1_000).unwrap();
+        assert_eq!((quote.amount_in, quote.trade_fee), (501, 0));
+    }
+    
+    #[test]
+    fn exact_output_round_trips_through_exact_input() {
+        // 按精确输出报价的输入进行精确输入交易，输出不少于期望的输出
+        for amount_out in [1u64, 7, 999, 144_000_000, 1_000_000_000_000] {
+            let output_quote = swap_base_output(SOL_RESERVE, USDC_RESERVE, &CONFIG_0_FEES, amount_out).unwrap();
+            let input_quote = swap_base_input(SOL_RESERVE, USDC_RESERVE, &CONFIG_0_FEES, output_quote.amount_in).unwrap();
+            assert!(input_quote.amount_out >= amount_out);
+        }
+    }
+    
+    #[test]
+    fn lp_to_token_amounts_rounds_by_direction() {
+        // 添加流动性向上取整，移除流动性向下取整
+        assert_eq!(lp_to_token_amounts(10, 3, 100, 200, true).unwrap(), (334, 667));
+        assert_eq!(lp_to_token_amounts(10, 3, 100, 200, false).unwrap(), (333, 666));
+        assert!(lp_to_token_amounts(1, 1_000, 10, 10, false).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+use raydium_cpmm_cpi::states::ObservationState;
+use crate::math::{self, to_u64};
+use crate::{DexError, FEE_DENOMINATOR};
+
+// 观察状态环形缓冲区的长度（与Raydium CPMM的OBSERVATION_NUM一致）
+pub const OBSERVATION_NUM: usize = 100;
+
+// 计算TWAP的目标时间窗口（秒）
+// Raydium CPMM每次交易最多每15秒写入一个观察点，环形缓冲区可覆盖约25分钟
+pub const TWAP_WINDOW_SECONDS: u64 = 300;
+
+// 计算TWAP所需的最短时间跨度（秒），历史不足时拒绝交易
+pub const MIN_TWAP_WINDOW_SECONDS: u64 = 30;
+
+// 时间加权平均价格: 以32位定点表示的token_0价格（每单位token_0可兑换的token_1数量）
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Twap {
+    pub token_0_price_x32: u128,
+    // 实际使用的时间跨度（秒）
+    pub window: u64,
+}
+
+// 交易前后检查现货价格时使用的TWAP及允许的最大偏离（以FEE_DENOMINATOR为分母的百万分比）
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TwapLimit {
+    pub twap: Twap,
+    pub max_deviation: u64,
+}
+
+// 从观察状态的环形缓冲区计算token_0的TWAP
+// 从最新的观察点向前查找，取时间跨度首次达到window的观察点；历史不足window时使用最早的可用观察点
+// 观察点不足或时间跨度小于MIN_TWAP_WINDOW_SECONDS时返回None
+pub fn token_0_twap(observation_state: &ObservationState, window: u64) -> Option<Twap> {
+    if !observation_state.initialized {
+        return None;
+    }
+    
+    // 观察状态为packed布局，逐个复制观察点，避免将整个环形缓冲区复制到栈上
+    let latest_index = observation_state.observation_index as usize % OBSERVATION_NUM;
+    let latest = observation_state.observations[latest_index];
+    let latest_timestamp = latest.block_timestamp;
+    
+    let mut oldest = None;
+    for offset in 1..OBSERVATION_NUM {
+        let observation = observation_state.observations[(latest_index + OBSERVATION_NUM - offset) % OBSERVATION_NUM];
+        let timestamp = observation.block_timestamp;
+        // 未写入的观察点，或时间戳不早于最新观察点（已回绕到最新观察点之后）
+        if timestamp == 0 || timestamp >= latest_timestamp {
+            break;
+        }
+        
+        oldest = Some(observation);
+        if latest_timestamp - timestamp >= window {
+            break;
+        }
+    }
+    
+    let oldest = oldest?;
+    let elapsed = latest_timestamp - oldest.block_timestamp;
+    if elapsed < MIN_TWAP_WINDOW_SECONDS {
+        return None;
+    }
+    
+    // 累计价格按时间线性累加，允许回绕
+    let cumulative_delta = latest
+        .cumulative_token_0_price_x32
+        .wrapping_sub(oldest.cumulative_token_0_price_x32);
+    
+    Some(Twap {
+        token_0_price_x32: cumulative_delta / elapsed as u128,
+        window: elapsed,
+    })
+}
+
+// 现货价格: 以32位定点表示的token_0价格，与观察点中的累计价格口径一致
+pub fn spot_token_0_price_x32(reserve_0: u64, reserve_1: u64) -> Result<u128> {
+    require!(reserve_0 > 0 && reserve_1 > 0, DexError::InsufficientLiquidity);
+    Ok(((reserve_1 as u128) << 32) / reserve_0 as u128)
+}
+
+// 现货价格相对TWAP的偏离（以FEE_DENOMINATOR为分母的百万分比，不区分方向）
+pub fn price_deviation(spot_price_x32: u128, twap_price_x32: u128) -> Result<u64> {
+    require!(twap_price_x32 > 0, DexError::InsufficientLiquidity);
+    
+    let difference = spot_price_x32.abs_diff(twap_price_x32);
+    to_u64(math::mul_div_floor(difference, FEE_DENOMINATOR as u128, twap_price_x32)?)
+}
@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use crate::codec::encode_instruction_data;
+
+// Raydium AMM v4指令标签（原生程序，指令数据首字节为指令序号）
+pub const SWAP_BASE_IN_TAG: u8 = 9;
+pub const SWAP_BASE_OUT_TAG: u8 = 11;
+
+// swap_base_in指令参数（精确输入）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SwapBaseInArgs {
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+}
+
+// swap_base_out指令参数（精确输出）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SwapBaseOutArgs {
+    pub max_amount_in: u64,
+    pub amount_out: u64,
+}
+
+// swap_base_in/swap_base_out指令共用的账户（顺序与Raydium AMM v4一致）
+// OpenBook市场相关账户仍需按顺序传入
+pub struct SwapAccounts {
+    pub token_program: Pubkey,
+    pub amm: Pubkey,
+    pub amm_authority: Pubkey,
+    pub amm_open_orders: Pubkey,
+    pub amm_target_orders: Pubkey,
+    pub pool_coin_token_account: Pubkey,
+    pub pool_pc_token_account: Pubkey,
+    pub serum_program: Pubkey,
+    pub serum_market: Pubkey,
+    pub serum_bids: Pubkey,
+    pub serum_asks: Pubkey,
+    pub serum_event_queue: Pubkey,
+    pub serum_coin_vault: Pubkey,
+    pub serum_pc_vault: Pubkey,
+    pub serum_vault_signer: Pubkey,
+    pub user_source_token_account: Pubkey,
+    pub user_destination_token_account: Pubkey,
+    pub user: Pubkey,
+}
+
+impl SwapAccounts {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new(self.amm, false),
+            AccountMeta::new_readonly(self.amm_authority, false),
+            AccountMeta::new(self.amm_open_orders, false),
+            AccountMeta::new(self.amm_target_orders, false),
+            AccountMeta::new(self.pool_coin_token_account, false),
+            AccountMeta::new(self.pool_pc_token_account, false),
+            AccountMeta::new_readonly(self.serum_program, false),
+            AccountMeta::new(self.serum_market, false),
+            AccountMeta::new(self.serum_bids, false),
+            AccountMeta::new(self.serum_asks, false),
+            AccountMeta::new(self.serum_event_queue, false),
+            AccountMeta::new(self.serum_coin_vault, false),
+            AccountMeta::new(self.serum_pc_vault, false),
+            AccountMeta::new_readonly(self.serum_vault_signer, false),
+            AccountMeta::new(self.user_source_token_account, false),
+            AccountMeta::new(self.user_destination_token_account, false),
+            AccountMeta::new_readonly(self.user, true),
+        ]
+    }
+}
+
+// 构建swap_base_in指令
+pub fn swap_base_in(program_id: Pubkey, accounts: &SwapAccounts, args: &SwapBaseInArgs) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data: encode_instruction_data(&[SWAP_BASE_IN_TAG], args),
+    }
+}
+
+// 构建swap_base_out指令
+pub fn swap_base_out(program_id: Pubkey, accounts: &SwapAccounts, args: &SwapBaseOutArgs) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data: encode_instruction_data(&[SWAP_BASE_OUT_TAG], args),
+    }
+}
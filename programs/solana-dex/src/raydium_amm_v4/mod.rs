@@ -0,0 +1,708 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+use anchor_spl::token_interface;
+use anchor_spl::associated_token::AssociatedToken;
+use solana_program::{
+    instruction::Instruction,
+    program::invoke,
+    pubkey::Pubkey,
+};
+use std::str::FromStr;
+use crate::fill::{read_balance, BalanceSnapshot, Fill, FillLimits, FillPair};
+use crate::math::{Price, SOL_DECIMALS};
+use crate::router::RouteQuoter;
+use crate::{token_utils, DexError, DexType, SwapMode, MAX_PRICE_IMPACT};
+
+pub mod instructions;
+pub mod quote;
+pub mod state;
+
+pub use state::AmmInfo;
+
+// Raydium AMM v4（旧版AMM）程序ID
+pub const RAYDIUM_AMM_V4_PROGRAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+
+// 获取Raydium AMM v4程序ID
+pub fn get_raydium_amm_v4_program_id() -> Pubkey {
+    Pubkey::from_str(RAYDIUM_AMM_V4_PROGRAM_ID).unwrap()
+}
+
+// 在Raydium AMM v4上购买代币（swap_base_in，精确SOL输入）
+pub fn buy_token(
+    ctx: Context<TradeTokenRaydiumAmmV4>,
+    amount_in: u64,  // 输入的SOL数量
+    min_amount_out: u64,  // 最小获得的代币数量（滑点控制）
+) -> Result<Fill> {
+    msg!("在Raydium AMM v4上购买代币: {} SOL, 最小获得代币数量: {}", amount_in, min_amount_out);
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    // 检查参数
+    require!(amount_in > 0, DexError::InvalidArgument);
+    require!(min_amount_out > 0, DexError::InvalidArgument);
+    
+    let fill = ctx.accounts.execute_swap(true, SwapMode::ExactIn, amount_in, min_amount_out)?;
+    
+    msg!("交易完成，获得代币");
+    Ok(fill)
+}
+
+// 在Raydium AMM v4上卖出代币（swap_base_in，精确代币输入）
+pub fn sell_token(
+    ctx: Context<TradeTokenRaydiumAmmV4>,
+    amount_in: u64,  // 输入的代币数量
+    min_amount_out: u64,  // 最小获得的SOL数量（滑点控制）
+    close_token_account: bool,  // 全部卖出后是否关闭用户代币账户以回收租金
+) -> Result<Fill> {
+    msg!("在Raydium AMM v4上卖出代币: {} 代币, 最小获得SOL数量: {}", amount_in, min_amount_out);
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    // 检查参数
+    require!(amount_in > 0, DexError::InvalidArgument);
+    require!(min_amount_out > 0, DexError::InvalidArgument);
+    
+    let fill = ctx.accounts.execute_swap(false, SwapMode::ExactIn, amount_in, min_amount_out)?;
+    
+    // 全部卖出后可选地关闭用户代币账户
+    if close_token_account {
+        ctx.accounts.close_empty_user_token_account()?;
+    }
+    
+    msg!("交易完成，获得SOL");
+    Ok(fill)
+}
+
+// 在Raydium AMM v4上购买精确数量的代币（swap_base_out）
+pub fn buy_exact_out(
+    ctx: Context<TradeTokenRaydiumAmmV4>,
+    amount_out: u64,  // 需要获得的代币数量
+    max_amount_in: u64,  // 最多花费的SOL数量（滑点控制）
+) -> Result<Fill> {
+    msg!("在Raydium AMM v4上购买精确数量的代币: {} 代币, 最多花费SOL: {}", amount_out, max_amount_in);
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    // 检查参数
+    require!(amount_out > 0, DexError::InvalidArgument);
+    require!(max_amount_in > 0, DexError::InvalidArgument);
+    
+    let fill = ctx.accounts.execute_swap(true, SwapMode::ExactOut, amount_out, max_amount_in)?;
+    
+    msg!("交易完成，获得代币");
+    Ok(fill)
+}
+
+// 在Raydium AMM v4上卖出代币以获得精确数量的SOL（swap_base_out）
+pub fn sell_exact_out(
+    ctx: Context<TradeTokenRaydiumAmmV4>,
+    amount_out: u64,  // 需要获得的SOL数量
+    max_amount_in: u64,  // 最多卖出的代币数量（滑点控制）
+    close_token_account: bool,  // 全部卖出后是否关闭用户代币账户以回收租金
+) -> Result<Fill> {
+    msg!("在Raydium AMM v4上卖出代币以获得精确数量的SOL: {} SOL, 最多卖出代币: {}", amount_out, max_amount_in);
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    // 检查参数
+    require!(amount_out > 0, DexError::InvalidArgument);
+    require!(max_amount_in > 0, DexError::InvalidArgument);
+    
+    let fill = ctx.accounts.execute_swap(false, SwapMode::ExactOut, amount_out, max_amount_in)?;
+    
+    // 全部卖出后可选地关闭用户代币账户
+    if close_token_account {
+        ctx.accounts.close_empty_user_token_account()?;
+    }
+    
+    msg!("交易完成，获得SOL");
+    Ok(fill)
+}
+
+// 检查代币是否在Raydium AMM v4上（以WSOL为另一侧的可交易池子）
+pub fn is_token_on_raydium_amm_v4(
+    ctx: Context<CheckTokenLocation>,
+    token_mint: Pubkey,
+) -> Result<bool> {
+    // 记录查询信息
+    msg!("检查代币是否在Raydium AMM v4上: {}", token_mint);
+    
+    let amm_account_info = ctx.accounts.amm.to_account_info();
+    
+    // 池子账户不存在或不属于Raydium AMM v4时，代币不在Raydium AMM v4上
+    if amm_account_info.data_is_empty() || *amm_account_info.owner != get_raydium_amm_v4_program_id() {
+        msg!("Raydium AMM v4池子账户不存在，代币不在Raydium AMM v4上");
+        return Ok(false);
+    }
+    
+    let (amm, token_is_coin) = match load_amm(
+        &amm_account_info,
+        &token_mint,
+        &ctx.accounts.pool_coin_token_account.key(),
+        &ctx.accounts.pool_pc_token_account.key(),
+    ) {
+        Ok(loaded) => loaded,
+        Err(_) => {
+            msg!("Raydium AMM v4池子与代币不匹配，代币不在Raydium AMM v4上");
+            return Ok(false);
+        }
+    };
+    
+    // 检查池子是否允许交易
+    if !amm.swap_enabled(Clock::get()?.unix_timestamp) {
+        msg!("Raydium AMM v4池子当前不允许交易，状态: {}", amm.status);
+        return Ok(false);
+    }
+    
+    // 检查池子是否有足够的流动性
+    let coin_vault_amount = read_balance(&ctx.accounts.pool_coin_token_account)?;
+    let pc_vault_amount = read_balance(&ctx.accounts.pool_pc_token_account)?;
+    let (coin_reserve, pc_reserve) = match quote::pool_reserves(&amm, coin_vault_amount, pc_vault_amount) {
+        Ok(reserves) => reserves,
+        Err(_) => return Ok(false),
+    };
+    
+    if coin_reserve == 0 || pc_reserve == 0 {
+        msg!("Raydium AMM v4池子流动性不足，代币不可交易");
+        return Ok(false);
+    }
+    
+    let (token_reserve, sol_reserve) = if token_is_coin {
+        (coin_reserve, pc_reserve)
+    } else {
+        (pc_reserve, coin_reserve)
+    };
+    msg!("代币在Raydium AMM v4上可用，代币储备: {}, SOL储备: {}", token_reserve, sol_reserve);
+    Ok(true)
+}
+
+// 获取Raydium AMM v4上的报价（精确输入）
+pub fn get_quote(
+    ctx: Context<GetPriceContext>,
+    amount_in: u64,
+    is_buy: bool,  // true表示买入（输入SOL），false表示卖出（输入代币）
+) -> Result<quote::AmmV4Quote> {
+    // 检查输入金额是否大于0
+    require!(amount_in > 0, DexError::InvalidArgument);
+    
+    // 记录查询信息
+    msg!("查询Raydium AMM v4上的报价: 输入金额 {}, 操作类型: {}",
+        amount_in,
+        if is_buy { "买入" } else { "卖出" }
+    );
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    let (amm, token_is_coin) = load_amm(
+        &ctx.accounts.amm,
+        &ctx.accounts.token_mint.key(),
+        &ctx.accounts.pool_coin_token_account.key(),
+        &ctx.accounts.pool_pc_token_account.key(),
+    )?;
+    
+    let (input_reserve, output_reserve) = directional_reserves(
+        quote::pool_reserves(
+            &amm,
+            ctx.accounts.pool_coin_token_account.amount,
+            ctx.accounts.pool_pc_token_account.amount,
+        )?,
+        token_is_coin != is_buy,
+    );
+    
+    msg!("池子输入储备: {}, 输出储备: {}", input_reserve, output_reserve);
+    
+    let amm_v4_quote = quote::swap_base_in(
+        input_reserve,
+        output_reserve,
+        &quote::AmmV4Fees::from_amm_info(&amm),
+        amount_in,
+    )?;
+    
+    msg!("Raydium AMM v4报价: 输入 {}, 输出 {}, 手续费 {}, 价格影响: {}%",
+        amm_v4_quote.amount_in,
+        amm_v4_quote.amount_out,
+        amm_v4_quote.fee_amount,
+        amm_v4_quote.price_impact as f64 / 10_000.0
+    );
+    
+    Ok(amm_v4_quote)
+}
+
+// 获取Raydium AMM v4上的价格（每单位输入可获得的输出，按两侧代币的小数位数换算）
+pub fn get_price(
+    ctx: Context<GetPriceContext>,
+    amount_in: u64,
+    is_buy: bool,  // true表示买入，false表示卖出
+) -> Result<Price> {
+    let token_decimals = ctx.accounts.token_mint.decimals;
+    let amm_v4_quote = get_quote(ctx, amount_in, is_buy)?;
+    
+    // 计算价格（买入时输入为SOL，卖出时输入为代币）
+    let price = if is_buy {
+        Price::from_amounts(amount_in, SOL_DECIMALS, amm_v4_quote.amount_out, token_decimals)?
+    } else {
+        Price::from_amounts(amount_in, token_decimals, amm_v4_quote.amount_out, SOL_DECIMALS)?
+    };
+    
+    msg!("Raydium AMM v4上的最终价格: {}", price.as_f64());
+    Ok(price)
+}
+
+// 路由报价器: 读取一次池子储备，按输入数量计算输出（买入为代币，卖出为SOL）
+pub fn route_quoter<'info>(accounts: &GetPriceContext<'info>, is_buy: bool) -> Result<RouteQuoter<'info>> {
+    let (amm, token_is_coin) = load_amm(
+        &accounts.amm,
+        &accounts.token_mint.key(),
+        &accounts.pool_coin_token_account.key(),
+        &accounts.pool_pc_token_account.key(),
+    )?;
+    
+    let (input_reserve, output_reserve) = directional_reserves(
+        quote::pool_reserves(
+            &amm,
+            accounts.pool_coin_token_account.amount,
+            accounts.pool_pc_token_account.amount,
+        )?,
+        token_is_coin != is_buy,
+    );
+    let fees = quote::AmmV4Fees::from_amm_info(&amm);
+    
+    Ok(Box::new(move |amount_in| {
+        Ok(quote::swap_base_in(input_reserve, output_reserve, &fees, amount_in)?.amount_out)
+    }))
+}
+
+// 精确输出路由报价器: 读取一次池子储备，按需要获得的输出数量计算所需的输入
+pub fn route_exact_out_quoter<'info>(accounts: &GetPriceContext<'info>, is_buy: bool) -> Result<RouteQuoter<'info>> {
+    let (amm, token_is_coin) = load_amm(
+        &accounts.amm,
+        &accounts.token_mint.key(),
+        &accounts.pool_coin_token_account.key(),
+        &accounts.pool_pc_token_account.key(),
+    )?;
+    
+    let (input_reserve, output_reserve) = directional_reserves(
+        quote::pool_reserves(
+            &amm,
+            accounts.pool_coin_token_account.amount,
+            accounts.pool_pc_token_account.amount,
+        )?,
+        token_is_coin != is_buy,
+    );
+    let fees = quote::AmmV4Fees::from_amm_info(&amm);
+    
+    Ok(Box::new(move |amount_out| {
+        Ok(quote::swap_base_out(input_reserve, output_reserve, &fees, amount_out)?.amount_in)
+    }))
+}
+
+// 加载并校验AmmInfo，返回(AmmInfo, 代币是否为coin一侧)
+// 只支持代币与WSOL的交易对，两侧顺序不限
+fn load_amm(
+    amm_account: &AccountInfo,
+    token_mint: &Pubkey,
+    pool_coin_token_account: &Pubkey,
+    pool_pc_token_account: &Pubkey,
+) -> Result<(AmmInfo, bool)> {
+    let amm = AmmInfo::try_from_account_info(amm_account)?;
+    
+    require_keys_eq!(amm.coin_vault, *pool_coin_token_account, DexError::InvalidRaydiumAmmV4Account);
+    require_keys_eq!(amm.pc_vault, *pool_pc_token_account, DexError::InvalidRaydiumAmmV4Account);
+    
+    let native_mint = spl_token::native_mint::id();
+    let token_is_coin = if amm.coin_vault_mint == *token_mint && amm.pc_vault_mint == native_mint {
+        true
+    } else if amm.pc_vault_mint == *token_mint && amm.coin_vault_mint == native_mint {
+        false
+    } else {
+        return err!(DexError::InvalidRaydiumAmmV4Account);
+    };
+    
+    Ok((amm, token_is_coin))
+}
+
+// 按交易方向排列储备，返回(输入储备, 输出储备)
+fn directional_reserves(reserves: (u64, u64), input_is_coin: bool) -> (u64, u64) {
+    if input_is_coin {
+        reserves
+    } else {
+        (reserves.1, reserves.0)
+    }
+}
+
+// Raydium AMM v4交易所需的账户结构
+#[derive(Accounts)]
+pub struct TradeTokenRaydiumAmmV4<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // DEX账户
+    #[account(
+        mut,
+        seeds = [b"dex_account".as_ref()],
+        bump
+    )]
+    pub dex_account: Account<'info, crate::DexAccount>,
+    
+    // 代币Mint地址（Raydium AMM v4只支持SPL Token）
+    pub token_mint: Account<'info, token::Mint>,
+    
+    // WSOL Mint
+    #[account(
+        constraint = wsol_mint.key() == spl_token::native_mint::id()
+    )]
+    pub wsol_mint: Account<'info, token::Mint>,
+    
+    // Raydium AMM v4池子账户（原生程序账户，在指令中手动反序列化）
+    #[account(mut)]
+    pub amm: UncheckedAccount<'info>,
+    
+    // 池子权限账户（在指令中按AmmInfo.nonce校验）
+    pub amm_authority: UncheckedAccount<'info>,
+    
+    // 池子的open_orders和target_orders账户（在指令中按AmmInfo校验）
+    #[account(mut)]
+    pub amm_open_orders: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub amm_target_orders: UncheckedAccount<'info>,
+    
+    // 池子coin和pc金库
+    #[account(mut)]
+    pub pool_coin_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub pool_pc_token_account: Account<'info, TokenAccount>,
+    
+    // OpenBook市场账户（订单簿已停用，但仍需按顺序传入）
+    pub serum_program: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub serum_market: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub serum_bids: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub serum_asks: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub serum_event_queue: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub serum_coin_vault: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub serum_pc_vault: UncheckedAccount<'info>,
+    pub serum_vault_signer: UncheckedAccount<'info>,
+    
+    // 用户代币账户（不存在时以幂等方式创建关联代币账户，在指令中检查mint和所有者）
+    #[account(mut)]
+    pub user_token_account: UncheckedAccount<'info>,
+    
+    // 用户WSOL账户（不存在时以幂等方式创建关联代币账户，交易完成后解包为SOL）
+    #[account(mut)]
+    pub user_wsol_account: UncheckedAccount<'info>,
+    
+    // Raydium AMM v4程序
+    #[account(
+        constraint = raydium_amm_v4_program.key() == get_raydium_amm_v4_program_id()
+    )]
+    pub raydium_amm_v4_program: AccountInfo<'info>,
+    
+    // 代币程序
+    pub token_program: Program<'info, Token>,
+    
+    // 系统程序
+    pub system_program: Program<'info, System>,
+    
+    // 关联代币程序
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> TradeTokenRaydiumAmmV4<'info> {
+    // 加载AmmInfo并校验池子相关账户，返回(AmmInfo, 代币是否为coin一侧)
+    fn load_pool_state(&self) -> Result<(AmmInfo, bool)> {
+        let (amm, token_is_coin) = load_amm(
+            &self.amm,
+            &self.token_mint.key(),
+            &self.pool_coin_token_account.key(),
+            &self.pool_pc_token_account.key(),
+        )?;
+        
+        require_keys_eq!(self.amm_authority.key(), state::get_amm_authority(amm.nonce)?, DexError::InvalidRaydiumAmmV4Account);
+        require_keys_eq!(self.amm_open_orders.key(), amm.open_orders, DexError::InvalidRaydiumAmmV4Account);
+        require_keys_eq!(self.amm_target_orders.key(), amm.target_orders, DexError::InvalidRaydiumAmmV4Account);
+        require_keys_eq!(self.serum_program.key(), amm.market_program, DexError::InvalidRaydiumAmmV4Account);
+        require_keys_eq!(self.serum_market.key(), amm.market, DexError::InvalidRaydiumAmmV4Account);
+        
+        require!(
+            amm.swap_enabled(Clock::get()?.unix_timestamp),
+            DexError::RaydiumAmmV4SwapDisabled
+        );
+        
+        Ok((amm, token_is_coin))
+    }
+    
+    // 确保用户代币账户存在
+    fn ensure_user_token_account(&self) -> Result<token_interface::TokenAccount> {
+        token_utils::ensure_user_token_account(
+            &self.user.to_account_info(),
+            &self.user_token_account.to_account_info(),
+            &self.token_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.associated_token_program.to_account_info(),
+        )
+    }
+    
+    // 确保用户WSOL账户存在
+    fn ensure_user_wsol_account(&self) -> Result<token_interface::TokenAccount> {
+        token_utils::ensure_user_token_account(
+            &self.user.to_account_info(),
+            &self.user_wsol_account.to_account_info(),
+            &self.wsol_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.associated_token_program.to_account_info(),
+        )
+    }
+    
+    // 执行交易: 校验池子、报价并检查价格影响和滑点、准备用户账户、调用Raydium AMM v4，最后将WSOL解包为SOL
+    // ExactIn时amount为输入数量，other_amount_threshold为最小输出；ExactOut时amount为输出数量，other_amount_threshold为最大输入
+    // 返回按实际余额变化结算的成交结果
+    fn execute_swap(
+        &self,
+        is_buy: bool,
+        swap_mode: SwapMode,
+        amount: u64,
+        other_amount_threshold: u64,
+    ) -> Result<Fill> {
+        let (amm, token_is_coin) = self.load_pool_state()?;
+        
+        // 买入时输入为WSOL，卖出时输入为代币
+        let (input_reserve, output_reserve) = directional_reserves(
+            quote::pool_reserves(&amm, self.pool_coin_token_account.amount, self.pool_pc_token_account.amount)?,
+            token_is_coin != is_buy,
+        );
+        let fees = quote::AmmV4Fees::from_amm_info(&amm);
+        
+        let swap_quote = match swap_mode {
+            SwapMode::ExactIn => {
+                let swap_quote = quote::swap_base_in(input_reserve, output_reserve, &fees, amount)?;
+                require!(swap_quote.amount_out >= other_amount_threshold, DexError::SlippageExceeded);
+                swap_quote
+            },
+            SwapMode::ExactOut => {
+                let swap_quote = quote::swap_base_out(input_reserve, output_reserve, &fees, amount)?;
+                require!(swap_quote.amount_in <= other_amount_threshold, DexError::SlippageExceeded);
+                swap_quote
+            },
+        };
+        
+        msg!("预计输入: {}, 预计输出: {}, 手续费: {}, 价格影响: {}%",
+            swap_quote.amount_in,
+            swap_quote.amount_out,
+            swap_quote.fee_amount,
+            swap_quote.price_impact as f64 / 10_000.0
+        );
+        
+        // 检查价格影响是否过大
+        require!(swap_quote.price_impact <= MAX_PRICE_IMPACT, DexError::ExcessivePriceImpact);
+        
+        // 输入为SOL时从用户的SOL中包装，ExactOut按最大花费包装，未用完的部分交易后解包退还
+        let max_amount_in = match swap_mode {
+            SwapMode::ExactIn => amount,
+            SwapMode::ExactOut => other_amount_threshold,
+        };
+        // 记录WSOL账户交易前的余额，交易后只解包超出交易前余额的部分，不动用账户中原有的WSOL
+        let wsol_balance_before = token_utils::balance_before_trade(&self.user_wsol_account)?;
+        let (source, destination) = if is_buy {
+            self.ensure_user_token_account()?;
+            self.ensure_user_wsol_account()?;
+            require!(self.user.lamports() >= max_amount_in, DexError::InsufficientFunds);
+            token_utils::wrap_sol(
+                &self.user.to_account_info(),
+                &self.user_wsol_account.to_account_info(),
+                max_amount_in,
+                &self.system_program.to_account_info(),
+                &self.token_program.to_account_info(),
+            )?;
+            (&self.user_wsol_account, &self.user_token_account)
+        } else {
+            let user_token_account = token_utils::read_token_account(&self.user_token_account)?;
+            require_keys_eq!(user_token_account.mint, self.token_mint.key(), DexError::InvalidTokenAccount);
+            require_keys_eq!(user_token_account.owner, self.user.key(), DexError::InvalidTokenAccount);
+            require!(user_token_account.amount >= swap_quote.amount_in, DexError::InsufficientFunds);
+            self.ensure_user_wsol_account()?;
+            (&self.user_token_account, &self.user_wsol_account)
+        };
+        
+        let swap_accounts = self.swap_accounts(source.key(), destination.key());
+        let swap_ix = match swap_mode {
+            SwapMode::ExactIn => instructions::swap_base_in(
+                get_raydium_amm_v4_program_id(),
+                &swap_accounts,
+                &instructions::SwapBaseInArgs {
+                    amount_in: amount,
+                    minimum_amount_out: other_amount_threshold,
+                },
+            ),
+            SwapMode::ExactOut => instructions::swap_base_out(
+                get_raydium_amm_v4_program_id(),
+                &swap_accounts,
+                &instructions::SwapBaseOutArgs {
+                    max_amount_in: other_amount_threshold,
+                    amount_out: amount,
+                },
+            ),
+        };
+        let snapshot = BalanceSnapshot::take(source, destination)?;
+        self.invoke_swap(&swap_ix, source, destination)?;
+        
+        // 按实际余额变化检查成交数量（解包WSOL之前）
+        let limits = match swap_mode {
+            SwapMode::ExactIn => FillLimits {
+                expected_amount_in: amount,
+                expected_amount_out: swap_quote.amount_out,
+                max_amount_in: amount,
+                min_amount_out: other_amount_threshold,
+            },
+            SwapMode::ExactOut => FillLimits {
+                expected_amount_in: swap_quote.amount_in,
+                expected_amount_out: amount,
+                max_amount_in: other_amount_threshold,
+                min_amount_out: amount,
+            },
+        };
+        let (input_mint, output_mint) = if is_buy {
+            (&self.wsol_mint, &self.token_mint)
+        } else {
+            (&self.token_mint, &self.wsol_mint)
+        };
+        let fill = snapshot.settle(
+            source,
+            destination,
+            self.user.key(),
+            &FillPair {
+                dex: DexType::RaydiumAmmV4,
+                token_in: input_mint.key(),
+                input_decimals: input_mint.decimals,
+                token_out: output_mint.key(),
+                output_decimals: output_mint.decimals,
+            },
+            &limits,
+        )?;
+        
+        // 交易完成后将WSOL解包为SOL（买入时退还剩余的WSOL，卖出时将所得WSOL转为SOL）
+        token_utils::unwrap_sol(
+            &self.user.to_account_info(),
+            &self.user_wsol_account.to_account_info(),
+            &self.wsol_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.associated_token_program.to_account_info(),
+            wsol_balance_before,
+        )?;
+        
+        Ok(fill)
+    }
+    
+    // 用户代币账户余额为0时关闭并回收租金
+    fn close_empty_user_token_account(&self) -> Result<()> {
+        token_utils::close_empty_token_account(
+            &self.user.to_account_info(),
+            &self.user_token_account.to_account_info(),
+            &self.token_program.to_account_info(),
+        )?;
+        Ok(())
+    }
+    
+    // swap_base_in/swap_base_out指令账户
+    fn swap_accounts(&self, user_source_token_account: Pubkey, user_destination_token_account: Pubkey) -> instructions::SwapAccounts {
+        instructions::SwapAccounts {
+            token_program: self.token_program.key(),
+            amm: self.amm.key(),
+            amm_authority: self.amm_authority.key(),
+            amm_open_orders: self.amm_open_orders.key(),
+            amm_target_orders: self.amm_target_orders.key(),
+            pool_coin_token_account: self.pool_coin_token_account.key(),
+            pool_pc_token_account: self.pool_pc_token_account.key(),
+            serum_program: self.serum_program.key(),
+            serum_market: self.serum_market.key(),
+            serum_bids: self.serum_bids.key(),
+            serum_asks: self.serum_asks.key(),
+            serum_event_queue: self.serum_event_queue.key(),
+            serum_coin_vault: self.serum_coin_vault.key(),
+            serum_pc_vault: self.serum_pc_vault.key(),
+            serum_vault_signer: self.serum_vault_signer.key(),
+            user_source_token_account,
+            user_destination_token_account,
+            user: self.user.key(),
+        }
+    }
+    
+    // 执行Raydium AMM v4的交易指令
+    fn invoke_swap(
+        &self,
+        swap_ix: &Instruction,
+        user_source_token_account: &UncheckedAccount<'info>,
+        user_destination_token_account: &UncheckedAccount<'info>,
+    ) -> Result<()> {
+        let account_infos = [
+            self.token_program.to_account_info(),
+            self.amm.to_account_info(),
+            self.amm_authority.to_account_info(),
+            self.amm_open_orders.to_account_info(),
+            self.amm_target_orders.to_account_info(),
+            self.pool_coin_token_account.to_account_info(),
+            self.pool_pc_token_account.to_account_info(),
+            self.serum_program.to_account_info(),
+            self.serum_market.to_account_info(),
+            self.serum_bids.to_account_info(),
+            self.serum_asks.to_account_info(),
+            self.serum_event_queue.to_account_info(),
+            self.serum_coin_vault.to_account_info(),
+            self.serum_pc_vault.to_account_info(),
+            self.serum_vault_signer.to_account_info(),
+            user_source_token_account.to_account_info(),
+            user_destination_token_account.to_account_info(),
+            self.user.to_account_info(),
+            self.raydium_amm_v4_program.to_account_info(),
+        ];
+        
+        invoke(swap_ix, &account_infos)?;
+        Ok(())
+    }
+}
+
+// 检查代币位置所需的账户结构
+#[derive(Accounts)]
+pub struct CheckTokenLocation<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Raydium AMM v4池子账户（可能不存在，在指令中手动检查）
+    pub amm: UncheckedAccount<'info>,
+    
+    // 池子coin金库
+    pub pool_coin_token_account: UncheckedAccount<'info>,
+    
+    // 池子pc金库
+    pub pool_pc_token_account: UncheckedAccount<'info>,
+    
+    // 系统程序
+    pub system_program: Program<'info, System>,
+}
+
+// 获取价格所需的账户结构
+#[derive(Accounts)]
+pub struct GetPriceContext<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // 代币Mint地址
+    pub token_mint: Account<'info, token::Mint>,
+    
+    // Raydium AMM v4池子账户（原生程序账户，在指令中手动反序列化）
+    pub amm: UncheckedAccount<'info>,
+    
+    // 池子coin金库
+    pub pool_coin_token_account: Account<'info, TokenAccount>,
+    
+    // 池子pc金库
+    pub pool_pc_token_account: Account<'info, TokenAccount>,
+    
+    // 系统程序
+    pub system_program: Program<'info, System>,
+    
+    // 代币程序
+    pub token_program: Program<'info, Token>,
+}
@@ -0,0 +1,245 @@
+use anchor_lang::prelude::*;
+use crate::math::{self, to_u64};
+use crate::DexError;
+use super::state::AmmInfo;
+
+// Raydium AMM v4交易手续费率（来自AmmInfo.fees，通常为25/10000）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct AmmV4Fees {
+    pub swap_fee_numerator: u64,
+    pub swap_fee_denominator: u64,
+}
+
+impl AmmV4Fees {
+    pub fn from_amm_info(amm: &AmmInfo) -> Self {
+        AmmV4Fees {
+            swap_fee_numerator: amm.fees.swap_fee_numerator,
+            swap_fee_denominator: amm.fees.swap_fee_denominator,
+        }
+    }
+    
+    // 交易手续费，以输入代币计算并向上取整
+    fn swap_fee(&self, amount: u64) -> Result<u64> {
+        math::fee_ceil(amount, self.swap_fee_numerator, self.swap_fee_denominator)
+    }
+    
+    // 已知扣除手续费后的数量，反推扣费前的数量（向上取整）
+    fn pre_fee_amount(&self, post_fee_amount: u64) -> Result<u64> {
+        let denominator = self.swap_fee_denominator
+            .checked_sub(self.swap_fee_numerator)
+            .filter(|denominator| *denominator > 0)
+            .ok_or(DexError::InvalidArgument)?;
+        
+        to_u64(math::mul_div_ceil(
+            post_fee_amount as u128,
+            self.swap_fee_denominator as u128,
+            denominator as u128,
+        )?)
+    }
+}
+
+// Raydium AMM v4报价结果
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct AmmV4Quote {
+    // 转入金库的输入代币数量（含交易手续费）
+    pub amount_in: u64,
+    // 从金库转出的输出代币数量
+    pub amount_out: u64,
+    // 交易手续费（输入代币）
+    pub fee_amount: u64,
+    // 交易后的池子储备
+    pub new_input_reserve: u64,
+    pub new_output_reserve: u64,
+    // 价格影响（以FEE_DENOMINATOR为分母的百万分比，不含手续费）
+    pub price_impact: u64,
+}
+
+// 池子实际储备 = 金库余额 - 尚未提取的PnL，返回(coin储备, pc储备)
+// OpenBook订单簿已停用，open_orders中不再持有池子资金
+pub fn pool_reserves(amm: &AmmInfo, coin_vault_amount: u64, pc_vault_amount: u64) -> Result<(u64, u64)> {
+    let coin_reserve = coin_vault_amount
+        .checked_sub(amm.state_data.need_take_pnl_coin)
+        .ok_or(DexError::InsufficientLiquidity)?;
+    let pc_reserve = pc_vault_amount
+        .checked_sub(amm.state_data.need_take_pnl_pc)
+        .ok_or(DexError::InsufficientLiquidity)?;
+    
+    Ok((coin_reserve, pc_reserve))
+}
+
+// 报价: swap_base_in（精确输入）
+pub fn swap_base_in(
+    input_reserve: u64,
+    output_reserve: u64,
+    fees: &AmmV4Fees,
+    amount_in: u64,
+) -> Result<AmmV4Quote> {
+    require!(amount_in > 0, DexError::InvalidArgument);
+    require!(input_reserve > 0 && output_reserve > 0, DexError::InsufficientLiquidity);
+    
+    let fee_amount = fees.swap_fee(amount_in)?;
+    let amount_in_less_fees = amount_in
+        .checked_sub(fee_amount)
+        .ok_or(DexError::ArithmeticOverflow)?;
+    
+    // 输出数量 = floor(output_reserve * amount_in_less_fees / (input_reserve + amount_in_less_fees))
+    let amount_out = to_u64(math::mul_div_floor(
+        output_reserve as u128,
+        amount_in_less_fees as u128,
+        input_reserve as u128 + amount_in_less_fees as u128,
+    )?)?;
+    require!(amount_out > 0, DexError::InsufficientLiquidity);
+    
+    build_quote(input_reserve, output_reserve, amount_in, amount_in_less_fees, amount_out, fee_amount)
+}
+
+// 报价: swap_base_out（精确输出）
+pub fn swap_base_out(
+    input_reserve: u64,
+    output_reserve: u64,
+    fees: &AmmV4Fees,
+    amount_out: u64,
+) -> Result<AmmV4Quote> {
+    require!(amount_out > 0, DexError::InvalidArgument);
+    require!(input_reserve > 0 && output_reserve > 0, DexError::InsufficientLiquidity);
+    require!(amount_out < output_reserve, DexError::InsufficientLiquidity);
+    
+    // 不含手续费的输入 = ceil(input_reserve * amount_out / (output_reserve - amount_out))
+    let amount_in_less_fees = to_u64(math::mul_div_ceil(
+        input_reserve as u128,
+        amount_out as u128,
+        (output_reserve - amount_out) as u128,
+    )?)?;
+    
+    // 与链上一致: 含手续费的输入 = ceil(amount_in_less_fees * denominator / (denominator - numerator))
+    let amount_in = fees.pre_fee_amount(amount_in_less_fees)?;
+    let fee_amount = amount_in - amount_in_less_fees;
+    
+    build_quote(input_reserve, output_reserve, amount_in, amount_in_less_fees, amount_out, fee_amount)
+}
+
+// 构建报价，更新储备并计算价格影响
+fn build_quote(
+    input_reserve: u64,
+    output_reserve: u64,
+    amount_in: u64,
+    amount_in_less_fees: u64,
+    amount_out: u64,
+    fee_amount: u64,
+) -> Result<AmmV4Quote> {
+    // 交易手续费留在池子中
+    let new_input_reserve = input_reserve
+        .checked_add(amount_in)
+        .ok_or(DexError::ArithmeticOverflow)?;
+    let new_output_reserve = output_reserve
+        .checked_sub(amount_out)
+        .ok_or(DexError::InsufficientLiquidity)?;
+    
+    // 价格影响 = 1 - 成交价格 / 现货价格 = 1 - amount_out * input_reserve / (amount_in_less_fees * output_reserve)
+    let price_impact = math::price_impact(
+        amount_out as u128 * input_reserve as u128,
+        amount_in_less_fees as u128 * output_reserve as u128,
+    )?;
+    
+    Ok(AmmV4Quote {
+        amount_in,
+        amount_out,
+        fee_amount,
+        new_input_reserve,
+        new_output_reserve,
+        price_impact,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raydium_amm_v4::state::AmmStateData;
+    
+    // 期望值按Raydium AMM v4程序的整数运算独立计算:
+    // swap_base_in: 手续费ceil(amount_in * 25 / 10000)，输出floor(output_reserve * 扣费后输入 / (input_reserve + 扣费后输入))；
+    // swap_base_out: 扣费后输入ceil(input_reserve * amount_out / (output_reserve - amount_out))，含费输入ceil(扣费后输入 * 10000 / 9975)
+    
+    // 500M代币 / 1200 SOL的池子
+    const COIN_RESERVE: u64 = 500_000_000_000_000;
+    const PC_RESERVE: u64 = 1_200_000_000_000;
+    
+    const FEES: AmmV4Fees = AmmV4Fees { swap_fee_numerator: 25, swap_fee_denominator: 10_000 };
+    
+    #[test]
+    fn swap_base_in_matches_reference() {
+        // 买入: 1 SOL换代币
+        let quote = swap_base_in(PC_RESERVE, COIN_RESERVE, &FEES, 1_000_000_000).unwrap();
+        assert_eq!(
+            quote,
+            AmmV4Quote {
+                amount_in: 1_000_000_000,
+                amount_out: 415_279_798_667,
+                fee_amount: 2_500_000,
+                new_input_reserve: 1_201_000_000_000,
+                new_output_reserve: 499_584_720_201_333,
+                price_impact: 830,
+            }
+        );
+        
+        // 卖出: 1M代币换SOL
+        let quote = swap_base_in(COIN_RESERVE, PC_RESERVE, &FEES, 1_000_000_000_000).unwrap();
+        assert_eq!(
+            quote,
+            AmmV4Quote {
+                amount_in: 1_000_000_000_000,
+                amount_out: 2_389_233_479,
+                fee_amount: 2_500_000_000,
+                new_input_reserve: 501_000_000_000_000,
+                new_output_reserve: 1_197_610_766_521,
+                price_impact: 1_991,
+            }
+        );
+    }
+    
+    #[test]
+    fn swap_base_in_rounds_fee_up() {
+        // 399 * 25 / 10000 = 0.9975，向上取整为1
+        let quote = swap_base_in(PC_RESERVE, COIN_RESERVE, &FEES, 399).unwrap();
+        assert_eq!((quote.fee_amount, quote.amount_out), (1, 165_833));
+    }
+    
+    #[test]
+    fn swap_base_out_matches_reference() {
+        let quote = swap_base_out(PC_RESERVE, COIN_RESERVE, &FEES, 1_000_000_000_000).unwrap();
+        assert_eq!(
+            quote,
+            AmmV4Quote {
+                amount_in: 2_410_836_712,
+                amount_out: 1_000_000_000_000,
+                fee_amount: 6_027_092,
+                new_input_reserve: 1_202_410_836_712,
+                new_output_reserve: 499_000_000_000_000,
+                price_impact: 2_000,
+            }
+        );
+        
+        // 扣费后输入ceil(0.0024)为1，含费输入ceil(10000 / 9975)为2
+        let quote = swap_base_out(PC_RESERVE, COIN_RESERVE, &FEES, 1).unwrap();
+        assert_eq!((quote.amount_in, quote.fee_amount), (2, 1));
+    }
+    
+    #[test]
+    fn pool_reserves_exclude_pending_pnl() {
+        let amm = AmmInfo {
+            state_data: AmmStateData {
+                need_take_pnl_coin: 3_000_000_000,
+                need_take_pnl_pc: 1_500_000,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            pool_reserves(&amm, COIN_RESERVE + 3_000_000_000, PC_RESERVE + 1_500_000).unwrap(),
+            (COIN_RESERVE, PC_RESERVE)
+        );
+        
+        // 金库余额少于尚未提取的PnL时视为流动性不足
+        assert!(pool_reserves(&amm, 2_999_999_999, PC_RESERVE).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+use solana_program::pubkey::Pubkey;
+use crate::DexError;
+use super::get_raydium_amm_v4_program_id;
+
+// AmmInfo账户大小（Raydium AMM v4为原生程序，账户没有discriminator）
+pub const AMM_INFO_LEN: usize = 752;
+
+// 池子权限PDA种子: ["amm authority", nonce]
+pub const AMM_AUTHORITY_SEED: &[u8] = b"amm authority";
+
+// AmmInfo中的手续费参数
+#[derive(AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct AmmFees {
+    pub min_separate_numerator: u64,
+    pub min_separate_denominator: u64,
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub pnl_numerator: u64,
+    pub pnl_denominator: u64,
+    // 交易手续费，从输入代币中扣除
+    pub swap_fee_numerator: u64,
+    pub swap_fee_denominator: u64,
+}
+
+// AmmInfo中的状态统计，need_take_pnl为尚未提取的PnL，仍在金库中但不属于池子储备
+#[derive(AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct AmmStateData {
+    pub need_take_pnl_coin: u64,
+    pub need_take_pnl_pc: u64,
+    pub total_pnl_pc: u64,
+    pub total_pnl_coin: u64,
+    pub pool_open_time: u64,
+    pub padding: [u64; 2],
+    pub orderbook_to_init_time: u64,
+    pub swap_coin_in_amount: u128,
+    pub swap_pc_out_amount: u128,
+    pub swap_acc_pc_fee: u64,
+    pub swap_pc_in_amount: u128,
+    pub swap_coin_out_amount: u128,
+    pub swap_acc_coin_fee: u64,
+}
+
+// Raydium AMM v4的AmmInfo账户（字段顺序与链上布局一致）
+#[derive(AnchorDeserialize, Clone, Debug, Default)]
+pub struct AmmInfo {
+    pub status: u64,
+    pub nonce: u64,
+    pub order_num: u64,
+    pub depth: u64,
+    pub coin_decimals: u64,
+    pub pc_decimals: u64,
+    pub state: u64,
+    pub reset_flag: u64,
+    pub min_size: u64,
+    pub vol_max_cut_ratio: u64,
+    pub amount_wave: u64,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub min_price_multiplier: u64,
+    pub max_price_multiplier: u64,
+    pub sys_decimal_value: u64,
+    pub fees: AmmFees,
+    pub state_data: AmmStateData,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub coin_vault_mint: Pubkey,
+    pub pc_vault_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub open_orders: Pubkey,
+    pub market: Pubkey,
+    pub market_program: Pubkey,
+    pub target_orders: Pubkey,
+    pub padding1: [u64; 8],
+    pub amm_owner: Pubkey,
+    pub lp_amount: u64,
+    pub client_order_id: u64,
+    pub recent_epoch: u64,
+    pub padding2: u64,
+}
+
+impl AmmInfo {
+    // AmmStatus中允许交易的状态
+    pub const STATUS_INITIALIZED: u64 = 1;
+    pub const STATUS_SWAP_ONLY: u64 = 6;
+    pub const STATUS_WAITING_TRADE: u64 = 7;
+    
+    // 从账户数据反序列化，检查账户大小
+    pub fn try_deserialize_raw(data: &[u8]) -> Result<Self> {
+        require!(data.len() == AMM_INFO_LEN, DexError::InvalidRaydiumAmmV4Account);
+        AmmInfo::deserialize(&mut &data[..]).map_err(|_| DexError::InvalidRaydiumAmmV4Account.into())
+    }
+    
+    // 从账户加载，检查所有者为Raydium AMM v4程序
+    pub fn try_from_account_info(account_info: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(
+            *account_info.owner,
+            get_raydium_amm_v4_program_id(),
+            DexError::InvalidRaydiumAmmV4Account
+        );
+        let data = account_info.try_borrow_data()?;
+        Self::try_deserialize_raw(&data)
+    }
+    
+    // 检查池子当前是否允许交易（WaitingTrade状态需要到达开放时间）
+    pub fn swap_enabled(&self, now: i64) -> bool {
+        match self.status {
+            Self::STATUS_INITIALIZED | Self::STATUS_SWAP_ONLY => true,
+            Self::STATUS_WAITING_TRADE => now >= 0 && now as u64 >= self.state_data.pool_open_time,
+            _ => false,
+        }
+    }
+}
+
+// 计算池子权限PDA: ["amm authority", nonce]
+pub fn get_amm_authority(nonce: u64) -> Result<Pubkey> {
+    let nonce = u8::try_from(nonce).map_err(|_| DexError::InvalidRaydiumAmmV4Account)?;
+    Pubkey::create_program_address(&[AMM_AUTHORITY_SEED, &[nonce]], &get_raydium_amm_v4_program_id())
+        .map_err(|_| DexError::InvalidRaydiumAmmV4Account.into())
+}
@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+// Raydium CLMM指令discriminator（Anchor规则: sha256("global:<指令名>")的前8字节）
+pub const SWAP_V2_DISCRIMINATOR: [u8; 8] = [43, 4, 237, 11, 26, 201, 30, 98];
+
+// swap_v2指令参数
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SwapV2Args {
+    // is_base_input为true时为输入数量，否则为输出数量
+    pub amount: u64,
+    // is_base_input为true时为最小输出，否则为最大输入
+    pub other_amount_threshold: u64,
+    // 价格限制，0表示不限制
+    pub sqrt_price_limit_x64: u128,
+    pub is_base_input: bool,
+}
+
+// swap_v2指令账户（顺序与Raydium CLMM IDL一致），TickArray等通过剩余账户传入
+pub struct SwapV2Accounts {
+    pub payer: Pubkey,
+    pub amm_config: Pubkey,
+    pub pool_state: Pubkey,
+    pub input_token_account: Pubkey,
+    pub output_token_account: Pubkey,
+    pub input_vault: Pubkey,
+    pub output_vault: Pubkey,
+    pub observation_state: Pubkey,
+    pub token_program: Pubkey,
+    pub token_program_2022: Pubkey,
+    pub memo_program: Pubkey,
+    pub input_vault_mint: Pubkey,
+    pub output_vault_mint: Pubkey,
+    // 可选的TickArrayBitmapExtension和交易方向上的TickArray
+    pub remaining_accounts: Vec<Pubkey>,
+}
+
+impl SwapV2Accounts {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.payer, true),
+            AccountMeta::new_readonly(self.amm_config, false),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.input_token_account, false),
+            AccountMeta::new(self.output_token_account, false),
+            AccountMeta::new(self.input_vault, false),
+            AccountMeta::new(self.output_vault, false),
+            AccountMeta::new(self.observation_state, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.token_program_2022, false),
+            AccountMeta::new_readonly(self.memo_program, false),
+            AccountMeta::new_readonly(self.input_vault_mint, false),
+            AccountMeta::new_readonly(self.output_vault_mint, false),
+        ];
+        
+        metas.extend(
+            self.remaining_accounts
+                .iter()
+                .map(|account| AccountMeta::new(*account, false)),
+        );
+        
+        metas
+    }
+}
+
+// 编码swap_v2指令数据: 8字节discriminator + Borsh序列化参数
+pub fn encode_swap_v2_data(args: &SwapV2Args) -> Vec<u8> {
+    let mut data = SWAP_V2_DISCRIMINATOR.to_vec();
+    // 写入Vec<u8>不会失败
+    args.serialize(&mut data).unwrap();
+    data
+}
+
+// 构建swap_v2指令
+pub fn swap_v2(program_id: Pubkey, accounts: &SwapV2Accounts, args: &SwapV2Args) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data: encode_swap_v2_data(args),
+    }
+}
@@ -0,0 +1,650 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use anchor_spl::associated_token::AssociatedToken;
+use solana_program::{
+    instruction::Instruction,
+    program::invoke,
+    pubkey::Pubkey,
+};
+use std::str::FromStr;
+use crate::fill::{BalanceSnapshot, Fill, FillLimits, FillPair};
+use crate::math::Price;
+use crate::router::RouteQuoter;
+use crate::{token_utils, DexError, DexType, MAX_PRICE_IMPACT};
+
+pub mod instructions;
+pub mod quote;
+pub mod state;
+pub mod tick_math;
+
+pub use state::{AmmConfig, PoolState, TickArray};
+
+// Raydium CLMM（集中流动性）程序ID
+pub const RAYDIUM_CLMM_PROGRAM_ID: &str = "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK";
+
+// 获取Raydium CLMM程序ID
+pub fn get_raydium_clmm_program_id() -> Pubkey {
+    Pubkey::from_str(RAYDIUM_CLMM_PROGRAM_ID).unwrap()
+}
+
+// 在Raydium CLMM上购买代币（swap_v2，精确SOL输入）
+// 剩余账户: 可选的TickArrayBitmapExtension，随后为交易方向上的TickArray（从当前tick所在的TickArray开始）
+pub fn buy_token<'info>(
+    ctx: Context<'_, '_, '_, 'info, TradeTokenRaydiumClmm<'info>>,
+    amount_in: u64,  // 输入的SOL数量
+    min_amount_out: u64,  // 最小获得的代币数量（滑点控制）
+) -> Result<Fill> {
+    msg!("在Raydium CLMM上购买代币: {} SOL, 最小获得代币数量: {}", amount_in, min_amount_out);
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    // 检查参数
+    require!(amount_in > 0, DexError::InvalidArgument);
+    require!(min_amount_out > 0, DexError::InvalidArgument);
+    
+    let fill = ctx.accounts.execute_swap(true, amount_in, min_amount_out, ctx.remaining_accounts)?;
+    
+    msg!("交易完成，获得代币");
+    Ok(fill)
+}
+
+// 在Raydium CLMM上卖出代币（swap_v2，精确代币输入）
+// 剩余账户与buy_token相同
+pub fn sell_token<'info>(
+    ctx: Context<'_, '_, '_, 'info, TradeTokenRaydiumClmm<'info>>,
+    amount_in: u64,  // 输入的代币数量
+    min_amount_out: u64,  // 最小获得的SOL数量（滑点控制）
+    close_token_account: bool,  // 全部卖出后是否关闭用户代币账户以回收租金
+) -> Result<Fill> {
+    msg!("在Raydium CLMM上卖出代币: {} 代币, 最小获得SOL数量: {}", amount_in, min_amount_out);
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    // 检查参数
+    require!(amount_in > 0, DexError::InvalidArgument);
+    require!(min_amount_out > 0, DexError::InvalidArgument);
+    
+    let fill = ctx.accounts.execute_swap(false, amount_in, min_amount_out, ctx.remaining_accounts)?;
+    
+    // 全部卖出后可选地关闭用户代币账户
+    if close_token_account {
+        ctx.accounts.close_empty_user_source_token_account()?;
+    }
+    
+    msg!("交易完成，获得SOL");
+    Ok(fill)
+}
+
+// 检查代币是否在Raydium CLMM上（以WSOL为另一侧的可交易池子）
+pub fn is_token_on_raydium_clmm(
+    ctx: Context<CheckTokenLocation>,
+    token_mint: Pubkey,
+) -> Result<bool> {
+    // 记录查询信息
+    msg!("检查代币是否在Raydium CLMM上: {}", token_mint);
+    
+    let pool_account_info = ctx.accounts.pool_state.to_account_info();
+    
+    // 池子账户不存在或不属于Raydium CLMM时，代币不在Raydium CLMM上
+    if pool_account_info.data_is_empty() || *pool_account_info.owner != get_raydium_clmm_program_id() {
+        msg!("Raydium CLMM池子账户不存在，代币不在Raydium CLMM上");
+        return Ok(false);
+    }
+    
+    let pool_state = match PoolState::try_from_account_info(&pool_account_info) {
+        Ok(pool_state) => pool_state,
+        Err(_) => {
+            msg!("Raydium CLMM池子账户无法解析，代币不在Raydium CLMM上");
+            return Ok(false);
+        }
+    };
+    
+    if token_is_token_0(&pool_state, &token_mint).is_err() {
+        msg!("Raydium CLMM池子与代币不匹配，代币不在Raydium CLMM上");
+        return Ok(false);
+    }
+    
+    // 检查池子是否允许交易
+    if !pool_state.swap_enabled(Clock::get()?.unix_timestamp) {
+        msg!("Raydium CLMM池子当前不允许交易，状态: {}", pool_state.status);
+        return Ok(false);
+    }
+    
+    // 检查当前价格区间是否有流动性
+    if pool_state.liquidity == 0 {
+        msg!("Raydium CLMM池子当前价格区间没有流动性，代币不可交易");
+        return Ok(false);
+    }
+    
+    msg!("代币在Raydium CLMM上可用，当前流动性: {}, 当前tick: {}", pool_state.liquidity, pool_state.tick_current);
+    Ok(true)
+}
+
+// 获取Raydium CLMM上的报价（精确输入，amount_in为扣除输入代币转账手续费前的数量）
+// 剩余账户: 交易方向上的TickArray（可在最前面附带TickArrayBitmapExtension）
+pub fn get_quote<'info>(
+    ctx: Context<'_, '_, '_, 'info, GetPriceContext<'info>>,
+    amount_in: u64,
+    is_buy: bool,  // true表示买入（输入SOL），false表示卖出（输入代币）
+) -> Result<quote::ClmmQuote> {
+    // 检查输入金额是否大于0
+    require!(amount_in > 0, DexError::InvalidArgument);
+    
+    // 记录查询信息
+    msg!("查询Raydium CLMM上的报价: 输入金额 {}, 操作类型: {}",
+        amount_in,
+        if is_buy { "买入" } else { "卖出" }
+    );
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    let pool_key = ctx.accounts.pool_state.key();
+    let pool_state = PoolState::try_from_account_info(&ctx.accounts.pool_state)?;
+    require_keys_eq!(ctx.accounts.amm_config.key(), pool_state.amm_config, DexError::InvalidRaydiumAmmConfig);
+    let amm_config = AmmConfig::try_from_account_info(&ctx.accounts.amm_config)?;
+    let token_is_token_0 = token_is_token_0(&pool_state, &ctx.accounts.token_mint.key())?;
+    
+    // 报价代币（SOL）一侧没有转账手续费，卖出时代币转入金库先扣除转账手续费
+    let actual_amount_in = if is_buy {
+        amount_in
+    } else {
+        token_utils::amount_after_transfer_fee(&ctx.accounts.token_mint.to_account_info(), amount_in)?
+    };
+    require!(actual_amount_in > 0, DexError::InvalidArgument);
+    
+    let tick_arrays = load_tick_arrays(&pool_key, ctx.remaining_accounts)?;
+    let pool = quote::ClmmPool::new(&pool_state, &amm_config, &tick_arrays)?;
+    let clmm_quote = quote::swap_base_input(&pool, actual_amount_in, token_is_token_0 != is_buy)?;
+    
+    msg!("Raydium CLMM报价: 输入 {}, 输出 {}, 手续费 {}, 穿过tick数: {}, 价格影响: {}%",
+        clmm_quote.amount_in,
+        clmm_quote.amount_out,
+        clmm_quote.fee_amount,
+        clmm_quote.ticks_crossed,
+        clmm_quote.price_impact as f64 / 10_000.0
+    );
+    
+    Ok(clmm_quote)
+}
+
+// 获取Raydium CLMM上的价格（每单位输入可获得的输出，按两侧代币的小数位数换算）
+pub fn get_price<'info>(
+    ctx: Context<'_, '_, '_, 'info, GetPriceContext<'info>>,
+    amount_in: u64,
+    is_buy: bool,  // true表示买入，false表示卖出
+) -> Result<Price> {
+    let token_mint_info = ctx.accounts.token_mint.to_account_info();
+    let pool_state = PoolState::try_from_account_info(&ctx.accounts.pool_state)?;
+    let clmm_quote = get_quote(ctx, amount_in, is_buy)?;
+    
+    // 买入时代币转给用户再扣除转账手续费
+    let amount_out = if is_buy {
+        token_utils::amount_after_transfer_fee(&token_mint_info, clmm_quote.amount_out)?
+    } else {
+        clmm_quote.amount_out
+    };
+    
+    // 计算价格（小数位数取自池子记录的两侧Mint）
+    let (token_decimals, other_decimals) = if token_is_token_0(&pool_state, token_mint_info.key)? {
+        (pool_state.mint_decimals_0, pool_state.mint_decimals_1)
+    } else {
+        (pool_state.mint_decimals_1, pool_state.mint_decimals_0)
+    };
+    let price = if is_buy {
+        Price::from_amounts(amount_in, other_decimals, amount_out, token_decimals)?
+    } else {
+        Price::from_amounts(amount_in, token_decimals, amount_out, other_decimals)?
+    };
+    
+    msg!("Raydium CLMM上的最终价格: {}", price.as_f64());
+    Ok(price)
+}
+
+// 路由报价器: 读取一次池子状态和TickArray，按输入数量计算用户实际到账的输出（含Token-2022转账手续费）
+// 剩余账户: 交易方向上的TickArray（可在最前面附带TickArrayBitmapExtension）
+pub fn route_quoter<'info>(
+    accounts: &GetPriceContext<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    is_buy: bool,
+) -> Result<RouteQuoter<'info>> {
+    let pool_key = accounts.pool_state.key();
+    let pool_state = PoolState::try_from_account_info(&accounts.pool_state)?;
+    require_keys_eq!(accounts.amm_config.key(), pool_state.amm_config, DexError::InvalidRaydiumAmmConfig);
+    let amm_config = AmmConfig::try_from_account_info(&accounts.amm_config)?;
+    let zero_for_one = token_is_token_0(&pool_state, &accounts.token_mint.key())? != is_buy;
+    
+    let tick_arrays = load_tick_arrays(&pool_key, remaining_accounts)?;
+    let pool = quote::ClmmPool::new(&pool_state, &amm_config, &tick_arrays)?;
+    let token_mint_info = accounts.token_mint.to_account_info();
+    
+    Ok(Box::new(move |amount_in| {
+        // 卖出时代币转入金库先扣除转账手续费，买入时代币转给用户再扣除转账手续费
+        let actual_amount_in = if is_buy {
+            amount_in
+        } else {
+            token_utils::amount_after_transfer_fee(&token_mint_info, amount_in)?
+        };
+        require!(actual_amount_in > 0, DexError::InvalidArgument);
+        let amount_out = quote::swap_base_input(&pool, actual_amount_in, zero_for_one)?.amount_out;
+        
+        if is_buy {
+            token_utils::amount_after_transfer_fee(&token_mint_info, amount_out)
+        } else {
+            Ok(amount_out)
+        }
+    }))
+}
+
+// 确认代币在池子中且另一侧为WSOL，返回代币是否为token_0
+fn token_is_token_0(pool_state: &PoolState, token_mint: &Pubkey) -> Result<bool> {
+    let native_mint = spl_token::native_mint::id();
+    if pool_state.token_mint_0 == *token_mint && pool_state.token_mint_1 == native_mint {
+        Ok(true)
+    } else if pool_state.token_mint_1 == *token_mint && pool_state.token_mint_0 == native_mint {
+        Ok(false)
+    } else {
+        err!(DexError::TokenNotInRaydiumPool)
+    }
+}
+
+// 校验并解析剩余账户中的TickArray
+// 第一个账户可以是该池子的TickArrayBitmapExtension（只在CPI中使用），其余账户必须是该池子的TickArray
+fn load_tick_arrays(pool: &Pubkey, remaining_accounts: &[AccountInfo]) -> Result<Vec<TickArray>> {
+    let tick_array_accounts = match remaining_accounts.first() {
+        Some(first) if TickArray::is_bitmap_extension(first) => {
+            let (bitmap_extension, _) = state::get_tick_array_bitmap_extension_pda(pool);
+            require_keys_eq!(first.key(), bitmap_extension, DexError::InvalidRaydiumClmmAccount);
+            &remaining_accounts[1..]
+        },
+        _ => remaining_accounts,
+    };
+    require!(!tick_array_accounts.is_empty(), DexError::MissingRaydiumClmmTickArray);
+    
+    tick_array_accounts
+        .iter()
+        .map(|account| {
+            let tick_array = TickArray::try_from_account_info(account)?;
+            require_keys_eq!(tick_array.pool_id, *pool, DexError::InvalidRaydiumClmmAccount);
+            Ok(tick_array)
+        })
+        .collect()
+}
+
+// Raydium CLMM交易所需的账户结构
+// TickArray（以及可选的TickArrayBitmapExtension）通过剩余账户传入
+#[derive(Accounts)]
+pub struct TradeTokenRaydiumClmm<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // DEX账户
+    #[account(
+        mut,
+        seeds = [b"dex_account".as_ref()],
+        bump
+    )]
+    pub dex_account: Account<'info, crate::DexAccount>,
+    
+    // 代币Mint地址（支持SPL Token和Token-2022）
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    // Raydium CLMM程序
+    #[account(
+        constraint = raydium_clmm_program.key() == get_raydium_clmm_program_id()
+    )]
+    pub raydium_clmm_program: AccountInfo<'info>,
+    
+    // Raydium CLMM池子账户（在指令中手动反序列化并校验）
+    pub amm_config: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub pool_state: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub observation_state: UncheckedAccount<'info>,
+    
+    // 池子金库
+    #[account(mut)]
+    pub input_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub output_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    // 用户账户
+    // 输入为SOL时源代币账户为用户的WSOL账户，不存在时以幂等方式创建关联代币账户
+    #[account(mut)]
+    pub user_source_token_account: UncheckedAccount<'info>,
+    // 目标代币账户不存在时以幂等方式创建关联代币账户
+    #[account(mut)]
+    pub user_destination_token_account: UncheckedAccount<'info>,
+    
+    // 输入和输出代币Mint（按Mint所属程序选择SPL Token或Token-2022）
+    pub input_token_mint: Box<InterfaceAccount<'info, Mint>>,
+    pub output_token_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    // swap_v2同时需要SPL Token和Token-2022程序
+    pub token_program: Program<'info, Token>,
+    pub token_program_2022: Program<'info, Token2022>,
+    
+    // SPL Memo程序
+    #[account(
+        constraint = memo_program.key() == token_utils::get_memo_program_id()
+    )]
+    pub memo_program: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> TradeTokenRaydiumClmm<'info> {
+    // 加载池子状态和AMM配置，校验观察状态账户属于该池子，
+    // 并根据pool_state中的token_0/token_1和买卖方向校验输入输出两侧的Mint和金库
+    fn load_pool_state(&self, is_buy: bool) -> Result<(PoolState, AmmConfig)> {
+        let pool_state = PoolState::try_from_account_info(&self.pool_state)?;
+        
+        require_keys_eq!(self.amm_config.key(), pool_state.amm_config, DexError::InvalidRaydiumAmmConfig);
+        let amm_config = AmmConfig::try_from_account_info(&self.amm_config)?;
+        require_keys_eq!(
+            self.observation_state.key(),
+            pool_state.observation_key,
+            DexError::InvalidRaydiumObservationState
+        );
+        
+        // 买入时代币一侧为输出，卖出时代币一侧为输入
+        let input_is_token_0 = token_is_token_0(&pool_state, &self.token_mint.key())? != is_buy;
+        
+        // (mint, 金库)
+        let token_0_side = (pool_state.token_mint_0, pool_state.token_vault_0);
+        let token_1_side = (pool_state.token_mint_1, pool_state.token_vault_1);
+        let (input_side, output_side) = if input_is_token_0 {
+            (token_0_side, token_1_side)
+        } else {
+            (token_1_side, token_0_side)
+        };
+        
+        require_keys_eq!(self.input_token_mint.key(), input_side.0, DexError::RaydiumMintMismatch);
+        require_keys_eq!(self.output_token_mint.key(), output_side.0, DexError::RaydiumMintMismatch);
+        require_keys_eq!(self.input_vault.key(), input_side.1, DexError::RaydiumVaultMismatch);
+        require_keys_eq!(self.output_vault.key(), output_side.1, DexError::RaydiumVaultMismatch);
+        
+        require!(
+            pool_state.swap_enabled(Clock::get()?.unix_timestamp),
+            DexError::RaydiumClmmSwapDisabled
+        );
+        
+        Ok((pool_state, amm_config))
+    }
+    
+    // 执行交易: 校验池子和TickArray、沿tick报价并检查价格影响和滑点、准备用户账户、调用Raydium CLMM，最后将WSOL解包为SOL
+    // 返回按实际余额变化结算的成交结果
+    fn execute_swap(
+        &self,
+        is_buy: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<Fill> {
+        let (pool_state, amm_config) = self.load_pool_state(is_buy)?;
+        let tick_arrays = load_tick_arrays(&self.pool_state.key(), remaining_accounts)?;
+        
+        // 与Raydium一致: 输入代币转入金库时先扣除转账手续费，输出代币转给用户时再扣除一次
+        let actual_amount_in = token_utils::amount_after_transfer_fee(
+            &self.input_token_mint.to_account_info(),
+            amount_in,
+        )?;
+        require!(actual_amount_in > 0, DexError::InvalidArgument);
+        
+        let pool = quote::ClmmPool::new(&pool_state, &amm_config, &tick_arrays)?;
+        let zero_for_one = self.input_token_mint.key() == pool_state.token_mint_0;
+        let swap_quote = quote::swap_base_input(&pool, actual_amount_in, zero_for_one)?;
+        let expected_amount_out = token_utils::amount_after_transfer_fee(
+            &self.output_token_mint.to_account_info(),
+            swap_quote.amount_out,
+        )?;
+        
+        msg!("预计输入: {}, 预计到账: {}, 手续费: {}, 穿过tick数: {}, 价格影响: {}%",
+            swap_quote.amount_in,
+            expected_amount_out,
+            swap_quote.fee_amount,
+            swap_quote.ticks_crossed,
+            swap_quote.price_impact as f64 / 10_000.0
+        );
+        
+        // 检查价格影响和滑点（min_amount_out为实际到账数量）
+        require!(swap_quote.price_impact <= MAX_PRICE_IMPACT, DexError::ExcessivePriceImpact);
+        require!(expected_amount_out >= min_amount_out, DexError::SlippageExceeded);
+        
+        // 记录两侧代币账户交易前的余额，交易后只解包WSOL账户中超出交易前余额的部分
+        let token_balances_before = self.token_balances_before()?;
+        
+        // 输入为SOL时包装amount_in，目标代币账户不存在时创建
+        let user_source_token_account = self.prepare_user_source_token_account(amount_in)?;
+        require!(user_source_token_account.amount >= amount_in, DexError::InsufficientFunds);
+        self.ensure_user_destination_token_account()?;
+        
+        let swap_ix = instructions::swap_v2(
+            get_raydium_clmm_program_id(),
+            &self.swap_v2_accounts(remaining_accounts),
+            &instructions::SwapV2Args {
+                amount: amount_in,
+                other_amount_threshold: min_amount_out,
+                sqrt_price_limit_x64: 0,
+                is_base_input: true,
+            },
+        );
+        let snapshot = BalanceSnapshot::take(&self.user_source_token_account, &self.user_destination_token_account)?;
+        self.invoke_swap(&swap_ix, remaining_accounts)?;
+        
+        // 按实际余额变化检查成交数量（解包WSOL之前）
+        let fill = snapshot.settle(
+            &self.user_source_token_account,
+            &self.user_destination_token_account,
+            self.user.key(),
+            &FillPair {
+                dex: DexType::RaydiumClmm,
+                token_in: self.input_token_mint.key(),
+                input_decimals: self.input_token_mint.decimals,
+                token_out: self.output_token_mint.key(),
+                output_decimals: self.output_token_mint.decimals,
+            },
+            &FillLimits {
+                expected_amount_in: amount_in,
+                expected_amount_out,
+                max_amount_in: amount_in,
+                min_amount_out,
+            },
+        )?;
+        
+        // 交易完成后将WSOL解包为SOL
+        self.unwrap_native_accounts(token_balances_before)?;
+        
+        Ok(fill)
+    }
+    
+    // Mint所属的代币程序（SPL Token或Token-2022）
+    fn token_program_for(&self, mint: &InterfaceAccount<'info, Mint>) -> AccountInfo<'info> {
+        if *mint.to_account_info().owner == self.token_program_2022.key() {
+            self.token_program_2022.to_account_info()
+        } else {
+            self.token_program.to_account_info()
+        }
+    }
+    
+    // 准备源代币账户并返回其状态
+    // 输入为SOL时确保WSOL账户存在，并从用户的SOL中包装amount_in，不动用账户中原有的WSOL
+    fn prepare_user_source_token_account(&self, amount_in: u64) -> Result<TokenAccount> {
+        let user_source_token_account = self.user_source_token_account.to_account_info();
+        let input_token_program = self.token_program_for(&self.input_token_mint);
+        
+        if !self.is_native_mint(&self.input_token_mint) {
+            require_keys_eq!(
+                *user_source_token_account.owner,
+                input_token_program.key(),
+                DexError::InvalidTokenAccount
+            );
+            let token_account = token_utils::read_token_account(&user_source_token_account)?;
+            require_keys_eq!(token_account.mint, self.input_token_mint.key(), DexError::InvalidTokenAccount);
+            require_keys_eq!(token_account.owner, self.user.key(), DexError::InvalidTokenAccount);
+            return Ok(token_account);
+        }
+        
+        token_utils::ensure_user_token_account(
+            &self.user.to_account_info(),
+            &user_source_token_account,
+            &self.input_token_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &input_token_program,
+            &self.associated_token_program.to_account_info(),
+        )?;
+        
+        require!(self.user.lamports() >= amount_in, DexError::InsufficientFunds);
+        token_utils::wrap_sol(
+            &self.user.to_account_info(),
+            &user_source_token_account,
+            amount_in,
+            &self.system_program.to_account_info(),
+            &input_token_program,
+        )?;
+        
+        token_utils::read_token_account(&user_source_token_account)
+    }
+    
+    // 确保目标代币账户存在（不存在时以幂等方式创建关联代币账户），并检查是否属于正确的代币类型
+    fn ensure_user_destination_token_account(&self) -> Result<TokenAccount> {
+        let token_account = token_utils::ensure_user_token_account(
+            &self.user.to_account_info(),
+            &self.user_destination_token_account.to_account_info(),
+            &self.output_token_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.token_program_for(&self.output_token_mint),
+            &self.associated_token_program.to_account_info(),
+        )?;
+        require_keys_eq!(token_account.mint, self.output_token_mint.key(), DexError::InvalidTokenAccount);
+        Ok(token_account)
+    }
+    
+    // 源和目标代币账户交易前的余额(source, destination)，账户尚未创建时为None
+    fn token_balances_before(&self) -> Result<(Option<u64>, Option<u64>)> {
+        Ok((
+            token_utils::balance_before_trade(&self.user_source_token_account)?,
+            token_utils::balance_before_trade(&self.user_destination_token_account)?,
+        ))
+    }
+    
+    // 交易完成后解包WSOL账户中超出交易前余额的部分: 买入时退还未用完的WSOL，卖出时将所得WSOL转为SOL
+    fn unwrap_native_accounts(
+        &self,
+        (source_balance_before, destination_balance_before): (Option<u64>, Option<u64>),
+    ) -> Result<()> {
+        if self.is_native_mint(&self.input_token_mint) {
+            token_utils::unwrap_sol(
+                &self.user.to_account_info(),
+                &self.user_source_token_account.to_account_info(),
+                &self.input_token_mint.to_account_info(),
+                &self.system_program.to_account_info(),
+                &self.token_program_for(&self.input_token_mint),
+                &self.associated_token_program.to_account_info(),
+                source_balance_before,
+            )?;
+        }
+        
+        if self.is_native_mint(&self.output_token_mint) {
+            token_utils::unwrap_sol(
+                &self.user.to_account_info(),
+                &self.user_destination_token_account.to_account_info(),
+                &self.output_token_mint.to_account_info(),
+                &self.system_program.to_account_info(),
+                &self.token_program_for(&self.output_token_mint),
+                &self.associated_token_program.to_account_info(),
+                destination_balance_before,
+            )?;
+        }
+        
+        Ok(())
+    }
+    
+    // 源代币账户余额为0时关闭并回收租金
+    fn close_empty_user_source_token_account(&self) -> Result<()> {
+        token_utils::close_empty_token_account(
+            &self.user.to_account_info(),
+            &self.user_source_token_account.to_account_info(),
+            &self.token_program_for(&self.input_token_mint),
+        )?;
+        Ok(())
+    }
+    
+    fn is_native_mint(&self, mint: &InterfaceAccount<'info, Mint>) -> bool {
+        mint.key() == spl_token::native_mint::id()
+    }
+    
+    // swap_v2指令账户
+    fn swap_v2_accounts(&self, remaining_accounts: &[AccountInfo<'info>]) -> instructions::SwapV2Accounts {
+        instructions::SwapV2Accounts {
+            payer: self.user.key(),
+            amm_config: self.amm_config.key(),
+            pool_state: self.pool_state.key(),
+            input_token_account: self.user_source_token_account.key(),
+            output_token_account: self.user_destination_token_account.key(),
+            input_vault: self.input_vault.key(),
+            output_vault: self.output_vault.key(),
+            observation_state: self.observation_state.key(),
+            token_program: self.token_program.key(),
+            token_program_2022: self.token_program_2022.key(),
+            memo_program: self.memo_program.key(),
+            input_vault_mint: self.input_token_mint.key(),
+            output_vault_mint: self.output_token_mint.key(),
+            remaining_accounts: remaining_accounts.iter().map(|account| account.key()).collect(),
+        }
+    }
+    
+    // 执行Raydium CLMM的交易指令
+    fn invoke_swap(&self, swap_ix: &Instruction, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let mut account_infos = vec![
+            self.user.to_account_info(),
+            self.amm_config.to_account_info(),
+            self.pool_state.to_account_info(),
+            self.user_source_token_account.to_account_info(),
+            self.user_destination_token_account.to_account_info(),
+            self.input_vault.to_account_info(),
+            self.output_vault.to_account_info(),
+            self.observation_state.to_account_info(),
+            self.token_program.to_account_info(),
+            self.token_program_2022.to_account_info(),
+            self.memo_program.to_account_info(),
+            self.input_token_mint.to_account_info(),
+            self.output_token_mint.to_account_info(),
+        ];
+        account_infos.extend(remaining_accounts.iter().cloned());
+        account_infos.push(self.raydium_clmm_program.to_account_info());
+        
+        invoke(swap_ix, &account_infos)?;
+        Ok(())
+    }
+}
+
+// 检查代币位置所需的账户结构
+#[derive(Accounts)]
+pub struct CheckTokenLocation<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Raydium CLMM池子账户（可能不存在，在指令中手动检查）
+    pub pool_state: UncheckedAccount<'info>,
+    
+    // 系统程序
+    pub system_program: Program<'info, System>,
+}
+
+// 获取价格所需的账户结构
+// 交易方向上的TickArray通过剩余账户传入
+#[derive(Accounts)]
+pub struct GetPriceContext<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // 代币Mint地址（支持SPL Token和Token-2022）
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    // Raydium CLMM池子账户（在指令中手动反序列化并校验）
+    pub amm_config: UncheckedAccount<'info>,
+    pub pool_state: UncheckedAccount<'info>,
+    
+    // 系统程序
+    pub system_program: Program<'info, System>,
+}
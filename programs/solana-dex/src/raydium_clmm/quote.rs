@@ -0,0 +1,478 @@
+use anchor_lang::prelude::*;
+use crate::math::{self, to_u64};
+use crate::DexError;
+use super::state::{AmmConfig, InitializedTick, PoolState, TickArray};
+use super::tick_math::{self, MAX_SQRT_PRICE_X64, MAX_TICK, MIN_SQRT_PRICE_X64, MIN_TICK};
+
+// Raydium CLMM费率分母
+pub const CLMM_FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+// Q64.64定点数中的1
+const Q64: u128 = 1 << 64;
+
+// Raydium CLMM报价结果（不含Token-2022转账手续费）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ClmmQuote {
+    // 转入金库的输入代币数量（含交易手续费）
+    pub amount_in: u64,
+    // 从金库转出的输出代币数量
+    pub amount_out: u64,
+    // 交易手续费（输入代币）
+    pub fee_amount: u64,
+    // 交易后的平方根价格
+    pub sqrt_price_x64_after: u128,
+    // 穿过的已初始化tick数量
+    pub ticks_crossed: u32,
+    // 价格影响（以FEE_DENOMINATOR为分母的百万分比，不含手续费）
+    pub price_impact: u64,
+}
+
+// 报价所需的池子状态: 当前价格、流动性和已加载TickArray中的tick
+#[derive(Clone, Debug)]
+pub struct ClmmPool {
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub liquidity: u128,
+    pub trade_fee_rate: u32,
+    // 已加载的连续TickArray覆盖的tick范围[tick_lower_bound, tick_upper_bound)
+    pub tick_lower_bound: i32,
+    pub tick_upper_bound: i32,
+    // 范围内已初始化的tick，按tick升序排列
+    pub ticks: Vec<InitializedTick>,
+}
+
+impl ClmmPool {
+    // 由池子状态和TickArray构建，TickArray顺序不限，只使用与当前tick所在TickArray相连的部分
+    pub fn new(pool_state: &PoolState, amm_config: &AmmConfig, tick_arrays: &[TickArray]) -> Result<Self> {
+        let ticks_per_array = pool_state.ticks_per_array();
+        require!(ticks_per_array > 0, DexError::InvalidRaydiumClmmAccount);
+        
+        let mut start_indexes: Vec<i32> = tick_arrays.iter().map(|tick_array| tick_array.start_tick_index).collect();
+        start_indexes.sort_unstable();
+        start_indexes.dedup();
+        
+        // 从当前tick所在的TickArray向两侧扩展连续覆盖范围
+        let current_start = pool_state.tick_array_start_index(pool_state.tick_current);
+        require!(
+            start_indexes.binary_search(&current_start).is_ok(),
+            DexError::MissingRaydiumClmmTickArray
+        );
+        
+        let mut tick_lower_bound = current_start;
+        while start_indexes.binary_search(&(tick_lower_bound - ticks_per_array)).is_ok() {
+            tick_lower_bound -= ticks_per_array;
+        }
+        let mut tick_upper_bound = current_start + ticks_per_array;
+        while start_indexes.binary_search(&tick_upper_bound).is_ok() {
+            tick_upper_bound += ticks_per_array;
+        }
+        
+        let mut ticks: Vec<InitializedTick> = tick_arrays
+            .iter()
+            .filter(|tick_array| {
+                tick_array.start_tick_index >= tick_lower_bound && tick_array.start_tick_index < tick_upper_bound
+            })
+            .flat_map(|tick_array| tick_array.initialized_ticks.iter().copied())
+            .collect();
+        ticks.sort_unstable_by_key(|tick| tick.tick);
+        ticks.dedup_by_key(|tick| tick.tick);
+        
+        Ok(ClmmPool {
+            sqrt_price_x64: pool_state.sqrt_price_x64,
+            tick_current: pool_state.tick_current,
+            liquidity: pool_state.liquidity,
+            trade_fee_rate: amm_config.trade_fee_rate,
+            tick_lower_bound: tick_lower_bound.max(MIN_TICK),
+            tick_upper_bound: tick_upper_bound.min(MAX_TICK),
+            ticks,
+        })
+    }
+    
+    // 交易方向上的下一个已初始化tick: 价格下降时为不大于当前tick的最大tick，价格上升时为大于当前tick的最小tick
+    fn next_initialized_tick(&self, tick: i32, zero_for_one: bool) -> Option<InitializedTick> {
+        if zero_for_one {
+            self.ticks.iter().rev().find(|initialized| initialized.tick <= tick).copied()
+        } else {
+            self.ticks.iter().find(|initialized| initialized.tick > tick).copied()
+        }
+    }
+}
+
+// 单个价格区间内的交易结果
+struct SwapStep {
+    sqrt_price_next_x64: u128,
+    amount_in: u64,
+    amount_out: u64,
+    fee_amount: u64,
+}
+
+// 在当前价格与目标价格之间按恒定流动性计算一步交易（精确输入，手续费从输入中扣除）
+fn compute_swap_step(
+    sqrt_price_current_x64: u128,
+    sqrt_price_target_x64: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee_rate: u32,
+    zero_for_one: bool,
+) -> Result<SwapStep> {
+    let fee_rate = fee_rate as u128;
+    let denominator = CLMM_FEE_RATE_DENOMINATOR as u128;
+    require!(fee_rate < denominator, DexError::InvalidRaydiumClmmAccount);
+    
+    let amount_remaining_less_fee = math::mul_div_floor(amount_remaining as u128, denominator - fee_rate, denominator)?;
+    
+    // 到达目标价格所需的输入数量
+    let amount_in_to_target = delta_amount_in(sqrt_price_current_x64, sqrt_price_target_x64, liquidity, zero_for_one)?;
+    
+    let sqrt_price_next_x64 = if amount_remaining_less_fee >= amount_in_to_target {
+        sqrt_price_target_x64
+    } else {
+        tick_math::get_next_sqrt_price_from_input(
+            sqrt_price_current_x64,
+            liquidity,
+            to_u64(amount_remaining_less_fee)?,
+            zero_for_one,
+        )?
+    };
+    let reached_target = sqrt_price_next_x64 == sqrt_price_target_x64;
+    
+    let amount_in = to_u64(if reached_target {
+        amount_in_to_target
+    } else {
+        delta_amount_in(sqrt_price_current_x64, sqrt_price_next_x64, liquidity, zero_for_one)?
+    })?;
+    let amount_out = to_u64(if zero_for_one {
+        tick_math::get_delta_amount_1(sqrt_price_next_x64, sqrt_price_current_x64, liquidity, false)?
+    } else {
+        tick_math::get_delta_amount_0(sqrt_price_current_x64, sqrt_price_next_x64, liquidity, false)?
+    })?;
+    
+    // 未到达目标价格时剩余输入全部计为手续费，否则按费率向上取整
+    let fee_amount = if reached_target {
+        to_u64(math::mul_div_ceil(amount_in as u128, fee_rate, denominator - fee_rate)?)?
+    } else {
+        amount_remaining
+            .checked_sub(amount_in)
+            .ok_or(DexError::ArithmeticOverflow)?
+    };
+    
+    Ok(SwapStep {
+        sqrt_price_next_x64,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+// 价格从sqrt_price_a移动到sqrt_price_b所需的输入数量（向上取整）
+fn delta_amount_in(sqrt_price_a_x64: u128, sqrt_price_b_x64: u128, liquidity: u128, zero_for_one: bool) -> Result<u128> {
+    if zero_for_one {
+        tick_math::get_delta_amount_0(sqrt_price_a_x64, sqrt_price_b_x64, liquidity, true)
+    } else {
+        tick_math::get_delta_amount_1(sqrt_price_a_x64, sqrt_price_b_x64, liquidity, true)
+    }
+}
+
+// 报价: 精确输入，沿交易方向逐个穿过已初始化tick，直到输入用完
+// zero_for_one为true表示输入token_0、输出token_1
+pub fn swap_base_input(pool: &ClmmPool, amount_in: u64, zero_for_one: bool) -> Result<ClmmQuote> {
+    require!(amount_in > 0, DexError::InvalidArgument);
+    require!(
+        pool.sqrt_price_x64 > MIN_SQRT_PRICE_X64 && pool.sqrt_price_x64 < MAX_SQRT_PRICE_X64,
+        DexError::InvalidRaydiumClmmAccount
+    );
+    
+    let sqrt_price_limit_x64 = if zero_for_one {
+        MIN_SQRT_PRICE_X64 + 1
+    } else {
+        MAX_SQRT_PRICE_X64 - 1
+    };
+    
+    let mut amount_remaining = amount_in;
+    let mut amount_out: u64 = 0;
+    let mut fee_amount: u64 = 0;
+    let mut sqrt_price_x64 = pool.sqrt_price_x64;
+    let mut tick = pool.tick_current;
+    let mut liquidity = pool.liquidity;
+    let mut ticks_crossed: u32 = 0;
+    
+    while amount_remaining > 0 && sqrt_price_x64 != sqrt_price_limit_x64 {
+        // 已加载范围内没有更多已初始化tick时，以范围边界为目标（流动性不变）
+        let next_tick = pool.next_initialized_tick(tick, zero_for_one);
+        let target_tick = match next_tick {
+            Some(initialized) => initialized.tick,
+            None if zero_for_one => pool.tick_lower_bound,
+            None => pool.tick_upper_bound,
+        };
+        
+        let sqrt_price_target_x64 = tick_math::get_sqrt_price_at_tick(target_tick)?;
+        // 已越过已加载范围的边界时无法继续报价
+        require!(
+            if zero_for_one {
+                sqrt_price_target_x64 <= sqrt_price_x64
+            } else {
+                sqrt_price_target_x64 >= sqrt_price_x64
+            },
+            DexError::MissingRaydiumClmmTickArray
+        );
+        let sqrt_price_step_target_x64 = if zero_for_one {
+            sqrt_price_target_x64.max(sqrt_price_limit_x64)
+        } else {
+            sqrt_price_target_x64.min(sqrt_price_limit_x64)
+        };
+        
+        let step = compute_swap_step(
+            sqrt_price_x64,
+            sqrt_price_step_target_x64,
+            liquidity,
+            amount_remaining,
+            pool.trade_fee_rate,
+            zero_for_one,
+        )?;
+        
+        amount_remaining = amount_remaining
+            .checked_sub(step.amount_in)
+            .and_then(|amount| amount.checked_sub(step.fee_amount))
+            .ok_or(DexError::ArithmeticOverflow)?;
+        amount_out = amount_out.checked_add(step.amount_out).ok_or(DexError::ArithmeticOverflow)?;
+        fee_amount = fee_amount.checked_add(step.fee_amount).ok_or(DexError::ArithmeticOverflow)?;
+        sqrt_price_x64 = step.sqrt_price_next_x64;
+        
+        if sqrt_price_x64 != sqrt_price_target_x64 {
+            // 输入已用完或到达价格限制，价格停在区间内部
+            break;
+        }
+        
+        match next_tick {
+            Some(initialized) => {
+                // 穿过tick时更新流动性，价格下降时方向相反
+                let liquidity_net = if zero_for_one {
+                    initialized.liquidity_net.checked_neg().ok_or(DexError::ArithmeticOverflow)?
+                } else {
+                    initialized.liquidity_net
+                };
+                liquidity = add_liquidity_delta(liquidity, liquidity_net)?;
+                ticks_crossed += 1;
+            },
+            None => {
+                // 输入尚未用完但已到达已加载TickArray的边界
+                require!(amount_remaining == 0, DexError::MissingRaydiumClmmTickArray);
+            },
+        }
+        
+        tick = if zero_for_one { target_tick - 1 } else { target_tick };
+    }
+    
+    let amount_in = amount_in - amount_remaining;
+    require!(amount_out > 0, DexError::InsufficientLiquidity);
+    
+    // 价格影响 = 1 - 成交价格 / 现货价格，现货价格 = (sqrt_price / 2^64)^2
+    let amount_in_less_fees = (amount_in - fee_amount) as u128;
+    let spot_amount_out = if zero_for_one {
+        let amount = math::mul_div_floor(amount_in_less_fees, pool.sqrt_price_x64, Q64)?;
+        math::mul_div_floor(amount, pool.sqrt_price_x64, Q64)?
+    } else {
+        let amount = math::mul_div_floor(amount_in_less_fees, Q64, pool.sqrt_price_x64)?;
+        math::mul_div_floor(amount, Q64, pool.sqrt_price_x64)?
+    };
+    let price_impact = math::price_impact(amount_out as u128, spot_amount_out)?;
+    
+    Ok(ClmmQuote {
+        amount_in,
+        amount_out,
+        fee_amount,
+        sqrt_price_x64_after: sqrt_price_x64,
+        ticks_crossed,
+        price_impact,
+    })
+}
+
+// 流动性加上有符号的变化量
+fn add_liquidity_delta(liquidity: u128, delta: i128) -> Result<u128> {
+    let liquidity = if delta < 0 {
+        liquidity.checked_sub(delta.unsigned_abs())
+    } else {
+        liquidity.checked_add(delta as u128)
+    };
+    
+    liquidity.ok_or(DexError::ArithmeticOverflow.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // 期望值按Raydium CLMM程序swap_math::compute_swap_step和池子swap循环的整数运算独立计算（精确输入）
+    
+    // AmmConfig中常见的0.25%交易费率
+    const TRADE_FEE_RATE: u32 = 2500;
+    
+    // tick_spacing为10时每个TickArray覆盖600个tick
+    const TICK_SPACING: u16 = 10;
+    
+    // 当前价格位于tick -3内部，两个仓位[-500, 500)和[-100, 100)的流动性合计为1e12
+    const SQRT_PRICE_X64: u128 = 18_443_977_407_934_613_650;
+    const LIQUIDITY: u128 = 1_000_000_000_000;
+    
+    fn ticks() -> Vec<InitializedTick> {
+        [(-500, 700_000_000_000), (-100, 300_000_000_000), (100, -300_000_000_000), (500, -700_000_000_000)]
+            .into_iter()
+            .map(|(tick, liquidity_net)| InitializedTick { tick, liquidity_net })
+            .collect()
+    }
+    
+    // 已加载[-600, 0)和[0, 600)两个TickArray的池子
+    fn pool() -> ClmmPool {
+        ClmmPool {
+            sqrt_price_x64: SQRT_PRICE_X64,
+            tick_current: -3,
+            liquidity: LIQUIDITY,
+            trade_fee_rate: TRADE_FEE_RATE,
+            tick_lower_bound: -600,
+            tick_upper_bound: 600,
+            ticks: ticks(),
+        }
+    }
+    
+    fn pool_state(tick_current: i32) -> PoolState {
+        PoolState {
+            tick_spacing: TICK_SPACING,
+            tick_current,
+            sqrt_price_x64: tick_math::get_sqrt_price_at_tick(tick_current).unwrap(),
+            liquidity: LIQUIDITY,
+            ..Default::default()
+        }
+    }
+    
+    fn tick_array(start_tick_index: i32, ticks: &[(i32, i128)]) -> TickArray {
+        TickArray {
+            start_tick_index,
+            initialized_ticks: ticks
+                .iter()
+                .map(|&(tick, liquidity_net)| InitializedTick { tick, liquidity_net })
+                .collect(),
+            ..Default::default()
+        }
+    }
+    
+    fn assert_error<T: std::fmt::Debug>(result: Result<T>, error: DexError) {
+        assert_eq!(result.unwrap_err(), error.into());
+    }
+    
+    #[test]
+    fn swap_within_a_single_tick_range() {
+        let quote = swap_base_input(&pool(), 1_000_000, true).unwrap();
+        assert_eq!(quote.amount_in, 1_000_000);
+        assert_eq!(quote.amount_out, 997_199);
+        assert_eq!(quote.fee_amount, 2_500);
+        assert_eq!(quote.sqrt_price_x64_after, 18_443_959_012_844_830_786);
+        assert_eq!(quote.ticks_crossed, 0);
+        assert!(quote.price_impact > 0);
+    }
+    
+    #[test]
+    fn swap_crossing_an_initialized_tick_downwards() {
+        // 穿过tick -100后流动性降为7e11
+        let quote = swap_base_input(&pool(), 10_000_000_000, true).unwrap();
+        assert_eq!(quote.amount_in, 10_000_000_000);
+        assert_eq!(quote.amount_out, 9_862_633_346);
+        assert_eq!(quote.fee_amount, 25_000_001);
+        assert_eq!(quote.sqrt_price_x64_after, 18_222_314_845_763_021_490);
+        assert_eq!(quote.ticks_crossed, 1);
+        assert!(quote.sqrt_price_x64_after < tick_math::get_sqrt_price_at_tick(-100).unwrap());
+    }
+    
+    #[test]
+    fn swap_crossing_an_initialized_tick_upwards() {
+        let quote = swap_base_input(&pool(), 10_000_000_000, false).unwrap();
+        assert_eq!(quote.amount_in, 10_000_000_000);
+        assert_eq!(quote.amount_out, 9_869_764_401);
+        assert_eq!(quote.fee_amount, 25_000_001);
+        assert_eq!(quote.sqrt_price_x64_after, 18_666_032_059_192_877_433);
+        assert_eq!(quote.ticks_crossed, 1);
+        assert!(quote.sqrt_price_x64_after > tick_math::get_sqrt_price_at_tick(100).unwrap());
+    }
+    
+    #[test]
+    fn swap_past_the_loaded_tick_arrays_is_rejected() {
+        // 输入用完之前越过了已加载TickArray的边界
+        assert_error(swap_base_input(&pool(), 100_000_000_000_000_000, true), DexError::MissingRaydiumClmmTickArray);
+        assert_error(swap_base_input(&pool(), 100_000_000_000_000_000, false), DexError::MissingRaydiumClmmTickArray);
+        
+        // 当前价格已在已加载范围[0, 600)之外
+        let pool = ClmmPool {
+            tick_lower_bound: 0,
+            ticks: ticks().into_iter().filter(|initialized| initialized.tick >= 0).collect(),
+            ..pool()
+        };
+        assert_error(swap_base_input(&pool, 1_000_000, true), DexError::MissingRaydiumClmmTickArray);
+    }
+    
+    #[test]
+    fn swap_stops_at_the_max_price_limit() {
+        // 价格推到MAX_SQRT_PRICE_X64 - 1时停止，输入未用完且输出不足1个单位
+        let pool = ClmmPool {
+            sqrt_price_x64: tick_math::get_sqrt_price_at_tick(MAX_TICK - 1).unwrap(),
+            tick_current: MAX_TICK - 1,
+            liquidity: 70_000_000_000_000,
+            trade_fee_rate: TRADE_FEE_RATE,
+            tick_lower_bound: 443_400,
+            tick_upper_bound: MAX_TICK,
+            ticks: Vec::new(),
+        };
+        assert_error(swap_base_input(&pool, u64::MAX, false), DexError::InsufficientLiquidity);
+    }
+    
+    #[test]
+    fn swap_rejects_prices_at_the_tick_range_bounds() {
+        for sqrt_price_x64 in [MIN_SQRT_PRICE_X64, MAX_SQRT_PRICE_X64] {
+            let pool = ClmmPool {
+                sqrt_price_x64,
+                ..pool()
+            };
+            assert_error(swap_base_input(&pool, 1_000_000, true), DexError::InvalidRaydiumClmmAccount);
+        }
+        assert_error(swap_base_input(&pool(), 0, true), DexError::InvalidArgument);
+    }
+    
+    #[test]
+    fn pool_uses_tick_arrays_contiguous_with_the_current_one() {
+        let amm_config = AmmConfig {
+            trade_fee_rate: TRADE_FEE_RATE,
+            ..Default::default()
+        };
+        let tick_arrays = [
+            tick_array(0, &[(100, -300_000_000_000), (500, -700_000_000_000)]),
+            tick_array(-600, &[(-500, 700_000_000_000), (-100, 300_000_000_000)]),
+            // 与已加载范围不相连，不参与报价
+            tick_array(1200, &[(1500, 1)]),
+        ];
+        
+        let pool = ClmmPool::new(&pool_state(-3), &amm_config, &tick_arrays).unwrap();
+        assert_eq!(pool.tick_lower_bound, -600);
+        assert_eq!(pool.tick_upper_bound, 600);
+        assert_eq!(pool.ticks, ticks());
+        assert_eq!(pool.trade_fee_rate, TRADE_FEE_RATE);
+        
+        // 缺少当前tick所在的TickArray
+        assert_error(
+            ClmmPool::new(&pool_state(600), &amm_config, &tick_arrays),
+            DexError::MissingRaydiumClmmTickArray
+        );
+    }
+    
+    #[test]
+    fn pool_bounds_are_clamped_to_the_tick_range() {
+        let amm_config = AmmConfig::default();
+        
+        let state = pool_state(MIN_TICK);
+        let start = state.tick_array_start_index(MIN_TICK);
+        let pool = ClmmPool::new(&state, &amm_config, &[tick_array(start, &[])]).unwrap();
+        assert_eq!(pool.tick_lower_bound, MIN_TICK);
+        
+        let state = pool_state(MAX_TICK);
+        let start = state.tick_array_start_index(MAX_TICK);
+        let pool = ClmmPool::new(&state, &amm_config, &[tick_array(start, &[])]).unwrap();
+        assert_eq!(pool.tick_upper_bound, MAX_TICK);
+    }
+}
@@ -0,0 +1,218 @@
+use anchor_lang::prelude::*;
+use solana_program::pubkey::Pubkey;
+use crate::codec::strip_discriminator;
+use crate::DexError;
+use super::get_raydium_clmm_program_id;
+
+// Raydium CLMM账户discriminator（Anchor规则: sha256("account:<账户名>")的前8字节）
+pub const POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+pub const AMM_CONFIG_DISCRIMINATOR: [u8; 8] = [218, 244, 33, 104, 203, 203, 43, 111];
+pub const TICK_ARRAY_STATE_DISCRIMINATOR: [u8; 8] = [192, 155, 85, 205, 49, 249, 129, 42];
+pub const TICK_ARRAY_BITMAP_EXTENSION_DISCRIMINATOR: [u8; 8] = [60, 150, 36, 219, 97, 128, 139, 153];
+
+// 每个TickArray包含的tick数量
+pub const TICK_ARRAY_SIZE: i32 = 60;
+
+// 去除discriminator后TickArray中ticks的起始偏移（pool_id + start_tick_index）和每个TickState的大小（紧凑布局）
+const TICK_ARRAY_TICKS_OFFSET: usize = 32 + 4;
+const TICK_STATE_LEN: usize = 168;
+
+// 池子奖励信息（仅用于按链上布局跳过）
+#[derive(AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct RewardInfo {
+    pub reward_state: u8,
+    pub open_time: u64,
+    pub end_time: u64,
+    pub last_update_time: u64,
+    pub emissions_per_second_x64: u128,
+    pub reward_total_emissioned: u64,
+    pub reward_claimed: u64,
+    pub token_mint: Pubkey,
+    pub token_vault: Pubkey,
+    pub authority: Pubkey,
+    pub reward_growth_global_x64: u128,
+}
+
+// Raydium CLMM的PoolState账户（字段顺序与链上紧凑布局一致，只读取交易所需的前缀部分）
+#[derive(AnchorDeserialize, Clone, Debug, Default)]
+pub struct PoolState {
+    pub bump: u8,
+    pub amm_config: Pubkey,
+    pub owner: Pubkey,
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    pub observation_key: Pubkey,
+    pub mint_decimals_0: u8,
+    pub mint_decimals_1: u8,
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub padding3: u16,
+    pub padding4: u16,
+    pub fee_growth_global_0_x64: u128,
+    pub fee_growth_global_1_x64: u128,
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+    pub swap_in_amount_token_0: u128,
+    pub swap_out_amount_token_1: u128,
+    pub swap_in_amount_token_1: u128,
+    pub swap_out_amount_token_0: u128,
+    pub status: u8,
+    pub padding: [u8; 7],
+    pub reward_infos: [RewardInfo; 3],
+    pub tick_array_bitmap: [u64; 16],
+    pub total_fees_token_0: u64,
+    pub total_fees_claimed_token_0: u64,
+    pub total_fees_token_1: u64,
+    pub total_fees_claimed_token_1: u64,
+    pub fund_fees_token_0: u64,
+    pub fund_fees_token_1: u64,
+    pub open_time: u64,
+}
+
+impl PoolState {
+    // status中禁用交易的标志位
+    pub const STATUS_SWAP_DISABLED: u8 = 1 << 4;
+    
+    // 从账户加载，检查所有者和discriminator
+    pub fn try_from_account_info(account_info: &AccountInfo) -> Result<Self> {
+        check_owner(account_info)?;
+        let data = account_info.try_borrow_data()?;
+        let mut data = strip_discriminator(&data, &POOL_STATE_DISCRIMINATOR, DexError::InvalidRaydiumClmmAccount)?;
+        PoolState::deserialize(&mut data).map_err(|_| DexError::InvalidRaydiumClmmAccount.into())
+    }
+    
+    // 检查池子当前是否允许交易
+    pub fn swap_enabled(&self, now: i64) -> bool {
+        self.status & Self::STATUS_SWAP_DISABLED == 0 && now >= 0 && now as u64 > self.open_time
+    }
+    
+    // 每个TickArray覆盖的tick范围
+    pub fn ticks_per_array(&self) -> i32 {
+        self.tick_spacing as i32 * TICK_ARRAY_SIZE
+    }
+    
+    // 包含指定tick的TickArray起始索引
+    pub fn tick_array_start_index(&self, tick: i32) -> i32 {
+        let ticks_per_array = self.ticks_per_array();
+        tick.div_euclid(ticks_per_array) * ticks_per_array
+    }
+}
+
+// Raydium CLMM的AmmConfig账户（费率以1_000_000为分母）
+#[derive(AnchorDeserialize, Clone, Debug, Default)]
+pub struct AmmConfig {
+    pub bump: u8,
+    pub index: u16,
+    pub owner: Pubkey,
+    pub protocol_fee_rate: u32,
+    pub trade_fee_rate: u32,
+    pub tick_spacing: u16,
+    pub fund_fee_rate: u32,
+}
+
+impl AmmConfig {
+    // 从账户加载，检查所有者和discriminator
+    pub fn try_from_account_info(account_info: &AccountInfo) -> Result<Self> {
+        check_owner(account_info)?;
+        let data = account_info.try_borrow_data()?;
+        let mut data = strip_discriminator(&data, &AMM_CONFIG_DISCRIMINATOR, DexError::InvalidRaydiumClmmAccount)?;
+        AmmConfig::deserialize(&mut data).map_err(|_| DexError::InvalidRaydiumClmmAccount.into())
+    }
+}
+
+// TickArray中已初始化的tick
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InitializedTick {
+    pub tick: i32,
+    // 从左向右穿过该tick时流动性的变化
+    pub liquidity_net: i128,
+}
+
+// Raydium CLMM的TickArrayState账户（只读取报价所需的字段）
+#[derive(Clone, Debug, Default)]
+pub struct TickArray {
+    pub pool_id: Pubkey,
+    pub start_tick_index: i32,
+    pub initialized_ticks: Vec<InitializedTick>,
+}
+
+impl TickArray {
+    // 从账户加载，检查所有者和discriminator
+    pub fn try_from_account_info(account_info: &AccountInfo) -> Result<Self> {
+        check_owner(account_info)?;
+        let data = account_info.try_borrow_data()?;
+        let data = strip_discriminator(&data, &TICK_ARRAY_STATE_DISCRIMINATOR, DexError::InvalidRaydiumClmmAccount)?;
+        
+        let ticks_end = TICK_ARRAY_TICKS_OFFSET + TICK_ARRAY_SIZE as usize * TICK_STATE_LEN;
+        require!(data.len() >= ticks_end, DexError::InvalidRaydiumClmmAccount);
+        
+        let pool_id = Pubkey::try_from(&data[..32]).map_err(|_| DexError::InvalidRaydiumClmmAccount)?;
+        let start_tick_index = read_i32(data, 32);
+        
+        // TickState: tick(i32) + liquidity_net(i128) + liquidity_gross(u128) + ...
+        let initialized_ticks = (0..TICK_ARRAY_SIZE as usize)
+            .map(|i| TICK_ARRAY_TICKS_OFFSET + i * TICK_STATE_LEN)
+            .filter(|offset| read_u128(data, offset + 20) > 0)
+            .map(|offset| InitializedTick {
+                tick: read_i32(data, offset),
+                liquidity_net: read_i128(data, offset + 4),
+            })
+            .collect();
+        
+        Ok(TickArray {
+            pool_id,
+            start_tick_index,
+            initialized_ticks,
+        })
+    }
+    
+    // 检查账户是否为TickArrayBitmapExtension（swap_v2剩余账户中可选的第一个账户）
+    pub fn is_bitmap_extension(account_info: &AccountInfo) -> bool {
+        account_info
+            .try_borrow_data()
+            .map(|data| data.len() >= 8 && data[..8] == TICK_ARRAY_BITMAP_EXTENSION_DISCRIMINATOR[..])
+            .unwrap_or(false)
+    }
+}
+
+// 检查账户所有者为Raydium CLMM程序
+fn check_owner(account_info: &AccountInfo) -> Result<()> {
+    require_keys_eq!(
+        *account_info.owner,
+        get_raydium_clmm_program_id(),
+        DexError::InvalidRaydiumClmmAccount
+    );
+    Ok(())
+}
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u128(data: &[u8], offset: usize) -> u128 {
+    u128::from_le_bytes(data[offset..offset + 16].try_into().unwrap())
+}
+
+fn read_i128(data: &[u8], offset: usize) -> i128 {
+    i128::from_le_bytes(data[offset..offset + 16].try_into().unwrap())
+}
+
+// 计算TickArray PDA: ["tick_array", pool, start_tick_index(大端)]
+pub fn get_tick_array_pda(pool: &Pubkey, start_tick_index: i32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"tick_array", pool.as_ref(), &start_tick_index.to_be_bytes()],
+        &get_raydium_clmm_program_id(),
+    )
+}
+
+// 计算TickArrayBitmapExtension PDA: ["pool_tick_array_bitmap_extension", pool]
+pub fn get_tick_array_bitmap_extension_pda(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"pool_tick_array_bitmap_extension", pool.as_ref()],
+        &get_raydium_clmm_program_id(),
+    )
+}
@@ -0,0 +1,217 @@
+use anchor_lang::prelude::*;
+use crate::math::{ceil_div_u512, u512_to_u128, U512};
+use crate::DexError;
+
+// tick范围与对应的Q64.64平方根价格范围
+pub const MIN_TICK: i32 = -443636;
+pub const MAX_TICK: i32 = 443636;
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673521066979257578248091;
+
+// Q64.64定点数的小数位数
+const RESOLUTION: usize = 64;
+
+// sqrt(1.0001)^(-2^i)的Q64.64表示，按tick绝对值的二进制位逐位相乘
+const TICK_RATIOS: [(u32, u128); 18] = [
+    (0x2, 0xfff97272373d4000),
+    (0x4, 0xfff2e50f5f657000),
+    (0x8, 0xffe5caca7e10f000),
+    (0x10, 0xffcb9843d60f7000),
+    (0x20, 0xff973b41fa98e800),
+    (0x40, 0xff2ea16466c9b000),
+    (0x80, 0xfe5dee046a9a3800),
+    (0x100, 0xfcbe86c7900bb000),
+    (0x200, 0xf987a7253ac65800),
+    (0x400, 0xf3392b0822bb6000),
+    (0x800, 0xe7159475a2caf000),
+    (0x1000, 0xd097f3bdfd2f2000),
+    (0x2000, 0xa9f746462d9f8000),
+    (0x4000, 0x70d869a156f31c00),
+    (0x8000, 0x31be135f97ed3200),
+    (0x10000, 0x9aa508b5b85a500),
+    (0x20000, 0x5d6af8dedc582c),
+    (0x40000, 0x2216e584f5fa),
+];
+
+// 计算tick对应的Q64.64平方根价格: sqrt(1.0001^tick) * 2^64
+pub fn get_sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    require!((MIN_TICK..=MAX_TICK).contains(&tick), DexError::InvalidArgument);
+    
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = if abs_tick & 0x1 != 0 {
+        U512::from(0xfffcb933bd6fb800u128)
+    } else {
+        U512::one() << RESOLUTION
+    };
+    
+    for (bit, tick_ratio) in TICK_RATIOS {
+        if abs_tick & bit != 0 {
+            ratio = (ratio * U512::from(tick_ratio)) >> RESOLUTION;
+        }
+    }
+    
+    // 正tick取倒数，与Raydium一致使用u128::MAX / ratio
+    if tick > 0 {
+        ratio = U512::from(u128::MAX) / ratio;
+    }
+    
+    u512_to_u128(ratio)
+}
+
+// 两个价格之间token_0的数量: L * (sqrt_b - sqrt_a) * 2^64 / (sqrt_a * sqrt_b)
+pub fn get_delta_amount_0(sqrt_price_a_x64: u128, sqrt_price_b_x64: u128, liquidity: u128, round_up: bool) -> Result<u128> {
+    let (sqrt_price_a_x64, sqrt_price_b_x64) = sort(sqrt_price_a_x64, sqrt_price_b_x64);
+    require!(sqrt_price_a_x64 > 0, DexError::ArithmeticOverflow);
+    
+    let numerator_1 = U512::from(liquidity) << RESOLUTION;
+    let numerator_2 = U512::from(sqrt_price_b_x64 - sqrt_price_a_x64);
+    
+    let amount = if round_up {
+        ceil_div_u512(ceil_div_u512(numerator_1 * numerator_2, U512::from(sqrt_price_b_x64))?, U512::from(sqrt_price_a_x64))?
+    } else {
+        numerator_1 * numerator_2 / U512::from(sqrt_price_b_x64) / U512::from(sqrt_price_a_x64)
+    };
+    
+    u512_to_u128(amount)
+}
+
+// 两个价格之间token_1的数量: L * (sqrt_b - sqrt_a) / 2^64
+pub fn get_delta_amount_1(sqrt_price_a_x64: u128, sqrt_price_b_x64: u128, liquidity: u128, round_up: bool) -> Result<u128> {
+    let (sqrt_price_a_x64, sqrt_price_b_x64) = sort(sqrt_price_a_x64, sqrt_price_b_x64);
+    
+    let product = U512::from(liquidity) * U512::from(sqrt_price_b_x64 - sqrt_price_a_x64);
+    let amount = if round_up {
+        ceil_div_u512(product, U512::one() << RESOLUTION)?
+    } else {
+        product >> RESOLUTION
+    };
+    
+    u512_to_u128(amount)
+}
+
+// 加入输入数量后的下一个平方根价格（输入为token_0时价格下降，输入为token_1时价格上升）
+pub fn get_next_sqrt_price_from_input(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount_in: u64,
+    zero_for_one: bool,
+) -> Result<u128> {
+    require!(sqrt_price_x64 > 0 && liquidity > 0, DexError::InsufficientLiquidity);
+    if amount_in == 0 {
+        return Ok(sqrt_price_x64);
+    }
+    
+    if zero_for_one {
+        // 向上取整: L * 2^64 * sqrt_p / (L * 2^64 + amount * sqrt_p)
+        let numerator_1 = U512::from(liquidity) << RESOLUTION;
+        let denominator = numerator_1 + U512::from(amount_in) * U512::from(sqrt_price_x64);
+        u512_to_u128(ceil_div_u512(numerator_1 * U512::from(sqrt_price_x64), denominator)?)
+    } else {
+        // 向下取整: sqrt_p + amount * 2^64 / L
+        let quotient = u512_to_u128((U512::from(amount_in) << RESOLUTION) / U512::from(liquidity))?;
+        sqrt_price_x64
+            .checked_add(quotient)
+            .ok_or(DexError::ArithmeticOverflow.into())
+    }
+}
+
+fn sort(a: u128, b: u128) -> (u128, u128) {
+    if a > b {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // 期望值按Raydium CLMM程序libraries/tick_math和sqrt_price_math模块的整数运算独立计算，
+    // 与sqrt(1.0001^tick) * 2^64的高精度值相对误差均小于1e-10
+    const SQRT_PRICES_AT_TICK: [(i32, u128); 14] = [
+        (0, 18446744073709551616),
+        (1, 18447666387855957090),
+        (-1, 18445821805675395072),
+        (10, 18455969290605287889),
+        (-10, 18437523468038803493),
+        (100, 18539204128674375874),
+        (-100, 18354745142194513203),
+        (1000, 19392480388906522465),
+        (10000, 30412779051186690180),
+        (-10000, 11188795550325113405),
+        (100000, 2737055259402209284734),
+        (-100000, 124324258983086206),
+        (200000, 406113483392345977776134),
+        (-200000, 837899702512935),
+    ];
+    
+    #[test]
+    fn sqrt_price_at_tick_matches_reference() {
+        for (tick, sqrt_price_x64) in SQRT_PRICES_AT_TICK {
+            assert_eq!(get_sqrt_price_at_tick(tick).unwrap(), sqrt_price_x64, "tick {}", tick);
+        }
+    }
+    
+    #[test]
+    fn sqrt_price_at_tick_bounds() {
+        assert_eq!(get_sqrt_price_at_tick(MIN_TICK).unwrap(), MIN_SQRT_PRICE_X64);
+        assert_eq!(get_sqrt_price_at_tick(MAX_TICK).unwrap(), MAX_SQRT_PRICE_X64);
+        assert!(get_sqrt_price_at_tick(MIN_TICK - 1).is_err());
+        assert!(get_sqrt_price_at_tick(MAX_TICK + 1).is_err());
+        assert!(get_sqrt_price_at_tick(i32::MIN).is_err());
+    }
+    
+    #[test]
+    fn sqrt_price_at_tick_is_strictly_increasing() {
+        let ticks = [MIN_TICK, MIN_TICK + 1, -200000, -1, 0, 1, 200000, MAX_TICK - 1, MAX_TICK];
+        for pair in ticks.windows(2) {
+            assert!(get_sqrt_price_at_tick(pair[0]).unwrap() < get_sqrt_price_at_tick(pair[1]).unwrap());
+        }
+    }
+    
+    #[test]
+    fn delta_amounts_round_in_the_requested_direction() {
+        let sqrt_price_lower_x64 = get_sqrt_price_at_tick(-100).unwrap();
+        let sqrt_price_upper_x64 = get_sqrt_price_at_tick(100).unwrap();
+        let liquidity = 1_000_000_000_000;
+        
+        assert_eq!(get_delta_amount_0(sqrt_price_lower_x64, sqrt_price_upper_x64, liquidity, false).unwrap(), 9_999_541_693);
+        assert_eq!(get_delta_amount_0(sqrt_price_lower_x64, sqrt_price_upper_x64, liquidity, true).unwrap(), 9_999_541_694);
+        assert_eq!(get_delta_amount_1(sqrt_price_lower_x64, sqrt_price_upper_x64, liquidity, false).unwrap(), 9_999_541_693);
+        assert_eq!(get_delta_amount_1(sqrt_price_lower_x64, sqrt_price_upper_x64, liquidity, true).unwrap(), 9_999_541_694);
+        
+        // 参数顺序不影响结果
+        assert_eq!(
+            get_delta_amount_0(sqrt_price_upper_x64, sqrt_price_lower_x64, liquidity, true).unwrap(),
+            get_delta_amount_0(sqrt_price_lower_x64, sqrt_price_upper_x64, liquidity, true).unwrap()
+        );
+        assert!(get_delta_amount_0(0, sqrt_price_upper_x64, liquidity, false).is_err());
+    }
+    
+    #[test]
+    fn delta_amounts_span_the_full_tick_range() {
+        // 最大流动性在整个价格范围上的token_1数量超出u128
+        assert!(get_delta_amount_1(MIN_SQRT_PRICE_X64, MAX_SQRT_PRICE_X64, u128::MAX, true).is_err());
+        assert!(get_delta_amount_0(MIN_SQRT_PRICE_X64, MAX_SQRT_PRICE_X64, 1_000_000, false).unwrap() > 0);
+    }
+    
+    #[test]
+    fn next_sqrt_price_from_input_matches_reference() {
+        let sqrt_price_x64 = 18_443_977_407_934_613_650;
+        let liquidity = 1_000_000_000_000;
+        
+        // 输入token_0价格下降（向上取整），输入token_1价格上升（向下取整）
+        assert_eq!(
+            get_next_sqrt_price_from_input(sqrt_price_x64, liquidity, 1_000_000, true).unwrap(),
+            18_443_958_966_741_894_970
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_input(sqrt_price_x64, liquidity, 1_000_000, false).unwrap(),
+            18_443_995_854_678_687_359
+        );
+        assert_eq!(get_next_sqrt_price_from_input(sqrt_price_x64, liquidity, 0, true).unwrap(), sqrt_price_x64);
+        assert!(get_next_sqrt_price_from_input(sqrt_price_x64, 0, 1, true).is_err());
+        assert!(get_next_sqrt_price_from_input(MAX_SQRT_PRICE_X64, 1, u64::MAX, false).is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use crate::codec::encode_instruction_data;
+
+// Raydium LaunchLab指令discriminator（Anchor规则: sha256("global:<指令名>")的前8字节）
+pub const BUY_EXACT_IN_DISCRIMINATOR: [u8; 8] = [250, 234, 13, 123, 213, 156, 19, 236];
+pub const SELL_EXACT_IN_DISCRIMINATOR: [u8; 8] = [149, 39, 222, 155, 211, 124, 152, 26];
+
+// buy_exact_in指令参数（精确quote输入）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct BuyExactInArgs {
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+    // 分成费率（不使用分成时为0）
+    pub share_fee_rate: u64,
+}
+
+// sell_exact_in指令参数（精确代币输入）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SellExactInArgs {
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+    // 分成费率（不使用分成时为0）
+    pub share_fee_rate: u64,
+}
+
+// buy_exact_in/sell_exact_in指令账户（两者顺序相同，与Raydium LaunchLab IDL一致）
+pub struct TradeAccounts {
+    pub payer: Pubkey,
+    pub authority: Pubkey,
+    pub global_config: Pubkey,
+    pub platform_config: Pubkey,
+    pub pool_state: Pubkey,
+    pub user_base_token: Pubkey,
+    pub user_quote_token: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub base_token_mint: Pubkey,
+    pub quote_token_mint: Pubkey,
+    pub base_token_program: Pubkey,
+    pub quote_token_program: Pubkey,
+    pub event_authority: Pubkey,
+    pub program: Pubkey,
+}
+
+impl TradeAccounts {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.payer, true),
+            AccountMeta::new_readonly(self.authority, false),
+            AccountMeta::new_readonly(self.global_config, false),
+            AccountMeta::new_readonly(self.platform_config, false),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.user_base_token, false),
+            AccountMeta::new(self.user_quote_token, false),
+            AccountMeta::new(self.base_vault, false),
+            AccountMeta::new(self.quote_vault, false),
+            AccountMeta::new_readonly(self.base_token_mint, false),
+            AccountMeta::new_readonly(self.quote_token_mint, false),
+            AccountMeta::new_readonly(self.base_token_program, false),
+            AccountMeta::new_readonly(self.quote_token_program, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new_readonly(self.program, false),
+        ]
+    }
+}
+
+// 编码buy_exact_in指令数据
+pub fn encode_buy_exact_in_data(args: &BuyExactInArgs) -> Vec<u8> {
+    encode_instruction_data(&BUY_EXACT_IN_DISCRIMINATOR, args)
+}
+
+// 编码sell_exact_in指令数据
+pub fn encode_sell_exact_in_data(args: &SellExactInArgs) -> Vec<u8> {
+    encode_instruction_data(&SELL_EXACT_IN_DISCRIMINATOR, args)
+}
+
+// 构建buy_exact_in指令
+pub fn buy_exact_in(program_id: Pubkey, accounts: &TradeAccounts, args: &BuyExactInArgs) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data: encode_buy_exact_in_data(args),
+    }
+}
+
+// 构建sell_exact_in指令
+pub fn sell_exact_in(program_id: Pubkey, accounts: &TradeAccounts, args: &SellExactInArgs) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data: encode_sell_exact_in_data(args),
+    }
+}
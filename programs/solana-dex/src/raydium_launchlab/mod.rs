@@ -0,0 +1,595 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token};
+use anchor_spl::token_interface::{self, Mint, TokenInterface};
+use anchor_spl::associated_token::AssociatedToken;
+use solana_program::{
+    instruction::Instruction,
+    program::invoke,
+    pubkey::Pubkey,
+};
+use std::str::FromStr;
+use crate::fill::{BalanceSnapshot, Fill, FillLimits, FillPair};
+use crate::math::Price;
+use crate::router::RouteQuoter;
+use crate::{token_utils, DexError, DexType, MAX_PRICE_IMPACT};
+
+pub mod instructions;
+pub mod quote;
+pub mod state;
+
+pub use state::{GlobalConfig, PlatformConfig, PoolState};
+
+// Raydium LaunchLab（绑定曲线发行平台）程序ID
+pub const RAYDIUM_LAUNCHLAB_PROGRAM_ID: &str = "LanMV9sAd7wArD4vJFi2qDdfnVhFxYSUg6eADduJ3uj";
+
+// 获取Raydium LaunchLab程序ID
+pub fn get_raydium_launchlab_program_id() -> Pubkey {
+    Pubkey::from_str(RAYDIUM_LAUNCHLAB_PROGRAM_ID).unwrap()
+}
+
+// 在Raydium LaunchLab上购买代币（buy_exact_in，精确SOL输入）
+// 绑定曲线剩余代币不足时只买入剩余部分，募集完成后代币迁移到Raydium CPMM（或AMM v4）继续交易
+pub fn buy_token(
+    ctx: Context<TradeTokenLaunchLab>,
+    amount_in: u64,  // 输入的SOL数量（包含手续费）
+    min_amount_out: u64,  // 最小获得的代币数量（滑点控制）
+) -> Result<Fill> {
+    msg!("在Raydium LaunchLab上购买代币: {} SOL, 最小获得代币数量: {}", amount_in, min_amount_out);
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    // 检查参数
+    require!(amount_in > 0, DexError::InvalidArgument);
+    require!(min_amount_out > 0, DexError::InvalidArgument);
+    
+    let (fill, completes_curve) = ctx.accounts.execute_swap(true, amount_in, min_amount_out)?;
+    
+    if completes_curve {
+        msg!("绑定曲线募集完成，代币迁移后将在Raydium上交易");
+    }
+    
+    msg!("交易完成，获得代币");
+    Ok(fill)
+}
+
+// 在Raydium LaunchLab上卖出代币（sell_exact_in，精确代币输入）
+pub fn sell_token(
+    ctx: Context<TradeTokenLaunchLab>,
+    amount_in: u64,  // 输入的代币数量
+    min_amount_out: u64,  // 最小获得的SOL数量（滑点控制）
+    close_token_account: bool,  // 全部卖出后是否关闭用户代币账户以回收租金
+) -> Result<Fill> {
+    msg!("在Raydium LaunchLab上卖出代币: {} 代币, 最小获得SOL数量: {}", amount_in, min_amount_out);
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    // 检查参数
+    require!(amount_in > 0, DexError::InvalidArgument);
+    require!(min_amount_out > 0, DexError::InvalidArgument);
+    
+    let (fill, _) = ctx.accounts.execute_swap(false, amount_in, min_amount_out)?;
+    
+    // 全部卖出后可选地关闭用户代币账户
+    if close_token_account {
+        token_utils::close_empty_token_account(
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.base_token_program.to_account_info(),
+        )?;
+    }
+    
+    msg!("交易完成，获得SOL");
+    Ok(fill)
+}
+
+// 检查代币是否在Raydium LaunchLab绑定曲线上（以WSOL募集且仍在募集中）
+// 募集完成的代币返回false，由路由继续在迁移目标（Raydium CPMM或AMM v4）上查找
+pub fn is_token_on_launchlab(
+    ctx: Context<CheckTokenLocation>,
+    token_mint: Pubkey,
+) -> Result<bool> {
+    // 记录查询信息
+    msg!("检查代币是否在Raydium LaunchLab上: {}", token_mint);
+    
+    // 传入的池子账户必须是该代币WSOL池子的PDA，否则无法据此判断代币位置
+    let (pool_pda, _) = state::get_pool_pda(&token_mint, &spl_token::native_mint::id());
+    let pool_account_info = ctx.accounts.pool_state.to_account_info();
+    if pool_account_info.key() != pool_pda {
+        msg!("LaunchLab池子账户与PDA不匹配: {}", pool_account_info.key());
+        return Ok(false);
+    }
+    
+    // 账户不存在说明代币从未在LaunchLab上发行
+    if pool_account_info.data_is_empty() || *pool_account_info.owner != get_raydium_launchlab_program_id() {
+        msg!("LaunchLab池子账户不存在，代币不在Raydium LaunchLab上");
+        return Ok(false);
+    }
+    
+    let pool_state = match PoolState::try_from_account_info(&pool_account_info) {
+        Ok(pool_state) => pool_state,
+        Err(_) => {
+            msg!("LaunchLab池子账户数据无效，代币不在Raydium LaunchLab上");
+            return Ok(false);
+        }
+    };
+    
+    // 募集完成后交易移交给迁移目标
+    if !pool_state.is_trading() {
+        match (pool_state.status, pool_state.migrate_type) {
+            (state::POOL_STATUS_MIGRATE, _) => msg!("LaunchLab募集已完成，等待迁移"),
+            (_, state::MIGRATE_TYPE_CPMM) => msg!("代币已从LaunchLab迁移到Raydium CPMM"),
+            _ => msg!("代币已从LaunchLab迁移到Raydium AMM v4"),
+        }
+        return Ok(false);
+    }
+    
+    // 检查绑定曲线是否有足够的流动性
+    let (base_reserve, quote_reserve) = pool_state.curve_reserves()?;
+    if base_reserve == 0 || quote_reserve == 0 || pool_state.remaining_base() == 0 {
+        msg!("LaunchLab绑定曲线没有足够的流动性，代币不可交易");
+        return Ok(false);
+    }
+    
+    msg!("代币在Raydium LaunchLab上可用，代币储备: {}, SOL储备: {}", base_reserve, quote_reserve);
+    Ok(true)
+}
+
+// 获取Raydium LaunchLab上的报价（amount_in为扣除代币转账手续费前的数量）
+pub fn get_quote(
+    ctx: Context<GetPriceContext>,
+    amount_in: u64,
+    is_buy: bool,  // true表示买入（输入SOL），false表示卖出（输入代币）
+) -> Result<quote::LaunchLabQuote> {
+    // 检查输入金额是否大于0
+    require!(amount_in > 0, DexError::InvalidArgument);
+    
+    // 记录查询信息
+    msg!("查询Raydium LaunchLab上的报价: 输入金额 {}, 操作类型: {}",
+        amount_in,
+        if is_buy { "买入" } else { "卖出" }
+    );
+    msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
+    
+    let (pool_state, global_config, platform_config) = load_pool(
+        &ctx.accounts.pool_state,
+        &ctx.accounts.global_config,
+        &ctx.accounts.platform_config,
+    )?;
+    let fees = quote::LaunchLabFees::from_configs(&global_config, &platform_config);
+    
+    let launchlab_quote = if is_buy {
+        quote::quote_buy_exact_in(&pool_state, &global_config, &fees, amount_in)?
+    } else {
+        // 卖出时代币转入金库先扣除转账手续费
+        let actual_amount_in = token_utils::amount_after_transfer_fee(
+            &ctx.accounts.token_mint.to_account_info(),
+            amount_in,
+        )?;
+        quote::quote_sell_exact_in(&pool_state, &global_config, &fees, actual_amount_in)?
+    };
+    
+    msg!("Raydium LaunchLab报价: 实际输入 {}, 毛输出 {}, 手续费 {}, 净输出 {}, 价格影响: {}%",
+        launchlab_quote.amount_in,
+        launchlab_quote.gross_amount_out,
+        launchlab_quote.fee_amount,
+        launchlab_quote.net_amount_out,
+        launchlab_quote.price_impact as f64 / 10_000.0
+    );
+    
+    Ok(launchlab_quote)
+}
+
+// 获取Raydium LaunchLab上的价格（每单位输入可获得的净输出，按两侧代币的小数位数换算）
+pub fn get_price(
+    ctx: Context<GetPriceContext>,
+    amount_in: u64,
+    is_buy: bool,  // true表示买入，false表示卖出
+) -> Result<Price> {
+    let token_mint_info = ctx.accounts.token_mint.to_account_info();
+    let pool_state = PoolState::try_from_account_info(&ctx.accounts.pool_state)?;
+    let launchlab_quote = get_quote(ctx, amount_in, is_buy)?;
+    
+    // 计算价格（买入时输入为SOL，代币转给用户再扣除转账手续费；卖出时输入为代币）
+    let price = if is_buy {
+        let amount_out = token_utils::amount_after_transfer_fee(&token_mint_info, launchlab_quote.net_amount_out)?;
+        Price::from_amounts(amount_in, pool_state.quote_decimals, amount_out, pool_state.base_decimals)?
+    } else {
+        Price::from_amounts(amount_in, pool_state.base_decimals, launchlab_quote.net_amount_out, pool_state.quote_decimals)?
+    };
+    
+    msg!("Raydium LaunchLab上的最终价格: {}", price.as_f64());
+    Ok(price)
+}
+
+// 路由报价器: 读取一次绑定曲线，按输入数量计算用户实际到账的净输出（含Token-2022转账手续费）
+pub fn route_quoter<'info>(accounts: &GetPriceContext<'info>, is_buy: bool) -> Result<RouteQuoter<'info>> {
+    let (pool_state, global_config, platform_config) = load_pool(
+        &accounts.pool_state,
+        &accounts.global_config,
+        &accounts.platform_config,
+    )?;
+    let fees = quote::LaunchLabFees::from_configs(&global_config, &platform_config);
+    let token_mint_info = accounts.token_mint.to_account_info();
+    
+    Ok(Box::new(move |amount_in| {
+        if is_buy {
+            let launchlab_quote = quote::quote_buy_exact_in(&pool_state, &global_config, &fees, amount_in)?;
+            token_utils::amount_after_transfer_fee(&token_mint_info, launchlab_quote.net_amount_out)
+        } else {
+            let actual_amount_in = token_utils::amount_after_transfer_fee(&token_mint_info, amount_in)?;
+            Ok(quote::quote_sell_exact_in(&pool_state, &global_config, &fees, actual_amount_in)?.net_amount_out)
+        }
+    }))
+}
+
+// 查询Raydium LaunchLab绑定曲线的募集进度
+pub fn get_curve_progress(ctx: Context<GetPriceContext>) -> Result<quote::CurveProgress> {
+    let pool_state = PoolState::try_from_account_info(&ctx.accounts.pool_state)?;
+    let curve_progress = quote::curve_progress(&pool_state)?;
+    
+    msg!("LaunchLab绑定曲线进度: {}%, 已卖出代币: {} / {}, 已募集SOL: {} / {}",
+        curve_progress.progress as f64 / 10_000.0,
+        curve_progress.sold_base,
+        curve_progress.total_base_sell,
+        curve_progress.raised_quote,
+        curve_progress.quote_fund_raising
+    );
+    
+    Ok(curve_progress)
+}
+
+// 加载池子状态和配置，校验配置账户属于该池子
+fn load_pool(
+    pool_state: &AccountInfo,
+    global_config: &AccountInfo,
+    platform_config: &AccountInfo,
+) -> Result<(PoolState, GlobalConfig, PlatformConfig)> {
+    let pool = PoolState::try_from_account_info(pool_state)?;
+    
+    require_keys_eq!(global_config.key(), pool.global_config, DexError::InvalidLaunchLabAccount);
+    require_keys_eq!(platform_config.key(), pool.platform_config, DexError::InvalidLaunchLabAccount);
+    
+    Ok((
+        pool,
+        GlobalConfig::try_from_account_info(global_config)?,
+        PlatformConfig::try_from_account_info(platform_config)?,
+    ))
+}
+
+// Raydium LaunchLab交易所需的账户结构
+#[derive(Accounts)]
+pub struct TradeTokenLaunchLab<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // DEX账户
+    #[account(
+        mut,
+        seeds = [b"dex_account".as_ref()],
+        bump
+    )]
+    pub dex_account: Account<'info, crate::DexAccount>,
+    
+    // 代币Mint地址（支持SPL Token和Token-2022）
+    #[account(mint::token_program = base_token_program)]
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    // WSOL Mint
+    #[account(
+        constraint = wsol_mint.key() == spl_token::native_mint::id()
+    )]
+    pub wsol_mint: Account<'info, token::Mint>,
+    
+    // LaunchLab池子账户（代币与WSOL的PDA，在指令中手动反序列化）
+    #[account(
+        mut,
+        seeds = [b"pool", token_mint.key().as_ref(), wsol_mint.key().as_ref()],
+        bump,
+        seeds::program = get_raydium_launchlab_program_id(),
+    )]
+    pub pool_state: UncheckedAccount<'info>,
+    
+    // 全局配置和发行平台配置（在指令中按池子状态校验）
+    pub global_config: UncheckedAccount<'info>,
+    pub platform_config: UncheckedAccount<'info>,
+    
+    // 金库权限账户（在指令中按PoolState.auth_bump校验）
+    pub authority: UncheckedAccount<'info>,
+    
+    // 池子代币和WSOL金库（在指令中按池子状态校验）
+    #[account(mut)]
+    pub base_vault: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub quote_vault: UncheckedAccount<'info>,
+    
+    // 用户代币账户（不存在时以幂等方式创建关联代币账户，在指令中检查mint和所有者）
+    #[account(mut)]
+    pub user_token_account: UncheckedAccount<'info>,
+    
+    // 用户WSOL账户（不存在时以幂等方式创建关联代币账户，交易完成后解包为SOL）
+    #[account(mut)]
+    pub user_wsol_account: UncheckedAccount<'info>,
+    
+    // LaunchLab事件权限账户
+    #[account(
+        seeds = [b"__event_authority"],
+        bump,
+        seeds::program = get_raydium_launchlab_program_id(),
+    )]
+    pub event_authority: UncheckedAccount<'info>,
+    
+    // Raydium LaunchLab程序
+    #[account(
+        constraint = raydium_launchlab_program.key() == get_raydium_launchlab_program_id()
+    )]
+    pub raydium_launchlab_program: AccountInfo<'info>,
+    
+    // 代币所属的代币程序（SPL Token或Token-2022）
+    pub base_token_program: Interface<'info, TokenInterface>,
+    
+    // WSOL所属的代币程序
+    pub token_program: Program<'info, Token>,
+    
+    // 系统程序
+    pub system_program: Program<'info, System>,
+    
+    // 关联代币程序
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> TradeTokenLaunchLab<'info> {
+    // 加载池子状态并校验配置、权限和金库账户
+    fn load_pool_state(&self) -> Result<(PoolState, GlobalConfig, PlatformConfig)> {
+        let (pool_state, global_config, platform_config) = load_pool(
+            &self.pool_state,
+            &self.global_config,
+            &self.platform_config,
+        )?;
+        
+        require_keys_eq!(pool_state.base_mint, self.token_mint.key(), DexError::InvalidLaunchLabAccount);
+        require_keys_eq!(pool_state.quote_mint, self.wsol_mint.key(), DexError::InvalidLaunchLabAccount);
+        require_keys_eq!(self.base_vault.key(), pool_state.base_vault, DexError::InvalidLaunchLabAccount);
+        require_keys_eq!(self.quote_vault.key(), pool_state.quote_vault, DexError::InvalidLaunchLabAccount);
+        
+        let authority = Pubkey::create_program_address(
+            &[b"vault_auth_seed", &[pool_state.auth_bump]],
+            &get_raydium_launchlab_program_id(),
+        )
+        .map_err(|_| error!(DexError::InvalidLaunchLabAccount))?;
+        require_keys_eq!(self.authority.key(), authority, DexError::InvalidLaunchLabAccount);
+        
+        Ok((pool_state, global_config, platform_config))
+    }
+    
+    // 确保用户代币账户存在
+    fn ensure_user_token_account(&self) -> Result<token_interface::TokenAccount> {
+        token_utils::ensure_user_token_account(
+            &self.user.to_account_info(),
+            &self.user_token_account.to_account_info(),
+            &self.token_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.base_token_program.to_account_info(),
+            &self.associated_token_program.to_account_info(),
+        )
+    }
+    
+    // 确保用户WSOL账户存在
+    fn ensure_user_wsol_account(&self) -> Result<token_interface::TokenAccount> {
+        token_utils::ensure_user_token_account(
+            &self.user.to_account_info(),
+            &self.user_wsol_account.to_account_info(),
+            &self.wsol_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.associated_token_program.to_account_info(),
+        )
+    }
+    
+    // 执行交易: 校验池子、沿绑定曲线报价并检查价格影响和滑点、准备用户账户、调用LaunchLab，最后将WSOL解包为SOL
+    // 返回按实际余额变化结算的成交结果，以及报价是否完成绑定曲线募集
+    fn execute_swap(
+        &self,
+        is_buy: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<(Fill, bool)> {
+        let (pool_state, global_config, platform_config) = self.load_pool_state()?;
+        let fees = quote::LaunchLabFees::from_configs(&global_config, &platform_config);
+        let token_mint_info = self.token_mint.to_account_info();
+        
+        // 与Raydium一致: 卖出时代币转入金库先扣除转账手续费，买入时代币转给用户再扣除一次
+        let (launchlab_quote, expected_amount_out) = if is_buy {
+            let launchlab_quote = quote::quote_buy_exact_in(&pool_state, &global_config, &fees, amount_in)?;
+            let expected_amount_out = token_utils::amount_after_transfer_fee(&token_mint_info, launchlab_quote.net_amount_out)?;
+            (launchlab_quote, expected_amount_out)
+        } else {
+            let actual_amount_in = token_utils::amount_after_transfer_fee(&token_mint_info, amount_in)?;
+            let launchlab_quote = quote::quote_sell_exact_in(&pool_state, &global_config, &fees, actual_amount_in)?;
+            let expected_amount_out = launchlab_quote.net_amount_out;
+            (launchlab_quote, expected_amount_out)
+        };
+        
+        msg!("预计输入: {}, 预计到账: {}, 手续费: {}, 价格影响: {}%",
+            launchlab_quote.amount_in,
+            expected_amount_out,
+            launchlab_quote.fee_amount,
+            launchlab_quote.price_impact as f64 / 10_000.0
+        );
+        
+        // 检查价格影响和滑点（min_amount_out为实际到账数量）
+        require!(launchlab_quote.price_impact <= MAX_PRICE_IMPACT, DexError::ExcessivePriceImpact);
+        require!(expected_amount_out >= min_amount_out, DexError::SlippageExceeded);
+        
+        // 记录WSOL账户交易前的余额，交易后只解包超出交易前余额的部分，不动用账户中原有的WSOL
+        let wsol_balance_before = token_utils::balance_before_trade(&self.user_wsol_account)?;
+        
+        // 买入时从用户的SOL中包装amount_in，未用完的部分交易后解包退还
+        if is_buy {
+            self.ensure_user_token_account()?;
+            self.ensure_user_wsol_account()?;
+            require!(self.user.lamports() >= amount_in, DexError::InsufficientFunds);
+            token_utils::wrap_sol(
+                &self.user.to_account_info(),
+                &self.user_wsol_account.to_account_info(),
+                amount_in,
+                &self.system_program.to_account_info(),
+                &self.token_program.to_account_info(),
+            )?;
+        } else {
+            let user_token_account = token_utils::read_token_account(&self.user_token_account)?;
+            require_keys_eq!(user_token_account.mint, self.token_mint.key(), DexError::InvalidTokenAccount);
+            require_keys_eq!(user_token_account.owner, self.user.key(), DexError::InvalidTokenAccount);
+            require!(user_token_account.amount >= amount_in, DexError::InsufficientFunds);
+            self.ensure_user_wsol_account()?;
+        }
+        
+        let trade_accounts = self.trade_accounts();
+        let trade_ix = if is_buy {
+            instructions::buy_exact_in(
+                get_raydium_launchlab_program_id(),
+                &trade_accounts,
+                &instructions::BuyExactInArgs {
+                    amount_in,
+                    minimum_amount_out: min_amount_out,
+                    share_fee_rate: 0,
+                },
+            )
+        } else {
+            instructions::sell_exact_in(
+                get_raydium_launchlab_program_id(),
+                &trade_accounts,
+                &instructions::SellExactInArgs {
+                    amount_in,
+                    minimum_amount_out: min_amount_out,
+                    share_fee_rate: 0,
+                },
+            )
+        };
+        let (input_account, output_account) = if is_buy {
+            (&self.user_wsol_account, &self.user_token_account)
+        } else {
+            (&self.user_token_account, &self.user_wsol_account)
+        };
+        let snapshot = BalanceSnapshot::take(input_account, output_account)?;
+        self.invoke_trade(&trade_ix)?;
+        
+        // 按实际余额变化检查成交数量（解包WSOL之前），买入数量被截断时只花费报价中的SOL
+        let (token_in, input_decimals, token_out, output_decimals) = if is_buy {
+            (self.wsol_mint.key(), self.wsol_mint.decimals, self.token_mint.key(), self.token_mint.decimals)
+        } else {
+            (self.token_mint.key(), self.token_mint.decimals, self.wsol_mint.key(), self.wsol_mint.decimals)
+        };
+        let fill = snapshot.settle(
+            input_account,
+            output_account,
+            self.user.key(),
+            &FillPair {
+                dex: DexType::RaydiumLaunchLab,
+                token_in,
+                input_decimals,
+                token_out,
+                output_decimals,
+            },
+            &FillLimits {
+                expected_amount_in: launchlab_quote.amount_in,
+                expected_amount_out,
+                max_amount_in: amount_in,
+                min_amount_out,
+            },
+        )?;
+        
+        // 交易完成后将WSOL解包为SOL（买入时退还剩余的WSOL，卖出时将所得WSOL转为SOL）
+        token_utils::unwrap_sol(
+            &self.user.to_account_info(),
+            &self.user_wsol_account.to_account_info(),
+            &self.wsol_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.associated_token_program.to_account_info(),
+            wsol_balance_before,
+        )?;
+        
+        Ok((fill, launchlab_quote.completes_curve))
+    }
+    
+    // buy_exact_in/sell_exact_in指令账户
+    fn trade_accounts(&self) -> instructions::TradeAccounts {
+        instructions::TradeAccounts {
+            payer: self.user.key(),
+            authority: self.authority.key(),
+            global_config: self.global_config.key(),
+            platform_config: self.platform_config.key(),
+            pool_state: self.pool_state.key(),
+            user_base_token: self.user_token_account.key(),
+            user_quote_token: self.user_wsol_account.key(),
+            base_vault: self.base_vault.key(),
+            quote_vault: self.quote_vault.key(),
+            base_token_mint: self.token_mint.key(),
+            quote_token_mint: self.wsol_mint.key(),
+            base_token_program: self.base_token_program.key(),
+            quote_token_program: self.token_program.key(),
+            event_authority: self.event_authority.key(),
+            program: self.raydium_launchlab_program.key(),
+        }
+    }
+    
+    // 执行Raydium LaunchLab的交易指令
+    fn invoke_trade(&self, trade_ix: &Instruction) -> Result<()> {
+        let account_infos = [
+            self.user.to_account_info(),
+            self.authority.to_account_info(),
+            self.global_config.to_account_info(),
+            self.platform_config.to_account_info(),
+            self.pool_state.to_account_info(),
+            self.user_token_account.to_account_info(),
+            self.user_wsol_account.to_account_info(),
+            self.base_vault.to_account_info(),
+            self.quote_vault.to_account_info(),
+            self.token_mint.to_account_info(),
+            self.wsol_mint.to_account_info(),
+            self.base_token_program.to_account_info(),
+            self.token_program.to_account_info(),
+            self.event_authority.to_account_info(),
+            self.raydium_launchlab_program.to_account_info(),
+        ];
+        
+        invoke(trade_ix, &account_infos)?;
+        Ok(())
+    }
+}
+
+// 检查代币位置所需的账户结构
+#[derive(Accounts)]
+pub struct CheckTokenLocation<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // LaunchLab池子账户（可能不存在，在指令中检查PDA、所有者和discriminator）
+    pub pool_state: UncheckedAccount<'info>,
+    
+    // 系统程序
+    pub system_program: Program<'info, System>,
+}
+
+// 获取价格所需的账户结构
+#[derive(Accounts)]
+pub struct GetPriceContext<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // 代币Mint地址（支持SPL Token和Token-2022）
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    // LaunchLab池子账户（代币与WSOL的PDA，在指令中手动反序列化）
+    #[account(
+        seeds = [b"pool", token_mint.key().as_ref(), spl_token::native_mint::ID.as_ref()],
+        bump,
+        seeds::program = get_raydium_launchlab_program_id(),
+    )]
+    pub pool_state: UncheckedAccount<'info>,
+    
+    // 全局配置和发行平台配置（在指令中按池子状态校验）
+    pub global_config: UncheckedAccount<'info>,
+    pub platform_config: UncheckedAccount<'info>,
+    
+    // 系统程序
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,320 @@
+use anchor_lang::prelude::*;
+use crate::math::{self, to_u64};
+use crate::{DexError, FEE_DENOMINATOR};
+use super::state::{GlobalConfig, PlatformConfig, PoolState, CURVE_TYPE_CONSTANT_PRODUCT};
+
+// Raydium LaunchLab费率分母
+pub const LAUNCHLAB_FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+// 交易手续费率: 协议费率（GlobalConfig）+ 发行平台费率（PlatformConfig）
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LaunchLabFees {
+    pub trade_fee_rate: u64,
+    pub platform_fee_rate: u64,
+}
+
+impl LaunchLabFees {
+    pub fn from_configs(global_config: &GlobalConfig, platform_config: &PlatformConfig) -> Self {
+        LaunchLabFees {
+            trade_fee_rate: global_config.trade_fee_rate,
+            platform_fee_rate: platform_config.fee_rate,
+        }
+    }
+    
+    // 总费率
+    pub fn total_fee_rate(&self) -> Result<u64> {
+        let total_fee_rate = self.trade_fee_rate
+            .checked_add(self.platform_fee_rate)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        require!(total_fee_rate < LAUNCHLAB_FEE_RATE_DENOMINATOR, DexError::InvalidLaunchLabAccount);
+        Ok(total_fee_rate)
+    }
+}
+
+// Raydium LaunchLab报价结果
+// 买入时手续费从输入的SOL中扣除，gross_amount_out与net_amount_out相同；
+// 卖出时手续费从输出的SOL中扣除
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct LaunchLabQuote {
+    // 用户实际支付的数量（买入为含手续费的SOL，卖出为代币）
+    pub amount_in: u64,
+    // 扣除手续费前的输出数量
+    pub gross_amount_out: u64,
+    // 手续费（SOL）
+    pub fee_amount: u64,
+    // 用户实际获得的数量
+    pub net_amount_out: u64,
+    // 价格影响（以FEE_DENOMINATOR为分母的百万分比，不含手续费）
+    pub price_impact: u64,
+    // 买入数量是否因绑定曲线剩余代币不足而被截断
+    pub capped_by_reserves: bool,
+    // 交易后绑定曲线是否完成（募集结束，等待迁移）
+    pub completes_curve: bool,
+}
+
+// 绑定曲线进度
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct CurveProgress {
+    // 池子状态（0为募集中，1为等待迁移，2为已迁移）
+    pub status: u8,
+    // 迁移目标（0为Raydium AMM v4，1为Raydium CPMM）
+    pub migrate_type: u8,
+    // 已卖出的代币数量和可卖出的代币总量
+    pub sold_base: u64,
+    pub total_base_sell: u64,
+    // 已募集的SOL数量和募集目标
+    pub raised_quote: u64,
+    pub quote_fund_raising: u64,
+    // 完成进度（以FEE_DENOMINATOR为分母的百万分比，按已卖出代币计算）
+    pub progress: u64,
+}
+
+// 计算绑定曲线进度
+pub fn curve_progress(pool_state: &PoolState) -> Result<CurveProgress> {
+    let progress = if pool_state.is_trading() && pool_state.total_base_sell > 0 {
+        to_u64(math::mul_div_floor(
+            pool_state.real_base.min(pool_state.total_base_sell) as u128,
+            FEE_DENOMINATOR as u128,
+            pool_state.total_base_sell as u128,
+        )?)?
+    } else {
+        // 募集结束后进度为100%
+        FEE_DENOMINATOR
+    };
+    
+    Ok(CurveProgress {
+        status: pool_state.status,
+        migrate_type: pool_state.migrate_type,
+        sold_base: pool_state.real_base,
+        total_base_sell: pool_state.total_base_sell,
+        raised_quote: pool_state.real_quote,
+        quote_fund_raising: pool_state.total_quote_fund_raising,
+        progress,
+    })
+}
+
+// 检查池子仍在绑定曲线上交易，返回恒定乘积曲线的(代币储备, SOL储备)
+fn tradable_curve_reserves(pool_state: &PoolState, global_config: &GlobalConfig) -> Result<(u128, u128)> {
+    require!(pool_state.is_trading(), DexError::LaunchLabCurveCompleted);
+    // 目前只支持恒定乘积曲线
+    require!(
+        global_config.curve_type == CURVE_TYPE_CONSTANT_PRODUCT,
+        DexError::UnsupportedLaunchLabCurve
+    );
+    
+    let (base_reserve, quote_reserve) = pool_state.curve_reserves()?;
+    require!(base_reserve > 0 && quote_reserve > 0, DexError::InsufficientLiquidity);
+    Ok((base_reserve as u128, quote_reserve as u128))
+}
+
+// 报价: 使用指定数量的SOL（含手续费）购买代币（精确SOL输入）
+pub fn quote_buy_exact_in(
+    pool_state: &PoolState,
+    global_config: &GlobalConfig,
+    fees: &LaunchLabFees,
+    amount_in: u64,
+) -> Result<LaunchLabQuote> {
+    require!(amount_in > 0, DexError::InvalidArgument);
+    let (base_reserve, quote_reserve) = tradable_curve_reserves(pool_state, global_config)?;
+    let total_fee_rate = fees.total_fee_rate()?;
+    
+    let fee_amount = math::fee_ceil(amount_in, total_fee_rate, LAUNCHLAB_FEE_RATE_DENOMINATOR)?;
+    let net_amount_in = amount_in - fee_amount;
+    
+    // 代币数量 = net_amount_in * base_reserve / (quote_reserve + net_amount_in)
+    let amount_out = to_u64(math::mul_div_floor(
+        net_amount_in as u128,
+        base_reserve,
+        quote_reserve + net_amount_in as u128,
+    )?)?;
+    
+    // 不能超过绑定曲线剩余可卖出的代币，超出时只买入剩余部分并按实际需要的SOL计费
+    let remaining_base = pool_state.remaining_base();
+    let capped_by_reserves = amount_out > remaining_base;
+    let (amount_in, fee_amount, net_amount_in, amount_out) = if capped_by_reserves {
+        require!(remaining_base > 0 && (remaining_base as u128) < base_reserve, DexError::InsufficientLiquidity);
+        
+        // 买入剩余代币所需的净SOL: ceil(remaining_base * quote_reserve / (base_reserve - remaining_base))
+        let net_amount_in = to_u64(math::mul_div_ceil(
+            remaining_base as u128,
+            quote_reserve,
+            base_reserve - remaining_base as u128,
+        )?)?;
+        // 按费率反推含手续费的输入: ceil(net * 1_000_000 / (1_000_000 - fee_rate))
+        let amount_in = to_u64(math::mul_div_ceil(
+            net_amount_in as u128,
+            LAUNCHLAB_FEE_RATE_DENOMINATOR as u128,
+            (LAUNCHLAB_FEE_RATE_DENOMINATOR - total_fee_rate) as u128,
+        )?)?;
+        (amount_in, amount_in - net_amount_in, net_amount_in, remaining_base)
+    } else {
+        (amount_in, fee_amount, net_amount_in, amount_out)
+    };
+    require!(amount_out > 0, DexError::InsufficientLiquidity);
+    
+    // 价格影响 = 1 - 成交价格 / 现货价格 = 1 - amount_out * quote_reserve / (net_amount_in * base_reserve)
+    let price_impact = math::price_impact(
+        amount_out as u128 * quote_reserve,
+        net_amount_in as u128 * base_reserve,
+    )?;
+    
+    Ok(LaunchLabQuote {
+        amount_in,
+        gross_amount_out: amount_out,
+        fee_amount,
+        net_amount_out: amount_out,
+        price_impact,
+        capped_by_reserves,
+        completes_curve: amount_out == remaining_base,
+    })
+}
+
+// 报价: 卖出指定数量的代币（精确代币输入）
+pub fn quote_sell_exact_in(
+    pool_state: &PoolState,
+    global_config: &GlobalConfig,
+    fees: &LaunchLabFees,
+    amount_in: u64,
+) -> Result<LaunchLabQuote> {
+    require!(amount_in > 0, DexError::InvalidArgument);
+    let (base_reserve, quote_reserve) = tradable_curve_reserves(pool_state, global_config)?;
+    
+    // 获得的SOL = amount_in * quote_reserve / (base_reserve + amount_in)
+    let gross_amount_out = to_u64(math::mul_div_floor(
+        amount_in as u128,
+        quote_reserve,
+        base_reserve + amount_in as u128,
+    )?)?;
+    
+    // 不能超过已募集的SOL
+    require!(
+        gross_amount_out > 0 && gross_amount_out <= pool_state.real_quote,
+        DexError::InsufficientLiquidity
+    );
+    
+    let fee_amount = math::fee_ceil(gross_amount_out, fees.total_fee_rate()?, LAUNCHLAB_FEE_RATE_DENOMINATOR)?;
+    let net_amount_out = gross_amount_out.saturating_sub(fee_amount);
+    
+    // 价格影响 = 1 - gross_amount_out * base_reserve / (amount_in * quote_reserve)
+    let price_impact = math::price_impact(
+        gross_amount_out as u128 * base_reserve,
+        amount_in as u128 * quote_reserve,
+    )?;
+    
+    Ok(LaunchLabQuote {
+        amount_in,
+        gross_amount_out,
+        fee_amount,
+        net_amount_out,
+        price_impact,
+        capped_by_reserves: false,
+        completes_curve: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raydium_launchlab::state::{POOL_STATUS_FUND, POOL_STATUS_MIGRATE};
+    
+    // 期望值按Raydium LaunchLab恒定乘积曲线的整数运算独立计算:
+    // 储备为(virtual_base - real_base, virtual_quote + real_quote)，买入手续费ceil(amount_in * 费率 / 1e6)从输入中扣除，
+    // 截断时净输入ceil(remaining * quote / (base - remaining))、含费输入ceil(net * 1e6 / (1e6 - 费率))，卖出手续费从输出中向上取整扣除
+    
+    const TOTAL_BASE_SELL: u64 = 793_100_000_000_000;
+    
+    // 0.25%协议费率 + 0.10%平台费率
+    const FEES: LaunchLabFees = LaunchLabFees { trade_fee_rate: 2_500, platform_fee_rate: 1_000 };
+    
+    // 测试中的real_quote按恒定乘积由real_base反推（向上取整）
+    fn pool_state(real_base: u64, real_quote: u64) -> PoolState {
+        PoolState {
+            status: POOL_STATUS_FUND,
+            total_base_sell: TOTAL_BASE_SELL,
+            virtual_base: 1_073_025_605_596_382,
+            virtual_quote: 30_000_852_951,
+            real_base,
+            real_quote,
+            total_quote_fund_raising: 85_000_000_000,
+            ..Default::default()
+        }
+    }
+    
+    // 已卖出400M代币
+    fn mid_pool() -> PoolState {
+        pool_state(400_000_000_000_000, 17_830_437_774)
+    }
+    
+    #[test]
+    fn buy_exact_in_matches_reference() {
+        let quote = quote_buy_exact_in(&mid_pool(), &GlobalConfig::default(), &FEES, 1_000_000_000).unwrap();
+        assert_eq!(
+            quote,
+            LaunchLabQuote {
+                amount_in: 1_000_000_000,
+                gross_amount_out: 13_735_415_959_203,
+                fee_amount: 3_500_000,
+                net_amount_out: 13_735_415_959_203,
+                price_impact: 20_408,
+                capped_by_reserves: false,
+                completes_curve: false,
+            }
+        );
+    }
+    
+    #[test]
+    fn buy_exact_in_caps_at_remaining_base() {
+        // 剩余1M代币，1 SOL可买入的数量超过剩余，只按买入剩余代币所需的SOL计费
+        let pool = pool_state(TOTAL_BASE_SELL - 1_000_000_000_000, 84_590_635_917);
+        let quote = quote_buy_exact_in(&pool, &GlobalConfig::default(), &FEES, 1_000_000_000).unwrap();
+        assert_eq!(
+            quote,
+            LaunchLabQuote {
+                amount_in: 410_801_891,
+                gross_amount_out: 1_000_000_000_000,
+                fee_amount: 1_437_807,
+                net_amount_out: 1_000_000_000_000,
+                price_impact: 3_559,
+                capped_by_reserves: true,
+                completes_curve: true,
+            }
+        );
+    }
+    
+    #[test]
+    fn sell_exact_in_matches_reference() {
+        let quote = quote_sell_exact_in(&mid_pool(), &GlobalConfig::default(), &FEES, 10_000_000_000_000).unwrap();
+        assert_eq!(
+            quote,
+            LaunchLabQuote {
+                amount_in: 10_000_000_000_000,
+                gross_amount_out: 700_285_470,
+                fee_amount: 2_451_000,
+                net_amount_out: 697_834_470,
+                price_impact: 14_640,
+                capped_by_reserves: false,
+                completes_curve: false,
+            }
+        );
+    }
+    
+    #[test]
+    fn curve_progress_follows_sold_base() {
+        assert_eq!(
+            curve_progress(&mid_pool()).unwrap(),
+            CurveProgress {
+                status: POOL_STATUS_FUND,
+                migrate_type: 0,
+                sold_base: 400_000_000_000_000,
+                total_base_sell: TOTAL_BASE_SELL,
+                raised_quote: 17_830_437_774,
+                quote_fund_raising: 85_000_000_000,
+                progress: 504_350,
+            }
+        );
+        
+        // 募集结束后进度为100%
+        let pool = PoolState { status: POOL_STATUS_MIGRATE, ..mid_pool() };
+        assert_eq!(curve_progress(&pool).unwrap().progress, FEE_DENOMINATOR);
+    }
+}
@@ -0,0 +1,185 @@
+use anchor_lang::prelude::*;
+use solana_program::pubkey::Pubkey;
+use crate::codec::strip_discriminator;
+use crate::DexError;
+use super::get_raydium_launchlab_program_id;
+
+// Raydium LaunchLab账户discriminator（Anchor规则: sha256("account:<账户名>")的前8字节）
+pub const POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+pub const GLOBAL_CONFIG_DISCRIMINATOR: [u8; 8] = [149, 8, 156, 202, 160, 252, 176, 217];
+pub const PLATFORM_CONFIG_DISCRIMINATOR: [u8; 8] = [160, 78, 128, 0, 248, 83, 230, 160];
+
+// 绑定曲线类型（GlobalConfig.curve_type）
+pub const CURVE_TYPE_CONSTANT_PRODUCT: u8 = 0;
+
+// 池子状态（PoolState.status）
+pub const POOL_STATUS_FUND: u8 = 0;  // 募集中，可在绑定曲线上交易
+pub const POOL_STATUS_MIGRATE: u8 = 1;  // 募集完成，等待迁移
+pub const POOL_STATUS_TRADE: u8 = 2;  // 已迁移到AMM
+
+// 迁移目标（PoolState.migrate_type）
+pub const MIGRATE_TYPE_AMM_V4: u8 = 0;
+pub const MIGRATE_TYPE_CPMM: u8 = 1;
+
+// 代币锁仓计划（仅用于按链上布局跳过）
+#[derive(AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct VestingSchedule {
+    pub total_locked_amount: u64,
+    pub cliff_period: u64,
+    pub unlock_period: u64,
+    pub start_time: u64,
+    pub allocated_share_amount: u64,
+}
+
+// Raydium LaunchLab的PoolState账户（字段顺序与链上布局一致，只读取交易所需的前缀部分）
+// base为发行的代币，quote为募集的代币（路由只支持WSOL）
+#[derive(AnchorDeserialize, Clone, Debug, Default)]
+pub struct PoolState {
+    pub epoch: u64,
+    pub auth_bump: u8,
+    pub status: u8,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub migrate_type: u8,
+    pub supply: u64,
+    // 绑定曲线上可卖出的代币总量
+    pub total_base_sell: u64,
+    pub virtual_base: u64,
+    pub virtual_quote: u64,
+    // 已卖出的代币数量和已募集的quote数量
+    pub real_base: u64,
+    pub real_quote: u64,
+    // 募集目标
+    pub total_quote_fund_raising: u64,
+    pub quote_protocol_fee: u64,
+    pub platform_fee: u64,
+    pub migrate_fee: u64,
+    pub vesting_schedule: VestingSchedule,
+    pub global_config: Pubkey,
+    pub platform_config: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub creator: Pubkey,
+}
+
+impl PoolState {
+    // 从账户加载，检查所有者和discriminator
+    pub fn try_from_account_info(account_info: &AccountInfo) -> Result<Self> {
+        check_owner(account_info)?;
+        let data = account_info.try_borrow_data()?;
+        let mut data = strip_discriminator(&data, &POOL_STATE_DISCRIMINATOR, DexError::InvalidLaunchLabAccount)?;
+        PoolState::deserialize(&mut data).map_err(|_| DexError::InvalidLaunchLabAccount.into())
+    }
+    
+    // 是否仍在绑定曲线上交易
+    pub fn is_trading(&self) -> bool {
+        self.status == POOL_STATUS_FUND
+    }
+    
+    // 绑定曲线上剩余可买入的代币数量
+    pub fn remaining_base(&self) -> u64 {
+        self.total_base_sell.saturating_sub(self.real_base)
+    }
+    
+    // 恒定乘积曲线的当前储备，返回(代币储备, quote储备)
+    pub fn curve_reserves(&self) -> Result<(u64, u64)> {
+        let base_reserve = self.virtual_base
+            .checked_sub(self.real_base)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        let quote_reserve = self.virtual_quote
+            .checked_add(self.real_quote)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        Ok((base_reserve, quote_reserve))
+    }
+}
+
+// Raydium LaunchLab的GlobalConfig账户（费率以1_000_000为分母）
+#[derive(AnchorDeserialize, Clone, Debug, Default)]
+pub struct GlobalConfig {
+    pub epoch: u64,
+    pub curve_type: u8,
+    pub index: u16,
+    pub migrate_fee: u64,
+    pub trade_fee_rate: u64,
+    pub max_share_fee_rate: u64,
+    pub min_base_supply: u64,
+    pub max_lock_rate: u64,
+    pub min_base_sell_rate: u64,
+    pub min_base_migrate_rate: u64,
+    pub min_quote_fund_raising: u64,
+    pub quote_mint: Pubkey,
+    pub protocol_fee_owner: Pubkey,
+    pub migrate_fee_owner: Pubkey,
+    pub migrate_to_amm_wallet: Pubkey,
+    pub migrate_to_cpswap_wallet: Pubkey,
+}
+
+impl GlobalConfig {
+    // 从账户加载，检查所有者和discriminator
+    pub fn try_from_account_info(account_info: &AccountInfo) -> Result<Self> {
+        check_owner(account_info)?;
+        let data = account_info.try_borrow_data()?;
+        let mut data = strip_discriminator(&data, &GLOBAL_CONFIG_DISCRIMINATOR, DexError::InvalidLaunchLabAccount)?;
+        GlobalConfig::deserialize(&mut data).map_err(|_| DexError::InvalidLaunchLabAccount.into())
+    }
+}
+
+// Raydium LaunchLab的PlatformConfig账户（发行平台的手续费配置，只读取费率之前的字段）
+#[derive(AnchorDeserialize, Clone, Debug, Default)]
+pub struct PlatformConfig {
+    pub epoch: u64,
+    pub platform_fee_wallet: Pubkey,
+    pub platform_nft_wallet: Pubkey,
+    pub platform_scale: u64,
+    pub creator_scale: u64,
+    pub burn_scale: u64,
+    pub fee_rate: u64,
+}
+
+impl PlatformConfig {
+    // 从账户加载，检查所有者和discriminator
+    pub fn try_from_account_info(account_info: &AccountInfo) -> Result<Self> {
+        check_owner(account_info)?;
+        let data = account_info.try_borrow_data()?;
+        let mut data = strip_discriminator(&data, &PLATFORM_CONFIG_DISCRIMINATOR, DexError::InvalidLaunchLabAccount)?;
+        PlatformConfig::deserialize(&mut data).map_err(|_| DexError::InvalidLaunchLabAccount.into())
+    }
+}
+
+// 检查账户所有者为Raydium LaunchLab程序
+fn check_owner(account_info: &AccountInfo) -> Result<()> {
+    require_keys_eq!(
+        *account_info.owner,
+        get_raydium_launchlab_program_id(),
+        DexError::InvalidLaunchLabAccount
+    );
+    Ok(())
+}
+
+// 计算池子PDA: ["pool", base_mint, quote_mint]
+pub fn get_pool_pda(base_mint: &Pubkey, quote_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"pool", base_mint.as_ref(), quote_mint.as_ref()],
+        &get_raydium_launchlab_program_id(),
+    )
+}
+
+// 计算池子金库PDA: ["pool_vault", pool, mint]
+pub fn get_pool_vault_pda(pool: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"pool_vault", pool.as_ref(), mint.as_ref()],
+        &get_raydium_launchlab_program_id(),
+    )
+}
+
+// 计算金库权限PDA: ["vault_auth_seed"]
+pub fn get_authority_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vault_auth_seed"], &get_raydium_launchlab_program_id())
+}
+
+// 计算事件权限PDA: ["__event_authority"]
+pub fn get_event_authority_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"__event_authority"], &get_raydium_launchlab_program_id())
+}
@@ -1,17 +1,97 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Bumps;
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
+use anchor_spl::token::{self, Token};
 use crate::pumpfun;
+use crate::pumpswap;
 use crate::raydium;
-use crate::{DexError, TradeInstruction, DexType, MAX_PRICE_IMPACT};
+use crate::raydium_amm_v4;
+use crate::raydium_clmm;
+use crate::raydium_launchlab;
+use crate::token_utils;
+use crate::fill::Fill;
+use crate::math::{self, to_u64};
+use crate::{DexError, RouteLeg, RouteVenue, TradeInstruction, DexType, SwapMode, MAX_PRICE_IMPACT};
 use solana_program::pubkey::Pubkey;
+use std::collections::BTreeSet;
 
 // 常量定义
 pub const MAX_BATCH_SIZE: usize = 5; // 最大批量交易指令数量
+pub const SPLIT_STEPS: u64 = 10; // 拆单时将输入数量等分的份数
+pub const MAX_ROUTE_LEGS: usize = 3; // 多跳路由的最大段数
 
-// 检查代币位置并选择正确的DEX
+// 路由报价器: 精确输入时按输入数量计算用户实际获得的净输出，精确输出时按需要获得的输出数量计算所需的输入
+// 池子状态只在创建时读取一次，拆单时在同一状态上多次报价
+pub type RouteQuoter<'info> = Box<dyn Fn(u64) -> Result<u64> + 'info>;
+
+// 单个DEX的报价（相同输入数量下的净输出）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct VenueQuote {
+    pub dex: DexType,
+    pub amount_out: u64,
+}
+
+// 路由分配: 在单个DEX上成交的输入数量和预期净输出
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RouteAllocation {
+    pub dex: DexType,
+    pub amount_in: u64,
+    pub expected_amount_out: u64,
+}
+
+// 智能交易中单个DEX的实际成交
+// ExactOut时amount_in为实际花费，不超过最大输入数量
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct AllocationFill {
+    pub dex: DexType,
+    pub amount_in: u64,
+    pub expected_amount_out: u64,
+    pub amount_out: u64,
+}
+
+// 多跳路由中单段的实际成交
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RouteLegFill {
+    pub dex: DexType,
+    pub token_mint: Pubkey,
+    pub is_buy: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+// 可报价的DEX
+struct Venue<'info> {
+    dex: DexType,
+    name: &'static str,
+    quoter: RouteQuoter<'info>,
+}
+
+impl<'info> Venue<'info> {
+    // 按输入数量报价，超出池子可交易范围等报价失败时返回None
+    fn amount_out(&self, amount_in: u64) -> Option<u64> {
+        if amount_in == 0 {
+            return Some(0);
+        }
+        (self.quoter)(amount_in).ok()
+    }
+}
+
+// 检查代币位置并选择正确的DEX（返回true表示代币在Pump.fun绑定曲线上）
 pub fn check_token_location(
     ctx: Context<CheckTokenLocationContext>,
     token_mint: Pubkey,
 ) -> Result<bool> {
+    let dex_type = locate_token(ctx, token_mint)?;
+    Ok(dex_type == DexType::PumpFun)
+}
+
+// 查找代币当前所在的DEX
+// 依次检查Pump.fun绑定曲线、PumpSwap（毕业后的Pump.fun代币）、Raydium LaunchLab绑定曲线、
+// Raydium CPMM（毕业后的LaunchLab代币）、Raydium AMM v4和Raydium CLMM
+pub fn locate_token(
+    ctx: Context<CheckTokenLocationContext>,
+    token_mint: Pubkey,
+) -> Result<DexType> {
     msg!("检查代币位置: {}", token_mint);
     
     // 记录检查开始时间
@@ -23,7 +103,7 @@ pub fn check_token_location(
         token_mint,
     )?;
     
-    // 如果代币在Pump.fun上，返回true
+    // 如果代币在Pump.fun上，返回PumpFun
     if on_pump {
         msg!("代币在Pump.fun上");
         
@@ -32,10 +112,44 @@ pub fn check_token_location(
         let execution_time = end_time - start_time;
         msg!("代币位置检查执行时间: {}ms", execution_time);
         
-        return Ok(true);
+        return Ok(DexType::PumpFun);
+    }
+    
+    // 绑定曲线完成后，代币通常迁移到PumpSwap
+    let on_pumpswap = pumpswap::is_token_on_pumpswap(
+        ctx.accounts.pumpswap_check_ctx.into(),
+        token_mint,
+    )?;
+    
+    if on_pumpswap {
+        msg!("代币在PumpSwap上");
+        
+        // 记录检查结束时间
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("代币位置检查执行时间: {}ms", execution_time);
+        
+        return Ok(DexType::PumpSwap);
+    }
+    
+    // 检查代币是否在Raydium LaunchLab绑定曲线上
+    let on_launchlab = raydium_launchlab::is_token_on_launchlab(
+        ctx.accounts.launchlab_check_ctx.into(),
+        token_mint,
+    )?;
+    
+    if on_launchlab {
+        msg!("代币在Raydium LaunchLab上");
+        
+        // 记录检查结束时间
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("代币位置检查执行时间: {}ms", execution_time);
+        
+        return Ok(DexType::RaydiumLaunchLab);
     }
     
-    // 如果代币不在Pump.fun上，检查是否在Raydium上
+    // LaunchLab募集完成后代币迁移到Raydium CPMM，检查是否在Raydium上
     let on_raydium = raydium::is_token_on_raydium(
         ctx.accounts.raydium_check_ctx.into(),
         token_mint,
@@ -49,11 +163,45 @@ pub fn check_token_location(
         let execution_time = end_time - start_time;
         msg!("代币位置检查执行时间: {}ms", execution_time);
         
-        return Ok(false);
+        return Ok(DexType::Raydium);
+    }
+    
+    // 早期从Pump.fun迁移的代币及大量SOL交易对仍在Raydium AMM v4上
+    let on_raydium_amm_v4 = raydium_amm_v4::is_token_on_raydium_amm_v4(
+        ctx.accounts.raydium_amm_v4_check_ctx.into(),
+        token_mint,
+    )?;
+    
+    if on_raydium_amm_v4 {
+        msg!("代币在Raydium AMM v4上");
+        
+        // 记录检查结束时间
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("代币位置检查执行时间: {}ms", execution_time);
+        
+        return Ok(DexType::RaydiumAmmV4);
+    }
+    
+    // 最后检查Raydium CLMM集中流动性池子
+    let on_raydium_clmm = raydium_clmm::is_token_on_raydium_clmm(
+        ctx.accounts.raydium_clmm_check_ctx.into(),
+        token_mint,
+    )?;
+    
+    if on_raydium_clmm {
+        msg!("代币在Raydium CLMM上");
+        
+        // 记录检查结束时间
+        let end_time = Clock::get()?.unix_timestamp;
+        let execution_time = end_time - start_time;
+        msg!("代币位置检查执行时间: {}ms", execution_time);
+        
+        return Ok(DexType::RaydiumClmm);
     }
     
-    // 如果代币既不在Pump.fun上也不在Raydium上，返回错误
-    msg!("代币既不在Pump.fun上也不在Raydium上");
+    // 如果代币不在任何支持的DEX上，返回错误
+    msg!("代币不在Pump.fun、PumpSwap、Raydium LaunchLab、Raydium、Raydium AMM v4或Raydium CLMM上");
     
     // 记录检查结束时间
     let end_time = Clock::get()?.unix_timestamp;
@@ -63,177 +211,701 @@ pub fn check_token_location(
     Err(DexError::TokenNotFound.into())
 }
 
+// 单个DEX的报价账户，按DEX类型创建路由报价器
+enum VenuePriceAccounts<'a, 'info> {
+    PumpFun(&'a pumpfun::GetPriceContext<'info>),
+    PumpSwap(&'a pumpswap::GetPriceContext<'info>),
+    Raydium(&'a raydium::GetPriceContext<'info>),
+    RaydiumAmmV4(&'a raydium_amm_v4::GetPriceContext<'info>),
+    RaydiumClmm(&'a raydium_clmm::GetPriceContext<'info>),
+    RaydiumLaunchLab(&'a raydium_launchlab::GetPriceContext<'info>),
+}
+
+impl<'a, 'info> VenuePriceAccounts<'a, 'info> {
+    fn dex(&self) -> DexType {
+        match self {
+            VenuePriceAccounts::PumpFun(_) => DexType::PumpFun,
+            VenuePriceAccounts::PumpSwap(_) => DexType::PumpSwap,
+            VenuePriceAccounts::Raydium(_) => DexType::Raydium,
+            VenuePriceAccounts::RaydiumAmmV4(_) => DexType::RaydiumAmmV4,
+            VenuePriceAccounts::RaydiumClmm(_) => DexType::RaydiumClmm,
+            VenuePriceAccounts::RaydiumLaunchLab(_) => DexType::RaydiumLaunchLab,
+        }
+    }
+    
+    fn name(&self) -> &'static str {
+        match self {
+            VenuePriceAccounts::PumpFun(_) => "Pump.fun",
+            VenuePriceAccounts::PumpSwap(_) => "PumpSwap",
+            VenuePriceAccounts::Raydium(_) => "Raydium",
+            VenuePriceAccounts::RaydiumAmmV4(_) => "Raydium AMM v4",
+            VenuePriceAccounts::RaydiumClmm(_) => "Raydium CLMM",
+            VenuePriceAccounts::RaydiumLaunchLab(_) => "Raydium LaunchLab",
+        }
+    }
+    
+    // 报价的代币Mint
+    fn token_mint(&self) -> Pubkey {
+        match self {
+            VenuePriceAccounts::PumpFun(accounts) => accounts.token_mint.key(),
+            VenuePriceAccounts::PumpSwap(accounts) => accounts.token_mint.key(),
+            VenuePriceAccounts::Raydium(accounts) => accounts.token_mint.key(),
+            VenuePriceAccounts::RaydiumAmmV4(accounts) => accounts.token_mint.key(),
+            VenuePriceAccounts::RaydiumClmm(accounts) => accounts.token_mint.key(),
+            VenuePriceAccounts::RaydiumLaunchLab(accounts) => accounts.token_mint.key(),
+        }
+    }
+    
+    // 报价使用的池子（Pump.fun为绑定曲线）
+    fn pool(&self) -> Pubkey {
+        match self {
+            VenuePriceAccounts::PumpFun(accounts) => accounts.bonding_curve.key(),
+            VenuePriceAccounts::PumpSwap(accounts) => accounts.pool.key(),
+            VenuePriceAccounts::Raydium(accounts) => accounts.pool_state.key(),
+            VenuePriceAccounts::RaydiumAmmV4(accounts) => accounts.amm.key(),
+            VenuePriceAccounts::RaydiumClmm(accounts) => accounts.pool_state.key(),
+            VenuePriceAccounts::RaydiumLaunchLab(accounts) => accounts.pool_state.key(),
+        }
+    }
+    
+    // 创建路由报价器，tick_array_accounts为Raydium CLMM交易方向上的TickArray
+    // 精确输出只支持Pump.fun买入、Raydium和Raydium AMM v4
+    fn route_quoter(
+        &self,
+        tick_array_accounts: &[AccountInfo<'info>],
+        is_buy: bool,
+        swap_mode: SwapMode,
+    ) -> Result<RouteQuoter<'info>> {
+        match (self, swap_mode) {
+            (VenuePriceAccounts::PumpFun(accounts), SwapMode::ExactIn) => pumpfun::route_quoter(accounts, is_buy),
+            (VenuePriceAccounts::PumpFun(accounts), SwapMode::ExactOut) => pumpfun::route_exact_out_quoter(accounts, is_buy),
+            (VenuePriceAccounts::PumpSwap(accounts), SwapMode::ExactIn) => pumpswap::route_quoter(accounts, is_buy),
+            (VenuePriceAccounts::Raydium(accounts), SwapMode::ExactIn) => raydium::route_quoter(accounts, is_buy),
+            (VenuePriceAccounts::Raydium(accounts), SwapMode::ExactOut) => raydium::route_exact_out_quoter(accounts, is_buy),
+            (VenuePriceAccounts::RaydiumAmmV4(accounts), SwapMode::ExactIn) => raydium_amm_v4::route_quoter(accounts, is_buy),
+            (VenuePriceAccounts::RaydiumAmmV4(accounts), SwapMode::ExactOut) => {
+                raydium_amm_v4::route_exact_out_quoter(accounts, is_buy)
+            },
+            (VenuePriceAccounts::RaydiumClmm(accounts), SwapMode::ExactIn) => {
+                raydium_clmm::route_quoter(accounts, tick_array_accounts, is_buy)
+            },
+            (VenuePriceAccounts::RaydiumLaunchLab(accounts), SwapMode::ExactIn) => raydium_launchlab::route_quoter(accounts, is_buy),
+            (_, SwapMode::ExactOut) => Err(DexError::UnsupportedSwapMode.into()),
+        }
+    }
+}
+
+// 加载各DEX的路由报价器，代币不一致、池子不存在、状态无效或不支持该交易模式的DEX不参与路由
+// 每项为DEX的报价账户及其Raydium CLMM交易方向上的TickArray
+fn load_venues<'info>(
+    price_accounts: Vec<(VenuePriceAccounts<'_, 'info>, &[AccountInfo<'info>])>,
+    token_mint: Pubkey,
+    is_buy: bool,
+    swap_mode: SwapMode,
+) -> Vec<Venue<'info>> {
+    let mut venues = Vec::with_capacity(price_accounts.len());
+    for (accounts, tick_array_accounts) in price_accounts {
+        let name = accounts.name();
+        if accounts.token_mint() != token_mint {
+            msg!("{}报价账户的代币Mint不一致，不参与路由", name);
+            continue;
+        }
+        
+        match accounts.route_quoter(tick_array_accounts, is_buy, swap_mode) {
+            Ok(quoter) => venues.push(Venue { dex: accounts.dex(), name, quoter }),
+            Err(_) => msg!("{}报价失败，不参与路由", name),
+        }
+    }
+    
+    venues
+}
+
+// 按相同输入数量查询各DEX的净输出，报价失败的DEX不包含在内
+fn quote_venues(venues: &[Venue], amount_in: u64, is_buy: bool) -> Vec<VenueQuote> {
+    let mut quotes = Vec::with_capacity(venues.len());
+    for venue in venues {
+        match venue.amount_out(amount_in) {
+            Some(amount_out) => {
+                msg!("{}比较 - {}: {}",
+                    if is_buy { "买入" } else { "卖出" },
+                    venue.name,
+                    amount_out
+                );
+                quotes.push(VenueQuote { dex: venue.dex, amount_out });
+            },
+            None => msg!("{}无法成交输入数量 {}，不参与比较", venue.name, amount_in),
+        }
+    }
+    
+    quotes
+}
+
+// 按相同输出数量查询各DEX所需的输入，报价失败的DEX不包含在内
+fn quote_venues_exact_out(venues: &[Venue], amount_out: u64, is_buy: bool) -> Vec<RouteAllocation> {
+    let mut allocations = Vec::with_capacity(venues.len());
+    for venue in venues {
+        match (venue.quoter)(amount_out) {
+            Ok(amount_in) => {
+                msg!("{}精确输出比较 - {}: 所需输入 {}",
+                    if is_buy { "买入" } else { "卖出" },
+                    venue.name,
+                    amount_in
+                );
+                allocations.push(RouteAllocation { dex: venue.dex, amount_in, expected_amount_out: amount_out });
+            },
+            Err(_) => msg!("{}无法成交输出数量 {}，不参与比较", venue.name, amount_out),
+        }
+    }
+    
+    allocations
+}
+
+// 选择所需输入最少的DEX，输入相同时按列表顺序优先
+fn best_exact_out_allocation(allocations: &[RouteAllocation]) -> Option<RouteAllocation> {
+    let mut best: Option<RouteAllocation> = None;
+    for allocation in allocations {
+        if best.map_or(true, |best| allocation.amount_in < best.amount_in) {
+            best = Some(*allocation);
+        }
+    }
+    
+    best
+}
+
+// 选择净输出最多的DEX，输出相同时按列表顺序优先
+fn best_quote(quotes: &[VenueQuote]) -> Option<VenueQuote> {
+    let mut best: Option<VenueQuote> = None;
+    for quote in quotes {
+        if best.map_or(true, |best| quote.amount_out > best.amount_out) {
+            best = Some(*quote);
+        }
+    }
+    
+    best
+}
+
+// 拆单: 将输入数量等分为SPLIT_STEPS份，每份分配给边际净输出最多的DEX
+// 各DEX的输出随输入数量边际递减，贪心分配使各DEX的边际价格趋于相等
+fn split_allocation(venues: &[Venue], amount_in: u64) -> Result<Vec<RouteAllocation>> {
+    let mut allocated = vec![0u64; venues.len()];
+    let mut amounts_out = vec![0u64; venues.len()];
+    
+    for step in 0..SPLIT_STEPS {
+        // 按比例切分，最后一份包含余数
+        let chunk = to_u64(math::mul_div_floor(amount_in as u128, (step + 1) as u128, SPLIT_STEPS as u128)?)?
+            - to_u64(math::mul_div_floor(amount_in as u128, step as u128, SPLIT_STEPS as u128)?)?;
+        if chunk == 0 {
+            continue;
+        }
+        
+        let mut best: Option<(usize, u64, u64)> = None;
+        for (index, venue) in venues.iter().enumerate() {
+            let amount_out = match venue.amount_out(allocated[index] + chunk) {
+                Some(amount_out) => amount_out,
+                None => continue,
+            };
+            let marginal_out = amount_out.saturating_sub(amounts_out[index]);
+            if best.map_or(true, |(_, best_marginal_out, _)| marginal_out > best_marginal_out) {
+                best = Some((index, marginal_out, amount_out));
+            }
+        }
+        
+        let (index, _, amount_out) = best.ok_or(DexError::InsufficientLiquidity)?;
+        allocated[index] += chunk;
+        amounts_out[index] = amount_out;
+    }
+    
+    Ok(venues
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| allocated[*index] > 0)
+        .map(|(index, venue)| RouteAllocation {
+            dex: venue.dex,
+            amount_in: allocated[index],
+            expected_amount_out: amounts_out[index],
+        })
+        .collect())
+}
+
 // 获取最优价格路由
-pub fn get_best_price(
-    ctx: Context<GetBestPrice>,
+// 按各DEX的实际报价公式（含手续费和Token-2022转账手续费）计算相同输入数量下的净输出，返回输出最多的DEX
+// 剩余账户: Raydium CLMM交易方向上的TickArray
+pub fn get_best_price<'info>(
+    ctx: Context<'_, '_, '_, 'info, GetBestPrice<'info>>,
     amount_in: u64,
     is_buy: bool,  // true表示买入，false表示卖出
-) -> Result<bool> {
+) -> Result<DexType> {
     msg!("开始比较DEX价格: 输入金额 {}, 操作类型: {}", 
         amount_in, 
         if is_buy { "买入" } else { "卖出" }
     );
     msg!("代币Mint地址: {}", ctx.accounts.token_mint.key());
     
+    // 检查输入金额是否大于0
+    require!(amount_in > 0, DexError::InvalidArgument);
+    
     // 记录价格查询开始时间
     let start_time = Clock::get()?.unix_timestamp;
     
-    // 获取Pump.fun的价格
-    let pump_price_result = pumpfun::get_price(
-        ctx.accounts.pump_price_ctx.into(),
-        amount_in,
+    let venues = load_venues(
+        ctx.accounts.venue_price_accounts(ctx.remaining_accounts),
+        ctx.accounts.token_mint.key(),
         is_buy,
+        SwapMode::ExactIn,
     );
+    let quotes = quote_venues(&venues, amount_in, is_buy);
+    let best = best_quote(&quotes).ok_or(DexError::PriceQueryFailed)?;
     
-    // 获取Raydium的价格
-    let raydium_price_result = raydium::get_price(
-        ctx.accounts.raydium_price_ctx.into(),
+    // 记录价格查询结束时间
+    let end_time = Clock::get()?.unix_timestamp;
+    let execution_time = end_time - start_time;
+    
+    msg!("选择{:?}进行交易，提供更好的价格", best.dex);
+    msg!("价格比较执行时间: {}ms", execution_time);
+    
+    // 发出价格比较事件
+    emit!(PriceCompared {
+        token_mint: ctx.accounts.token_mint.key(),
         amount_in,
         is_buy,
-    );
+        quotes,
+        best_dex: best.dex,
+        best_amount_out: best.amount_out,
+        execution_time,
+        slot: Clock::get()?.slot,
+    });
+    
+    Ok(best.dex)
+}
+
+// 智能路由交易
+// 按各候选DEX的实际报价选择净输出最多的DEX成交；split为true时按边际价格将订单拆分到多个DEX
+// ExactOut时amount_in为最大输入数量，min_amount_out为需要获得的精确输出数量，
+// 按各DEX的精确输出报价选择所需输入最少的DEX，不拆单
+// instruction.dex_type必须为Auto，只在instruction.venues列出的候选DEX中选择
+// 剩余账户: 依次为各候选DEX的报价账户和交易账户，按该DEX报价和交易指令的账户结构顺序排列，
+// Raydium CLMM之后紧跟tick_array_count个账户（可选的TickArrayBitmapExtension和交易方向上的TickArray，报价和交易共用）
+pub fn smart_trade<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SmartTradeContext<'info>>,
+    instruction: TradeInstruction,
+    split: bool,  // 是否允许拆单
+) -> Result<()> {
+    msg!("开始智能交易路由，代币: {}", instruction.token_mint);
+    
+    require!(instruction.dex_type == DexType::Auto, DexError::InvalidDexType);
+    
+    let mut remaining_accounts = ctx.remaining_accounts;
+    let mut candidates = parse_candidates(&instruction.venues, &mut remaining_accounts)?;
+    
+    // 剩余账户必须恰好为各候选DEX的账户
+    require!(remaining_accounts.is_empty(), DexError::InvalidRoute);
+    
+    execute_smart_trade(ctx.accounts.user.key(), &mut candidates, &instruction, split)
+}
+
+// 在候选DEX中按实际报价选择并成交，发出智能交易事件
+fn execute_smart_trade(
+    user: Pubkey,
+    candidates: &mut [RouteCandidate],
+    instruction: &TradeInstruction,
+    split: bool,
+) -> Result<()> {
+    let TradeInstruction { token_mint, amount_in, min_amount_out, is_buy, swap_mode, .. } = *instruction;
+    
+    // 检查输入金额是否大于0
+    require!(amount_in > 0, DexError::InvalidArgument);
+    
+    // 检查最小输出金额是否大于0
+    require!(min_amount_out > 0, DexError::InvalidArgument);
+    
+    // 拆单只支持精确输入
+    require!(!split || swap_mode == SwapMode::ExactIn, DexError::UnsupportedSwapMode);
+    
+    // 候选DEX的交易账户必须对应所交易的代币和签名用户，报价使用的池子必须与交易使用的池子一致
+    for candidate in candidates.iter_mut() {
+        let price_accounts = candidate.price_accounts.venue_price_accounts();
+        let trade_accounts = candidate.trade_accounts.venue_accounts();
+        require_keys_eq!(trade_accounts.token_mint(), token_mint, DexError::InvalidRoute);
+        require_keys_eq!(trade_accounts.user(), user, DexError::InvalidRoute);
+        require_keys_eq!(price_accounts.pool(), trade_accounts.pool(), DexError::RoutePoolMismatch);
+    }
+    
+    // 记录交易开始时间
+    let start_time = Clock::get()?.unix_timestamp;
     
-    // 处理价格查询结果
-    let (pump_price, raydium_price) = match (pump_price_result, raydium_price_result) {
-        (Ok(pump), Ok(raydium)) => (pump, raydium),
-        (Ok(pump), Err(_)) => {
-            msg!("Raydium价格查询失败，使用Pump.fun");
-            return Ok(true);
+    // 按各DEX的实际报价计算分配方案
+    let venues = load_venues(
+        candidates
+            .iter()
+            .map(|candidate| (candidate.price_accounts.venue_price_accounts(), candidate.tick_array_accounts))
+            .collect(),
+        token_mint,
+        is_buy,
+        swap_mode,
+    );
+    let allocations = match swap_mode {
+        SwapMode::ExactIn => {
+            let quotes = quote_venues(&venues, amount_in, is_buy);
+            let best = best_quote(&quotes).ok_or(DexError::PriceQueryFailed)?;
+            
+            let mut allocations = vec![RouteAllocation {
+                dex: best.dex,
+                amount_in,
+                expected_amount_out: best.amount_out,
+            }];
+            if split && venues.len() > 1 {
+                // 拆单后的总输出不优于单一DEX时（如固定费用或曲线截断），仍在单一DEX成交
+                let split_allocations = split_allocation(&venues, amount_in)?;
+                let split_amount_out: u64 = split_allocations.iter().map(|allocation| allocation.expected_amount_out).sum();
+                if split_amount_out > best.amount_out {
+                    allocations = split_allocations;
+                }
+            }
+            allocations
         },
-        (Err(_), Ok(raydium)) => {
-            msg!("Pump.fun价格查询失败，使用Raydium");
-            return Ok(false);
+        SwapMode::ExactOut => {
+            // 按获得min_amount_out所需的输入选择，所需输入不能超过最大输入数量
+            let exact_out_allocations = quote_venues_exact_out(&venues, min_amount_out, is_buy);
+            let best = best_exact_out_allocation(&exact_out_allocations).ok_or(DexError::PriceQueryFailed)?;
+            require!(best.amount_in <= amount_in, DexError::SlippageExceeded);
+            vec![best]
         },
-        (Err(_), Err(_)) => {
-            msg!("两个DEX的价格查询都失败");
-            return Err(DexError::PriceQueryFailed.into());
-        }
     };
+    let expected_amount_out: u64 = allocations.iter().map(|allocation| allocation.expected_amount_out).sum();
+    msg!("路由方案: 预计总输出 {}, 使用DEX数量: {}", expected_amount_out, allocations.len());
     
-    // 计算考虑滑点和费用后的实际价格
-    let pump_price_with_slippage = calculate_price_with_slippage(pump_price, is_buy);
-    let raydium_price_with_slippage = calculate_price_with_slippage(raydium_price, is_buy);
-    
-    // 比较价格，选择最优的DEX
-    // 如果是买入操作，选择价格较低的DEX（获得更多代币）
-    // 如果是卖出操作，选择价格较高的DEX（获得更多SOL）
-    let use_pump = if is_buy {
-        // 买入时比较能获得的代币数量
-        let pump_tokens_out = amount_in.checked_mul(1_000_000_000).unwrap_or(0).checked_div(pump_price_with_slippage).unwrap_or(0);
-        let raydium_tokens_out = amount_in.checked_mul(1_000_000_000).unwrap_or(0).checked_div(raydium_price_with_slippage).unwrap_or(0);
-        
-        msg!("买入比较 - Pump.fun: {} 代币/SOL, Raydium: {} 代币/SOL", 
-            pump_tokens_out, raydium_tokens_out);
-        
-        pump_tokens_out >= raydium_tokens_out
-    } else {
-        // 卖出时比较能获得的SOL数量
-        let pump_sol_out = amount_in.checked_mul(pump_price_with_slippage).unwrap_or(0).checked_div(1_000_000_000).unwrap_or(0);
-        let raydium_sol_out = amount_in.checked_mul(raydium_price_with_slippage).unwrap_or(0).checked_div(1_000_000_000).unwrap_or(0);
+    // 检查滑点
+    require!(expected_amount_out >= min_amount_out, DexError::SlippageExceeded);
+    
+    // 依次在各DEX上成交，记录按余额变化结算的实际成交数量
+    let mut allocation_fills = Vec::with_capacity(allocations.len());
+    for allocation in &allocations {
+        let candidate = candidates
+            .iter_mut()
+            .find(|candidate| candidate.dex() == allocation.dex)
+            .ok_or(DexError::InvalidRoute)?;
         
-        msg!("卖出比较 - Pump.fun: {} SOL/代币, Raydium: {} SOL/代币", 
-            pump_sol_out, raydium_sol_out);
+        let fill = match swap_mode {
+            SwapMode::ExactIn => {
+                // 按预期输出比例分摊最小输出（向上取整，各部分之和不小于min_amount_out）
+                let leg_min_amount_out = to_u64(math::mul_div_ceil(
+                    min_amount_out as u128,
+                    allocation.expected_amount_out as u128,
+                    expected_amount_out as u128,
+                )?)?;
+                
+                msg!("在{:?}上成交: 输入 {}, 预计输出 {}, 最小输出 {}",
+                    allocation.dex,
+                    allocation.amount_in,
+                    allocation.expected_amount_out,
+                    leg_min_amount_out
+                );
+                
+                execute_on_venue(
+                    candidate.trade_accounts.venue_accounts(),
+                    candidate.tick_array_accounts,
+                    allocation.amount_in,
+                    leg_min_amount_out,
+                    is_buy,
+                    swap_mode,
+                )?
+            },
+            SwapMode::ExactOut => {
+                msg!("在{:?}上成交: 预计输入 {}, 最大输入 {}, 精确输出 {}",
+                    allocation.dex,
+                    allocation.amount_in,
+                    amount_in,
+                    min_amount_out
+                );
+                
+                execute_on_venue(
+                    candidate.trade_accounts.venue_accounts(),
+                    candidate.tick_array_accounts,
+                    amount_in,
+                    min_amount_out,
+                    is_buy,
+                    swap_mode,
+                )?
+            },
+        };
         
-        pump_sol_out >= raydium_sol_out
-    };
+        allocation_fills.push(AllocationFill {
+            dex: allocation.dex,
+            amount_in: fill.amount_in,
+            expected_amount_out: allocation.expected_amount_out,
+            amount_out: fill.amount_out,
+        });
+    }
+    let amount_out: u64 = allocation_fills.iter().map(|allocation_fill| allocation_fill.amount_out).sum();
     
-    // 记录价格查询结束时间
+    // 记录交易结束时间
     let end_time = Clock::get()?.unix_timestamp;
     let execution_time = end_time - start_time;
     
-    if use_pump {
-        msg!("选择Pump.fun进行交易，提供更好的价格");
-    } else {
-        msg!("选择Raydium进行交易，提供更好的价格");
-    }
-    
-    msg!("价格比较执行时间: {}ms", execution_time);
+    msg!("智能交易完成，实际总输出: {}, 执行时间: {}ms", amount_out, execution_time);
     
-    // 发出价格比较事件
-    emit!(PriceCompared {
-        token_mint: ctx.accounts.token_mint.key(),
+    // 发出智能交易事件
+    emit!(SmartTradeExecuted {
+        user,
+        token_mint,
         amount_in,
+        min_amount_out,
         is_buy,
-        pump_price,
-        raydium_price,
-        pump_price_with_slippage,
-        raydium_price_with_slippage,
-        use_pump,
+        expected_amount_out,
+        amount_out,
+        allocations: allocation_fills,
         execution_time,
         slot: Clock::get()?.slot,
     });
     
-    // 返回true表示使用Pump.fun，false表示使用Raydium
-    Ok(use_pump)
+    Ok(())
 }
 
-// 计算考虑滑点和费用后的价格
-fn calculate_price_with_slippage(price: u64, is_buy: bool) -> u64 {
-    // 买入时增加价格（减少获得的代币），卖出时减少价格（减少获得的SOL）
-    if is_buy {
-        price.checked_mul(102).unwrap_or(price).checked_div(100).unwrap_or(price) // 增加2%
-    } else {
-        price.checked_mul(98).unwrap_or(price).checked_div(100).unwrap_or(price) // 减少2%
+// 单个DEX的交易账户，按DEX类型分发交易
+pub enum VenueTradeAccounts<'a, 'info> {
+    PumpFun(&'a mut pumpfun::TradeToken<'info>),
+    PumpSwap(&'a mut pumpswap::TradeTokenPumpSwap<'info>),
+    Raydium(&'a mut raydium::TradeTokenRaydium<'info>),
+    RaydiumAmmV4(&'a mut raydium_amm_v4::TradeTokenRaydiumAmmV4<'info>),
+    RaydiumClmm(&'a mut raydium_clmm::TradeTokenRaydiumClmm<'info>),
+    RaydiumLaunchLab(&'a mut raydium_launchlab::TradeTokenLaunchLab<'info>),
+}
+
+impl<'a, 'info> VenueTradeAccounts<'a, 'info> {
+    // 交易的代币Mint
+    fn token_mint(&self) -> Pubkey {
+        match self {
+            VenueTradeAccounts::PumpFun(accounts) => accounts.token_mint.key(),
+            VenueTradeAccounts::PumpSwap(accounts) => accounts.token_mint.key(),
+            VenueTradeAccounts::Raydium(accounts) => accounts.token_mint.key(),
+            VenueTradeAccounts::RaydiumAmmV4(accounts) => accounts.token_mint.key(),
+            VenueTradeAccounts::RaydiumClmm(accounts) => accounts.token_mint.key(),
+            VenueTradeAccounts::RaydiumLaunchLab(accounts) => accounts.token_mint.key(),
+        }
+    }
+    
+    // 交易的用户
+    fn user(&self) -> Pubkey {
+        match self {
+            VenueTradeAccounts::PumpFun(accounts) => accounts.user.key(),
+            VenueTradeAccounts::PumpSwap(accounts) => accounts.user.key(),
+            VenueTradeAccounts::Raydium(accounts) => accounts.user.key(),
+            VenueTradeAccounts::RaydiumAmmV4(accounts) => accounts.user.key(),
+            VenueTradeAccounts::RaydiumClmm(accounts) => accounts.user.key(),
+            VenueTradeAccounts::RaydiumLaunchLab(accounts) => accounts.user.key(),
+        }
+    }
+    
+    // 交易使用的池子（Pump.fun为绑定曲线）
+    fn pool(&self) -> Pubkey {
+        match self {
+            VenueTradeAccounts::PumpFun(accounts) => accounts.bonding_curve.key(),
+            VenueTradeAccounts::PumpSwap(accounts) => accounts.pool.key(),
+            VenueTradeAccounts::Raydium(accounts) => accounts.pool_state.key(),
+            VenueTradeAccounts::RaydiumAmmV4(accounts) => accounts.amm.key(),
+            VenueTradeAccounts::RaydiumClmm(accounts) => accounts.pool_state.key(),
+            VenueTradeAccounts::RaydiumLaunchLab(accounts) => accounts.pool_state.key(),
+        }
+    }
+    
+    // SOL一侧使用的WSOL账户，Pump.fun直接使用用户钱包中的SOL
+    fn wsol_account(&self, is_buy: bool) -> Option<Pubkey> {
+        match self {
+            VenueTradeAccounts::PumpFun(_) => None,
+            VenueTradeAccounts::PumpSwap(accounts) => Some(accounts.user_quote_token_account.key()),
+            VenueTradeAccounts::Raydium(accounts) => Some(if is_buy {
+                accounts.user_source_token_account.key()
+            } else {
+                accounts.user_destination_token_account.key()
+            }),
+            VenueTradeAccounts::RaydiumAmmV4(accounts) => Some(accounts.user_wsol_account.key()),
+            VenueTradeAccounts::RaydiumClmm(accounts) => Some(if is_buy {
+                accounts.user_source_token_account.key()
+            } else {
+                accounts.user_destination_token_account.key()
+            }),
+            VenueTradeAccounts::RaydiumLaunchLab(accounts) => Some(accounts.user_wsol_account.key()),
+        }
     }
 }
 
-// 智能路由交易
-pub fn smart_trade(
-    ctx: Context<SmartTradeContext>,
-    token_mint: Pubkey,
+// 在指定DEX上执行交易，返回按余额变化结算的实际成交数量
+// ExactOut时amount_in为最大输入数量，min_amount_out为需要获得的精确输出数量
+// 剩余账户: Raydium CLMM交易方向上的TickArray
+pub fn execute_on_venue<'info>(
+    venue: VenueTradeAccounts<'_, 'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    amount_in: u64,
+    min_amount_out: u64,
+    is_buy: bool,
+    swap_mode: SwapMode,
+) -> Result<Fill> {
+    match venue {
+        VenueTradeAccounts::PumpFun(accounts) => {
+            let ctx = Context::new(&crate::ID, accounts, remaining_accounts, Default::default());
+            match (swap_mode, is_buy) {
+                (SwapMode::ExactIn, true) => pumpfun::buy_token_exact_sol_in(ctx, amount_in, min_amount_out, false),
+                (SwapMode::ExactIn, false) => pumpfun::sell_token(ctx, amount_in, min_amount_out, false),
+                (SwapMode::ExactOut, true) => pumpfun::buy_token(ctx, min_amount_out, amount_in, false),
+                (SwapMode::ExactOut, false) => Err(DexError::UnsupportedSwapMode.into()),
+            }
+        },
+        VenueTradeAccounts::PumpSwap(accounts) => {
+            let ctx = Context::new(&crate::ID, accounts, remaining_accounts, Default::default());
+            match (swap_mode, is_buy) {
+                (SwapMode::ExactIn, true) => pumpswap::buy_token(ctx, amount_in, min_amount_out),
+                (SwapMode::ExactIn, false) => pumpswap::sell_token(ctx, amount_in, min_amount_out, false),
+                (SwapMode::ExactOut, _) => Err(DexError::UnsupportedSwapMode.into()),
+            }
+        },
+        VenueTradeAccounts::Raydium(accounts) => {
+            let ctx = Context::new(&crate::ID, accounts, remaining_accounts, Default::default());
+            match (swap_mode, is_buy) {
+                (SwapMode::ExactIn, true) => raydium::buy_token(ctx, amount_in, min_amount_out, None),
+                (SwapMode::ExactIn, false) => raydium::sell_token(ctx, amount_in, min_amount_out, false, None),
+                (SwapMode::ExactOut, true) => raydium::buy_exact_out(ctx, min_amount_out, amount_in, None),
+                (SwapMode::ExactOut, false) => raydium::sell_exact_out(ctx, min_amount_out, amount_in, false, None),
+            }
+        },
+        VenueTradeAccounts::RaydiumAmmV4(accounts) => {
+            let ctx = Context::new(&crate::ID, accounts, remaining_accounts, Default::default());
+            match (swap_mode, is_buy) {
+                (SwapMode::ExactIn, true) => raydium_amm_v4::buy_token(ctx, amount_in, min_amount_out),
+                (SwapMode::ExactIn, false) => raydium_amm_v4::sell_token(ctx, amount_in, min_amount_out, false),
+                (SwapMode::ExactOut, true) => raydium_amm_v4::buy_exact_out(ctx, min_amount_out, amount_in),
+                (SwapMode::ExactOut, false) => raydium_amm_v4::sell_exact_out(ctx, min_amount_out, amount_in, false),
+            }
+        },
+        VenueTradeAccounts::RaydiumClmm(accounts) => {
+            // Raydium CLMM只支持精确输入
+            let ctx = Context::new(&crate::ID, accounts, remaining_accounts, Default::default());
+            match (swap_mode, is_buy) {
+                (SwapMode::ExactIn, true) => raydium_clmm::buy_token(ctx, amount_in, min_amount_out),
+                (SwapMode::ExactIn, false) => raydium_clmm::sell_token(ctx, amount_in, min_amount_out, false),
+                (SwapMode::ExactOut, _) => Err(DexError::UnsupportedSwapMode.into()),
+            }
+        },
+        VenueTradeAccounts::RaydiumLaunchLab(accounts) => {
+            // Raydium LaunchLab只支持精确输入
+            let ctx = Context::new(&crate::ID, accounts, remaining_accounts, Default::default());
+            match (swap_mode, is_buy) {
+                (SwapMode::ExactIn, true) => raydium_launchlab::buy_token(ctx, amount_in, min_amount_out),
+                (SwapMode::ExactIn, false) => raydium_launchlab::sell_token(ctx, amount_in, min_amount_out, false),
+                (SwapMode::ExactOut, _) => Err(DexError::UnsupportedSwapMode.into()),
+            }
+        },
+    }
+}
+
+// 多跳路由交易
+// 每段均为SOL与代币之间的精确输入交易，上一段的实际输出作为下一段的输入，
+// 例如卖出Pump.fun代币换取SOL，再用所得SOL买入Raydium代币
+// 中间段不设最小输出，最后一段的实际输出按min_amount_out检查
+// 剩余账户: 依次为各段的交易账户，按该段DEX交易指令的账户结构顺序排列，
+// Raydium CLMM段之后紧跟tick_array_count个账户（可选的TickArrayBitmapExtension和交易方向上的TickArray）
+pub fn route_swap<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RouteSwapContext<'info>>,
+    legs: Vec<RouteLeg>,
     amount_in: u64,
     min_amount_out: u64,
-    is_buy: bool,  // true表示买入，false表示卖出
 ) -> Result<()> {
-    msg!("开始智能交易路由，代币: {}", token_mint);
+    // 检查路由段数
+    require!(!legs.is_empty() && legs.len() <= MAX_ROUTE_LEGS, DexError::InvalidRoute);
     
-    // 检查输入金额是否大于0
+    // 检查输入金额和最小输出金额是否大于0
     require!(amount_in > 0, DexError::InvalidArgument);
-    
-    // 检查最小输出金额是否大于0
     require!(min_amount_out > 0, DexError::InvalidArgument);
     
+    // 相邻两段必须首尾相接: 卖出后买入（经SOL中转），或买入后卖出同一代币
+    for pair in legs.windows(2) {
+        let connected = if pair[0].is_buy {
+            !pair[1].is_buy && pair[1].token_mint == pair[0].token_mint
+        } else {
+            pair[1].is_buy
+        };
+        require!(connected, DexError::InvalidRoute);
+    }
+    
+    msg!("开始多跳路由交易: 段数 {}, 输入数量 {}, 最小输出 {}", legs.len(), amount_in, min_amount_out);
+    
     // 记录交易开始时间
     let start_time = Clock::get()?.unix_timestamp;
     
-    // 首先检查代币的位置
-    let on_pump = check_token_location(
-        ctx.accounts.check_location_ctx.into(),
-        token_mint,
-    )?;
+    // 记录路由开始前用户WSOL账户的余额，路由中只解包超出该余额的部分，用户原有的WSOL保持不变
+    let wsol_balance_before = token_utils::balance_before_trade(&ctx.accounts.user_wsol_account)?;
     
-    // 根据代币位置选择正确的DEX
-    if on_pump {
-        msg!("代币在Pump.fun上，使用Pump.fun进行交易");
+    let mut leg_fills = Vec::with_capacity(legs.len());
+    let mut leg_amount_in = amount_in;
+    let mut remaining_accounts = ctx.remaining_accounts;
+    for (index, leg) in legs.iter().enumerate() {
+        let mut accounts = RouteLegAccounts::try_accounts(leg.dex_type, &mut remaining_accounts)?;
+        let tick_array_accounts = take_tick_array_accounts(leg.dex_type, leg.tick_array_count, &mut remaining_accounts)?;
+        check_leg_accounts(&accounts.venue_accounts(), ctx.accounts, leg)?;
         
-        if is_buy {
-            pumpfun::buy_token(ctx.accounts.pump_trade_ctx.into(), amount_in, min_amount_out)?;
-        } else {
-            pumpfun::sell_token(ctx.accounts.pump_trade_ctx.into(), amount_in, min_amount_out)?;
-        }
-    } else {
-        msg!("代币在Raydium上，使用Raydium进行交易");
+        let leg_min_amount_out = if index + 1 == legs.len() { min_amount_out } else { 1 };
+        msg!("路由第{}段: 在{:?}上{}，输入 {}",
+            index + 1,
+            leg.dex_type,
+            if leg.is_buy { "买入" } else { "卖出" },
+            leg_amount_in
+        );
         
-        if is_buy {
-            raydium::buy_token(ctx.accounts.raydium_trade_ctx.into(), amount_in, min_amount_out)?;
-        } else {
-            raydium::sell_token(ctx.accounts.raydium_trade_ctx.into(), amount_in, min_amount_out)?;
+        // 该段的实际输出为交易前后输出账户（代币账户或WSOL账户，Pump.fun卖出时为用户钱包）的余额变化
+        let fill = execute_on_venue(
+            accounts.venue_accounts(),
+            tick_array_accounts,
+            leg_amount_in,
+            leg_min_amount_out,
+            leg.is_buy,
+            SwapMode::ExactIn,
+        )?;
+        let leg_amount_out = fill.amount_out;
+        require!(leg_amount_out > 0, DexError::UnexpectedBalanceChange);
+        
+        msg!("路由第{}段实际输出: {}", index + 1, leg_amount_out);
+        
+        // 各DEX买入时从用户的SOL中包装输入，卖出所得仍留在WSOL账户中时先解包，供下一段买入使用
+        if !leg.is_buy {
+            ctx.accounts.unwrap_proceeds(wsol_balance_before)?;
         }
+        
+        leg_fills.push(RouteLegFill {
+            dex: leg.dex_type,
+            token_mint: leg.token_mint,
+            is_buy: leg.is_buy,
+            amount_in: leg_amount_in,
+            amount_out: leg_amount_out,
+        });
+        leg_amount_in = leg_amount_out;
     }
     
+    // 剩余账户必须恰好为各段的交易账户
+    require!(remaining_accounts.is_empty(), DexError::InvalidRoute);
+    
+    // 检查端到端滑点
+    let amount_out = leg_amount_in;
+    require!(amount_out >= min_amount_out, DexError::SlippageExceeded);
+    
+    // 解包WSOL账户中超出路由开始前余额的部分
+    ctx.accounts.unwrap_proceeds(wsol_balance_before)?;
+    
     // 记录交易结束时间
     let end_time = Clock::get()?.unix_timestamp;
     let execution_time = end_time - start_time;
     
-    msg!("智能交易完成，执行时间: {}ms", execution_time);
+    msg!("多跳路由交易完成: 输入 {}, 输出 {}, 执行时间: {}ms", amount_in, amount_out, execution_time);
     
-    // 发出智能交易事件
-    emit!(SmartTradeExecuted {
-        user: ctx.accounts.pump_trade_ctx.user.key(),
-        token_mint,
+    // 输入输出代币: 卖出段为代币换SOL，买入段为SOL换代币
+    let first_leg = &legs[0];
+    let last_leg = &legs[legs.len() - 1];
+    emit!(RouteSwapExecuted {
+        user: ctx.accounts.user.key(),
+        token_in: if first_leg.is_buy { spl_token::native_mint::id() } else { first_leg.token_mint },
+        token_out: if last_leg.is_buy { last_leg.token_mint } else { spl_token::native_mint::id() },
         amount_in,
+        amount_out,
         min_amount_out,
-        is_buy,
-        dex_used: if on_pump { "Pump.fun".to_string() } else { "Raydium".to_string() },
+        legs: leg_fills,
         execution_time,
         slot: Clock::get()?.slot,
     });
@@ -241,9 +913,195 @@ pub fn smart_trade(
     Ok(())
 }
 
+// 执行一条交易指令，用于批量交易和承诺交易
+// Auto时在instruction.venues列出的候选DEX中按实际报价选择，否则直接在指定的DEX上交易
+// accounts: 该指令的账户（Auto时为各候选DEX的报价账户和交易账户，否则为指定DEX的交易账户，
+// Raydium CLMM之后紧跟tick_array_count个账户），解析后accounts指向之后的账户
+pub fn execute_trade<'info>(
+    user: Pubkey,
+    instruction: &TradeInstruction,
+    accounts: &mut &'info [AccountInfo<'info>],
+) -> Result<()> {
+    if instruction.dex_type == DexType::Auto {
+        let mut candidates = parse_candidates(&instruction.venues, accounts)?;
+        return execute_smart_trade(user, &mut candidates, instruction, false);
+    }
+    
+    // 指定DEX时只携带该DEX的账户
+    require!(instruction.venues.len() == 1, DexError::InvalidRoute);
+    let venue = &instruction.venues[0];
+    require!(venue.dex_type == instruction.dex_type, DexError::InvalidRoute);
+    
+    let mut trade_accounts = RouteLegAccounts::try_accounts(venue.dex_type, accounts)?;
+    let tick_array_accounts = take_tick_array_accounts(venue.dex_type, venue.tick_array_count, accounts)?;
+    
+    // 交易账户必须对应所交易的代币和签名用户
+    let venue_accounts = trade_accounts.venue_accounts();
+    require_keys_eq!(venue_accounts.token_mint(), instruction.token_mint, DexError::InvalidRoute);
+    require_keys_eq!(venue_accounts.user(), user, DexError::InvalidRoute);
+    
+    execute_on_venue(
+        venue_accounts,
+        tick_array_accounts,
+        instruction.amount_in,
+        instruction.min_amount_out,
+        instruction.is_buy,
+        instruction.swap_mode,
+    )?;
+    
+    Ok(())
+}
+
+// 智能路由中的一个候选DEX: 报价账户、交易账户和Raydium CLMM的TickArray
+struct RouteCandidate<'info> {
+    price_accounts: RoutePriceAccounts<'info>,
+    trade_accounts: RouteLegAccounts<'info>,
+    tick_array_accounts: &'info [AccountInfo<'info>],
+}
+
+impl<'info> RouteCandidate<'info> {
+    fn dex(&self) -> DexType {
+        self.price_accounts.venue_price_accounts().dex()
+    }
+}
+
+// 按venues从剩余账户中依次解析各候选DEX的账户，每个DEX最多出现一次
+fn parse_candidates<'info>(
+    venues: &[RouteVenue],
+    accounts: &mut &'info [AccountInfo<'info>],
+) -> Result<Vec<RouteCandidate<'info>>> {
+    require!(!venues.is_empty(), DexError::InvalidRoute);
+    
+    let mut candidates: Vec<RouteCandidate<'info>> = Vec::with_capacity(venues.len());
+    for venue in venues {
+        require!(
+            candidates.iter().all(|candidate| candidate.dex() != venue.dex_type),
+            DexError::InvalidRoute
+        );
+        
+        let price_accounts = RoutePriceAccounts::try_accounts(venue.dex_type, accounts)?;
+        let trade_accounts = RouteLegAccounts::try_accounts(venue.dex_type, accounts)?;
+        let tick_array_accounts = take_tick_array_accounts(venue.dex_type, venue.tick_array_count, accounts)?;
+        candidates.push(RouteCandidate { price_accounts, trade_accounts, tick_array_accounts });
+    }
+    
+    Ok(candidates)
+}
+
+// 智能路由中一个候选DEX的报价账户（从剩余账户中按DEX类型解析）
+enum RoutePriceAccounts<'info> {
+    PumpFun(Box<pumpfun::GetPriceContext<'info>>),
+    PumpSwap(Box<pumpswap::GetPriceContext<'info>>),
+    Raydium(Box<raydium::GetPriceContext<'info>>),
+    RaydiumAmmV4(Box<raydium_amm_v4::GetPriceContext<'info>>),
+    RaydiumClmm(Box<raydium_clmm::GetPriceContext<'info>>),
+    RaydiumLaunchLab(Box<raydium_launchlab::GetPriceContext<'info>>),
+}
+
+impl<'info> RoutePriceAccounts<'info> {
+    // 按DEX类型解析报价账户并检查账户约束，解析后accounts指向之后的账户
+    fn try_accounts(dex: DexType, accounts: &mut &'info [AccountInfo<'info>]) -> Result<Self> {
+        match dex {
+            DexType::PumpFun => Ok(RoutePriceAccounts::PumpFun(Box::new(parse_accounts(accounts)?))),
+            DexType::PumpSwap => Ok(RoutePriceAccounts::PumpSwap(Box::new(parse_accounts(accounts)?))),
+            DexType::Raydium => Ok(RoutePriceAccounts::Raydium(Box::new(parse_accounts(accounts)?))),
+            DexType::RaydiumAmmV4 => Ok(RoutePriceAccounts::RaydiumAmmV4(Box::new(parse_accounts(accounts)?))),
+            DexType::RaydiumClmm => Ok(RoutePriceAccounts::RaydiumClmm(Box::new(parse_accounts(accounts)?))),
+            DexType::RaydiumLaunchLab => Ok(RoutePriceAccounts::RaydiumLaunchLab(Box::new(parse_accounts(accounts)?))),
+            DexType::Auto => Err(DexError::InvalidDexType.into()),
+        }
+    }
+    
+    fn venue_price_accounts(&self) -> VenuePriceAccounts<'_, 'info> {
+        match self {
+            RoutePriceAccounts::PumpFun(accounts) => VenuePriceAccounts::PumpFun(accounts),
+            RoutePriceAccounts::PumpSwap(accounts) => VenuePriceAccounts::PumpSwap(accounts),
+            RoutePriceAccounts::Raydium(accounts) => VenuePriceAccounts::Raydium(accounts),
+            RoutePriceAccounts::RaydiumAmmV4(accounts) => VenuePriceAccounts::RaydiumAmmV4(accounts),
+            RoutePriceAccounts::RaydiumClmm(accounts) => VenuePriceAccounts::RaydiumClmm(accounts),
+            RoutePriceAccounts::RaydiumLaunchLab(accounts) => VenuePriceAccounts::RaydiumLaunchLab(accounts),
+        }
+    }
+}
+
+// 多跳路由中一段的交易账户（从剩余账户中按DEX类型解析）
+// 各段账户中的DEX账户只读取，交易后不写回，由路由指令的DEX账户写回
+enum RouteLegAccounts<'info> {
+    PumpFun(Box<pumpfun::TradeToken<'info>>),
+    PumpSwap(Box<pumpswap::TradeTokenPumpSwap<'info>>),
+    Raydium(Box<raydium::TradeTokenRaydium<'info>>),
+    RaydiumAmmV4(Box<raydium_amm_v4::TradeTokenRaydiumAmmV4<'info>>),
+    RaydiumClmm(Box<raydium_clmm::TradeTokenRaydiumClmm<'info>>),
+    RaydiumLaunchLab(Box<raydium_launchlab::TradeTokenLaunchLab<'info>>),
+}
+
+impl<'info> RouteLegAccounts<'info> {
+    // 按DEX类型解析一段的交易账户并检查账户约束，解析后accounts指向之后的账户
+    fn try_accounts(dex: DexType, accounts: &mut &'info [AccountInfo<'info>]) -> Result<Self> {
+        match dex {
+            DexType::PumpFun => Ok(RouteLegAccounts::PumpFun(Box::new(parse_accounts(accounts)?))),
+            DexType::PumpSwap => Ok(RouteLegAccounts::PumpSwap(Box::new(parse_accounts(accounts)?))),
+            DexType::Raydium => Ok(RouteLegAccounts::Raydium(Box::new(parse_accounts(accounts)?))),
+            DexType::RaydiumAmmV4 => Ok(RouteLegAccounts::RaydiumAmmV4(Box::new(parse_accounts(accounts)?))),
+            DexType::RaydiumClmm => Ok(RouteLegAccounts::RaydiumClmm(Box::new(parse_accounts(accounts)?))),
+            DexType::RaydiumLaunchLab => Ok(RouteLegAccounts::RaydiumLaunchLab(Box::new(parse_accounts(accounts)?))),
+            // 路由段不再携带报价账户，必须指定DEX
+            DexType::Auto => Err(DexError::InvalidDexType.into()),
+        }
+    }
+    
+    fn venue_accounts(&mut self) -> VenueTradeAccounts<'_, 'info> {
+        match self {
+            RouteLegAccounts::PumpFun(accounts) => VenueTradeAccounts::PumpFun(accounts),
+            RouteLegAccounts::PumpSwap(accounts) => VenueTradeAccounts::PumpSwap(accounts),
+            RouteLegAccounts::Raydium(accounts) => VenueTradeAccounts::Raydium(accounts),
+            RouteLegAccounts::RaydiumAmmV4(accounts) => VenueTradeAccounts::RaydiumAmmV4(accounts),
+            RouteLegAccounts::RaydiumClmm(accounts) => VenueTradeAccounts::RaydiumClmm(accounts),
+            RouteLegAccounts::RaydiumLaunchLab(accounts) => VenueTradeAccounts::RaydiumLaunchLab(accounts),
+        }
+    }
+}
+
+// 按账户结构的字段顺序从accounts中解析账户
+fn parse_accounts<'info, T>(accounts: &mut &'info [AccountInfo<'info>]) -> Result<T>
+where
+    T: Accounts<'info, T::Bumps> + Bumps,
+    T::Bumps: Default,
+{
+    T::try_accounts(&crate::ID, accounts, &[], &mut T::Bumps::default(), &mut BTreeSet::new())
+}
+
+// 取出Raydium CLMM的TickArray账户，其他DEX不携带额外账户
+fn take_tick_array_accounts<'info>(
+    dex: DexType,
+    tick_array_count: u8,
+    accounts: &mut &'info [AccountInfo<'info>],
+) -> Result<&'info [AccountInfo<'info>]> {
+    let count = tick_array_count as usize;
+    require!(count == 0 || dex == DexType::RaydiumClmm, DexError::InvalidRoute);
+    require!(accounts.len() >= count, DexError::InvalidRoute);
+    
+    let (tick_array_accounts, rest) = accounts.split_at(count);
+    *accounts = rest;
+    Ok(tick_array_accounts)
+}
+
+// 检查路由段的交易账户: 代币与路由段一致，用户为路由的签名者，
+// SOL一侧为用户的WSOL关联代币账户，使上一段卖出的WSOL可以直接用于下一段买入
+fn check_leg_accounts(venue: &VenueTradeAccounts, accounts: &RouteSwapContext, leg: &RouteLeg) -> Result<()> {
+    require_keys_eq!(venue.token_mint(), leg.token_mint, DexError::InvalidRoute);
+    require_keys_eq!(venue.user(), accounts.user.key(), DexError::InvalidRoute);
+    if let Some(wsol_account) = venue.wsol_account(leg.is_buy) {
+        require_keys_eq!(wsol_account, accounts.user_wsol_account.key(), DexError::InvalidRoute);
+    }
+    
+    Ok(())
+}
+
 // 批量交易
-pub fn batch_trade(
-    ctx: Context<BatchTradeContext>,
+// 剩余账户: 依次为各指令的账户，见execute_trade
+pub fn batch_trade<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BatchTradeContext<'info>>,
     instructions: Vec<TradeInstruction>,
 ) -> Result<()> {
     // 检查指令是否为空
@@ -261,56 +1119,16 @@ pub fn batch_trade(
     let start_time = Clock::get()?.unix_timestamp;
     
     // 执行每个交易指令
+    let user = ctx.accounts.user.key();
+    let mut remaining_accounts = ctx.remaining_accounts;
     for (i, instruction) in instructions.iter().enumerate() {
         msg!("执行批量交易指令 {}/{}", i + 1, instructions.len());
-        
-        // 根据DEX类型选择正确的交易方式
-        match instruction.dex_type {
-            DexType::Auto => {
-                // 使用智能路由
-                smart_trade(
-                    ctx.accounts.smart_trade_ctx.into(),
-                    instruction.token_mint,
-                    instruction.amount_in,
-                    instruction.min_amount_out,
-                    instruction.is_buy,
-                )?;
-            },
-            DexType::PumpFun => {
-                // 直接使用Pump.fun
-                if instruction.is_buy {
-                    pumpfun::buy_token(
-                        ctx.accounts.pump_trade_ctx.into(),
-                        instruction.amount_in,
-                        instruction.min_amount_out,
-                    )?;
-                } else {
-                    pumpfun::sell_token(
-                        ctx.accounts.pump_trade_ctx.into(),
-                        instruction.amount_in,
-                        instruction.min_amount_out,
-                    )?;
-                }
-            },
-            DexType::Raydium => {
-                // 直接使用Raydium
-                if instruction.is_buy {
-                    raydium::buy_token(
-                        ctx.accounts.raydium_trade_ctx.into(),
-                        instruction.amount_in,
-                        instruction.min_amount_out,
-                    )?;
-                } else {
-                    raydium::sell_token(
-                        ctx.accounts.raydium_trade_ctx.into(),
-                        instruction.amount_in,
-                        instruction.min_amount_out,
-                    )?;
-                }
-            },
-        }
+        execute_trade(user, instruction, &mut remaining_accounts)?;
     }
     
+    // 剩余账户必须恰好为各指令的账户
+    require!(remaining_accounts.is_empty(), DexError::InvalidRoute);
+    
     // 记录批量交易结束时间
     let end_time = Clock::get()?.unix_timestamp;
     let execution_time = end_time - start_time;
@@ -319,7 +1137,7 @@ pub fn batch_trade(
     
     // 发出批量交易事件
     emit!(BatchTradeExecuted {
-        user: ctx.accounts.pump_trade_ctx.user.key(),
+        user,
         instruction_count: instructions.len() as u8,
         execution_time,
         slot: Clock::get()?.slot,
@@ -332,7 +1150,11 @@ pub fn batch_trade(
 #[derive(Accounts)]
 pub struct CheckTokenLocationContext<'info> {
     pub pump_check_ctx: pumpfun::CheckTokenLocation<'info>,
+    pub pumpswap_check_ctx: pumpswap::CheckTokenLocation<'info>,
+    pub launchlab_check_ctx: raydium_launchlab::CheckTokenLocation<'info>,
     pub raydium_check_ctx: raydium::CheckTokenLocation<'info>,
+    pub raydium_amm_v4_check_ctx: raydium_amm_v4::CheckTokenLocation<'info>,
+    pub raydium_clmm_check_ctx: raydium_clmm::CheckTokenLocation<'info>,
     
     // DEX账户
     #[account(
@@ -346,7 +1168,11 @@ pub struct CheckTokenLocationContext<'info> {
 #[derive(Accounts)]
 pub struct GetBestPrice<'info> {
     pub pump_price_ctx: pumpfun::GetPriceContext<'info>,
+    pub pumpswap_price_ctx: pumpswap::GetPriceContext<'info>,
+    pub launchlab_price_ctx: raydium_launchlab::GetPriceContext<'info>,
     pub raydium_price_ctx: raydium::GetPriceContext<'info>,
+    pub raydium_amm_v4_price_ctx: raydium_amm_v4::GetPriceContext<'info>,
+    pub raydium_clmm_price_ctx: raydium_clmm::GetPriceContext<'info>,
     
     // DEX账户
     #[account(
@@ -359,13 +1185,30 @@ pub struct GetBestPrice<'info> {
     pub token_mint: Account<'info, anchor_spl::token::Mint>,
 }
 
+impl<'info> GetBestPrice<'info> {
+    // 各DEX的报价账户，Raydium CLMM使用剩余账户中的TickArray
+    fn venue_price_accounts<'a>(
+        &'a self,
+        tick_array_accounts: &'a [AccountInfo<'info>],
+    ) -> Vec<(VenuePriceAccounts<'a, 'info>, &'a [AccountInfo<'info>])> {
+        let no_tick_arrays: &[AccountInfo<'info>] = &[];
+        vec![
+            (VenuePriceAccounts::PumpFun(&self.pump_price_ctx), no_tick_arrays),
+            (VenuePriceAccounts::PumpSwap(&self.pumpswap_price_ctx), no_tick_arrays),
+            (VenuePriceAccounts::RaydiumLaunchLab(&self.launchlab_price_ctx), no_tick_arrays),
+            (VenuePriceAccounts::Raydium(&self.raydium_price_ctx), no_tick_arrays),
+            (VenuePriceAccounts::RaydiumAmmV4(&self.raydium_amm_v4_price_ctx), no_tick_arrays),
+            (VenuePriceAccounts::RaydiumClmm(&self.raydium_clmm_price_ctx), tick_array_accounts),
+        ]
+    }
+}
+
 // 智能交易所需的账户结构
+// 各候选DEX的报价账户和交易账户通过剩余账户传入，每个DEX只包含该DEX所需的账户
 #[derive(Accounts)]
 pub struct SmartTradeContext<'info> {
-    pub pump_trade_ctx: pumpfun::TradeToken<'info>,
-    pub raydium_trade_ctx: raydium::TradeTokenRaydium<'info>,
-    pub check_location_ctx: CheckTokenLocationContext<'info>,
-    pub get_best_price_ctx: GetBestPrice<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
     
     // DEX账户
     #[account(
@@ -377,11 +1220,44 @@ pub struct SmartTradeContext<'info> {
 }
 
 // 批量交易所需的账户结构
+// 各指令的账户通过剩余账户传入，每条指令只包含所用DEX所需的账户
 #[derive(Accounts)]
 pub struct BatchTradeContext<'info> {
-    pub pump_trade_ctx: pumpfun::TradeToken<'info>,
-    pub raydium_trade_ctx: raydium::TradeTokenRaydium<'info>,
-    pub smart_trade_ctx: SmartTradeContext<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // DEX账户
+    #[account(
+        mut,
+        seeds = [b"dex_account".as_ref()],
+        bump
+    )]
+    pub dex_account: Account<'info, crate::DexAccount>,
+}
+
+// 多跳路由交易所需的账户结构
+// 各段的交易账户通过剩余账户传入，每段只包含该段DEX所需的账户
+#[derive(Accounts)]
+pub struct RouteSwapContext<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // 用户WSOL账户（各段SOL一侧共用的关联代币账户，可以尚未创建）
+    #[account(
+        mut,
+        address = get_associated_token_address(&user.key(), &spl_token::native_mint::id())
+    )]
+    pub user_wsol_account: UncheckedAccount<'info>,
+    
+    // WSOL Mint地址
+    #[account(
+        constraint = wsol_mint.key() == spl_token::native_mint::id()
+    )]
+    pub wsol_mint: Account<'info, token::Mint>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     
     // DEX账户
     #[account(
@@ -392,17 +1268,36 @@ pub struct BatchTradeContext<'info> {
     pub dex_account: Account<'info, crate::DexAccount>,
 }
 
+impl<'info> RouteSwapContext<'info> {
+    // 将留在WSOL账户中的卖出所得解包为SOL，路由开始前已存在的WSOL余额保持不变
+    // 账户已由交易段关闭时无需解包
+    fn unwrap_proceeds(&self, wsol_balance_before: Option<u64>) -> Result<()> {
+        if self.user_wsol_account.data_is_empty() {
+            return Ok(());
+        }
+        
+        token_utils::unwrap_sol(
+            &self.user.to_account_info(),
+            &self.user_wsol_account.to_account_info(),
+            &self.wsol_mint.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.associated_token_program.to_account_info(),
+            wsol_balance_before,
+        )
+    }
+}
+
 // 价格比较事件
 #[event]
 pub struct PriceCompared {
     pub token_mint: Pubkey,
     pub amount_in: u64,
     pub is_buy: bool,
-    pub pump_price: u64,
-    pub raydium_price: u64,
-    pub pump_price_with_slippage: u64,
-    pub raydium_price_with_slippage: u64,
-    pub use_pump: bool,
+    // 各DEX的净输出，报价失败的DEX不包含在内
+    pub quotes: Vec<VenueQuote>,
+    pub best_dex: DexType,
+    pub best_amount_out: u64,
     pub execution_time: i64,
     pub slot: u64,
 }
@@ -415,7 +1310,11 @@ pub struct SmartTradeExecuted {
     pub amount_in: u64,
     pub min_amount_out: u64,
     pub is_buy: bool,
-    pub dex_used: String,
+    pub expected_amount_out: u64,
+    // 各DEX实际输出之和
+    pub amount_out: u64,
+    // 各DEX的实际成交（不拆单时只有一项）
+    pub allocations: Vec<AllocationFill>,
     pub execution_time: i64,
     pub slot: u64,
 }
@@ -427,4 +1326,19 @@ pub struct BatchTradeExecuted {
     pub instruction_count: u8,
     pub execution_time: i64,
     pub slot: u64,
-} 
\ No newline at end of file
+} 
+
+// 多跳路由交易事件
+#[event]
+pub struct RouteSwapExecuted {
+    pub user: Pubkey,
+    // 原生SOL以WSOL Mint表示
+    pub token_in: Pubkey,
+    pub token_out: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub min_amount_out: u64,
+    pub legs: Vec<RouteLegFill>,
+    pub execution_time: i64,
+    pub slot: u64,
+}
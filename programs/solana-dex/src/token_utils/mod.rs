@@ -0,0 +1,246 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token;
+use anchor_spl::token_2022::{
+    self,
+    spl_token_2022::{
+        extension::{
+            transfer_fee::{TransferFeeConfig, MAX_FEE_BASIS_POINTS},
+            BaseStateWithExtensions,
+            StateWithExtensions,
+        },
+        state::Mint,
+    },
+};
+use anchor_spl::token_interface::{self, TokenAccount};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id};
+use solana_program::{program::invoke, system_instruction};
+use std::str::FromStr;
+use crate::DexError;
+
+// SPL Memo程序（Raydium CPMM和CLMM的部分指令要求传入，用于Token-2022的memo转账扩展）
+pub const MEMO_PROGRAM_ID: &str = "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr";
+
+// 获取SPL Memo程序ID
+pub fn get_memo_program_id() -> Pubkey {
+    Pubkey::from_str(MEMO_PROGRAM_ID).unwrap()
+}
+
+// 检查账户是否属于SPL Token或Token-2022程序
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    *program_id == token::ID || *program_id == token_2022::ID
+}
+
+// 读取代币账户（用于未在账户结构中反序列化的代币账户，兼容Token-2022扩展）
+pub fn read_token_account(account_info: &AccountInfo) -> Result<TokenAccount> {
+    require!(is_token_program(account_info.owner), DexError::InvalidTokenAccount);
+    let data = account_info.try_borrow_data()?;
+    TokenAccount::try_deserialize(&mut &data[..])
+}
+
+// 计算Token-2022转账手续费，经典SPL代币或未启用转账手续费扩展时为0
+pub fn get_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    if *mint.owner != token_2022::ID {
+        return Ok(0);
+    }
+    
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Mint>::unpack(&data)?;
+    let fee = match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee_config) => transfer_fee_config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(DexError::ArithmeticOverflow)?,
+        Err(_) => 0,
+    };
+    
+    Ok(fee)
+}
+
+// 已知到账数量，反推Token-2022转账手续费（与Raydium一致，费率为100%时取最大手续费）
+pub fn get_transfer_inverse_fee(mint: &AccountInfo, post_fee_amount: u64) -> Result<u64> {
+    if *mint.owner != token_2022::ID {
+        return Ok(0);
+    }
+    
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Mint>::unpack(&data)?;
+    let fee = match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee_config) => {
+            let transfer_fee = transfer_fee_config.get_epoch_fee(Clock::get()?.epoch);
+            if u16::from(transfer_fee.transfer_fee_basis_points) == MAX_FEE_BASIS_POINTS {
+                u64::from(transfer_fee.maximum_fee)
+            } else {
+                transfer_fee
+                    .calculate_inverse_fee(post_fee_amount)
+                    .ok_or(DexError::ArithmeticOverflow)?
+            }
+        }
+        Err(_) => 0,
+    };
+    
+    Ok(fee)
+}
+
+// 扣除转账手续费后实际到账的数量
+pub fn amount_after_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    let transfer_fee = get_transfer_fee(mint, amount)?;
+    amount
+        .checked_sub(transfer_fee)
+        .ok_or(DexError::ArithmeticOverflow.into())
+}
+
+// 使对方实际到账指定数量所需转出的数量（含转账手续费）
+pub fn amount_before_transfer_fee(mint: &AccountInfo, post_fee_amount: u64) -> Result<u64> {
+    let transfer_fee = get_transfer_inverse_fee(mint, post_fee_amount)?;
+    post_fee_amount
+        .checked_add(transfer_fee)
+        .ok_or(DexError::ArithmeticOverflow.into())
+}
+
+// 确保用户代币账户存在
+// 账户不存在时以幂等方式创建用户的关联代币账户，已存在时检查mint和所有者
+pub fn ensure_user_token_account<'info>(
+    user: &AccountInfo<'info>,
+    user_token_account: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+) -> Result<TokenAccount> {
+    if user_token_account.data_is_empty() {
+        // 只能创建用户的关联代币账户
+        require_keys_eq!(
+            user_token_account.key(),
+            get_associated_token_address_with_program_id(&user.key(), &mint.key(), &token_program.key()),
+            DexError::InvalidTokenAccount
+        );
+        
+        msg!("创建用户关联代币账户: {}", user_token_account.key());
+        
+        associated_token::create_idempotent(CpiContext::new(
+            associated_token_program.clone(),
+            associated_token::Create {
+                payer: user.clone(),
+                associated_token: user_token_account.clone(),
+                authority: user.clone(),
+                mint: mint.clone(),
+                system_program: system_program.clone(),
+                token_program: token_program.clone(),
+            },
+        ))?;
+    }
+    
+    let token_account = read_token_account(user_token_account)?;
+    require_keys_eq!(token_account.mint, mint.key(), DexError::InvalidTokenAccount);
+    require_keys_eq!(token_account.owner, user.key(), DexError::InvalidTokenAccount);
+    
+    Ok(token_account)
+}
+
+// 关闭余额为0的用户代币账户，租金退还给用户，返回是否已关闭
+pub fn close_empty_token_account<'info>(
+    user: &AccountInfo<'info>,
+    user_token_account: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> Result<bool> {
+    let token_account = read_token_account(user_token_account)?;
+    require_keys_eq!(token_account.owner, user.key(), DexError::InvalidTokenAccount);
+    
+    if token_account.amount > 0 {
+        msg!("代币账户仍有余额 {}，不关闭", token_account.amount);
+        return Ok(false);
+    }
+    
+    token_interface::close_account(CpiContext::new(
+        token_program.clone(),
+        token_interface::CloseAccount {
+            account: user_token_account.clone(),
+            destination: user.clone(),
+            authority: user.clone(),
+        },
+    ))?;
+    
+    msg!("已关闭代币账户并退还租金: {}", user_token_account.key());
+    Ok(true)
+}
+
+// 将用户的SOL包装为WSOL: 转入lamports后同步WSOL账户余额
+pub fn wrap_sol<'info>(
+    user: &AccountInfo<'info>,
+    wsol_account: &AccountInfo<'info>,
+    amount: u64,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    msg!("包装SOL为WSOL: {}", amount);
+    
+    invoke(
+        &system_instruction::transfer(&user.key(), &wsol_account.key(), amount),
+        &[user.clone(), wsol_account.clone(), system_program.clone()],
+    )?;
+    
+    token_interface::sync_native(CpiContext::new(
+        token_program.clone(),
+        token_interface::SyncNative {
+            account: wsol_account.clone(),
+        },
+    ))
+}
+
+// 读取交易前用户代币账户的余额，账户尚未创建时返回None
+pub fn balance_before_trade(user_token_account: &AccountInfo) -> Result<Option<u64>> {
+    if user_token_account.data_is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(read_token_account(user_token_account)?.amount))
+}
+
+// 交易后将WSOL账户中超出交易前余额的部分解包为SOL退还给用户（买入时未用完的WSOL，卖出时所得的WSOL）
+// balance_before为None表示WSOL账户由本指令创建，直接关闭账户并退还全部余额及租金；
+// 交易前已存在的WSOL账户无法只解包部分余额，先关闭账户全部解包，再重新创建关联账户并包装回交易前的余额，用户原有的WSOL保持不变
+pub fn unwrap_sol<'info>(
+    user: &AccountInfo<'info>,
+    wsol_account: &AccountInfo<'info>,
+    wsol_mint: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+    balance_before: Option<u64>,
+) -> Result<()> {
+    let token_account = read_token_account(wsol_account)?;
+    require!(token_account.is_native(), DexError::InvalidTokenAccount);
+    require_keys_eq!(token_account.owner, user.key(), DexError::InvalidTokenAccount);
+    
+    let retained_amount = balance_before.unwrap_or(0);
+    if balance_before.is_some() && token_account.amount <= retained_amount {
+        msg!("WSOL余额未超过交易前余额，无需解包: {}", token_account.amount);
+        return Ok(());
+    }
+    
+    msg!("解包WSOL为SOL: {}", token_account.amount - retained_amount);
+    
+    token_interface::close_account(CpiContext::new(
+        token_program.clone(),
+        token_interface::CloseAccount {
+            account: wsol_account.clone(),
+            destination: user.clone(),
+            authority: user.clone(),
+        },
+    ))?;
+    
+    // 交易前已存在的WSOL账户: 重新创建并包装回交易前的余额
+    if balance_before.is_some() {
+        ensure_user_token_account(
+            user,
+            wsol_account,
+            wsol_mint,
+            system_program,
+            token_program,
+            associated_token_program,
+        )?;
+        if retained_amount > 0 {
+            wrap_sol(user, wsol_account, retained_amount, system_program, token_program)?;
+        }
+    }
+    
+    Ok(())
+}